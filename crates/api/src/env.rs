@@ -1,7 +1,18 @@
 use metastable_common::EnvVars;
+use metastable_runtime::DEFAULT_MAX_EXAMPLE_DIALOGUE_CHARS;
+
+const DEFAULT_MAX_REQUEST_BODY_BYTES: usize = 2 * 1024 * 1024;
+const DEFAULT_CHAT_REQUEST_TIMEOUT_SECS: u64 = 300;
+const DEFAULT_CRUD_REQUEST_TIMEOUT_SECS: u64 = 30;
 
 pub struct ApiServerEnv {
     pub secret_salt: String,
+    pub previous_secret_salts: Vec<String>,
+    pub balance_reserve_floor: i64,
+    pub max_example_dialogue_chars: usize,
+    pub max_request_body_bytes: usize,
+    pub chat_request_timeout_secs: u64,
+    pub crud_request_timeout_secs: u64,
     pub fish_audio_api_key: String,
     pub hasura_graphql_url: String,
     pub hasura_graphql_admin_secret: String,
@@ -15,6 +26,26 @@ impl EnvVars for ApiServerEnv {
     fn load() -> Self {
         Self {
             secret_salt: std::env::var("SECRET_SALT").unwrap(),
+            previous_secret_salts: std::env::var("PREVIOUS_SECRET_SALTS").unwrap_or_default()
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            balance_reserve_floor: std::env::var("BALANCE_RESERVE_FLOOR").ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            max_example_dialogue_chars: std::env::var("MAX_EXAMPLE_DIALOGUE_CHARS").ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_MAX_EXAMPLE_DIALOGUE_CHARS),
+            max_request_body_bytes: std::env::var("MAX_REQUEST_BODY_BYTES").ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_MAX_REQUEST_BODY_BYTES),
+            chat_request_timeout_secs: std::env::var("CHAT_REQUEST_TIMEOUT_SECS").ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_CHAT_REQUEST_TIMEOUT_SECS),
+            crud_request_timeout_secs: std::env::var("CRUD_REQUEST_TIMEOUT_SECS").ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_CRUD_REQUEST_TIMEOUT_SECS),
             fish_audio_api_key: std::env::var("FISH_AUDIO_API_KEY").unwrap(),
             hasura_graphql_url: std::env::var("HASURA_GRAPHQL_URL").unwrap(),
             hasura_graphql_admin_secret: std::env::var("HASURA_GRAPHQL_ADMIN_SECRET").unwrap(),
@@ -10,10 +10,12 @@ use metastable_runtime_roleplay::agents::{
     RoleplayCharacterCreationV1Agent,
     CharacterCreationAgent,
 };
-use metastable_runtime_roleplay::preload_characters;
+use metastable_runtime_roleplay::{preload_characters, MemoryUpdater};
 use sqlx::types::Uuid;
 use tokio::sync::mpsc;
 
+use crate::SessionBroadcast;
+
 define_agent_router! {
     RoleplayV1 as roleplay_v1 (RoleplayV1Agent),
     RoleplayCharacterCreationV1 as roleplay_character_creation_v1 (RoleplayCharacterCreationV1Agent),
@@ -26,6 +28,8 @@ pub struct GlobalState {
     pub agents_router: AgentsRouter,
     pub http_client: Client,
     pub memory_update_tx: mpsc::Sender<Uuid>,
+    pub memory_updater: MemoryUpdater,
+    pub session_broadcast: SessionBroadcast,
     pub stripe_client: StripeClient,
     pub r2_client: R2Client,
     pub fish_audio_client: FishAudioClient,
@@ -40,6 +44,7 @@ impl GlobalState {
         let r2_client = R2Client::setup_connection().await;
         let fish_audio_client = FishAudioClient::setup_connection().await;
         let (memory_update_tx, memory_update_rx) = mpsc::channel(50);
+        let memory_updater = MemoryUpdater::new().await?;
 
         let mut tx = db.get_client().begin().await?;
         let admin_user = User::find_one_by_criteria(
@@ -57,6 +62,8 @@ impl GlobalState {
                 agents_router,
                 http_client,
                 memory_update_tx,
+                memory_updater,
+                session_broadcast: SessionBroadcast::default(),
                 stripe_client,
                 r2_client,
                 fish_audio_client,
@@ -4,9 +4,12 @@ mod response;
 mod utils;
 mod routes;
 mod global_state;
+mod session_broadcast;
 
 pub use routes::{
     misc_routes,
+    admin_routes,
+    ReprocessMemoryResponse,
     graphql_route,
     voice_routes,
     runtime_routes,
@@ -17,6 +20,7 @@ pub use routes::{
 
 pub use env::ApiServerEnv;
 pub use utils::setup_tracing;
-pub use middleware::{authenticate, ensure_account};
+pub use middleware::{authenticate, ensure_account, with_body_limit, with_timeout, within_budget, PAYLOAD_TOO_LARGE_CODE, REQUEST_TIMEOUT_CODE};
 pub use response::{AppError, AppSuccess};
-pub use global_state::GlobalState;
\ No newline at end of file
+pub use global_state::GlobalState;
+pub use session_broadcast::{SessionBroadcast, SessionSubscription, DEFAULT_SESSION_BROADCAST_CAPACITY};
\ No newline at end of file
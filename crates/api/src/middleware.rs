@@ -1,7 +1,12 @@
-use axum::body::Body;
-use axum::http::StatusCode;
-use axum::{extract::Request, response::Response};
-use axum::middleware::Next;
+use std::future::Future;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use axum::body::{to_bytes, Body};
+use axum::extract::{DefaultBodyLimit, State};
+use axum::http::{header, StatusCode};
+use axum::{extract::Request, response::Response, Router};
+use axum::middleware::{self, Next};
 
 use metastable_common::ModuleClient;
 use metastable_common::EnvVars;
@@ -20,7 +25,7 @@ pub async fn authenticate(
     let maybe_auth_token = extract_auth_token(&req);
 
     let user_id = maybe_auth_token.and_then(|token| {
-        match User::verify_auth_token(&token, &env.get_env_var("SECRET_SALT")) {
+        match User::verify_auth_token(&token, &env.get_env_var("SECRET_SALT"), &env.previous_secret_salts) {
             Ok(uid) => {
                 Ok(uid)
             }
@@ -52,4 +57,67 @@ pub async fn ensure_account(
     tx.commit().await?;
 
     Ok(maybe_user)
-}
\ No newline at end of file
+}
+
+/// Machine code of the `413` returned for oversized request bodies.
+pub const PAYLOAD_TOO_LARGE_CODE: &str = "payload_too_large";
+
+/// Caps request bodies at `limit` bytes, answering larger ones with a `413` `AppError`
+/// instead of the extractors' plain text rejection.
+pub fn with_body_limit<S>(router: Router<S>, limit: usize) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    router
+        .layer(middleware::from_fn_with_state(limit, limit_request_body))
+        .layer(DefaultBodyLimit::max(limit))
+}
+
+async fn limit_request_body(
+    State(limit): State<usize>, req: Request, next: Next
+) -> Result<Response<Body>, AppError> {
+    let too_large = || AppError::new(
+        StatusCode::PAYLOAD_TOO_LARGE,
+        anyhow!("[limit_request_body] Request body exceeds {} bytes", limit)
+    ).with_code(PAYLOAD_TOO_LARGE_CODE);
+
+    let content_length = req.headers().get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    if content_length.is_some_and(|len| len > limit) {
+        return Err(too_large());
+    }
+
+    // Chunked bodies declare no length, so read them up to the limit
+    let (parts, body) = req.into_parts();
+    let bytes = to_bytes(body, limit).await.map_err(|_| too_large())?;
+
+    Ok(next.run(Request::from_parts(parts, Body::from(bytes))).await)
+}
+
+pub const REQUEST_TIMEOUT_CODE: &str = "request_timeout";
+
+/// Gives every request on `router` at most `budget` to produce a response, answering
+/// slower ones with a `504` `AppError`. The handler's future is dropped when it runs out,
+/// so routes that charge for a model call bound just that call with `within_budget`.
+pub fn with_timeout<S>(router: Router<S>, budget: Duration) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    router.layer(middleware::from_fn_with_state(budget, limit_request_time))
+}
+
+async fn limit_request_time(
+    State(budget): State<Duration>, req: Request, next: Next
+) -> Result<Response<Body>, AppError> {
+    within_budget(budget, next.run(req)).await
+}
+
+/// Runs `fut` for at most `budget`, failing with the same `504` as `with_timeout`.
+pub async fn within_budget<T>(budget: Duration, fut: impl Future<Output = T>) -> Result<T, AppError> {
+    tokio::time::timeout(budget, fut).await
+        .map_err(|_| AppError::new(
+            StatusCode::GATEWAY_TIMEOUT,
+            anyhow!("[within_budget] Request exceeded its {}ms budget", budget.as_millis())
+        ).with_code(REQUEST_TIMEOUT_CODE))
+}
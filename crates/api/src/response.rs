@@ -31,11 +31,17 @@ impl IntoResponse for GenericResponse {
 }
 
 // Make our own error that wraps `anyhow::Error`.
+// The optional machine code lets clients tell errors apart without parsing messages.
 #[derive(Debug)]
-pub struct AppError(pub StatusCode, pub anyhow::Error);
+pub struct AppError(pub StatusCode, pub anyhow::Error, pub Option<&'static str>);
 impl AppError {
     pub fn new(status: StatusCode, err: anyhow::Error) -> Self {
-        Self(status, err)
+        Self(status, err, None)
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.2 = Some(code);
+        self
     }
 }
 
@@ -43,7 +49,11 @@ impl AppError {
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         tracing::error!("CODE: {}, MESSAGE: {}", self.0.as_u16(), self.1);
-        GenericResponse::new(self.0, &self.1.to_string(), json!({})).into_response()
+        let data = match self.2 {
+            Some(code) => json!({ "code": code }),
+            None => json!({}),
+        };
+        GenericResponse::new(self.0, &self.1.to_string(), data).into_response()
     }
 }
 
@@ -54,6 +64,6 @@ where
     E: Into<anyhow::Error>,
 {
     fn from(err: E) -> Self {
-        Self(StatusCode::BAD_REQUEST, err.into())
+        Self(StatusCode::BAD_REQUEST, err.into(), None)
     }
 }
@@ -0,0 +1,136 @@
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode, middleware,
+    routing::{get, post}, Json, Router
+};
+use sqlx::types::Uuid;
+
+use metastable_clients::BatchUpdateSummary;
+use metastable_common::ModuleClient;
+use metastable_database::Cursor;
+use metastable_runtime::{Character, CharacterLanguage, CharacterStatus, UserRole};
+
+use crate::{
+    ensure_account,
+    middleware::authenticate,
+    response::{AppError, AppSuccess},
+    GlobalState
+};
+
+pub fn admin_routes() -> Router<GlobalState> {
+    Router::new()
+        .route("/admin/characters/bulk-status",
+            post(bulk_update_character_status)
+            .route_layer(middleware::from_fn(authenticate))
+        )
+        .route("/admin/characters/reviewing",
+            get(reviewing_queue)
+            .route_layer(middleware::from_fn(authenticate))
+        )
+        .route("/admin/sessions/{session_id}/reprocess-memory",
+            post(reprocess_session_memory)
+            .route_layer(middleware::from_fn(authenticate))
+        )
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BulkCharacterStatusRequest {
+    pub ids: Vec<Uuid>,
+    pub status: CharacterStatus,
+}
+
+async fn bulk_update_character_status(
+    State(state): State<GlobalState>,
+    Extension(user_id_str): Extension<String>,
+    Json(payload): Json<BulkCharacterStatusRequest>,
+) -> Result<AppSuccess, AppError> {
+    let user = ensure_account(&state.db, &user_id_str).await?
+        .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, anyhow!("[bulk_update_character_status] User not found")))?;
+    if user.role != UserRole::Admin {
+        return Err(AppError::new(StatusCode::FORBIDDEN, anyhow!("[bulk_update_character_status] User not authorized")));
+    }
+
+    let mut tx = state.db.get_client().begin().await?;
+    let outcomes = Character::bulk_update_status(&payload.ids, payload.status, &mut tx).await?;
+    tx.commit().await?;
+
+    let results = outcomes.into_iter()
+        .map(|(id, outcome)| match outcome {
+            Ok(()) => json!({ "id": id, "success": true }),
+            Err(e) => json!({ "id": id, "success": false, "error": e }),
+        })
+        .collect::<Vec<_>>();
+
+    Ok(AppSuccess::new(StatusCode::OK, "Character statuses updated", json!(results)))
+}
+
+const REVIEWING_QUEUE_PAGE_SIZE: i64 = 20;
+
+/// Empty parameters are treated as absent.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ReviewingQueueQuery {
+    /// `{created_at}_{id}` of the last character on the previous page
+    pub cursor: Option<String>,
+    pub language: Option<String>,
+    pub tag: Option<String>,
+}
+
+async fn reviewing_queue(
+    State(state): State<GlobalState>,
+    Extension(user_id_str): Extension<String>,
+    Query(query): Query<ReviewingQueueQuery>,
+) -> Result<AppSuccess, AppError> {
+    let user = ensure_account(&state.db, &user_id_str).await?
+        .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, anyhow!("[reviewing_queue] User not found")))?;
+    if user.role != UserRole::Admin {
+        return Err(AppError::new(StatusCode::FORBIDDEN, anyhow!("[reviewing_queue] User not authorized")));
+    }
+
+    let non_empty = |v: Option<String>| v.filter(|v| !v.trim().is_empty());
+    let cursor = non_empty(query.cursor)
+        .map(|cursor| cursor.parse::<Cursor<i64>>()
+            .map_err(|e| AppError::new(StatusCode::BAD_REQUEST, anyhow!("[reviewing_queue] Invalid cursor: {}", e))))
+        .transpose()?;
+    let language = non_empty(query.language)
+        .map(|language| language.parse::<CharacterLanguage>()
+            .map_err(|e| AppError::new(StatusCode::BAD_REQUEST, anyhow!("[reviewing_queue] Invalid language: {}", e))))
+        .transpose()?;
+
+    let mut tx = state.db.get_client().begin().await?;
+    let page = Character::reviewing_queue(
+        language, non_empty(query.tag), cursor, REVIEWING_QUEUE_PAGE_SIZE, &mut *tx
+    ).await?;
+    tx.commit().await?;
+
+    Ok(AppSuccess::new(StatusCode::OK, "Reviewing characters fetched successfully", json!({
+        "characters": page.items,
+        "next_cursor": page.next_cursor.map(|cursor| cursor.to_string()),
+    })))
+}
+
+/// Outcome of re-running memory extraction for a session. `summary` is `None`
+/// when the session had too little history to process.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReprocessMemoryResponse {
+    pub session_id: Uuid,
+    pub summary: Option<BatchUpdateSummary>,
+}
+
+async fn reprocess_session_memory(
+    State(state): State<GlobalState>,
+    Extension(user_id_str): Extension<String>,
+    Path(session_id): Path<Uuid>,
+) -> Result<AppSuccess, AppError> {
+    let user = ensure_account(&state.db, &user_id_str).await?
+        .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, anyhow!("[reprocess_session_memory] User not found")))?;
+    if user.role != UserRole::Admin {
+        return Err(AppError::new(StatusCode::FORBIDDEN, anyhow!("[reprocess_session_memory] User not authorized")));
+    }
+
+    let summary = state.memory_updater.update_memory(&session_id).await?;
+    let response = ReprocessMemoryResponse { session_id, summary };
+    Ok(AppSuccess::new(StatusCode::OK, "Session memory reprocessed", json!(response)))
+}
@@ -5,6 +5,7 @@ mod graphql;
 mod user;
 mod auth;
 mod stripe;
+mod admin;
 
 pub use misc::misc_routes;
 pub use runtime::runtime_routes;
@@ -12,4 +13,5 @@ pub use tts::voice_routes;
 pub use graphql::graphql_route;
 pub use user::user_routes;
 pub use auth::auth_routes;
-pub use stripe::stripe_routes;
\ No newline at end of file
+pub use stripe::stripe_routes;
+pub use admin::{admin_routes, ReprocessMemoryResponse};
\ No newline at end of file
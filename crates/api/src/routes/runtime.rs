@@ -1,13 +1,19 @@
+use std::time::Duration;
+
 use anyhow::anyhow;
-use metastable_common::get_current_timestamp;
-use metastable_runtime::{AgentRouter, CardPool, Character, CharacterFeature, ChatSession, DrawHistory, DrawType, Prompt, User};
+use metastable_common::{get_current_timestamp, EnvVars};
+use metastable_runtime::{Agent, AgentRouter, CardPool, Character, CharacterFeature, ChatSession, DrawHistory, DrawType, Prompt, User};
 use metastable_runtime_roleplay::RoleplayInput;
+use metastable_runtime_roleplay::agents::{RoleplayCharacterCreationV1Agent, RoleplayV1Agent};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use futures::{stream, Stream};
+use tokio::sync::broadcast::error::RecvError;
 use axum::{
-    extract::{Extension, Path, State}, 
+    extract::{Extension, Path, Query, State}, 
     http::StatusCode, middleware, 
-    routing::post, Json, Router
+    response::sse::{Event, KeepAlive, Sse},
+    routing::{get, post}, Json, Router
 };
 use sqlx::types::Uuid;
 
@@ -15,7 +21,7 @@ use metastable_database::{OrderDirection, QueryCriteria, SqlxCrud, SqlxFilterQue
 use metastable_common::ModuleClient;
 
 use crate::{
-    ensure_account, global_state::{AgentRouterInput, AgentRouterOutput}, middleware::authenticate, response::{AppError, AppSuccess}, GlobalState
+    ensure_account, global_state::{AgentRouterInput, AgentRouterOutput}, middleware::{authenticate, within_budget}, response::{AppError, AppSuccess}, ApiServerEnv, GlobalState
 };
 
 pub fn runtime_routes() -> Router<GlobalState> {
@@ -28,6 +34,10 @@ pub fn runtime_routes() -> Router<GlobalState> {
             post(create_session)
             .route_layer(middleware::from_fn(authenticate))
         )
+        .route("/runtime/session/{session_id}/stream",
+            get(stream_session)
+            .route_layer(middleware::from_fn(authenticate))
+        )
         .route("/runtime/cards/draw/{card_pool_id}",
             post(draw_card)
             .route_layer(middleware::from_fn(authenticate))
@@ -55,34 +65,49 @@ async fn call_agent(
     Extension(user_id_str): Extension<String>,
     Json(payload): Json<RuntimeCallRequest>,
 ) -> Result<AppSuccess, AppError> {
-    let mut user = ensure_account(&state.db, &user_id_str).await?
+    let user = ensure_account(&state.db, &user_id_str).await?
         .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, anyhow!("[call_agent] User not found")))?;
 
+    let env = ApiServerEnv::load();
+    // a regular roleplay turn is essential; everything else keeps the reserve floor
+    let reserve_floor = match payload.call_type {
+        RuntimeCallType::RoleplayV1 => None,
+        _ => Some(env.balance_reserve_floor),
+    };
+    // Only the model call is bounded, so a turn that finishes is always charged
+    let model_budget = Duration::from_secs(env.chat_request_timeout_secs);
+
+    // Checked against a snapshot so no row lock is held across the model call;
+    // the charge below re-checks it under the lock.
     let price = match payload.call_type {
-        RuntimeCallType::CharacterCreation => user.try_pay(3),
-        RuntimeCallType::RoleplayV1 => user.try_pay(3),
-        RuntimeCallType::RoleplayV1Regenerate => user.try_pay(1),
+        RuntimeCallType::CharacterCreation => user.try_pay(3, reserve_floor),
+        RuntimeCallType::RoleplayV1 => user.try_pay(3, reserve_floor),
+        RuntimeCallType::RoleplayV1Regenerate => user.try_pay(1, reserve_floor),
     }?;
 
-    let mut tx = state.db.get_client().begin().await?;    
-    let result = (async || match payload.call_type {
+    match payload.call_type {
         RuntimeCallType::CharacterCreation => {
             let payload = AgentRouterInput::CharacterCreation(payload.session_id);
-            let response = state.agents_router.route(&user.id, payload).await?;
-            if let AgentRouterOutput::CharacterCreation(m, _, val) = response {
-                let value = val
-                    .ok_or_else(|| AppError::new(StatusCode::INTERNAL_SERVER_ERROR, anyhow!("[call_agent::CharacterCreation] Value is required")))?;
-
-                let log = user.pay_for_character_creation(price, m.id.clone())?;
-                log.create(&mut *tx).await?;
-                user.update(&mut *tx).await?;
-
-                Ok(value)
-            } else {
-                Err(AppError::new(StatusCode::INTERNAL_SERVER_ERROR, anyhow!("[call_agent::CharacterCreation] Unexpected response")))
-            }
+            let response = within_budget(model_budget, state.agents_router.route(&user.id, payload)).await??;
+            let AgentRouterOutput::CharacterCreation(m, _, val) = response else {
+                return Err(AppError::new(StatusCode::INTERNAL_SERVER_ERROR, anyhow!("[call_agent::CharacterCreation] Unexpected response")));
+            };
+            let value = val
+                .ok_or_else(|| AppError::new(StatusCode::INTERNAL_SERVER_ERROR, anyhow!("[call_agent::CharacterCreation] Value is required")))?;
+
+            let mut tx = state.db.get_client().begin().await?;
+            let mut user = User::find_for_update(&user.id, &mut tx).await?
+                .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, anyhow!("[call_agent] User not found")))?;
+            user.try_pay(price, reserve_floor)?;
+            let log = user.pay_for_character_creation(price, m.id, reserve_floor)?;
+            log.create(&mut *tx).await?;
+            user.update(&mut *tx).await?;
+            tx.commit().await?;
+
+            Ok(AppSuccess::new(StatusCode::OK, "agent call success", value))
         }
         RuntimeCallType::RoleplayV1 | RuntimeCallType::RoleplayV1Regenerate => {
+            let mut tx = state.db.get_client().begin().await?;
             let session = ChatSession::find_one_by_criteria(
                 QueryCriteria::new().add_valued_filter("id", "=", payload.session_id),
                 &mut *tx
@@ -91,6 +116,7 @@ async fn call_agent(
 
             let character = session.fetch_character(&mut *tx).await?
                 .ok_or(AppError::new(StatusCode::NOT_FOUND, anyhow!("[call_agent::RoleplayV1] Character not found")))?;
+            tx.commit().await?;
 
             let is_pure_roleplay = character.features.contains(&CharacterFeature::Roleplay);
             let character_creator = character.creator;
@@ -114,62 +140,89 @@ async fn call_agent(
                 false => AgentRouterInput::RoleplayCharacterCreationV1(roleplay_input),
             };
 
-            let response = state.agents_router.route(&user.id, input).await?;
-            let message_id = match response {
-                AgentRouterOutput::RoleplayV1(m, _, _) => m.id,
-                AgentRouterOutput::RoleplayCharacterCreationV1(m, _, _) => m.id,
+            let response = within_budget(model_budget, state.agents_router.route(&user.id, input)).await??;
+            let message = match response {
+                AgentRouterOutput::RoleplayV1(m, _, _) => m,
+                AgentRouterOutput::RoleplayCharacterCreationV1(m, _, _) => m,
                 _ => {
                     return Err(AppError::new(StatusCode::INTERNAL_SERVER_ERROR, anyhow!("[call_agent::RoleplayV1] Unexpected response")));
                 }
             };
+            let message_id = message.id;
 
-            let mut creator = User::find_one_by_criteria(
-                QueryCriteria::new().add_valued_filter("id", "=", character_creator),
-                &mut *tx
-            ).await?
-                .ok_or(AppError::new(StatusCode::NOT_FOUND, anyhow!("[call_agent] Creator not found")))?;
-
-            match payload.call_type {
+            let mut tx = state.db.get_client().begin().await?;
+            let usage = match payload.call_type {
                 RuntimeCallType::RoleplayV1 => {
-                    let log = user.pay_for_chat_message(price, message_id, character_creator, 1)?;
+                    let (mut user, creator) = lock_chat_balances(&user.id, &character_creator, &mut tx).await?;
+                    user.try_pay(price, reserve_floor)?;
+                    let log = user.pay_for_chat_message(price, message_id, character_creator, 1, reserve_floor)?;
                     if log.reward_to.is_some() {
-                        let creator_log = creator.creator_reward(1);
-                        creator_log.create(&mut *tx).await?;
-                        creator.update(&mut *tx).await?;
+                        // Chatting with your own character rewards the row already locked as `user`
+                        match creator {
+                            Some(mut creator) => {
+                                creator.creator_reward(1).create(&mut *tx).await?;
+                                creator.update(&mut *tx).await?;
+                            }
+                            None => {
+                                user.creator_reward(1).create(&mut *tx).await?;
+                            }
+                        }
                     }
-                    log.create(&mut *tx).await?;
+                    let log = log.create(&mut *tx).await?;
                     user.update(&mut *tx).await?;
+                    log.usage()
                 },
                 RuntimeCallType::RoleplayV1Regenerate => {
-                    let log = user.pay_for_chat_message_regenerate(price, message_id)?;
-                    log.create(&mut *tx).await?;
+                    let mut user = User::find_for_update(&user.id, &mut tx).await?
+                        .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, anyhow!("[call_agent] User not found")))?;
+                    user.try_pay(price, reserve_floor)?;
+                    let log = user.pay_for_chat_message_regenerate(price, message_id, reserve_floor)?;
+                    let log = log.create(&mut *tx).await?;
                     user.update(&mut *tx).await?;
+                    log.usage()
                 },
                 _ => unreachable!(),
-            }
+            };
+            tx.commit().await?;
 
             state.memory_update_tx.send(payload.session_id).await?;
+            state.session_broadcast.publish(payload.session_id, message);
 
-            Ok(json!(()))
+            Ok(AppSuccess::new(StatusCode::OK, "agent call success", json!({ "usage": usage })))
         }
-    })().await;
+    }
+}
 
-    match result {
-        Ok(value) => {
-            tx.commit().await?;
-            Ok(AppSuccess::new(StatusCode::OK, "agent call success", value))
-        }
-        Err(e) => {
-            tx.rollback().await?;
-            Err(e)
-        }
+/// Locks the chatting user and, when it is someone else, the character's creator.
+/// Rows are locked in id order so two users chatting with each other's characters
+/// can't deadlock.
+async fn lock_chat_balances(
+    user_id: &Uuid, creator_id: &Uuid, conn: &mut sqlx::PgConnection
+) -> Result<(User, Option<User>), AppError> {
+    let not_found = |id: &Uuid| AppError::new(StatusCode::NOT_FOUND, anyhow!("[lock_chat_balances] User {} not found", id));
+    if creator_id == user_id {
+        let user = User::find_for_update(user_id, conn).await?.ok_or_else(|| not_found(user_id))?;
+        return Ok((user, None));
     }
+
+    let (first_id, second_id) = if user_id < creator_id { (user_id, creator_id) } else { (creator_id, user_id) };
+    let first = User::find_for_update(first_id, conn).await?.ok_or_else(|| not_found(first_id))?;
+    let second = User::find_for_update(second_id, conn).await?.ok_or_else(|| not_found(second_id))?;
+    Ok(if first.id == *user_id { (first, Some(second)) } else { (second, Some(first)) })
 }
 
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CreateSessionQuery {
+    pub system_config: Option<String>,
+    pub system_config_version: Option<i64>,
+    /// `false` starts an ephemeral session that never touches memory.
+    pub persist_memory: Option<bool>,
+}
 async fn create_session(
     State(state): State<GlobalState>,
     Extension(user_id_str): Extension<String>,
     Path(character_id): Path<Uuid>,
+    Query(query): Query<CreateSessionQuery>,
 ) -> Result<AppSuccess, AppError> {
     let user = ensure_account(&state.db, &user_id_str).await?
         .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, anyhow!("[create_session] User not found")))?;
@@ -181,7 +234,19 @@ async fn create_session(
     ).await?
         .ok_or(AppError::new(StatusCode::NOT_FOUND, anyhow!("[create_session] Character not found")))?;
 
-    let session = ChatSession::new(character_id, user.id, character.features.contains(&CharacterFeature::Roleplay));
+    let is_pure_roleplay = character.features.contains(&CharacterFeature::Roleplay);
+    let config_name = query.system_config.unwrap_or_else(|| match is_pure_roleplay {
+        true => RoleplayV1Agent::SYSTEM_CONFIG_NAME.to_string(),
+        false => RoleplayCharacterCreationV1Agent::SYSTEM_CONFIG_NAME.to_string(),
+    });
+
+    let mut session = ChatSession::new_with_config(
+        character_id, user.id, is_pure_roleplay,
+        &config_name, query.system_config_version,
+        &mut *tx
+    ).await
+        .map_err(|e| AppError::new(StatusCode::BAD_REQUEST, e))?;
+    session.persist_memory = query.persist_memory.unwrap_or(true);
     let session = session.create(&mut *tx).await?;
 
     tx.commit().await?;
@@ -191,6 +256,44 @@ async fn create_session(
     })))
 }
 
+/// Streams the session's new messages as server-sent events to its owner and
+/// participants, starting from the moment they connect.
+async fn stream_session(
+    State(state): State<GlobalState>,
+    Extension(user_id_str): Extension<String>,
+    Path(session_id): Path<Uuid>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, AppError> {
+    let user = ensure_account(&state.db, &user_id_str).await?
+        .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, anyhow!("[stream_session] User not found")))?;
+
+    let mut tx = state.db.get_client().begin().await?;
+    let session = ChatSession::find_one_by_criteria(
+        QueryCriteria::new().add_valued_filter("id", "=", session_id),
+        &mut *tx
+    ).await?
+        .ok_or(AppError::new(StatusCode::NOT_FOUND, anyhow!("[stream_session] Session not found")))?;
+    let participants = session.participants(&mut *tx).await?;
+    tx.commit().await?;
+
+    if session.owner != user.id && !participants.iter().any(|p| p.user_id == user.id) {
+        return Err(AppError::new(StatusCode::FORBIDDEN, anyhow!("[stream_session] Not a participant of this session")));
+    }
+
+    let receiver = state.session_broadcast.subscribe(session_id);
+    let events = stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(message) => return Some((Event::default().json_data(&message), receiver)),
+                // A lagging client skips what it missed instead of disconnecting
+                Err(RecvError::Lagged(skipped)) => tracing::warn!("[stream_session] Subscriber lagged by {} messages", skipped),
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DrawCardRequest {  pub draw_type: DrawType }
 async fn draw_card(
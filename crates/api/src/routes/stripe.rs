@@ -155,10 +155,8 @@ async fn stripe_webhook(
                 if user_uuid.is_err() {
                     tracing::error!("[stripe_webhook] Invalid user id: {}", user_id);
                 } else {
-                    let mut user = User::find_one_by_criteria(
-                        QueryCriteria::new().add_valued_filter("id", "=", user_uuid.unwrap()),
-                        &mut *tx
-                    ).await?.ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, anyhow!("[stripe_webhook] User not found")))?;
+                    let mut user = User::find_for_update(&user_uuid.unwrap(), &mut tx)
+                        .await?.ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, anyhow!("[stripe_webhook] User not found")))?;
 
                     let maybe_payment = UserPayment::find_one_by_criteria(
                         QueryCriteria::new().add_valued_filter("checkout_session_id", "=", session_id.clone()),
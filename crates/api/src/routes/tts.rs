@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use anyhow::anyhow;
 use axum::{
     extract::{Extension, Path, State},
@@ -6,14 +8,14 @@ use axum::{
 use serde_json::json;
 use sqlx::types::Uuid;
 
-use metastable_common::ModuleClient;
+use metastable_common::{EnvVars, ModuleClient};
 use metastable_database::{QueryCriteria, SqlxFilterQuery, SqlxCrud};
-use metastable_runtime::{CharacterFeature, Message, MultimodelMessage, MultimodelMessageType, ToolCall};
+use metastable_runtime::{CharacterFeature, Message, MultimodelMessage, MultimodelMessageType, ToolCall, User};
 use metastable_runtime_roleplay::agents::{RoleplayMessageType, SendMessage};
 use metastable_clients::{TTSConfig, AudioFormat};
 
 use crate::{
-    ensure_account, middleware::authenticate, AppSuccess, GlobalState
+    ensure_account, middleware::{authenticate, within_budget}, ApiServerEnv, AppSuccess, GlobalState
 };
 use crate::response::AppError;
 
@@ -30,11 +32,15 @@ async fn tts(
     Extension(user_id_str): Extension<String>,
     Path(message_id): Path<Uuid>
 ) -> Result<impl IntoResponse, AppError> {
-    let mut user = ensure_account(&state.db, &user_id_str).await?
+    let user = ensure_account(&state.db, &user_id_str).await?
         .ok_or(AppError::new(StatusCode::FORBIDDEN, anyhow!("[/tts] user not found")))?;
 
-    user.try_pay(6)?;
+    let env = ApiServerEnv::load();
+    let reserve_floor = Some(env.balance_reserve_floor);
     let mut tx = state.db.get_client().begin().await?;
+    let mut user = User::find_for_update(&user.id, &mut tx).await?
+        .ok_or(AppError::new(StatusCode::FORBIDDEN, anyhow!("[/tts] user not found")))?;
+    user.try_pay(6, reserve_floor)?;
     let message = Message::find_one_by_criteria(
         QueryCriteria::new().add_valued_filter("id", "=", message_id),
         &mut *tx
@@ -81,10 +87,11 @@ async fn tts(
         ..Default::default()
     };
 
-    // Generate TTS and upload to R2
-    let audio_url = state.fish_audio_client
-        .generate_and_upload_to_r2(tts_config, &state.r2_client)
-        .await
+    // Generate TTS and upload to R2; only this call is bounded, so finished audio is always charged
+    let audio_url = within_budget(
+        Duration::from_secs(env.chat_request_timeout_secs),
+        state.fish_audio_client.generate_and_upload_to_r2(tts_config, &state.r2_client)
+    ).await?
         .map_err(|e| AppError::new(StatusCode::INTERNAL_SERVER_ERROR, anyhow!("[/tts] Failed to generate and upload audio: {}", e)))?;
 
     let multimodel_message = MultimodelMessage {
@@ -99,7 +106,7 @@ async fn tts(
     };
     multimodel_message.create(&mut *tx).await?;
     
-    let log = user.pay_for_voice_generation(6, message_id)?;
+    let log = user.pay_for_voice_generation(6, message_id, reserve_floor)?;
     log.create(&mut *tx).await?;
     user.update(&mut *tx).await?;
     tx.commit().await?;
@@ -1,6 +1,8 @@
+use std::collections::HashMap;
+
 use anyhow::anyhow;
 use async_openai::types::FunctionCall;
-use axum::{extract::Path, routing::get};
+use axum::{extract::{Path, Query}, routing::get};
 use metastable_runtime_roleplay::agents::SendMessage;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
@@ -11,17 +13,17 @@ use axum::{
 };
 use sqlx::types::Uuid;
 
-use metastable_common::{get_current_timestamp, ModuleClient};
-use metastable_database::{QueryCriteria, SqlxFilterQuery, SqlxCrud};
+use metastable_common::{get_current_timestamp, EnvVars, ModuleClient};
+use metastable_database::{QueryCriteria, SqlxFilterQuery, SqlxCrud, SqlxUpsert, VersionConflict};
 
 use metastable_runtime::{
-    BackgroundStories, BehaviorTraits, Character, CharacterFeature, CharacterHistory, CharacterLanguage, CharacterOrientation, CharacterPost, CharacterPostComments, CharacterStatus, CharacterSub, Relationships, SkillsAndInterests, ToolCall, User, UserFollow, UserNotification, UserReferral, UserRole, UserUrl
+    estimate_cost, BehaviorTraits, Character, CharacterFeature, CharacterFeatureSet, CharacterHistory, CharacterLanguage, CharacterOrientation, CharacterPost, CharacterPostComments, CharacterRelationship, CharacterStatus, CharacterSub, Message, OrderedBackgroundStory, Relationships, SkillsAndInterests, ToolCall, User, UserFollow, UserNotification, UserPointsLog, UserPointsLogKind, UserReferral, UserRole, UserUrl
 };
 use crate::{
     ensure_account, 
     middleware::authenticate, 
     response::{AppError, AppSuccess},
-    GlobalState
+    ApiServerEnv, GlobalState
 };
 
 pub fn user_routes() -> Router<GlobalState> {
@@ -35,6 +37,11 @@ pub fn user_routes() -> Router<GlobalState> {
             .route_layer(middleware::from_fn(authenticate))
         )
 
+        .route("/user/points/history",
+            get(points_history)
+            .route_layer(middleware::from_fn(authenticate))
+        )
+
         .route("/user/referral/buy",
             post(buy_referral)
             .route_layer(middleware::from_fn(authenticate))
@@ -108,7 +115,9 @@ async fn register(
     }
 
     let mut referral_code = UserReferral::find_one_by_criteria(
-        QueryCriteria::new().add_valued_filter("code", "=", payload.referral_code.clone()),
+        QueryCriteria::new()
+            .add_valued_filter("code", "=", payload.referral_code.clone())
+            .for_update(),
         &mut *tx
     ).await?
         .ok_or(anyhow::anyhow!("[/user/register] Referral code not found"))?;
@@ -116,7 +125,7 @@ async fn register(
     if referral_code.used_by.is_some() {
         return Err(AppError::new(StatusCode::BAD_REQUEST, anyhow!("[/user/register] Referral code already used")));
     }
-    let mut referer = referral_code.fetch_user_id(&mut *tx).await?
+    let mut referer = User::find_for_update(&referral_code.user_id, &mut tx).await?
         .ok_or(anyhow!("[/user/register] Referral code not found not valid"))?;
 
     let mut user = User::default();
@@ -137,9 +146,7 @@ async fn register(
     referer.update(&mut *tx).await?;
     user.update(&mut *tx).await?;
 
-    claimed_log.create(&mut *tx).await?;
-    invitaion_log.create(&mut *tx).await?;
-    invitation_reward_log.create(&mut *tx).await?;
+    UserPointsLog::create_many(vec![claimed_log, invitaion_log, invitation_reward_log], &mut *tx).await?;
 
     tx.commit().await?;
 
@@ -156,10 +163,12 @@ async fn buy_referral(
     Json(payload): Json<BuyReferralRequest>,
 ) -> Result<AppSuccess, AppError> {
     let count = payload.count.unwrap_or(1);
-    let mut user = ensure_account(&state.db, &user_id_str).await?
+    let user = ensure_account(&state.db, &user_id_str).await?
         .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, anyhow!("[buy_referral] User not found")))?;
 
     let mut tx = state.db.get_client().begin().await?;
+    let mut user = User::find_for_update(&user.id, &mut tx).await?
+        .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, anyhow!("[buy_referral] User not found")))?;
 
     let referrals = user.buy_referral_code(count)?;
     for referral in &referrals {
@@ -210,13 +219,13 @@ async fn follow(
     ).await?
         .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, anyhow!("[follow] User not found")))?;
 
-    let maybe_follow = UserFollow::find_one_by_criteria(
+    let already_followed = UserFollow::exists_by_criteria(
         QueryCriteria::new()
             .add_valued_filter("follower_id", "=", follower.id)
             .add_valued_filter("following_id", "=", following.id),
         &mut *tx
     ).await?;
-    if maybe_follow.is_some() {
+    if already_followed {
         return Err(AppError::new(StatusCode::BAD_REQUEST, anyhow!("[follow] Already followed")));
     }
 
@@ -247,15 +256,18 @@ pub struct UpdateCharacterRequest {
     pub prompts_first_message: Option<FunctionCall>,
 
     pub prompts_additional_example_dialogue: Option<Vec<String>>,
-    pub prompts_background_stories: Option<Vec<BackgroundStories>>,
+    pub prompts_background_stories: Option<Vec<OrderedBackgroundStory>>,
     pub prompts_behavior_traits: Option<Vec<BehaviorTraits>>,
     pub prompts_relationships: Option<Vec<Relationships>>,
+    pub related_characters: Option<Vec<CharacterRelationship>>,
     pub prompts_skills_and_interests: Option<Vec<SkillsAndInterests>>,
     pub prompts_additional_info: Option<Vec<String>>,
 
     pub creator_notes: Option<String>,
 
     pub tags: Option<Vec<String>>,
+
+    pub memory_recall_threshold: Option<f32>,
 }
 async fn update_character(
     State(state): State<GlobalState>,
@@ -299,59 +311,39 @@ async fn update_character(
     old_character.prompts_background_stories = sqlx::types::Json(payload.prompts_background_stories.unwrap_or(old_character.prompts_background_stories.0));
     old_character.prompts_behavior_traits = sqlx::types::Json(payload.prompts_behavior_traits.unwrap_or(old_character.prompts_behavior_traits.0));
     old_character.prompts_relationships = sqlx::types::Json(payload.prompts_relationships.unwrap_or(old_character.prompts_relationships.0));
+    old_character.related_characters = sqlx::types::Json(payload.related_characters.unwrap_or(old_character.related_characters.0));
     old_character.prompts_skills_and_interests = sqlx::types::Json(payload.prompts_skills_and_interests.unwrap_or(old_character.prompts_skills_and_interests.0));
     old_character.prompts_additional_info = sqlx::types::Json(payload.prompts_additional_info.unwrap_or(old_character.prompts_additional_info.0));
     old_character.prompts_additional_example_dialogue = sqlx::types::Json(payload.prompts_additional_example_dialogue.unwrap_or(old_character.prompts_additional_example_dialogue.0));
     old_character.creator_notes = payload.creator_notes.or(old_character.creator_notes);
     old_character.tags = payload.tags.unwrap_or(old_character.tags);
+    old_character.memory_recall_threshold = payload.memory_recall_threshold.or(old_character.memory_recall_threshold);
 
     if let Some(voice_model_id) = payload.voice_model_id {
-        let mut found = false;
-        for feature in &mut old_character.features.0 {
-            if let CharacterFeature::Voice(ref mut id) = feature {
-                *id = voice_model_id.clone();
-                found = true;
-                break;
-            }
-        }
-        if !found {
-            old_character.features.push(CharacterFeature::Voice(voice_model_id));
-        }
+        old_character.features.set(CharacterFeature::Voice(voice_model_id));
     }
-
     if let Some(avatar_url) = payload.avatar_url {
-        let mut found = false;
-        for feature in &mut old_character.features.0 {
-            if let CharacterFeature::AvatarImage(ref mut url) = feature {
-                *url = avatar_url.clone();
-                found = true;
-                break;
-            }
-        }
-        if !found {
-            old_character.features.push(CharacterFeature::AvatarImage(avatar_url));
-        }
+        old_character.features.set(CharacterFeature::AvatarImage(avatar_url));
     }
     if let Some(background_url) = payload.background_url {
-        let mut found = false;
-        for feature in &mut old_character.features.0 {
-            if let CharacterFeature::BackgroundImage(ref mut url) = feature {
-                *url = background_url.clone();
-                found = true;
-                break;
-            }
-        }
-        if !found {
-            old_character.features.push(CharacterFeature::BackgroundImage(background_url));
-        }
+        old_character.features.set(CharacterFeature::BackgroundImage(background_url));
     }
 
+    old_character.validate_example_dialogue_size(ApiServerEnv::load().max_example_dialogue_chars)
+        .map_err(|e| AppError::new(StatusCode::BAD_REQUEST, e))?;
+    old_character.validate_recall_threshold()
+        .map_err(|e| AppError::new(StatusCode::BAD_REQUEST, e))?;
+
     if old_character.status != CharacterStatus::Draft {
         old_character.status = CharacterStatus::Reviewing;
     }
 
-    old_character.version += 1;
-    old_character.update(&mut *tx).await?;
+    old_character.update(&mut *tx).await
+        .map_err(|e| if VersionConflict::is(&e) {
+            AppError::new(StatusCode::CONFLICT, anyhow!("[update_character] Character was modified concurrently"))
+        } else {
+            e.into()
+        })?;
     tx.commit().await?;
 
     Ok(AppSuccess::new(StatusCode::OK, "Character updated successfully", json!(())))
@@ -366,15 +358,15 @@ async fn new_character(
     let user = ensure_account(&state.db, &user_id_str).await?
         .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, anyhow!("[new_character] User not found")))?;
 
-    let mut features = vec![CharacterFeature::Roleplay];
+    let mut features = CharacterFeatureSet::from(vec![CharacterFeature::Roleplay]);
     if let Some(avatar_url) = payload.avatar_url {
-        features.push(CharacterFeature::AvatarImage(avatar_url));
+        features.set(CharacterFeature::AvatarImage(avatar_url));
     }
     if let Some(background_url) = payload.background_url {
-        features.push(CharacterFeature::BackgroundImage(background_url));
+        features.set(CharacterFeature::BackgroundImage(background_url));
     }
     if let Some(voice_model_id) = payload.voice_model_id {
-        features.push(CharacterFeature::Voice(voice_model_id));
+        features.set(CharacterFeature::Voice(voice_model_id));
     }
 
     let first_message = {
@@ -400,18 +392,26 @@ async fn new_character(
         prompts_personality: payload.prompts_personality.unwrap_or("Unknown".to_string()),
         prompts_example_dialogue: payload.prompts_example_dialogue.unwrap_or("Unknown".to_string()),
         prompts_first_message: sqlx::types::Json(Some(first_message)),
-        prompts_background_stories: sqlx::types::Json(payload.prompts_background_stories.unwrap_or(vec![BackgroundStories::default()])),
+        prompts_background_stories: sqlx::types::Json(payload.prompts_background_stories.unwrap_or(vec![OrderedBackgroundStory::default()])),
         prompts_behavior_traits: sqlx::types::Json(payload.prompts_behavior_traits.unwrap_or(vec![BehaviorTraits::default()])),
         prompts_additional_example_dialogue: sqlx::types::Json(payload.prompts_additional_example_dialogue.unwrap_or(vec![String::default()])),
         prompts_relationships: sqlx::types::Json(payload.prompts_relationships.unwrap_or(vec![Relationships::default()])),
+        related_characters: sqlx::types::Json(payload.related_characters.unwrap_or_default()),
         prompts_skills_and_interests: sqlx::types::Json(payload.prompts_skills_and_interests.unwrap_or(vec![SkillsAndInterests::default()])),
         prompts_additional_info: sqlx::types::Json(payload.prompts_additional_info.unwrap_or(vec![String::default()])),
         creator_notes: payload.creator_notes,
         tags: payload.tags.unwrap_or(vec![]),
+        memory_recall_threshold: payload.memory_recall_threshold,
+        deleted_at: None,
         created_at: get_current_timestamp(),
         updated_at: get_current_timestamp(),
     };
 
+    character.validate_example_dialogue_size(ApiServerEnv::load().max_example_dialogue_chars)
+        .map_err(|e| AppError::new(StatusCode::BAD_REQUEST, e))?;
+    character.validate_recall_threshold()
+        .map_err(|e| AppError::new(StatusCode::BAD_REQUEST, e))?;
+
     let mut tx = state.db.get_client().begin().await?;
     let _ = character.create(&mut *tx).await?;
     tx.commit().await?;
@@ -429,20 +429,14 @@ async fn create_character_sub(
         .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, anyhow!("[create_character_sub] User not found")))?;
 
     let mut tx = state.db.get_client().begin().await?;
-    let maybe_sub = CharacterSub::find_one_by_criteria(
-        QueryCriteria::new()
-            .add_valued_filter("user", "=", user.id)
-            .add_valued_filter("character", "=", character_id),
-        &mut *tx
-    ).await?;
-    if maybe_sub.is_some() {
+    // A concurrent request for the same pair inserts nothing instead of failing the unique index
+    let character_sub = CharacterSub::new(user.id, character_id, vec![]);
+    if character_sub.create_if_absent(&mut *tx).await?.is_none() {
         return Err(AppError::new(StatusCode::BAD_REQUEST, anyhow!("[create_character_sub] Already subscribed")));
     }
 
-    let character_sub = CharacterSub::new(user.id, character_id, vec![]);
     let notify = UserNotification::new_character_favorite(user.id, character_id);
     notify.create(&mut *tx).await?;
-    character_sub.create(&mut *tx).await?;
     tx.commit().await?;
 
     Ok(AppSuccess::new(StatusCode::OK, "Character sub created successfully", json!(())))
@@ -453,10 +447,12 @@ async fn daily_checkin(
     State(state): State<GlobalState>,
     Extension(user_id_str): Extension<String>,
 ) -> Result<AppSuccess, AppError> {
-    let mut user = ensure_account(&state.db, &user_id_str).await?
+    let user = ensure_account(&state.db, &user_id_str).await?
         .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, anyhow!("[daily_checkin] User not found")))?;
 
     let mut tx = state.db.get_client().begin().await?;
+    let mut user = User::find_for_update(&user.id, &mut tx).await?
+        .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, anyhow!("[daily_checkin] User not found")))?;
     let checkin_log = user.daily_checkin()?;  // 100 points per checkin    
     checkin_log.create(&mut *tx).await?;
     user.update(&mut *tx).await?;
@@ -579,7 +575,12 @@ async fn create_character_review(
         UserNotification::character_review_outcome_rejected(character.id.clone(), character_id, payload.comments)
     };
     notify.create(&mut *tx).await?;
-    character.update(&mut *tx).await?;
+    character.update(&mut *tx).await
+        .map_err(|e| if VersionConflict::is(&e) {
+            AppError::new(StatusCode::CONFLICT, anyhow!("[create_character_review] Character was modified concurrently"))
+        } else {
+            e.into()
+        })?;
     tx.commit().await?;
 
     Ok(AppSuccess::new(StatusCode::OK, "Character review created successfully", json!(())))
@@ -605,4 +606,70 @@ async fn get_character_detail(
     }
 
     Ok(AppSuccess::new(StatusCode::OK, "Character detail fetched successfully", json!(character)))
-}
\ No newline at end of file
+}
+
+const POINTS_HISTORY_PAGE_SIZE: i64 = 20;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PointsHistoryQuery {
+    pub kind: Option<UserPointsLogKind>,
+    /// `{created_at}_{id}` of the last entry on the previous page
+    pub cursor: Option<String>,
+}
+
+async fn points_history(
+    State(state): State<GlobalState>,
+    Extension(user_id_str): Extension<String>,
+    Query(query): Query<PointsHistoryQuery>,
+) -> Result<AppSuccess, AppError> {
+    let user = ensure_account(&state.db, &user_id_str).await?
+        .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, anyhow!("[points_history] User not found")))?;
+
+    let cursor = query.cursor
+        .map(|cursor| {
+            cursor.split_once('_')
+                .and_then(|(created_at, id)| Some((created_at.parse::<i64>().ok()?, id.parse::<Uuid>().ok()?)))
+                .ok_or_else(|| AppError::new(StatusCode::BAD_REQUEST, anyhow!("[points_history] Invalid cursor")))
+        })
+        .transpose()?;
+
+    let mut tx = state.db.get_client().begin().await?;
+    let logs = UserPointsLog::history(
+        user.id, query.kind, cursor, POINTS_HISTORY_PAGE_SIZE, &mut *tx
+    ).await?;
+
+    let message_ids = logs.iter().filter_map(|log| log.message).collect::<Vec<_>>();
+    let costs = if message_ids.is_empty() {
+        HashMap::new()
+    } else {
+        Message::find_by_criteria(
+            QueryCriteria::new().add_in_filter("id", message_ids),
+            &mut *tx
+        ).await?
+            .into_iter()
+            .filter_map(|message| {
+                let cost = estimate_cost(message.usage.0.as_ref()?, &message.model_name)?;
+                Some((message.id, cost))
+            })
+            .collect::<HashMap<_, _>>()
+    };
+
+    let next_cursor = if logs.len() as i64 == POINTS_HISTORY_PAGE_SIZE {
+        logs.last().map(|log| format!("{}_{}", log.created_at, log.id))
+    } else {
+        None
+    };
+
+    // Unpriced models and logs without a message carry a null cost
+    let logs = logs.into_iter().map(|log| {
+        let estimated_cost = log.message.and_then(|id| costs.get(&id).copied());
+        let mut entry = json!(log);
+        entry["estimated_cost"] = json!(estimated_cost);
+        entry
+    }).collect::<Vec<_>>();
+
+    Ok(AppSuccess::new(StatusCode::OK, "Points history fetched successfully", json!({
+        "logs": logs,
+        "next_cursor": next_cursor,
+    })))
+}
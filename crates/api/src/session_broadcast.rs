@@ -0,0 +1,93 @@
+use std::{collections::HashMap, ops::{Deref, DerefMut}, sync::{Arc, Mutex}};
+
+use metastable_runtime::Message;
+use sqlx::types::Uuid;
+use tokio::sync::broadcast;
+
+/// Messages a slow subscriber may fall behind by before it starts missing them.
+pub const DEFAULT_SESSION_BROADCAST_CAPACITY: usize = 64;
+
+type Channels = Arc<Mutex<HashMap<Uuid, broadcast::Sender<Message>>>>;
+
+/// Per-session fan-out of new messages to everyone streaming a multiplayer
+/// session. Channels are created on first subscribe and dropped with their
+/// last `SessionSubscription`.
+#[derive(Clone)]
+pub struct SessionBroadcast {
+    channels: Channels,
+    capacity: usize,
+}
+
+/// A receiver of one session's messages. Dereferences to the underlying
+/// `broadcast::Receiver`; dropping the last one removes the session's channel.
+pub struct SessionSubscription {
+    session_id: Uuid,
+    channels: Channels,
+    /// Only `None` while dropping, so the count seen under the lock excludes it.
+    receiver: Option<broadcast::Receiver<Message>>,
+}
+
+impl Deref for SessionSubscription {
+    type Target = broadcast::Receiver<Message>;
+
+    fn deref(&self) -> &Self::Target {
+        self.receiver.as_ref().expect("receiver is only taken on drop")
+    }
+}
+
+impl DerefMut for SessionSubscription {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.receiver.as_mut().expect("receiver is only taken on drop")
+    }
+}
+
+impl Drop for SessionSubscription {
+    fn drop(&mut self) {
+        drop(self.receiver.take());
+        let mut channels = self.channels.lock().expect("session broadcast lock poisoned");
+        // Subscribing also takes the lock, so no receiver can appear while we check
+        if channels.get(&self.session_id).is_some_and(|sender| sender.receiver_count() == 0) {
+            channels.remove(&self.session_id);
+        }
+    }
+}
+
+impl Default for SessionBroadcast {
+    fn default() -> Self {
+        Self::new(DEFAULT_SESSION_BROADCAST_CAPACITY)
+    }
+}
+
+impl SessionBroadcast {
+    pub fn new(capacity: usize) -> Self {
+        Self { channels: Arc::new(Mutex::new(HashMap::new())), capacity: capacity.max(1) }
+    }
+
+    /// Receives every message published to `session_id` from now on.
+    pub fn subscribe(&self, session_id: Uuid) -> SessionSubscription {
+        let mut channels = self.channels.lock().expect("session broadcast lock poisoned");
+        let receiver = channels.entry(session_id)
+            .or_insert_with(|| broadcast::channel(self.capacity).0)
+            .subscribe();
+        SessionSubscription { session_id, channels: self.channels.clone(), receiver: Some(receiver) }
+    }
+
+    /// Sends `message` to the current subscribers of `session_id` and returns
+    /// how many there were.
+    pub fn publish(&self, session_id: Uuid, message: Message) -> usize {
+        let channels = self.channels.lock().expect("session broadcast lock poisoned");
+        channels.get(&session_id)
+            .and_then(|sender| sender.send(message).ok())
+            .unwrap_or(0)
+    }
+
+    pub fn subscriber_count(&self, session_id: Uuid) -> usize {
+        let channels = self.channels.lock().expect("session broadcast lock poisoned");
+        channels.get(&session_id).map_or(0, |sender| sender.receiver_count())
+    }
+
+    /// Sessions that currently have a channel.
+    pub fn session_count(&self) -> usize {
+        self.channels.lock().expect("session broadcast lock poisoned").len()
+    }
+}
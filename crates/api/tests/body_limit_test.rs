@@ -0,0 +1,53 @@
+use axum::{routing::post, Json, Router};
+use metastable_service_api::{with_body_limit, PAYLOAD_TOO_LARGE_CODE};
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+const LIMIT: usize = 1024;
+
+async fn serve() -> String {
+    let app = with_body_limit(
+        Router::new().route("/echo", post(|Json(body): Json<Value>| async move { Json(body) })),
+        LIMIT,
+    );
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    addr
+}
+
+fn payload(len: usize) -> String {
+    json!({ "content": "a".repeat(len) }).to_string()
+}
+
+#[tokio::test]
+async fn test_oversized_bodies_are_rejected() {
+    let addr = serve().await;
+    let client = reqwest::Client::new();
+    let post = |body: String| client.post(format!("http://{}/echo", addr))
+        .header("content-type", "application/json")
+        .body(body)
+        .send();
+
+    let body: Value = post(payload(100)).await.unwrap().json().await.unwrap();
+    assert_eq!(body["content"].as_str().unwrap().len(), 100);
+
+    // Errors travel as a `GenericResponse` body, like every other `AppError`
+    let body: Value = post(payload(LIMIT)).await.unwrap().json().await.unwrap();
+    assert_eq!(body["status"], 413);
+    assert_eq!(body["data"]["code"], PAYLOAD_TOO_LARGE_CODE);
+
+    // Without a Content-Length the body is counted as it is read
+    let body = payload(LIMIT);
+    let mut stream = TcpStream::connect(&addr).await.unwrap();
+    let request = format!(
+        "POST /echo HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n{:x}\r\n{}\r\n0\r\n\r\n",
+        addr, body.len(), body
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.contains("\"status\":413"), "{}", response);
+    assert!(response.contains(PAYLOAD_TOO_LARGE_CODE), "{}", response);
+}
@@ -0,0 +1,86 @@
+use axum::response::IntoResponse;
+use axum::http::StatusCode;
+use metastable_clients::{BatchUpdateSummary, PostgresClient};
+use metastable_common::ModuleClient;
+use metastable_database::SqlxCrud;
+use metastable_runtime::{Character, ChatSession, Message, MessageType, User};
+use metastable_runtime_roleplay::MemoryUpdater;
+use metastable_service_api::{AppSuccess, ReprocessMemoryResponse};
+use serde_json::{json, Value};
+use sqlx::types::{Json, Uuid};
+
+async fn response_json(response: ReprocessMemoryResponse) -> Value {
+    let body = AppSuccess::new(StatusCode::OK, "Session memory reprocessed", json!(response)).into_response().into_body();
+    let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+fn session_message(owner: Uuid, session: Uuid, summary: &str) -> Message {
+    Message {
+        id: Uuid::new_v4(),
+        owner,
+        system_config: Uuid::new_v4(),
+        session: Some(session),
+        turn_index: None,
+        user_message_content: summary.to_string(),
+        user_message_content_type: MessageType::Text,
+        input_toolcall: Json(None),
+        assistant_message_content: "ok".to_string(),
+        assistant_message_content_type: MessageType::Text,
+        assistant_message_tool_call: Json(None),
+        summary: Some(summary.to_string()),
+        model_name: "test".to_string(),
+        usage: Json(None),
+        finish_reason: None,
+        refusal: None,
+        is_stale: false,
+        is_memorizeable: true,
+        is_in_memory: true,
+        is_migrated: false,
+        migration_claimed_at: None,
+        deleted_at: None,
+        created_at: 0,
+        updated_at: 0,
+    }
+}
+
+#[tokio::test]
+async fn test_summary_fields_are_in_response() {
+    let session_id = Uuid::new_v4();
+    let body = response_json(ReprocessMemoryResponse {
+        session_id,
+        summary: Some(BatchUpdateSummary { added: 2, updated: 1, deleted: 0 }),
+    }).await;
+
+    assert_eq!(body["data"]["session_id"], json!(session_id));
+    assert_eq!(body["data"]["summary"], json!({ "added": 2, "updated": 1, "deleted": 0 }));
+
+    let skipped = response_json(ReprocessMemoryResponse { session_id, summary: None }).await;
+    assert_eq!(skipped["data"]["summary"], Value::Null);
+}
+
+#[tokio::test]
+async fn test_reprocess_session_reports_summary() {
+    let db = PostgresClient::setup_connection().await;
+    let mut tx = db.get_client().begin().await.unwrap();
+
+    let user = User { user_id: format!("reprocess_{}", Uuid::new_v4()), user_aka: "Bob".to_string(), ..Default::default() }
+        .create(&mut *tx).await.unwrap();
+    let character = Character { name: "Alice".to_string(), creator: user.id, ..Default::default() }
+        .create(&mut *tx).await.unwrap();
+    let session = ChatSession::new(character.id, user.id, false).create(&mut *tx).await.unwrap();
+    // The six newest messages are left alone; the rest are reprocessed
+    for i in 0..14 {
+        session_message(user.id, session.id, &format!("Bob mentioned he has {} cats and loves green tea", i + 1))
+            .create(&mut *tx).await.unwrap();
+    }
+    tx.commit().await.unwrap();
+
+    let summary = MemoryUpdater::new().await.unwrap().update_memory(&session.id).await.unwrap();
+    let body = response_json(ReprocessMemoryResponse { session_id: session.id, summary }).await;
+
+    let summary = &body["data"]["summary"];
+    for field in ["added", "updated", "deleted"] {
+        assert!(summary[field].is_u64(), "missing `{}` in {}", field, body);
+    }
+}
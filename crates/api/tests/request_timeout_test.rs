@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use axum::{http::StatusCode, routing::get, Router};
+use metastable_service_api::{with_timeout, within_budget, REQUEST_TIMEOUT_CODE};
+use serde_json::Value;
+use tokio::net::TcpListener;
+
+const BUDGET: Duration = Duration::from_millis(200);
+
+async fn serve() -> String {
+    let app = with_timeout(
+        Router::new()
+            .route("/fast", get(|| async { "done" }))
+            .route("/slow", get(|| async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                "too late"
+            })),
+        BUDGET,
+    );
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    addr
+}
+
+#[tokio::test]
+async fn test_slow_handlers_time_out() {
+    let addr = serve().await;
+    let client = reqwest::Client::new();
+
+    let response = client.get(format!("http://{}/fast", addr)).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.text().await.unwrap(), "done");
+
+    let started = std::time::Instant::now();
+    let response = client.get(format!("http://{}/slow", addr)).send().await.unwrap();
+    assert!(started.elapsed() < Duration::from_secs(2));
+
+    // Errors travel as a `GenericResponse` body, like every other `AppError`
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["status"], 504);
+    assert_eq!(body["data"]["code"], REQUEST_TIMEOUT_CODE);
+}
+
+#[tokio::test]
+async fn test_within_budget_bounds_only_the_wrapped_call() {
+    assert_eq!(within_budget(BUDGET, async { "done" }).await.unwrap(), "done");
+
+    let err = within_budget(BUDGET, tokio::time::sleep(Duration::from_secs(5))).await.unwrap_err();
+    assert_eq!(err.0, StatusCode::GATEWAY_TIMEOUT);
+    assert_eq!(err.2, Some(REQUEST_TIMEOUT_CODE));
+}
@@ -0,0 +1,104 @@
+use metastable_runtime::{Message, MessageType};
+use metastable_service_api::SessionBroadcast;
+use sqlx::types::{Json, Uuid};
+use tokio::sync::broadcast::error::TryRecvError;
+
+fn session_message(owner: Uuid, session: Uuid, content: &str) -> Message {
+    Message {
+        id: Uuid::new_v4(),
+        owner,
+        system_config: Uuid::new_v4(),
+        session: Some(session),
+        turn_index: None,
+        user_message_content: content.to_string(),
+        user_message_content_type: MessageType::Text,
+        input_toolcall: Json(None),
+        assistant_message_content: "ok".to_string(),
+        assistant_message_content_type: MessageType::Text,
+        assistant_message_tool_call: Json(None),
+        summary: None,
+        model_name: "test".to_string(),
+        usage: Json(None),
+        finish_reason: None,
+        refusal: None,
+        is_stale: false,
+        is_memorizeable: false,
+        is_in_memory: false,
+        is_migrated: false,
+        migration_claimed_at: None,
+        deleted_at: None,
+        created_at: 0,
+        updated_at: 0,
+    }
+}
+
+#[tokio::test]
+async fn test_every_subscriber_receives_new_messages() {
+    let broadcast = SessionBroadcast::default();
+    let session = Uuid::new_v4();
+    let other_session = Uuid::new_v4();
+
+    let mut alice = broadcast.subscribe(session);
+    let mut bob = broadcast.subscribe(session);
+    let mut elsewhere = broadcast.subscribe(other_session);
+    assert_eq!(broadcast.subscriber_count(session), 2);
+
+    let first = session_message(Uuid::new_v4(), session, "hello everyone");
+    assert_eq!(broadcast.publish(session, first.clone()), 2);
+    assert_eq!(alice.recv().await.unwrap().id, first.id);
+    assert_eq!(bob.recv().await.unwrap().id, first.id);
+    assert!(matches!(elsewhere.try_recv(), Err(TryRecvError::Empty)));
+
+    // A late subscriber only sees what is published after it joined
+    let mut carol = broadcast.subscribe(session);
+    assert!(matches!(carol.try_recv(), Err(TryRecvError::Empty)));
+
+    let second = session_message(Uuid::new_v4(), session, "welcome carol");
+    assert_eq!(broadcast.publish(session, second.clone()), 3);
+    for receiver in [&mut alice, &mut bob, &mut carol] {
+        assert_eq!(receiver.recv().await.unwrap().id, second.id);
+    }
+}
+
+#[tokio::test]
+async fn test_last_subscriber_drop_removes_the_channel() {
+    let broadcast = SessionBroadcast::default();
+    let session = Uuid::new_v4();
+
+    assert_eq!(broadcast.publish(session, session_message(Uuid::new_v4(), session, "nobody")), 0);
+    assert_eq!(broadcast.session_count(), 0);
+
+    let alice = broadcast.subscribe(session);
+    let bob = broadcast.subscribe(session);
+    assert_eq!(broadcast.session_count(), 1);
+
+    // The channel outlives all but the last subscriber, without any publish
+    drop(alice);
+    assert_eq!(broadcast.session_count(), 1);
+    assert_eq!(broadcast.subscriber_count(session), 1);
+    drop(bob);
+    assert_eq!(broadcast.session_count(), 0);
+    assert_eq!(broadcast.subscriber_count(session), 0);
+    assert_eq!(broadcast.publish(session, session_message(Uuid::new_v4(), session, "gone")), 0);
+}
+
+#[test]
+fn test_concurrent_drops_remove_the_channel() {
+    let broadcast = SessionBroadcast::default();
+    for _ in 0..200 {
+        let session = Uuid::new_v4();
+        let subscriptions = (0..4).map(|_| broadcast.subscribe(session)).collect::<Vec<_>>();
+        let barrier = std::sync::Arc::new(std::sync::Barrier::new(subscriptions.len()));
+        let handles = subscriptions.into_iter().map(|subscription| {
+            let barrier = barrier.clone();
+            std::thread::spawn(move || {
+                barrier.wait();
+                drop(subscription);
+            })
+        }).collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(broadcast.session_count(), 0);
+    }
+}
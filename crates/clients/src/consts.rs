@@ -1,7 +1,20 @@
 pub type Embedding = Vec<f32>;
 pub const EMBEDDING_DIMS: i32 = 1024;
 pub const EMBEDDING_MODEL: &str = "Qwen/Qwen3-Embedding-0.6B";
+pub const DEFAULT_EMBEDDING_CHUNK_SIZE: usize = 64;
+pub const DEFAULT_EMBEDDING_MAX_CONCURRENCY: usize = 4;
 
 pub const DEFAULT_GRAPH_DB_TEXT_SEARCH_THRESHOLD: f32 = 0.7;
 pub const DEFAULT_GRAPH_DB_SEARCH_LIMIT: usize = 100;
+pub const DEFAULT_GRAPH_DB_QUERY_TIMEOUT_MS: u64 = 30_000;
+pub const DEFAULT_GRAPH_DB_MAX_RELATIONSHIPS: usize = 20;
+pub const DEFAULT_GRAPH_DB_COMMIT_BATCH_SIZE: usize = 100;
 pub const DEFAULT_GRAPH_DB_VECTOR_SEARCH_THRESHOLD: f32 = 0.9;
+pub const DEFAULT_GRAPH_DB_VECTOR_INDEX: &str = "memzero";
+pub const DEFAULT_GRAPH_DB_RECENCY_HALF_LIFE_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+pub const MAX_GRAPH_DB_TRAVERSAL_DEPTH: u8 = 3;
+
+pub const DEFAULT_MIN_EXTRACTION_CHARS: usize = 6;
+/// CJK characters carry about a word each, so far fewer are needed.
+pub const DEFAULT_MIN_EXTRACTION_CJK_CHARS: usize = 3;
+pub const DEFAULT_MIN_EXTRACTION_TOKENS: usize = 1;
@@ -1,6 +1,8 @@
-use std::env;
+use std::{collections::HashMap, env, future::Future, sync::{Arc, OnceLock, RwLock}};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures::{stream, StreamExt, TryStreamExt};
 use metastable_common::{define_module_client, ModuleClient};
 
 use async_openai::{
@@ -10,48 +12,202 @@ use async_openai::{
 };
 
 use crate::{
-    Embedding, EMBEDDING_MODEL
+    Embedding, EMBEDDING_DIMS, EMBEDDING_MODEL
 };
 
-define_module_client! {
-    (struct EmbederClient, "embeder")
-    client_type: Client<OpenAIConfig>,
-    env: ["EMBEDDING_BASE_URL", "EMBEDDING_API_KEY"],
-    setup: async {
-        let base_url = env::var("EMBEDDING_BASE_URL").expect("EMBEDDING_BASE_URL is not set");
-        let api_key = env::var("EMBEDDING_API_KEY").expect("EMBEDDING_API_KEY is not set");
-        let embeder_config = OpenAIConfig::new()
+/// Name of the built-in provider for OpenAI-compatible embedding APIs.
+pub const OPENAI_EMBEDDING_PROVIDER: &str = "openai";
+
+/// Turns texts into embeddings for `EmbederClient`. Output order matches the input.
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    async fn embed(&self, text: Vec<String>) -> Result<Vec<Embedding>>;
+}
+
+/// Talks to any OpenAI-compatible `/embeddings` endpoint.
+pub struct OpenAiEmbeddingProvider {
+    client: Client<OpenAIConfig>,
+}
+
+impl OpenAiEmbeddingProvider {
+    pub fn new(base_url: &str, api_key: &str) -> Self {
+        let config = OpenAIConfig::new()
             .with_api_base(base_url)
             .with_api_key(api_key);
+        Self { client: Client::build(reqwest::Client::new(), config, Default::default()) }
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OpenAiEmbeddingProvider {
+    async fn embed(&self, text: Vec<String>) -> Result<Vec<Embedding>> {
+        let request = CreateEmbeddingRequestArgs::default()
+            .model(EMBEDDING_MODEL)
+            .input(text)
+            .build()?;
+
+        let response = self.client.embeddings().create(request).await?;
+        Ok(response.data.into_iter().map(|item| item.embedding).collect())
+    }
+}
+
+/// What `EmbederClient::embed` does when the provider fails on a batch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmbedFallback {
+    /// Return the error.
+    #[default]
+    Fail,
+    /// Retry each input alone and use a zero vector for those that still fail,
+    /// keeping the output aligned to the inputs. Still an error when every
+    /// input fails, since then the provider is down rather than flaky.
+    ZeroVector,
+}
+
+impl EmbedFallback {
+    /// `ZeroVector` when `EMBEDDING_ZERO_FALLBACK` is `true` or `1`, `Fail` otherwise.
+    pub fn from_env() -> Self {
+        match env::var("EMBEDDING_ZERO_FALLBACK").as_deref().map(str::trim) {
+            Ok("true") | Ok("1") => Self::ZeroVector,
+            _ => Self::Fail,
+        }
+    }
+}
+
+fn embedding_providers() -> &'static RwLock<HashMap<String, Arc<dyn EmbeddingProvider>>> {
+    static PROVIDERS: OnceLock<RwLock<HashMap<String, Arc<dyn EmbeddingProvider>>>> = OnceLock::new();
+    PROVIDERS.get_or_init(Default::default)
+}
+
+/// Makes `provider` selectable with `EMBEDDING_PROVIDER=<name>`. Register it
+/// before `EmbederClient::setup_connection`.
+pub fn register_embedding_provider(name: &str, provider: Arc<dyn EmbeddingProvider>) {
+    embedding_providers().write().expect("embedding provider registry poisoned")
+        .insert(name.to_string(), provider);
+}
+
+/// The provider named by `EMBEDDING_PROVIDER`, defaulting to the OpenAI-compatible
+/// one configured by `EMBEDDING_BASE_URL` and `EMBEDDING_API_KEY`.
+fn provider_from_env() -> Result<Arc<dyn EmbeddingProvider>> {
+    let name = env::var("EMBEDDING_PROVIDER").unwrap_or_else(|_| OPENAI_EMBEDDING_PROVIDER.to_string());
+    if let Some(provider) = embedding_providers().read().expect("embedding provider registry poisoned").get(&name) {
+        return Ok(provider.clone());
+    }
+    if name != OPENAI_EMBEDDING_PROVIDER {
+        return Err(anyhow!("[EmbederClient] Unknown embedding provider {}", name));
+    }
 
-        Client::build(
-            reqwest::Client::new(),
-            embeder_config,
-            Default::default()
-        )
+    let base_url = env::var("EMBEDDING_BASE_URL").map_err(|_| anyhow!("EMBEDDING_BASE_URL is not set"))?;
+    let api_key = env::var("EMBEDDING_API_KEY").map_err(|_| anyhow!("EMBEDDING_API_KEY is not set"))?;
+    Ok(Arc::new(OpenAiEmbeddingProvider::new(&base_url, &api_key)))
+}
+
+define_module_client! {
+    (struct EmbederClient, "embeder")
+    client_type: Arc<dyn EmbeddingProvider>,
+    env: [],
+    setup: async {
+        provider_from_env().expect("[EmbederClient::setup] Failed to select an embedding provider")
     }
 }
 
 impl EmbederClient {
+    /// A client embedding through `provider`, bypassing the env selection.
+    pub fn with_provider(provider: Arc<dyn EmbeddingProvider>) -> Self {
+        Self { client: Some(Arc::new(provider)) }
+    }
+
+    /// Embeds `text`, failing on empty inputs. What happens when the provider
+    /// fails follows `EmbedFallback::from_env`.
     pub async fn embed(&self, text: Vec<String>) -> Result<Vec<Embedding>> {
+        self.embed_with_fallback(text, EmbedFallback::from_env()).await
+    }
+
+    pub async fn embed_with_fallback(&self, text: Vec<String>, fallback: EmbedFallback) -> Result<Vec<Embedding>> {
         tracing::debug!("[EmbederClient::embed] Embedding text: {:?}", text);
         if text.is_empty() {
             return Ok(vec![]);
         }
+        if let Some(i) = text.iter().position(|t| t.trim().is_empty()) {
+            return Err(anyhow!("[EmbederClient::embed] Input {} is empty", i));
+        }
 
-        let request = CreateEmbeddingRequestArgs::default()
-            .model(EMBEDDING_MODEL)
-            .input(text)
-            .build()?;
-
-        let response = self.get_client().embeddings().create(request).await?;
-        let embeddings = response.data
-            .into_iter()
-            .map(|item| item.embedding)
-            .collect::<Vec<_>>();
-
+        let embeddings = match self.get_client().embed(text.clone()).await {
+            Ok(embeddings) if embeddings.len() == text.len() => embeddings,
+            result if fallback == EmbedFallback::Fail => {
+                let embeddings = result?;
+                return Err(anyhow!(
+                    "[EmbederClient::embed] Provider returned {} embeddings for {} inputs", embeddings.len(), text.len()
+                ));
+            }
+            _ => self.embed_each_or_zero(text).await?,
+        };
         tracing::debug!("[EmbedderClient::embed] Embedding response: {}", embeddings.len());
 
         Ok(embeddings)
     }
-}   
\ No newline at end of file
+
+    /// Embeds inputs one by one after a failed batch, standing in a zero vector
+    /// for each input that still fails. Fails with the last error when none succeed.
+    async fn embed_each_or_zero(&self, text: Vec<String>) -> Result<Vec<Embedding>> {
+        let total = text.len();
+        let mut embeddings = Vec::with_capacity(total);
+        let mut last_error = None;
+        for (i, t) in text.into_iter().enumerate() {
+            match self.get_client().embed(vec![t]).await {
+                Ok(mut e) if e.len() == 1 => embeddings.push(e.pop()),
+                Ok(e) => {
+                    tracing::warn!("[EmbederClient::embed] Input {} got {} embeddings, using a zero vector", i, e.len());
+                    last_error = Some(anyhow!("[EmbederClient::embed] Input {} got {} embeddings", i, e.len()));
+                    embeddings.push(None);
+                }
+                Err(e) => {
+                    tracing::warn!("[EmbederClient::embed] Input {} failed, using a zero vector: {}", i, e);
+                    last_error = Some(e);
+                    embeddings.push(None);
+                }
+            }
+        }
+
+        let zeroed = embeddings.iter().filter(|e| e.is_none()).count();
+        if zeroed == total {
+            let err = last_error.unwrap_or_else(|| anyhow!("[EmbederClient::embed] No embeddings returned"));
+            return Err(err.context(format!("[EmbederClient::embed] All {} inputs failed, not using zero vectors", total)));
+        }
+        if zeroed > 0 {
+            tracing::warn!("[EmbederClient::embed] Using zero vectors for {} of {} inputs", zeroed, total);
+        }
+
+        let dims = embeddings.iter().flatten().next().map_or(EMBEDDING_DIMS as usize, |e| e.len());
+        Ok(embeddings.into_iter().map(|e| e.unwrap_or_else(|| vec![0.0; dims])).collect())
+    }
+}   
+
+impl EmbederClient {
+    /// Splits large inputs into chunks of `chunk_size` and embeds them with at most
+    /// `max_concurrency` requests in flight. Output order matches `text`.
+    pub async fn embed_chunked(&self, text: Vec<String>, chunk_size: usize, max_concurrency: usize) -> Result<Vec<Embedding>> {
+        embed_in_chunks(text, chunk_size, max_concurrency, |chunk| self.embed(chunk)).await
+    }
+}
+
+/// The chunking behind [`EmbederClient::embed_chunked`], generic over the embed call.
+pub async fn embed_in_chunks<F, Fut>(
+    text: Vec<String>, chunk_size: usize, max_concurrency: usize, embed: F
+) -> Result<Vec<Embedding>>
+where
+    F: Fn(Vec<String>) -> Fut,
+    Fut: Future<Output = Result<Vec<Embedding>>>,
+{
+    let chunks: Vec<Vec<String>> = text
+        .chunks(chunk_size.max(1))
+        .map(|chunk| chunk.to_vec())
+        .collect();
+
+    let embedded: Vec<Vec<Embedding>> = stream::iter(chunks)
+        .map(embed)
+        .buffered(max_concurrency.max(1))
+        .try_collect()
+        .await?;
+
+    Ok(embedded.into_iter().flatten().collect())
+}
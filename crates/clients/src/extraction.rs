@@ -0,0 +1,89 @@
+use std::env;
+use std::future::Future;
+
+use anyhow::Result;
+
+use crate::{DEFAULT_MIN_EXTRACTION_CHARS, DEFAULT_MIN_EXTRACTION_CJK_CHARS, DEFAULT_MIN_EXTRACTION_TOKENS};
+
+/// Minimum size a message must have before fact/entity extraction spends an
+/// LLM call on it, so chatter like "ok" or "lol" is skipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtractionGate {
+    /// Non-whitespace characters required of alphabetic scripts.
+    pub min_chars: usize,
+    /// CJK characters required of Chinese, Japanese or Korean text. Mixed text
+    /// passes once both kinds together reach their thresholds proportionally.
+    pub min_cjk_chars: usize,
+    /// Tokens required: whitespace-separated words, each CJK character counting
+    /// as its own token.
+    pub min_tokens: usize,
+}
+
+/// Han, kana and Hangul syllables.
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{AC00}'..='\u{D7AF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{2FA1F}'
+    )
+}
+
+impl Default for ExtractionGate {
+    fn default() -> Self {
+        Self {
+            min_chars: DEFAULT_MIN_EXTRACTION_CHARS,
+            min_cjk_chars: DEFAULT_MIN_EXTRACTION_CJK_CHARS,
+            min_tokens: DEFAULT_MIN_EXTRACTION_TOKENS,
+        }
+    }
+}
+
+impl ExtractionGate {
+    /// Reads `MIN_EXTRACTION_CHARS`, `MIN_EXTRACTION_CJK_CHARS` and
+    /// `MIN_EXTRACTION_TOKENS`, falling back to the defaults.
+    pub fn from_env() -> Self {
+        let read = |key: &str, default: usize| env::var(key).ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(default);
+        Self {
+            min_chars: read("MIN_EXTRACTION_CHARS", DEFAULT_MIN_EXTRACTION_CHARS),
+            min_cjk_chars: read("MIN_EXTRACTION_CJK_CHARS", DEFAULT_MIN_EXTRACTION_CJK_CHARS),
+            min_tokens: read("MIN_EXTRACTION_TOKENS", DEFAULT_MIN_EXTRACTION_TOKENS),
+        }
+    }
+
+    pub fn allows(&self, text: &str) -> bool {
+        let cjk = text.chars().filter(|&c| is_cjk(c)).count();
+        let other = text.chars().filter(|&c| !c.is_whitespace() && !is_cjk(c)).count();
+        let tokens: usize = text.split_whitespace()
+            .map(|word| word.chars().filter(|&c| is_cjk(c)).count() + usize::from(word.chars().any(|c| !is_cjk(c))))
+            .sum();
+
+        // other / min_chars + cjk / min_cjk_chars >= 1, without dividing
+        let enough_chars = match (self.min_chars, self.min_cjk_chars) {
+            (0, _) | (_, 0) => true,
+            (min_chars, min_cjk) => other * min_cjk + cjk * min_chars >= min_chars * min_cjk,
+        };
+        enough_chars && tokens >= self.min_tokens
+    }
+
+    /// Runs `extract` only when `text` passes the gate, returning `None` and
+    /// logging the skip otherwise.
+    pub async fn run<T, F, Fut>(&self, operation: &str, text: &str, extract: F) -> Result<Option<T>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        if !self.allows(text) {
+            tracing::info!(
+                "[ExtractionGate::run] skipped {} for a {} char message (min {} chars, {} tokens)",
+                operation, text.chars().count(), self.min_chars, self.min_tokens
+            );
+            return Ok(None);
+        }
+        extract().await.map(Some)
+    }
+}
@@ -1,4 +1,5 @@
 mod consts;
+mod extraction;
 
 #[cfg(feature = "embeder")]
 mod embeder;
@@ -12,7 +13,10 @@ mod r2;
 mod fish_audio;
 
 #[cfg(feature = "embeder")]
-pub use embeder::EmbederClient;
+pub use embeder::{
+    EmbederClient, EmbedFallback, EmbeddingProvider, OpenAiEmbeddingProvider, OPENAI_EMBEDDING_PROVIDER,
+    register_embedding_provider, embed_in_chunks,
+};
 #[cfg(feature = "llm")]
 pub use llm::LlmClient;
 #[cfg(feature = "postgres")]
@@ -23,6 +27,7 @@ pub use r2::{R2Client, ImageFolder, ImageUpload};
 pub use fish_audio::{FishAudioClient, TTSConfig, AudioFormat, Latency, ProsodyControl, ReferenceAudio, AudioFolder, AudioUpload};
 
 mod vector;
-pub use vector::{EmbeddingMessage, MemoryEvent, MemoryUpdateEntry, BatchUpdateSummary, Mem0Filter};
+pub use vector::{DimensionMismatch, EmbeddingMessage, PgVectorConfig, MemoryEvent, MemoryUpdateEntry, BatchUpdateSummary, Mem0Filter};
 
 pub use consts::*;
+pub use extraction::ExtractionGate;
@@ -1,10 +1,16 @@
-use anyhow::Result;
+use std::{env, fmt};
+
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
-use sqlx::types::Uuid;
+use serde_json::Value;
+use sqlx::types::{Json, Uuid};
 use metastable_common::{ModuleClient, get_current_timestamp};
-use metastable_database::{OrderDirection, SqlxObject, Vector};
+use metastable_database::{OrderDirection, SqlxObject, Vector, VectorMetric};
 
-use crate::{EmbederClient, PgvectorClient, DEFAULT_GRAPH_DB_VECTOR_SEARCH_THRESHOLD};
+use crate::{
+    EmbederClient, PgvectorClient, DEFAULT_GRAPH_DB_VECTOR_SEARCH_THRESHOLD, EMBEDDING_DIMS,
+    DEFAULT_EMBEDDING_CHUNK_SIZE, DEFAULT_EMBEDDING_MAX_CONCURRENCY,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Mem0Filter {
@@ -28,6 +34,10 @@ pub struct MemoryUpdateEntry {
     pub filter: Mem0Filter,
     pub event: MemoryEvent,
     pub content: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub metadata: Option<Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,6 +47,92 @@ pub struct BatchUpdateSummary {
     pub deleted: usize,
 }
 
+/// A query vector whose dimension differs from the stored embeddings, usually
+/// because the embedding model changed without re-embedding existing memories.
+/// Returned inside `anyhow::Error`; use `downcast_ref` to match on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DimensionMismatch {
+    pub expected: usize,
+    pub actual: usize,
+}
+
+impl fmt::Display for DimensionMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[EmbeddingMessage] Embedding has {} dimensions but stored embeddings have {}; re-embed memories with the current model",
+            self.actual, self.expected)
+    }
+}
+
+impl std::error::Error for DimensionMismatch {}
+
+impl DimensionMismatch {
+    /// Fails when any of `embeddings` cannot be searched against or stored
+    /// next to embeddings of `expected` dimensions.
+    pub fn check<'a>(expected: usize, embeddings: impl IntoIterator<Item = &'a [f32]>) -> Result<(), Self> {
+        match embeddings.into_iter().map(<[f32]>::len).find(|&actual| actual != expected) {
+            Some(actual) => Err(Self { expected, actual }),
+            None => Ok(()),
+        }
+    }
+
+    /// Recognizes pgvector's "different vector dimensions 1024 and 768" error,
+    /// in case the stored column no longer matches `EMBEDDING_DIMS`.
+    pub fn from_db_error(err: &sqlx::Error) -> Option<Self> {
+        let message = err.as_database_error()?.message();
+        let dims = message.strip_prefix("different vector dimensions ")?;
+        let (expected, actual) = dims.split_once(" and ")?;
+        Some(Self { expected: expected.trim().parse().ok()?, actual: actual.trim().parse().ok()? })
+    }
+}
+
+/// Dimension and distance function of the `embeddings` table. `Default` keeps
+/// the cosine search over `EMBEDDING_DIMS` dimensions. The column type is fixed
+/// to `vector(EMBEDDING_DIMS)` by the derive, so only the metric may vary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PgVectorConfig {
+    pub dimension: usize,
+    pub metric: VectorMetric,
+}
+
+impl Default for PgVectorConfig {
+    fn default() -> Self {
+        Self { dimension: EMBEDDING_DIMS as usize, metric: VectorMetric::Cosine }
+    }
+}
+
+impl PgVectorConfig {
+    /// Reads `PGVECTOR_METRIC` (`cosine`, `l2` or `inner_product`, default
+    /// `cosine`) and `PGVECTOR_DIMENSION`, then checks them with `validate`.
+    pub fn from_env() -> Result<Self> {
+        let metric = match env::var("PGVECTOR_METRIC").unwrap_or_default().trim().to_lowercase().as_str() {
+            "" | "cosine" => VectorMetric::Cosine,
+            "l2" => VectorMetric::L2,
+            "inner_product" | "ip" => VectorMetric::InnerProduct,
+            other => return Err(anyhow!("[PgVectorConfig::from_env] Unknown PGVECTOR_METRIC {:?}; expected cosine, l2 or inner_product", other)),
+        };
+        let dimension = match env::var("PGVECTOR_DIMENSION") {
+            Ok(v) => v.trim().parse()
+                .map_err(|_| anyhow!("[PgVectorConfig::from_env] Invalid PGVECTOR_DIMENSION {:?}", v))?,
+            Err(_) => EMBEDDING_DIMS as usize,
+        };
+        let config = Self { dimension, metric };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Rejects a dimension the `embeddings` column cannot hold. Call at startup
+    /// so a mismatched config fails before any search or insert does.
+    pub fn validate(&self) -> Result<()> {
+        if self.dimension != EMBEDDING_DIMS as usize {
+            return Err(anyhow!(
+                "[PgVectorConfig::validate] Dimension {} does not match the embeddings column (vector({})); only the metric is configurable",
+                self.dimension, EMBEDDING_DIMS
+            ));
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, SqlxObject)]
 #[table_name = "embeddings"]
 pub struct EmbeddingMessage {
@@ -50,6 +146,13 @@ pub struct EmbeddingMessage {
     #[vector_dimension(1024)]
     pub embedding: Vector,
     pub content: String,
+    /// Categories such as "preference", "fact" or "event".
+    #[indexed(gin)]
+    #[column_default = "'{}'"]
+    pub tags: Vec<String>,
+    /// Free-form JSON object, matched with `search_embeddings_with_metadata`.
+    #[indexed(gin)]
+    pub metadata: Option<Json<Value>>,
 
     pub created_at: i64,
     pub updated_at: i64,
@@ -61,7 +164,9 @@ impl EmbeddingMessage {
             return Ok(vec![]);
         }
 
-        let embeddings = embeder.embed(raw_messages.to_vec()).await?;
+        let embeddings = embeder.embed_chunked(
+            raw_messages.to_vec(), DEFAULT_EMBEDDING_CHUNK_SIZE, DEFAULT_EMBEDDING_MAX_CONCURRENCY
+        ).await?;
         let embedding_messages = embeddings
             .iter()
             .zip(raw_messages)
@@ -73,6 +178,8 @@ impl EmbeddingMessage {
 
                 embedding: embedding.clone().into(),
                 content: messages.clone(),
+                tags: vec![],
+                metadata: None,
                 created_at: get_current_timestamp(),
                 updated_at: get_current_timestamp(),
             }).collect::<Vec<_>>();
@@ -80,14 +187,56 @@ impl EmbeddingMessage {
         Ok(embedding_messages)
     }
 
-    pub async fn batch_search(vector_db: &PgvectorClient, filter: &Mem0Filter, embeddings: &[Self], limit: i64) -> Result<Vec<Vec<Self>>> {
+    /// Searches the memories of `filter` closest to each of `embeddings`; with a
+    /// `tag`, only memories carrying it.
+    pub async fn batch_search(
+        vector_db: &PgvectorClient, filter: &Mem0Filter, embeddings: &[Self], limit: i64, tag: Option<&str>,
+    ) -> Result<Vec<Vec<Self>>> {
+        Self::batch_search_with_threshold(vector_db, filter, embeddings, limit, DEFAULT_GRAPH_DB_VECTOR_SEARCH_THRESHOLD, tag).await
+    }
+
+    pub async fn batch_search_with_threshold(
+        vector_db: &PgvectorClient, filter: &Mem0Filter, embeddings: &[Self], limit: i64, threshold: f32, tag: Option<&str>,
+    ) -> Result<Vec<Vec<Self>>> {
+        Self::batch_search_with_config(vector_db, &PgVectorConfig::default(), filter, embeddings, limit, threshold, tag).await
+    }
+
+    /// Like `batch_search_with_threshold`, but checks query dimensions against
+    /// `config.dimension` and scores with `config.metric`, whose similarity the
+    /// threshold then applies to.
+    pub async fn batch_search_with_config(
+        vector_db: &PgvectorClient, config: &PgVectorConfig, filter: &Mem0Filter, embeddings: &[Self], limit: i64, threshold: f32,
+        tag: Option<&str>,
+    ) -> Result<Vec<Vec<Self>>> {
+        Self::search_inner(vector_db, config, filter, embeddings, limit, threshold, tag, None).await
+    }
+
+    /// Like `batch_search`, restricted to memories whose metadata contains
+    /// `metadata` (JSONB `@>`), e.g. `json!({"source": "chat"})`.
+    pub async fn search_embeddings_with_metadata(
+        vector_db: &PgvectorClient, filter: &Mem0Filter, embeddings: &[Self], limit: i64, metadata: Value,
+    ) -> Result<Vec<Vec<Self>>> {
+        Self::search_inner(
+            vector_db, &PgVectorConfig::default(), filter, embeddings, limit,
+            DEFAULT_GRAPH_DB_VECTOR_SEARCH_THRESHOLD, None, Some(metadata)
+        ).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn search_inner(
+        vector_db: &PgvectorClient, config: &PgVectorConfig, filter: &Mem0Filter, embeddings: &[Self], limit: i64, threshold: f32,
+        tag: Option<&str>, metadata: Option<Value>,
+    ) -> Result<Vec<Vec<Self>>> {
+        DimensionMismatch::check(config.dimension, embeddings.iter().map(|e| e.embedding.as_slice()))?;
+
         let mut tx = vector_db.get_client().begin().await?;
     
         let mut all_results = Vec::new();
         for embedding in embeddings {
             let criteria = QueryCriteria::new()
                 .find_similarity(embedding.embedding.clone(), "similarity")
-                .with_similarity_threshold(DEFAULT_GRAPH_DB_VECTOR_SEARCH_THRESHOLD)
+                .with_similarity_metric(config.metric)
+                .with_similarity_threshold(threshold)
                 .add_filter("user_id", "=", Some(filter.user_id))
                 .add_filter("character_id", "=", filter.character_id);
 
@@ -96,15 +245,39 @@ impl EmbeddingMessage {
                 None => criteria,
             };
 
+            let criteria = match tag {
+                Some(tag) => criteria.add_valued_filter("tags", "@>", vec![tag.to_string()]),
+                None => criteria,
+            };
+
+            let criteria = match &metadata {
+                Some(metadata) => criteria.add_jsonb_contains("metadata", metadata.clone()),
+                None => criteria,
+            };
+
             let criteria = criteria
                 .order_by("similarity", OrderDirection::Desc)
                 .limit(limit);
-            all_results.push(EmbeddingMessage::find_by_criteria(criteria, &mut *tx).await?);
+            let results = EmbeddingMessage::find_by_criteria(criteria, &mut *tx).await
+                .map_err(|e| match DimensionMismatch::from_db_error(&e) {
+                    Some(mismatch) => anyhow::Error::from(mismatch),
+                    None => e.into(),
+                })?;
+            all_results.push(results);
         }
         tx.commit().await?;
         Ok(all_results)
     }
 
+    /// Creates the HNSW index `batch_search_with_config` needs for `config.metric`.
+    pub async fn create_vector_index(vector_db: &PgvectorClient, config: &PgVectorConfig) -> Result<()> {
+        config.validate()?;
+        let sql = config.metric.index_sql(<Self as SqlxSchema>::TABLE_NAME, "embedding");
+        let pool: &sqlx::PgPool = vector_db.get_client();
+        sqlx::query(&sql).execute(pool).await?;
+        Ok(())
+    }
+
     pub async fn db_batch_update(embeder: &EmbederClient, vector_db: &PgvectorClient, updates: Vec<MemoryUpdateEntry>) -> Result<BatchUpdateSummary> {
         if updates.is_empty() {
             return Ok(BatchUpdateSummary { added: 0, updated: 0, deleted: 0 });
@@ -127,7 +300,9 @@ impl EmbeddingMessage {
         let update_contents: Vec<String> = to_update.iter().map(|u| u.content.clone()).collect();
 
         let all_contents_to_embed = [add_contents.as_slice(), update_contents.as_slice()].concat();
-        let embeddings = embeder.embed(all_contents_to_embed).await?;
+        let embeddings = embeder.embed_chunked(
+            all_contents_to_embed, DEFAULT_EMBEDDING_CHUNK_SIZE, DEFAULT_EMBEDDING_MAX_CONCURRENCY
+        ).await?;
 
         let (add_embeddings, update_embeddings) = embeddings.split_at(add_contents.len());
 
@@ -152,6 +327,8 @@ impl EmbeddingMessage {
 
                     embedding: embedding.clone().into(),
                     content: update.content,
+                    tags: update.tags,
+                    metadata: update.metadata.map(Json),
                     created_at: now,
                     updated_at: now,
                 })
@@ -172,6 +349,8 @@ impl EmbeddingMessage {
 
                     embedding: embedding.clone().into(),
                     content: update.content,
+                    tags: update.tags,
+                    metadata: update.metadata.map(Json),
                     created_at: now,
                     updated_at: now,
                 })
@@ -180,9 +359,7 @@ impl EmbeddingMessage {
 
         let mut tx = vector_db.get_client().begin().await?;
 
-        for embedding in add_messages {
-            embedding.create(&mut *tx).await?;
-        }
+        EmbeddingMessage::create_many(add_messages, &mut *tx).await?;
 
         for update in update_messages {
             update.update(&mut *tx).await?;
@@ -190,7 +367,7 @@ impl EmbeddingMessage {
 
         if !to_delete_ids.is_empty() {
             EmbeddingMessage::delete_by_criteria(
-                QueryCriteria::new().add_filter("id", " = ANY($1)", Some(to_delete_ids)),
+                QueryCriteria::new().add_in_filter("id", to_delete_ids),
                 &mut *tx
             ).await?;
         }
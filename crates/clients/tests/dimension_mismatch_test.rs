@@ -0,0 +1,28 @@
+use metastable_clients::{DimensionMismatch, EmbeddingMessage, Mem0Filter, PgvectorClient, EMBEDDING_DIMS};
+use metastable_common::ModuleClient;
+use sqlx::types::Uuid;
+
+#[tokio::test]
+async fn test_wrong_dimension_query_returns_typed_error() {
+    let db = PgvectorClient::setup_connection().await;
+
+    let user_id = Uuid::new_v4();
+    let query = EmbeddingMessage {
+        id: Uuid::new_v4(),
+        user_id,
+        character_id: None,
+        session_id: None,
+        embedding: vec![0.5f32; 768].into(),
+        content: "query".to_string(),
+        tags: vec![],
+        metadata: None,
+        created_at: 0,
+        updated_at: 0,
+    };
+    let filter = Mem0Filter { user_id, character_id: None, session_id: None };
+
+    let err = EmbeddingMessage::batch_search(&db, &filter, &[query], 10, None).await.unwrap_err();
+    let mismatch = err.downcast_ref::<DimensionMismatch>().expect("expected a DimensionMismatch error");
+    assert_eq!(mismatch, &DimensionMismatch { expected: EMBEDDING_DIMS as usize, actual: 768 });
+    assert!(err.to_string().contains("re-embed"));
+}
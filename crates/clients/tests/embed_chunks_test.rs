@@ -0,0 +1,49 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use metastable_clients::embed_in_chunks;
+
+#[tokio::test]
+async fn test_chunked_embedding_preserves_order_and_bounds_in_flight() {
+    let texts: Vec<String> = (0..1000).map(|i| i.to_string()).collect();
+    let in_flight = AtomicUsize::new(0);
+    let max_in_flight = AtomicUsize::new(0);
+    let calls = AtomicUsize::new(0);
+
+    let embeddings = embed_in_chunks(texts, 32, 3, |chunk| {
+        let in_flight = &in_flight;
+        let max_in_flight = &max_in_flight;
+        let calls = &calls;
+        async move {
+            let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            max_in_flight.fetch_max(current, Ordering::SeqCst);
+            calls.fetch_add(1, Ordering::SeqCst);
+            assert!(chunk.len() <= 32);
+
+            tokio::time::sleep(Duration::from_millis(5)).await;
+
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(chunk.iter().map(|t| vec![t.parse::<f32>().unwrap()]).collect())
+        }
+    }).await.unwrap();
+
+    assert_eq!(embeddings.len(), 1000);
+    assert!(embeddings.iter().enumerate().all(|(i, e)| e == &vec![i as f32]));
+    assert_eq!(calls.load(Ordering::SeqCst), 32);
+    assert!(max_in_flight.load(Ordering::SeqCst) <= 3);
+    assert!(max_in_flight.load(Ordering::SeqCst) > 1);
+}
+
+#[tokio::test]
+async fn test_chunked_embedding_propagates_errors() {
+    let texts: Vec<String> = (0..10).map(|i| i.to_string()).collect();
+    let result = embed_in_chunks(texts, 4, 2, |chunk| async move {
+        if chunk.contains(&"5".to_string()) {
+            Err(anyhow::anyhow!("provider error"))
+        } else {
+            Ok(chunk.iter().map(|_| vec![0.0]).collect())
+        }
+    }).await;
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use metastable_clients::{Embedding, EmbedFallback, EmbederClient, EmbeddingProvider};
+
+/// Fails any batch containing "bad"; embeds everything else as `[len, 1]`.
+struct FlakyProvider;
+
+#[async_trait]
+impl EmbeddingProvider for FlakyProvider {
+    async fn embed(&self, text: Vec<String>) -> Result<Vec<Embedding>> {
+        if text.iter().any(|t| t.contains("bad")) {
+            return Err(anyhow!("provider hiccup"));
+        }
+        Ok(text.iter().map(|t| vec![t.len() as f32, 1.0]).collect())
+    }
+}
+
+fn texts(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| s.to_string()).collect()
+}
+
+#[tokio::test]
+async fn test_empty_inputs_are_rejected() {
+    let embeder = EmbederClient::with_provider(Arc::new(FlakyProvider));
+
+    let err = embeder.embed_with_fallback(texts(&["ok", ""]), EmbedFallback::ZeroVector).await.unwrap_err();
+    assert!(err.to_string().contains("Input 1 is empty"), "{}", err);
+    let err = embeder.embed_with_fallback(texts(&["   "]), EmbedFallback::Fail).await.unwrap_err();
+    assert!(err.to_string().contains("Input 0 is empty"), "{}", err);
+
+    // No inputs at all is still fine
+    assert!(embeder.embed_with_fallback(vec![], EmbedFallback::Fail).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_zero_vector_fallback_keeps_outputs_aligned() {
+    let embeder = EmbederClient::with_provider(Arc::new(FlakyProvider));
+    let batch = texts(&["one", "bad input", "three"]);
+
+    let err = embeder.embed_with_fallback(batch.clone(), EmbedFallback::Fail).await.unwrap_err();
+    assert!(err.to_string().contains("provider hiccup"));
+
+    let embeddings = embeder.embed_with_fallback(batch, EmbedFallback::ZeroVector).await.unwrap();
+    assert_eq!(embeddings, vec![vec![3.0, 1.0], vec![0.0, 0.0], vec![5.0, 1.0]]);
+}
+
+#[tokio::test]
+async fn test_zero_vector_fallback_fails_when_every_input_fails() {
+    let embeder = EmbederClient::with_provider(Arc::new(FlakyProvider));
+
+    let err = embeder.embed_with_fallback(texts(&["bad one", "bad two"]), EmbedFallback::ZeroVector).await.unwrap_err();
+    assert!(format!("{:#}", err).contains("All 2 inputs failed"), "{:#}", err);
+    assert!(format!("{:#}", err).contains("provider hiccup"), "{:#}", err);
+}
@@ -0,0 +1,62 @@
+use metastable_clients::{EmbeddingMessage, Mem0Filter, PgvectorClient};
+use metastable_common::ModuleClient;
+use metastable_database::{QueryCriteria, SqlxCrud, SqlxFilterQuery};
+use serde_json::{json, Value};
+use sqlx::types::{Json, Uuid};
+
+fn memory(user_id: Uuid, content: &str, metadata: Option<Value>) -> EmbeddingMessage {
+    let mut embedding = vec![0.0f32; 1024];
+    embedding[0] = 1.0;
+
+    EmbeddingMessage {
+        id: Uuid::new_v4(),
+        user_id,
+        character_id: None,
+        session_id: None,
+        embedding: embedding.into(),
+        content: content.to_string(),
+        tags: vec![],
+        metadata: metadata.map(Json),
+        created_at: 0,
+        updated_at: 0,
+    }
+}
+
+#[tokio::test]
+async fn test_search_filters_on_metadata() {
+    let db = PgvectorClient::setup_connection().await;
+    let user_id = Uuid::new_v4();
+    let filter = Mem0Filter { user_id, character_id: None, session_id: None };
+
+    let mut tx = db.get_client().begin().await.unwrap();
+    let from_chat = memory(user_id, "from chat", Some(json!({"source": "chat", "lang": "en"}))).create(&mut *tx).await.unwrap();
+    let from_import = memory(user_id, "from import", Some(json!({"source": "import"}))).create(&mut *tx).await.unwrap();
+    let from_chat_zh = memory(user_id, "from chat zh", Some(json!({"source": "chat", "lang": "zh"}))).create(&mut *tx).await.unwrap();
+    memory(user_id, "no metadata", None).create(&mut *tx).await.unwrap();
+    tx.commit().await.unwrap();
+
+    let query = memory(user_id, "query", None);
+    let results = EmbeddingMessage::search_embeddings_with_metadata(
+        &db, &filter, &[query.clone()], 10, json!({"source": "chat"})
+    ).await.unwrap();
+    let mut ids = results[0].iter().map(|m| m.id).collect::<Vec<_>>();
+    ids.sort();
+    let mut expected = vec![from_chat.id, from_chat_zh.id];
+    expected.sort();
+    assert_eq!(ids, expected);
+
+    let results = EmbeddingMessage::search_embeddings_with_metadata(
+        &db, &filter, &[query.clone()], 10, json!({"source": "chat", "lang": "zh"})
+    ).await.unwrap();
+    assert_eq!(results[0].iter().map(|m| m.id).collect::<Vec<_>>(), vec![from_chat_zh.id]);
+
+    let results = EmbeddingMessage::search_embeddings_with_metadata(
+        &db, &filter, &[query], 10, json!({"source": "import"})
+    ).await.unwrap();
+    assert_eq!(results[0].len(), 1);
+    assert_eq!(results[0][0].metadata.as_ref().map(|m| m.0.clone()), from_import.metadata.map(|m| m.0));
+
+    let mut tx = db.get_client().begin().await.unwrap();
+    EmbeddingMessage::delete_by_criteria(QueryCriteria::new().add_valued_filter("user_id", "=", user_id), &mut *tx).await.unwrap();
+    tx.commit().await.unwrap();
+}
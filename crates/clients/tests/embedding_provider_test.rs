@@ -0,0 +1,48 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use metastable_clients::{register_embedding_provider, Embedding, EmbederClient, EmbeddingProvider};
+use metastable_common::ModuleClient;
+
+/// Embeds each text as its length and records what it was asked for.
+#[derive(Default)]
+struct LengthProvider {
+    calls: Mutex<Vec<Vec<String>>>,
+}
+
+#[async_trait]
+impl EmbeddingProvider for LengthProvider {
+    async fn embed(&self, text: Vec<String>) -> Result<Vec<Embedding>> {
+        self.calls.lock().unwrap().push(text.clone());
+        Ok(text.iter().map(|t| vec![t.len() as f32]).collect())
+    }
+}
+
+#[tokio::test]
+async fn test_embed_routes_through_a_custom_provider() {
+    let provider = Arc::new(LengthProvider::default());
+    let embeder = EmbederClient::with_provider(provider.clone());
+
+    let embeddings = embeder.embed(vec!["a".to_string(), "abc".to_string()]).await.unwrap();
+    assert_eq!(embeddings, vec![vec![1.0], vec![3.0]]);
+
+    // Empty input never reaches the provider
+    assert!(embeder.embed(vec![]).await.unwrap().is_empty());
+    assert_eq!(*provider.calls.lock().unwrap(), vec![vec!["a".to_string(), "abc".to_string()]]);
+
+    let chunked = embeder.embed_chunked((0..5).map(|i| "x".repeat(i)).collect(), 2, 2).await.unwrap();
+    assert_eq!(chunked, (0..5).map(|i| vec![i as f32]).collect::<Vec<_>>());
+    assert_eq!(provider.calls.lock().unwrap().len(), 4);
+}
+
+#[tokio::test]
+async fn test_registered_provider_is_selected_from_env() {
+    let provider = Arc::new(LengthProvider::default());
+    register_embedding_provider("length", provider.clone());
+    std::env::set_var("EMBEDDING_PROVIDER", "length");
+
+    let embeder = EmbederClient::setup_connection().await;
+    assert_eq!(embeder.embed(vec!["four".to_string()]).await.unwrap(), vec![vec![4.0]]);
+    assert_eq!(provider.calls.lock().unwrap().len(), 1);
+}
@@ -0,0 +1,56 @@
+use metastable_clients::{EmbeddingMessage, Mem0Filter, PgvectorClient};
+use metastable_common::ModuleClient;
+use metastable_database::{QueryCriteria, SqlxCrud, SqlxFilterQuery};
+use sqlx::types::Uuid;
+
+fn tagged_memory(user_id: Uuid, content: &str, tags: &[&str]) -> EmbeddingMessage {
+    let mut embedding = vec![0.0f32; 1024];
+    embedding[0] = 1.0;
+
+    EmbeddingMessage {
+        id: Uuid::new_v4(),
+        user_id,
+        character_id: None,
+        session_id: None,
+        embedding: embedding.into(),
+        content: content.to_string(),
+        tags: tags.iter().map(|t| t.to_string()).collect(),
+        metadata: None,
+        created_at: 0,
+        updated_at: 0,
+    }
+}
+
+#[tokio::test]
+async fn test_search_restricted_to_tag() {
+    let db = PgvectorClient::setup_connection().await;
+    let user_id = Uuid::new_v4();
+    let filter = Mem0Filter { user_id, character_id: None, session_id: None };
+
+    let mut tx = db.get_client().begin().await.unwrap();
+    let likes_tea = tagged_memory(user_id, "likes tea", &["preference"]).create(&mut *tx).await.unwrap();
+    let lives_in_beijing = tagged_memory(user_id, "lives in Beijing", &["fact"]).create(&mut *tx).await.unwrap();
+    let hates_rain = tagged_memory(user_id, "hates rain", &["preference", "fact"]).create(&mut *tx).await.unwrap();
+    tagged_memory(user_id, "untagged", &[]).create(&mut *tx).await.unwrap();
+    tx.commit().await.unwrap();
+
+    let query = tagged_memory(user_id, "query", &[]);
+    let results = EmbeddingMessage::batch_search(&db, &filter, &[query.clone()], 10, Some("preference")).await.unwrap();
+    let mut ids = results[0].iter().map(|m| m.id).collect::<Vec<_>>();
+    ids.sort();
+    let mut expected = vec![likes_tea.id, hates_rain.id];
+    expected.sort();
+    assert_eq!(ids, expected);
+    assert!(results[0].iter().all(|m| m.tags.contains(&"preference".to_string())));
+
+    let results = EmbeddingMessage::batch_search(&db, &filter, &[query.clone()], 10, Some("event")).await.unwrap();
+    assert!(results[0].is_empty());
+
+    let results = EmbeddingMessage::batch_search(&db, &filter, &[query], 10, None).await.unwrap();
+    assert_eq!(results[0].len(), 4);
+    assert!(results[0].iter().any(|m| m.id == lives_in_beijing.id));
+
+    let mut tx = db.get_client().begin().await.unwrap();
+    EmbeddingMessage::delete_by_criteria(QueryCriteria::new().add_valued_filter("user_id", "=", user_id), &mut *tx).await.unwrap();
+    tx.commit().await.unwrap();
+}
@@ -0,0 +1,53 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use metastable_clients::ExtractionGate;
+
+#[tokio::test]
+async fn test_short_messages_skip_extraction() {
+    let gate = ExtractionGate { min_chars: 6, min_cjk_chars: 3, min_tokens: 2 };
+    let calls = AtomicUsize::new(0);
+    let extract = || async {
+        calls.fetch_add(1, Ordering::SeqCst);
+        Ok(vec!["Bob likes green tea".to_string()])
+    };
+
+    let skipped = gate.run("fact extraction", "lol", extract).await.unwrap();
+    assert!(skipped.is_none());
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+    let facts = gate.run("fact extraction", "I really like green tea", extract).await.unwrap();
+    assert_eq!(facts, Some(vec!["Bob likes green tea".to_string()]));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_gate_thresholds() {
+    let gate = ExtractionGate { min_chars: 6, min_cjk_chars: 3, min_tokens: 2 };
+    assert!(!gate.allows("ok"));
+    // Long enough, but a single token
+    assert!(!gate.allows("wonderful"));
+    assert!(!gate.allows("  o k  "));
+    assert!(gate.allows("sounds good"));
+
+    let chars_only = ExtractionGate { min_chars: 6, min_cjk_chars: 3, min_tokens: 1 };
+    assert!(chars_only.allows("我今天很开心"));
+    assert!(!chars_only.allows("   "));
+}
+
+#[test]
+fn test_cjk_counts_by_script() {
+    let gate = ExtractionGate::default();
+    // Four characters, but a full sentence
+    assert!(gate.allows("我喜欢猫"));
+    assert!(gate.allows("猫が好き"));
+    assert!(!gate.allows("好的"));
+    assert!(!gate.allows("哈哈"));
+    assert!(!gate.allows("lol"));
+
+    // Each CJK character is a token, so a two-token minimum still passes
+    let two_tokens = ExtractionGate { min_tokens: 2, ..ExtractionGate::default() };
+    assert!(two_tokens.allows("我喜欢猫"));
+    // Mixed text: half of each threshold is enough
+    assert!(two_tokens.allows("我 love"));
+    assert!(!two_tokens.allows("我 ok"));
+}
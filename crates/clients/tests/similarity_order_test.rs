@@ -0,0 +1,48 @@
+use metastable_clients::{EmbeddingMessage, PgvectorClient};
+use metastable_common::ModuleClient;
+use metastable_database::{OrderDirection, QueryCriteria, SqlxCrud, SqlxFilterQuery};
+use sqlx::types::Uuid;
+
+fn embedding_row(user_id: Uuid, content: &str) -> EmbeddingMessage {
+    let mut embedding = vec![0.0f32; 1024];
+    embedding[0] = 1.0;
+
+    EmbeddingMessage {
+        id: Uuid::new_v4(),
+        user_id,
+        character_id: None,
+        session_id: None,
+        embedding: embedding.into(),
+        content: content.to_string(),
+        tags: vec![],
+        metadata: None,
+        created_at: 0,
+        updated_at: 0,
+    }
+}
+
+#[tokio::test]
+async fn test_similarity_ties_are_ordered_by_id() {
+    let db = PgvectorClient::setup_connection().await;
+    let mut tx = db.get_client().begin().await.unwrap();
+
+    let user_id = Uuid::new_v4();
+    let first = embedding_row(user_id, "first").create(&mut *tx).await.unwrap();
+    let second = embedding_row(user_id, "second").create(&mut *tx).await.unwrap();
+
+    let criteria = || QueryCriteria::new()
+        .find_similarity(first.embedding.clone(), "similarity")
+        .add_valued_filter("user_id", "=", user_id)
+        .order_by("similarity", OrderDirection::Desc);
+
+    let mut expected = vec![first.id, second.id];
+    expected.sort();
+
+    for _ in 0..5 {
+        let results = EmbeddingMessage::find_by_criteria(criteria(), &mut *tx).await.unwrap();
+        let ids: Vec<Uuid> = results.iter().map(|r| r.id).collect();
+        assert_eq!(ids, expected);
+    }
+
+    tx.rollback().await.unwrap();
+}
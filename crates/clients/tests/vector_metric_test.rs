@@ -0,0 +1,119 @@
+use metastable_clients::{EmbeddingMessage, Mem0Filter, PgVectorConfig, PgvectorClient};
+use metastable_common::ModuleClient;
+use metastable_database::{OrderDirection, QueryCriteria, SqlxCrud, SqlxFilterQuery, VectorMetric};
+use sqlx::types::Uuid;
+
+fn embedding_row(user_id: Uuid, content: &str, x: f32, y: f32) -> EmbeddingMessage {
+    let mut embedding = vec![0.0f32; 1024];
+    embedding[0] = x;
+    embedding[1] = y;
+
+    EmbeddingMessage {
+        id: Uuid::new_v4(),
+        user_id,
+        character_id: None,
+        session_id: None,
+        embedding: embedding.into(),
+        content: content.to_string(),
+        tags: vec![],
+        metadata: None,
+        created_at: 0,
+        updated_at: 0,
+    }
+}
+
+#[test]
+fn test_metric_sql() {
+    assert_eq!(VectorMetric::default(), VectorMetric::Cosine);
+    assert_eq!(VectorMetric::Cosine.similarity_sql("embedding", 1), "COALESCE(NULLIF(1 - (embedding <=> $1), 'NaN'), 0)");
+    assert_eq!(VectorMetric::L2.operator(), "<->");
+    assert_eq!(VectorMetric::InnerProduct.opclass(), "vector_ip_ops");
+    assert!(VectorMetric::L2.index_sql("embeddings", "embedding").contains("USING hnsw (\"embedding\" vector_l2_ops)"));
+    assert_eq!(PgVectorConfig::default(), PgVectorConfig { dimension: 1024, metric: VectorMetric::Cosine });
+}
+
+#[tokio::test]
+async fn test_metrics_rank_differently() {
+    let db = PgvectorClient::setup_connection().await;
+    let mut tx = db.get_client().begin().await.unwrap();
+
+    // Same direction as the query but far away, versus off-direction but close
+    let user_id = Uuid::new_v4();
+    let aligned = embedding_row(user_id, "aligned", 10.0, 0.0).create(&mut *tx).await.unwrap();
+    let nearby = embedding_row(user_id, "nearby", 1.0, 1.0).create(&mut *tx).await.unwrap();
+    let query = embedding_row(user_id, "query", 1.0, 0.0);
+
+    let top = |metric| QueryCriteria::new()
+        .find_similarity(query.embedding.clone(), "similarity")
+        .with_similarity_metric(metric)
+        .add_valued_filter("user_id", "=", user_id)
+        .order_by("similarity", OrderDirection::Desc)
+        .limit(1);
+
+    let cosine = EmbeddingMessage::find_by_criteria(top(VectorMetric::Cosine), &mut *tx).await.unwrap();
+    assert_eq!(cosine[0].id, aligned.id);
+    let l2 = EmbeddingMessage::find_by_criteria(top(VectorMetric::L2), &mut *tx).await.unwrap();
+    assert_eq!(l2[0].id, nearby.id);
+    let inner = EmbeddingMessage::find_by_criteria(top(VectorMetric::InnerProduct), &mut *tx).await.unwrap();
+    assert_eq!(inner[0].id, aligned.id);
+
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_config_dimension_is_checked() {
+    let db = PgvectorClient::setup_connection().await;
+    let user_id = Uuid::new_v4();
+    let filter = Mem0Filter { user_id, character_id: None, session_id: None };
+    let config = PgVectorConfig { dimension: 768, metric: VectorMetric::L2 };
+
+    let err = EmbeddingMessage::batch_search_with_config(
+        &db, &config, &filter, &[embedding_row(user_id, "query", 1.0, 0.0)], 1, 0.0, None
+    ).await.unwrap_err();
+    assert!(err.to_string().contains("768"));
+}
+
+#[test]
+fn test_config_from_env_rejects_other_dimensions() {
+    std::env::set_var("PGVECTOR_METRIC", "l2");
+    assert_eq!(PgVectorConfig::from_env().unwrap(), PgVectorConfig { dimension: 1024, metric: VectorMetric::L2 });
+
+    // The column is vector(1024); a different dimension must fail at startup
+    std::env::set_var("PGVECTOR_DIMENSION", "768");
+    let err = PgVectorConfig::from_env().unwrap_err();
+    assert!(err.to_string().contains("768"), "{}", err);
+    std::env::remove_var("PGVECTOR_DIMENSION");
+
+    std::env::set_var("PGVECTOR_METRIC", "manhattan");
+    assert!(PgVectorConfig::from_env().is_err());
+    std::env::remove_var("PGVECTOR_METRIC");
+
+    assert!(PgVectorConfig::default().validate().is_ok());
+    assert!(PgVectorConfig { dimension: 768, metric: VectorMetric::Cosine }.validate().is_err());
+}
+
+#[tokio::test]
+async fn test_zero_vector_query_matches_nothing() {
+    let db = PgvectorClient::setup_connection().await;
+    let mut tx = db.get_client().begin().await.unwrap();
+
+    let user_id = Uuid::new_v4();
+    embedding_row(user_id, "stored", 1.0, 0.0).create(&mut *tx).await.unwrap();
+    // What the zero-vector embedding fallback produces
+    let query = embedding_row(user_id, "query", 0.0, 0.0);
+
+    let criteria = QueryCriteria::new()
+        .find_similarity(query.embedding.clone(), "similarity")
+        .with_similarity_threshold(0.5)
+        .add_valued_filter("user_id", "=", user_id)
+        .order_by("similarity", OrderDirection::Desc);
+    assert!(EmbeddingMessage::find_by_criteria(criteria, &mut *tx).await.unwrap().is_empty());
+
+    let similarity: f64 = sqlx::query_scalar(&format!("SELECT {} FROM embeddings WHERE user_id = $2", VectorMetric::Cosine.similarity_sql("embedding", 1)))
+        .bind(query.embedding.clone())
+        .bind(user_id)
+        .fetch_one(&mut *tx).await.unwrap();
+    assert_eq!(similarity, 0.0);
+
+    tx.rollback().await.unwrap();
+}
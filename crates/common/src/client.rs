@@ -37,7 +37,7 @@ macro_rules! define_module_client {
 
             fn validate_env() -> bool {
                 const ENV_VARS: &'static [&'static str] = &[ $( $env_var ),* ];
-                let missing_vars: Vec<&'static str> = ENV_VARS.iter().cloned().filter(|var| std::env::var(var).is_err()).collect();
+                let missing_vars: Vec<&'static str> = ENV_VARS.iter().cloned().filter(|var| ::metastable_common::uri_from_env(var).is_none()).collect();
 
                 if missing_vars.is_empty() {
                     return true;
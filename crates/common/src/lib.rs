@@ -13,7 +13,7 @@ pub use crypto::{
     ecdh_encrypt, ecdh_decrypt,
 };
 pub use crypto_hash::CryptoHash;
-pub use env::EnvVars;
+pub use env::{EnvVars, uri_from_env, build_uri};
 pub use client::ModuleClient;
 
 pub fn get_current_timestamp() -> i64 {
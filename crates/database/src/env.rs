@@ -1,6 +1,7 @@
+#[cfg(feature = "mongodb")]
 use std::env;
 
-use metastable_common::EnvVars;
+use metastable_common::{EnvVars, uri_from_env};
 
 
 #[cfg(feature = "mongodb")]
@@ -34,8 +35,8 @@ pub struct PostgresDbEnv {
 impl EnvVars for PostgresDbEnv {
     fn load() -> Self {
         Self {
-            postgres_uri: env::var("DATABASE_URL").unwrap(),
-            pgvector_uri: env::var("PGVECTOR_URI").unwrap(),
+            postgres_uri: uri_from_env("DATABASE_URL").unwrap(),
+            pgvector_uri: uri_from_env("PGVECTOR_URI").unwrap(),
         }
     }
 
@@ -6,6 +6,9 @@ mod postgres_connect;
 #[cfg(feature = "postgres")]
 mod sqlx_postgres;
 
+#[cfg(feature = "postgres")]
+mod transaction;
+
 #[cfg(feature = "mongodb")]
 mod mongodb;
 
@@ -20,5 +23,8 @@ pub use mongodb::*;
 #[cfg(feature = "postgres")]
 pub use sqlx_postgres::*;
 
+#[cfg(feature = "postgres")]
+pub use transaction::{with_retry, SerializationFailure, SERIALIZATION_FAILURE_CODE, RETRY_BASE_DELAY};
+
 #[cfg(feature = "postgres")]
 pub use pgvector::Vector;
@@ -46,7 +46,7 @@ macro_rules! init_databases {
 
         async fn connect(drop_tables: bool, create_tables: bool, run_migrations: bool) -> &'static sqlx::PgPool {
             POOL.get_or_init(|| async {
-                let database_url = std::env::var("DATABASE_URL")
+                let database_url = ::metastable_common::uri_from_env("DATABASE_URL")
                     .expect("DATABASE_URL environment variable not set");
                 
                 let pool = PgPoolOptions::new()
@@ -123,7 +123,7 @@ macro_rules! init_databases {
 
         async fn connect_pgvector(drop_tables: bool, create_tables: bool, run_migrations: bool) -> &'static sqlx::PgPool {
             PGVECTOR_POOL.get_or_init(|| async {
-                let database_url = std::env::var("PGVECTOR_URI")
+                let database_url = ::metastable_common::uri_from_env("PGVECTOR_URI")
                     .expect("PGVECTOR_URI environment variable not set");
                 
                 let pool = PgPoolOptions::new()
@@ -1,21 +1,32 @@
-use sqlx::{FromRow, Postgres, Error as SqlxError, postgres::PgArguments, Executor};
+use sqlx::{FromRow, Postgres, Error as SqlxError, postgres::PgArguments, Acquire, Executor, types::Uuid};
+
+/// Postgres rejects statements with more bind parameters than this.
+pub const POSTGRES_MAX_BIND_PARAMS: usize = 65535;
 
 /// Trait to define the schema of a database object for PostgreSQL.
 // No async_trait needed here as no methods are async by default in the trait itself.
 pub trait SqlxSchema: Send + Sync + Unpin + Clone + std::fmt::Debug {
-    /// The type of the primary key for this database object.
-    type Id: Send + Sync + for<'q> sqlx::Encode<'q, Postgres> + sqlx::Type<Postgres> + Clone;
+    /// The type of the primary key for this database object: `Uuid` for the
+    /// default `id`, or a tuple of the `#[primary_key]` field types.
+    type Id: Send + Sync + Clone;
 
     /// The intermediate type that implements FromRow, used for fetching from the database.
     type Row: for<'r> FromRow<'r, sqlx::postgres::PgRow> + Send + Unpin;
 
     const TABLE_NAME: &'static str;
+    /// The first primary key column; the whole key is `PRIMARY_KEY_COLUMNS`.
     const ID_COLUMN_NAME: &'static str;
+    const PRIMARY_KEY_COLUMNS: &'static [&'static str];
     const COLUMNS: &'static [&'static str];
     const INDEXES_SQL: &'static [&'static str];
+    /// The `#[optimistic_lock]` column, bumped on every `update()`.
+    const VERSION_COLUMN: Option<&'static str> = None;
+    /// Whether the table is `#[soft_delete]`, so reads skip rows with `deleted_at` set.
+    const SOFT_DELETE: bool = false;
 
     // Default utility methods to access consts 
     fn id_column_name() -> &'static str { Self::ID_COLUMN_NAME }
+    fn primary_key_columns() -> &'static [&'static str] { Self::PRIMARY_KEY_COLUMNS }
     fn table_name() -> &'static str { Self::TABLE_NAME }
     fn columns() -> &'static [&'static str] { Self::COLUMNS }
     fn indexes_sql() -> &'static [&'static str] { Self::INDEXES_SQL }
@@ -26,6 +37,10 @@ pub trait SqlxSchema: Send + Sync + Unpin + Clone + std::fmt::Debug {
     /// Converts the intermediate Row type to the Self type.
     fn from_row(row: Self::Row) -> Self;
 
+    /// Decodes the intermediate Row type from `COLUMNS.len()` consecutive columns
+    /// starting at `offset`, for queries that select several tables side by side.
+    fn row_at(row: &sqlx::postgres::PgRow, offset: usize) -> Result<Self::Row, SqlxError>;
+
     // SQL generation methods (to be implemented by the derive macro)
     fn create_table_sql() -> String;
     fn drop_table_sql() -> String;
@@ -50,6 +65,21 @@ pub trait SqlxCrud: SqlxSchema + SqlxFilterQuery + Sized {
         E: Executor<'e, Database = Postgres> + Send,
         Self: Send;
 
+    /// Inserts all records with multi-row `INSERT`s, split into as many statements as
+    /// needed to stay under Postgres's bind parameter limit. Rows are returned in
+    /// input order.
+    async fn create_many<'a, A>(items: Vec<Self>, conn: A) -> Result<Vec<Self>, SqlxError>
+    where
+        A: Acquire<'a, Database = Postgres> + Send,
+        Self: Send;
+
+    /// Inserts the record as-is, keeping its id and timestamps (e.g. when restoring a backup).
+    /// Returns `None` when the row was skipped under [`OnConflict::Skip`].
+    async fn restore<'e, E>(self, on_conflict: OnConflict, executor: E) -> Result<Option<Self>, SqlxError>
+    where
+        E: Executor<'e, Database = Postgres> + Send,
+        Self: Send;
+
     /// Updates an existing record in the database (identified by its primary key).
     /// The derive macro will implement this using a specific update-by-ID SQL query.
     async fn update<'e, E>(self, executor: E) -> Result<Self, SqlxError>
@@ -64,6 +94,14 @@ pub trait SqlxCrud: SqlxSchema + SqlxFilterQuery + Sized {
         E: Executor<'e, Database = Postgres> + Send,
         Self: Send;
 
+    /// Deletes every record whose primary key is in `ids` with a single
+    /// `DELETE ... WHERE id = ANY($1)`, returning the number of rows affected.
+    /// Soft-delete tables only stamp `deleted_at`, like [`SqlxCrud::delete`].
+    async fn delete_by_ids<'e, E>(ids: &[Self::Id], executor: E) -> Result<u64, SqlxError>
+    where
+        E: Executor<'e, Database = Postgres> + Send,
+        Self: Send;
+
     async fn force_set_timestamp<'e, E>(self, executor: E, created_at: i64, updated_at: i64) -> Result<Self, SqlxError>
     where
         E: Executor<'e, Database = Postgres> + Send,
@@ -72,8 +110,156 @@ pub trait SqlxCrud: SqlxSchema + SqlxFilterQuery + Sized {
     async fn toggle_trigger<'e, E>(executor: E, status: bool) -> Result<(), SqlxError>
     where
         E: Executor<'e, Database = Postgres> + Send;
+
+    /// Sets a single column to the same value for every row in `ids` with one
+    /// `UPDATE ... WHERE id = ANY($2)`, returning the updated rows.
+    async fn batch_update_field<'e, E, V>(
+        ids: Vec<Self::Id>, column: &'static str, value: V, executor: E
+    ) -> Result<Vec<Self>, SqlxError>
+    where
+        E: Executor<'e, Database = Postgres> + Send,
+        V: for<'q> sqlx::Encode<'q, Postgres> + sqlx::Type<Postgres> + Send + 'static,
+        Vec<Self::Id>: for<'q> sqlx::Encode<'q, Postgres> + sqlx::Type<Postgres>,
+        Self::Id: 'static,
+        Self: Send
+    {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Invalidates copies read before this update, see `#[optimistic_lock]`
+        let bump_version = Self::VERSION_COLUMN
+            .map(|version| format!(", \"{0}\" = \"{0}\" + 1", version))
+            .unwrap_or_default();
+        let sql = format!(
+            "UPDATE \"{}\" SET \"{}\" = $1{} WHERE \"{}\" = ANY($2) RETURNING *",
+            Self::TABLE_NAME, column, bump_version, Self::ID_COLUMN_NAME
+        );
+
+        let rows = sqlx::query_as::<_, Self::Row>(&sql)
+            .bind(value)
+            .bind(ids)
+            .fetch_all(executor)
+            .await?;
+
+        Ok(rows.into_iter().map(Self::from_row).collect())
+    }
 } 
 
+/// How [`SqlxCrud::restore`] treats a row whose id already exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnConflict {
+    /// Surface the unique violation as an error.
+    Fail,
+    /// Keep the existing row and report nothing restored.
+    Skip,
+    /// Replace every column of the existing row.
+    Overwrite,
+}
+
+/// Returned by `update()` on an `#[optimistic_lock]` table when the row's
+/// version no longer matches the one that was read, or the row is gone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionConflict {
+    pub table: &'static str,
+}
+
+impl VersionConflict {
+    /// Whether `err` is a `VersionConflict`, e.g. to answer with HTTP 409.
+    pub fn is(err: &SqlxError) -> bool {
+        err.as_database_error()
+            .is_some_and(|e| e.try_downcast_ref::<VersionConflict>().is_some())
+    }
+}
+
+impl std::fmt::Display for VersionConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "row in \"{}\" was modified or deleted since it was read", self.table)
+    }
+}
+
+impl std::error::Error for VersionConflict {}
+
+impl sqlx::error::DatabaseError for VersionConflict {
+    fn message(&self) -> &str {
+        "row was modified or deleted since it was read"
+    }
+
+    fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+        self
+    }
+
+    fn table(&self) -> Option<&str> {
+        Some(self.table)
+    }
+
+    fn kind(&self) -> sqlx::error::ErrorKind {
+        sqlx::error::ErrorKind::Other
+    }
+}
+
+/// Bumps `updated_at` without touching any other column.
+/// Only derived for structs that have an `updated_at` field.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` has no `updated_at` column and cannot be touched",
+    label = "add an `updated_at: i64` field to `{Self}` to derive `SqlxTouch`"
+)]
+#[async_trait::async_trait]
+pub trait SqlxTouch: SqlxSchema + Sized {
+    /// Sets `updated_at` to the current unix timestamp and returns the refreshed row.
+    async fn touch<'e, E>(self, executor: E) -> Result<Self, SqlxError>
+    where
+        E: Executor<'e, Database = Postgres> + Send,
+        Self: Send;
+}
+
+/// Undo and hard-delete for tables marked `#[soft_delete]`, where
+/// [`SqlxCrud::delete`] and `delete_by_criteria` only set `deleted_at`.
+#[async_trait::async_trait]
+pub trait SqlxSoftDelete: SqlxSchema + Sized {
+    /// Clears `deleted_at` and returns the restored row.
+    async fn restore_deleted<'e, E>(self, executor: E) -> Result<Self, SqlxError>
+    where
+        E: Executor<'e, Database = Postgres> + Send,
+        Self: Send;
+
+    /// Permanently removes the row, whether or not it was soft-deleted.
+    async fn purge<'e, E>(self, executor: E) -> Result<u64, SqlxError>
+    where
+        E: Executor<'e, Database = Postgres> + Send,
+        Self: Send;
+}
+
+/// Insert-or-update for tables that declare `#[conflict_target(...)]`.
+#[async_trait::async_trait]
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be upserted",
+    note = "add `#[conflict_target(column, ...)]` to the struct deriving `SqlxObject`"
+)]
+pub trait SqlxUpsert: SqlxSchema + Sized {
+    /// Inserts the record, or on a conflict over the target columns updates every
+    /// other column from it (keeping `created_at`, refreshing `updated_at`).
+    async fn upsert<'e, E>(self, executor: E) -> Result<Self, SqlxError>
+    where
+        E: Executor<'e, Database = Postgres> + Send,
+        Self: Send;
+
+    /// Inserts the record unless a row with the same target columns exists, in
+    /// which case nothing changes and `None` is returned.
+    async fn create_if_absent<'e, E>(self, executor: E) -> Result<Option<Self>, SqlxError>
+    where
+        E: Executor<'e, Database = Postgres> + Send,
+        Self: Send;
+}
+
 /// Specifies the direction for ordering query results.
 #[derive(Debug, Clone, Copy)]
 pub enum OrderDirection {
@@ -110,12 +296,289 @@ where
     }
 }
 
+/// Escapes the `LIKE` wildcards `%` and `_` (and the escape character `\`
+/// itself) so `pattern` matches literally under `ESCAPE '\'`.
+pub fn escape_like(pattern: &str) -> String {
+    let mut escaped = String::with_capacity(pattern.len());
+    for c in pattern.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Operator of `add_jsonb_path_eq` conditions, which bind two values.
+const JSONB_PATH_EQ_OPERATOR: &str = "#>>";
+
+#[derive(Clone)]
+struct JsonbPathEq {
+    path: Vec<String>,
+    value: String,
+}
+
+impl JsonbPathEq {
+    const PLACEHOLDERS: usize = 2;
+}
+
+impl AsSqlxArg for JsonbPathEq {
+    fn add_to_args<'q>(&self, args: &mut PgArguments) -> Result<(), SqlxError> {
+        self.path.add_to_args(args)?;
+        self.value.add_to_args(args)
+    }
+}
+
+/// Text search configuration of `#[fulltext]` columns and `add_fulltext` queries.
+/// `simple` neither stems nor drops stop words, so it suits any language.
+pub const FULLTEXT_CONFIG: &str = "simple";
+
+/// Order key for the `ts_rank` of the `add_fulltext` query, e.g.
+/// `.order_by(FULLTEXT_RANK, OrderDirection::Desc)`.
+pub const FULLTEXT_RANK: &str = "fulltext_rank";
+
+/// The generated `tsvector` column backing a `#[fulltext]` column.
+pub fn fulltext_column(column: &str) -> String {
+    format!("{}_tsv", column)
+}
+
+/// Operator of `add_fulltext` conditions, rendered as `"column_tsv" @@ plainto_tsquery(..)`.
+const FULLTEXT_OPERATOR: &str = "@@";
+
+/// Holds the query of a full-text search, for ranking by `FULLTEXT_RANK`.
+pub struct FulltextSearch {
+    pub column: &'static str,
+    pub query: String,
+}
+
+impl FulltextSearch {
+    /// `ts_rank` of the column against the query bound as `$placeholder_idx`.
+    pub fn rank_sql(&self, placeholder_idx: usize) -> String {
+        format!(
+            "ts_rank(\"{}\", plainto_tsquery('{}', ${}))",
+            fulltext_column(self.column), FULLTEXT_CONFIG, placeholder_idx
+        )
+    }
+}
+
+/// Operator of `after_cursor` conditions, rendered as
+/// `("column", key...) > ($n, ...)` over the table's full primary key.
+const KEYSET_OPERATOR: &str = "> (keyset)";
+/// Like `KEYSET_OPERATOR` when paginating on the key itself: `(key...) > ($n, ...)`.
+const KEYSET_KEY_OPERATOR: &str = "> (keyset key)";
+
+/// Stands for every primary key column in `QueryCriteria::order_by`; the
+/// derived query expands it to `PRIMARY_KEY_COLUMNS`.
+pub const PRIMARY_KEY: &str = "(primary key)";
+
+/// A primary key as keyset pagination binds it: one placeholder per key
+/// column. Implemented for the key types `SqlxObject` generates.
+pub trait CursorKey: Send + Sync + Clone + 'static {
+    fn add_key_args(&self, args: &mut PgArguments) -> Result<(), SqlxError>;
+}
+
+macro_rules! scalar_cursor_key {
+    ($($ty:ty),*) => {
+        $(impl CursorKey for $ty {
+            fn add_key_args(&self, args: &mut PgArguments) -> Result<(), SqlxError> {
+                self.add_to_args(args)
+            }
+        })*
+    };
+}
+
+scalar_cursor_key!(Uuid, String, i64, i32);
+
+macro_rules! tuple_cursor_key {
+    ($($part:ident),+) => {
+        impl<$($part: CursorKey),+> CursorKey for ($($part,)+) {
+            #[allow(non_snake_case)]
+            fn add_key_args(&self, args: &mut PgArguments) -> Result<(), SqlxError> {
+                let ($($part,)+) = self;
+                $($part.add_key_args(args)?;)+
+                Ok(())
+            }
+        }
+    };
+}
+
+tuple_cursor_key!(A, B);
+tuple_cursor_key!(A, B, C);
+tuple_cursor_key!(A, B, C, D);
+
+/// Binds only the key of a cursor, for `KEYSET_KEY_OPERATOR`.
+struct CursorKeyArg<K>(K);
+
+impl<K: CursorKey> AsSqlxArg for CursorKeyArg<K> {
+    fn add_to_args<'q>(&self, args: &mut PgArguments) -> Result<(), SqlxError> {
+        self.0.add_key_args(args)
+    }
+}
+
+/// Where a page of keyset pagination ended: the sort column's value and the
+/// primary key of the last row, which orders rows sharing that value. `K` is
+/// the table's `SqlxSchema::Id`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Cursor<V, K = Uuid> {
+    pub value: V,
+    pub id: K,
+}
+
+impl<V, K> AsSqlxArg for Cursor<V, K>
+where
+    V: for<'a> ::sqlx::Encode<'a, Postgres> + ::sqlx::Type<Postgres> + Send + Sync + Clone + 'static,
+    K: CursorKey,
+{
+    fn add_to_args<'q>(&self, args: &mut PgArguments) -> Result<(), SqlxError> {
+        self.value.add_to_args(args)?;
+        self.id.add_key_args(args)
+    }
+}
+
+/// Renders as `{value}_{id}`, the form cursors take in query strings.
+impl<V: std::fmt::Display> std::fmt::Display for Cursor<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}_{}", self.value, self.id)
+    }
+}
+
+impl<V: std::str::FromStr> std::str::FromStr for Cursor<V> {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (value, id) = s.rsplit_once('_')
+            .ok_or_else(|| anyhow::anyhow!("[Cursor::from_str] Expected `{{value}}_{{id}}`, got {:?}", s))?;
+        Ok(Self {
+            value: value.parse().map_err(|_| anyhow::anyhow!("[Cursor::from_str] Invalid cursor value {:?}", value))?,
+            id: id.parse()?,
+        })
+    }
+}
+
+/// A page returned by `SqlxFilterQuery::find_page`. `next_cursor` is `None`
+/// once a page comes back short.
+#[derive(Debug, Clone)]
+pub struct PageResult<T, V, K = Uuid> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<Cursor<V, K>>,
+}
+
+/// Operator of `add_in_filter` conditions; the array is bound as `= ANY($n)`.
+pub const ANY_OPERATOR: &str = "= ANY";
+
 /// Represents a single filter condition for a database query.
 pub struct FilterCondition {
     pub column: &'static str,
     pub operator: &'static str,
     /// Holds the value for the condition's placeholder, if any.
     pub value: Option<Box<dyn AsSqlxArg>>,
+    /// SQL appended verbatim after the placeholder, e.g. ` ESCAPE '\'` or `::jsonb`.
+    pub suffix: Option<&'static str>,
+}
+
+impl FilterCondition {
+    /// Renders `"column" operator $n`, binding the value (if any) as the next
+    /// placeholder. Operators that already contain a `$` are used verbatim.
+    pub fn to_sql(&self, arguments: &mut PgArguments, placeholder_idx: &mut usize) -> Result<String, SqlxError> {
+        self.to_sql_with_key(&["id"], arguments, placeholder_idx)
+    }
+
+    /// Like `to_sql`, with `after_cursor` conditions compared over `key_columns`,
+    /// the table's `PRIMARY_KEY_COLUMNS`.
+    pub fn to_sql_with_key(&self, key_columns: &[&str], arguments: &mut PgArguments, placeholder_idx: &mut usize) -> Result<String, SqlxError> {
+        if self.operator == KEYSET_OPERATOR || self.operator == KEYSET_KEY_OPERATOR {
+            if let Some(cursor) = &self.value {
+                cursor.add_to_args(arguments)?;
+                let mut columns = key_columns.iter().map(|c| format!("\"{}\"", c)).collect::<Vec<_>>();
+                if self.operator == KEYSET_OPERATOR {
+                    columns.insert(0, format!("\"{}\"", self.column));
+                }
+                let placeholders = (0..columns.len()).map(|i| format!("${}", *placeholder_idx + i)).collect::<Vec<_>>();
+                *placeholder_idx += columns.len();
+                return Ok(format!("({}) > ({})", columns.join(", "), placeholders.join(", ")));
+            }
+        }
+
+        if self.operator == FULLTEXT_OPERATOR {
+            if let Some(query) = &self.value {
+                query.add_to_args(arguments)?;
+                let sql = format!(
+                    "\"{}\" @@ plainto_tsquery('{}', ${})",
+                    fulltext_column(self.column), FULLTEXT_CONFIG, placeholder_idx
+                );
+                *placeholder_idx += 1;
+                return Ok(sql);
+            }
+        }
+
+        let mut sql = format!("\"{}\" {}", self.column, self.operator);
+        if let Some(value) = &self.value {
+            value.add_to_args(arguments)?;
+            if self.operator == ANY_OPERATOR {
+                sql.push_str(&format!("(${})", placeholder_idx));
+            } else if self.operator == JSONB_PATH_EQ_OPERATOR {
+                sql.push_str(&format!(" ${} = ${}", placeholder_idx, *placeholder_idx + 1));
+                *placeholder_idx += JsonbPathEq::PLACEHOLDERS - 1;
+            } else if !self.operator.contains('$') {
+                sql.push_str(&format!(" ${}", placeholder_idx));
+            }
+            *placeholder_idx += 1;
+        }
+        if let Some(suffix) = self.suffix {
+            sql.push_str(suffix);
+        }
+        Ok(sql)
+    }
+}
+
+/// Distance function of a pgvector similarity search and its index.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VectorMetric {
+    #[default]
+    Cosine,
+    L2,
+    InnerProduct,
+}
+
+impl VectorMetric {
+    /// The pgvector distance operator.
+    pub fn operator(&self) -> &'static str {
+        match self {
+            VectorMetric::Cosine => "<=>",
+            VectorMetric::L2 => "<->",
+            VectorMetric::InnerProduct => "<#>",
+        }
+    }
+
+    /// Operator class an index must use for `operator` to be index-assisted.
+    pub fn opclass(&self) -> &'static str {
+        match self {
+            VectorMetric::Cosine => "vector_cosine_ops",
+            VectorMetric::L2 => "vector_l2_ops",
+            VectorMetric::InnerProduct => "vector_ip_ops",
+        }
+    }
+
+    /// Similarity of `column` to the vector bound as `$placeholder_idx`, higher
+    /// being closer. Cosine gives `1 - distance`, L2 `1 / (1 + distance)` and
+    /// inner product the inner product itself (`<#>` returns its negation).
+    /// Cosine distance to a zero vector is NaN, which Postgres sorts above every
+    /// number and which passes any threshold, so it is mapped to 0.
+    pub fn similarity_sql(&self, column: &str, placeholder_idx: usize) -> String {
+        match self {
+            VectorMetric::Cosine => format!("COALESCE(NULLIF(1 - ({} <=> ${}), 'NaN'), 0)", column, placeholder_idx),
+            VectorMetric::L2 => format!("1 / (1 + ({} <-> ${}))", column, placeholder_idx),
+            VectorMetric::InnerProduct => format!("({} <#> ${}) * -1", column, placeholder_idx),
+        }
+    }
+
+    /// HNSW index over `column` of `table` matching this metric.
+    pub fn index_sql(&self, table: &str, column: &str) -> String {
+        format!(
+            "CREATE INDEX IF NOT EXISTS \"idx_{}_{}_{}\" ON \"{}\" USING hnsw (\"{}\" {})",
+            table, column, self.opclass(), table, column, self.opclass()
+        )
+    }
 }
 
 /// Holds parameters for a vector similarity search.
@@ -123,6 +586,7 @@ pub struct SimilaritySearch {
     pub vector: pgvector::Vector,
     pub as_field: &'static str,
     pub threshold: Option<f32>,
+    pub metric: VectorMetric,
 }
 
 /// Represents the complete criteria for a filtered database query.
@@ -132,10 +596,20 @@ pub struct SimilaritySearch {
 #[derive(Default)]
 pub struct QueryCriteria {
     pub conditions: Vec<FilterCondition>,
+    /// Each group is rendered as `(a OR b ...)` and ANDed with the other conditions.
+    pub or_groups: Vec<Vec<FilterCondition>>,
     pub limit: Option<i64>,
     pub offset: Option<i64>,
     pub order_by: Vec<(&'static str, OrderDirection)>,
     pub similarity_search: Option<SimilaritySearch>,
+    /// Set by `add_fulltext`; makes `FULLTEXT_RANK` usable in `order_by`.
+    pub fulltext_search: Option<FulltextSearch>,
+    pub for_update: bool,
+    pub skip_locked: bool,
+    /// Also return soft-deleted rows on `#[soft_delete]` tables.
+    pub include_deleted: bool,
+    /// Sort column of keyset pagination, set by `after_cursor`.
+    pub cursor_column: Option<&'static str>,
 }
 
 impl QueryCriteria {
@@ -153,6 +627,7 @@ impl QueryCriteria {
             column,
             operator,
             value: value.map(|v| Box::new(v) as Box<dyn AsSqlxArg>),
+            suffix: None,
         });
         self
     }
@@ -165,6 +640,132 @@ impl QueryCriteria {
         self.add_filter(column, operator, Some(value))
     }
     
+    /// Matches rows whose `column` is one of `values`, binding them as a single
+    /// Postgres array: `"column" = ANY($n)`. An empty list matches nothing.
+    pub fn add_in_filter<V>(mut self, column: &'static str, values: Vec<V>) -> Self
+    where
+        Vec<V>: for<'a> ::sqlx::Encode<'a, Postgres> + ::sqlx::Type<Postgres> + Send + Sync + Clone + 'static,
+    {
+        if values.is_empty() {
+            // Renders as FALSE, see `filter_clauses`
+            self.or_groups.push(Vec::new());
+            return self;
+        }
+        self.add_valued_filter(column, ANY_OPERATOR, values)
+    }
+
+    /// Case-sensitive substring match: `"column" LIKE '%pattern%' ESCAPE '\'`.
+    /// `%`, `_` and `\` in `pattern` match literally, see `escape_like`.
+    pub fn add_like_filter(self, column: &'static str, pattern: &str) -> Self {
+        self.add_substring_filter(column, "LIKE", pattern)
+    }
+
+    /// Case-insensitive `add_like_filter`, using `ILIKE`.
+    pub fn add_ilike_filter(self, column: &'static str, pattern: &str) -> Self {
+        self.add_substring_filter(column, "ILIKE", pattern)
+    }
+
+    fn add_substring_filter(mut self, column: &'static str, operator: &'static str, pattern: &str) -> Self {
+        self.conditions.push(FilterCondition {
+            column,
+            operator,
+            value: Some(Box::new(format!("%{}%", escape_like(pattern))) as Box<dyn AsSqlxArg>),
+            suffix: Some(" ESCAPE '\\'"),
+        });
+        self
+    }
+
+    /// Matches rows whose JSONB `column` contains `value`: `"column" @> $n::jsonb`.
+    /// For example `json!(["Roleplay"])` against `features`.
+    pub fn add_jsonb_contains(mut self, column: &'static str, value: serde_json::Value) -> Self {
+        self.conditions.push(FilterCondition {
+            column,
+            operator: "@>",
+            value: Some(Box::new(sqlx::types::Json(value)) as Box<dyn AsSqlxArg>),
+            suffix: Some("::jsonb"),
+        });
+        self
+    }
+
+    /// Matches rows where the JSONB value at `path` in `column`, as text, equals
+    /// `value`: `"column" #>> $n = $m`, with the path bound as a text array.
+    /// JSON `true` compares as `"true"`.
+    pub fn add_jsonb_path_eq(mut self, column: &'static str, path: &[&str], value: impl Into<String>) -> Self {
+        let path = path.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        self.conditions.push(FilterCondition {
+            column,
+            operator: JSONB_PATH_EQ_OPERATOR,
+            value: Some(Box::new(JsonbPathEq { path, value: value.into() }) as Box<dyn AsSqlxArg>),
+            suffix: None,
+        });
+        self
+    }
+
+    /// Matches rows whose `#[fulltext]` `column` contains the words of `query`:
+    /// `"column_tsv" @@ plainto_tsquery($n)`. Order by `FULLTEXT_RANK` to get
+    /// the best matches first; with several calls the last query is ranked.
+    pub fn add_fulltext(mut self, column: &'static str, query: &str) -> Self {
+        self.conditions.push(FilterCondition {
+            column,
+            operator: FULLTEXT_OPERATOR,
+            value: Some(Box::new(query.to_string()) as Box<dyn AsSqlxArg>),
+            suffix: None,
+        });
+        self.fulltext_search = Some(FulltextSearch { column, query: query.to_string() });
+        self
+    }
+
+    /// Adds a group of conditions where any one matching is enough, e.g.
+    /// `name ILIKE $1 OR description ILIKE $2`. The group is ANDed with the rest.
+    pub fn or_group<V>(mut self, conditions: Vec<(&'static str, &'static str, V)>) -> Self
+    where
+        V: for<'a> ::sqlx::Encode<'a, Postgres> + ::sqlx::Type<Postgres> + Send + Sync + Clone + 'static,
+    {
+        self.or_groups.push(conditions.into_iter()
+            .map(|(column, operator, value)| FilterCondition {
+                column,
+                operator,
+                value: Some(Box::new(value) as Box<dyn AsSqlxArg>),
+                suffix: None,
+            })
+            .collect());
+        self
+    }
+
+    /// Matches rows whose `column` is NULL or satisfies `operator value`, e.g.
+    /// `("claimed_at" IS NULL OR "claimed_at" < $1)`. ANDed with the rest.
+    pub fn add_null_or_filter<V>(mut self, column: &'static str, operator: &'static str, value: V) -> Self
+    where
+        V: for<'a> ::sqlx::Encode<'a, Postgres> + ::sqlx::Type<Postgres> + Send + Sync + Clone + 'static,
+    {
+        self.or_groups.push(vec![
+            FilterCondition { column, operator: "IS NULL", value: None, suffix: None },
+            FilterCondition { column, operator, value: Some(Box::new(value) as Box<dyn AsSqlxArg>), suffix: None },
+        ]);
+        self
+    }
+
+    /// Renders the filter conditions and OR groups as clauses to be joined with
+    /// `AND`, binding their values from `placeholder_idx` onwards.
+    pub fn filter_clauses(&self, key_columns: &[&str], arguments: &mut PgArguments, placeholder_idx: &mut usize) -> Result<Vec<String>, SqlxError> {
+        let mut clauses = Vec::with_capacity(self.conditions.len() + self.or_groups.len());
+        for condition in &self.conditions {
+            clauses.push(condition.to_sql_with_key(key_columns, arguments, placeholder_idx)?);
+        }
+        for group in &self.or_groups {
+            // An empty OR matches nothing
+            if group.is_empty() {
+                clauses.push("FALSE".to_string());
+                continue;
+            }
+            let parts = group.iter()
+                .map(|condition| condition.to_sql(arguments, placeholder_idx))
+                .collect::<Result<Vec<_>, _>>()?;
+            clauses.push(format!("({})", parts.join(" OR ")));
+        }
+        Ok(clauses)
+    }
+
     /// Sets the LIMIT for the query.
     pub fn limit(mut self, limit_val: i64) -> Self {
         self.limit = Some(limit_val);
@@ -183,16 +784,75 @@ impl QueryCriteria {
         self
     }
 
+    /// Keyset pagination on `column`, then the primary key for rows sharing a
+    /// value: only rows after `cursor` match, and the ordering is replaced with
+    /// `"column" ASC` followed by every key column. Pass `None` for the first
+    /// page, and `"id"` to page by a `Uuid` `id` alone. Use with
+    /// `SqlxFilterQuery::find_page`, whose cursors carry the whole key.
+    pub fn after_cursor<V, K>(mut self, column: &'static str, cursor: Option<Cursor<V, K>>) -> Self
+    where
+        V: for<'a> ::sqlx::Encode<'a, Postgres> + ::sqlx::Type<Postgres> + Send + Sync + Clone + 'static,
+        K: CursorKey,
+    {
+        self.cursor_column = Some(column);
+        self.order_by = if column == "id" {
+            vec![(PRIMARY_KEY, OrderDirection::Asc)]
+        } else {
+            vec![(column, OrderDirection::Asc), (PRIMARY_KEY, OrderDirection::Asc)]
+        };
+
+        let Some(cursor) = cursor else {
+            return self;
+        };
+        let (operator, value) = if column == "id" {
+            (KEYSET_KEY_OPERATOR, Box::new(CursorKeyArg(cursor.id)) as Box<dyn AsSqlxArg>)
+        } else {
+            (KEYSET_OPERATOR, Box::new(cursor) as Box<dyn AsSqlxArg>)
+        };
+        self.conditions.push(FilterCondition { column, operator, value: Some(value), suffix: None });
+        self
+    }
+
+    /// Locks the selected rows with `FOR UPDATE` until the transaction ends.
+    pub fn for_update(mut self) -> Self {
+        self.for_update = true;
+        self
+    }
+
+    /// Locks with `FOR UPDATE SKIP LOCKED`, silently skipping rows already
+    /// locked by another transaction. Useful for queue-style consumers.
+    pub fn skip_locked(mut self) -> Self {
+        self.for_update = true;
+        self.skip_locked = true;
+        self
+    }
+
+    /// Includes rows whose `deleted_at` is set. No effect on tables without `#[soft_delete]`.
+    pub fn include_deleted(mut self) -> Self {
+        self.include_deleted = true;
+        self
+    }
+
     /// Configures a vector similarity search.
     pub fn find_similarity(mut self, vector: pgvector::Vector, as_field: &'static str) -> Self {
         self.similarity_search = Some(SimilaritySearch {
             vector,
             as_field,
             threshold: None,
+            metric: VectorMetric::default(),
         });
         self
     }
 
+    /// Sets the distance function of a vector search, cosine by default.
+    /// This is only effective if `find_similarity` has also been called.
+    pub fn with_similarity_metric(mut self, metric: VectorMetric) -> Self {
+        if let Some(ss) = &mut self.similarity_search {
+            ss.metric = metric;
+        }
+        self
+    }
+
     /// Sets the similarity threshold for a vector search.
     /// This is only effective if `find_similarity` has also been called.
     pub fn with_similarity_threshold(mut self, threshold: f32) -> Self {
@@ -235,6 +895,59 @@ pub trait SqlxFilterQuery: SqlxSchema + Sized {
         Ok(results.pop()) // Returns None if empty, or the single element.
     }
 
+    /// Fetches up to `limit` rows of a keyset-paginated query built with
+    /// `QueryCriteria::after_cursor`. Pass `next_cursor` back to `after_cursor`
+    /// for the following page.
+    async fn find_page<'e, E, V>(
+        criteria: QueryCriteria,
+        limit: i64,
+        executor: E,
+    ) -> Result<PageResult<Self, V, Self::Id>, SqlxError>
+    where
+        E: Executor<'e, Database = Postgres> + Send,
+        Self: SqlxSchema + serde::Serialize + Send,
+        V: serde::de::DeserializeOwned + Send,
+    {
+        let column = criteria.cursor_column
+            .ok_or_else(|| SqlxError::Protocol("[SqlxFilterQuery::find_page] criteria has no cursor, see QueryCriteria::after_cursor".to_string()))?;
+        let items = Self::find_by_criteria(criteria.limit(limit), executor).await?;
+
+        let next_cursor = match items.last() {
+            Some(last) if items.len() as i64 >= limit => {
+                let row = serde_json::to_value(last).map_err(|e| SqlxError::Decode(Box::new(e)))?;
+                let value = row.get(column).cloned()
+                    .ok_or_else(|| SqlxError::ColumnNotFound(column.to_string()))?;
+                Some(Cursor {
+                    value: serde_json::from_value(value).map_err(|e| SqlxError::Decode(Box::new(e)))?,
+                    id: last.get_id_value(),
+                })
+            }
+            _ => None,
+        };
+        Ok(PageResult { items, next_cursor })
+    }
+
+    /// Counts the records `find_by_criteria` would match, ignoring ordering,
+    /// `limit` and `offset`. A similarity search only narrows the count when it
+    /// has a threshold.
+    async fn count_by_criteria<'e, E>(
+        criteria: QueryCriteria,
+        executor: E,
+    ) -> Result<i64, SqlxError>
+    where
+        E: Executor<'e, Database = Postgres> + Send,
+        Self: Send;
+
+    /// Whether `find_by_criteria` would match any record, without fetching one.
+    /// Ignores ordering, `limit` and `offset` like `count_by_criteria`.
+    async fn exists_by_criteria<'e, E>(
+        criteria: QueryCriteria,
+        executor: E,
+    ) -> Result<bool, SqlxError>
+    where
+        E: Executor<'e, Database = Postgres> + Send,
+        Self: Send;
+
     /// Deletes records based on the provided criteria.
     /// The implementation of this method is typically generated by the SqlxObject derive macro.
     async fn delete_by_criteria<'e, E>(
@@ -253,6 +966,9 @@ pub trait TextEnumCodec: Sized {
     /// Generates a string representation of the enum suitable for including in a prompt.
     fn to_prompt_text(&self, lang: &str) -> String;
 
+    /// Parses text in the format produced by `to_prompt_text`.
+    fn from_prompt_text(text: &str) -> anyhow::Result<Self>;
+
     /// Generate JSON schema for tool calls.
     fn schema(lang: Option<&str>) -> serde_json::Value;
 
@@ -263,8 +979,271 @@ pub trait TextEnumCodec: Sized {
     fn schema_lang() -> &'static str { "en" }
 }
 
+/// Delimiters of the paren prompt format, `Prefix(a,[b])`, escaped inside content.
+const PAREN_DELIMITERS: &[char] = &['(', ')', ',', '[', ']', '\\'];
+
+/// Escapes the paren format delimiters `(`, `)`, `,`, `[`, `]` and `\` with a
+/// backslash, so content reads back exactly through `unescape_text`.
+pub fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if PAREN_DELIMITERS.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Reverses `escape_text`. A backslash before anything but a delimiter is kept,
+/// so hand-written text such as `C:\temp` is left alone.
+pub fn unescape_text(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(next) if c == '\\' && PAREN_DELIMITERS.contains(next) => {
+                unescaped.push(*next);
+                chars.next();
+            }
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Delimiters around and between the items of `Vec<String>` variant content,
+/// set per variant with `#[collection(open = "[", sep = "; ", close = "]")]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextListFormat {
+    pub open: &'static str,
+    pub sep: &'static str,
+    pub close: &'static str,
+}
+
+impl Default for TextListFormat {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl TextListFormat {
+    /// Bare `,`-separated items, as in `a,b,c`.
+    pub const DEFAULT: Self = Self { open: "", sep: ",", close: "" };
+
+    /// Joins `items` with `sep` between `open` and `close`, escaping `\`, `[`, `]`
+    /// and the first character of each delimiter inside items so `decode`
+    /// reconstructs them exactly.
+    pub fn encode(&self, items: &[String]) -> String {
+        self.encode_escaping(items, &[])
+    }
+
+    /// Like `encode`, also escaping the paren format's `(` and `)`.
+    pub fn encode_paren(&self, items: &[String]) -> String {
+        self.encode_escaping(items, &['(', ')'])
+    }
+
+    fn encode_escaping(&self, items: &[String], extra: &[char]) -> String {
+        let delimiters: Vec<char> = [self.open, self.sep, self.close].iter()
+            .filter_map(|d| d.chars().next())
+            .collect();
+        let joined = items.iter()
+            .map(|item| {
+                let mut escaped = String::with_capacity(item.len());
+                for c in item.chars() {
+                    if matches!(c, '[' | ']' | '\\') || delimiters.contains(&c) || extra.contains(&c) {
+                        escaped.push('\\');
+                    }
+                    escaped.push(c);
+                }
+                escaped
+            })
+            .collect::<Vec<_>>()
+            .join(self.sep);
+        format!("{}{}{}", self.open, joined, self.close)
+    }
+
+    /// Strips `open` and `close` when present, splits on unescaped `sep` and
+    /// unescapes each item. Items are trimmed before unescaping, matching the
+    /// lenient parsing of hand-written lists; blank content is an empty list.
+    pub fn decode(&self, s: &str) -> Vec<String> {
+        let s = s.trim();
+        let s = s.strip_prefix(self.open).unwrap_or(s);
+        let s = match s.strip_suffix(self.close) {
+            // An escaped close belongs to the last item
+            Some(rest) if rest.chars().rev().take_while(|c| *c == '\\').count() % 2 == 0 => rest,
+            _ => s,
+        };
+        if s.trim().is_empty() {
+            return vec![];
+        }
+
+        let mut raw_items = vec![String::new()];
+        let mut rest = s;
+        while let Some(c) = rest.chars().next() {
+            if !self.sep.is_empty() && rest.starts_with(self.sep) {
+                raw_items.push(String::new());
+                rest = &rest[self.sep.len()..];
+                continue;
+            }
+
+            let current = raw_items.last_mut().expect("raw_items is never empty");
+            current.push(c);
+            rest = &rest[c.len_utf8()..];
+            if c == '\\' {
+                if let Some(next) = rest.chars().next() {
+                    current.push(next);
+                    rest = &rest[next.len_utf8()..];
+                }
+            }
+        }
+
+        raw_items.iter()
+            .map(|raw| {
+                let mut item = String::with_capacity(raw.len());
+                let mut chars = raw.trim().chars();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => item.push(chars.next().unwrap_or('\\')),
+                        _ => item.push(c),
+                    }
+                }
+                item
+            })
+            .collect()
+    }
+}
+
+/// Joins `Vec<String>` variant content with `,`, escaping `,`, `[`, `]` and `\`
+/// inside elements so `decode_text_list` reconstructs them exactly.
+pub fn encode_text_list(items: &[String]) -> String {
+    TextListFormat::DEFAULT.encode(items)
+}
+
+/// Splits on unescaped `,` and unescapes each element. Elements are trimmed
+/// before unescaping, matching the lenient parsing of hand-written lists.
+pub fn decode_text_list(s: &str) -> Vec<String> {
+    TextListFormat::DEFAULT.decode(s)
+}
+
+/// Controls how `SchemaMigrator::migrate_with` applies schema changes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MigrateOptions {
+    /// Log and return the statements without executing them.
+    pub dry_run: bool,
+    /// Allow dropping columns that are no longer on the struct. Drops still
+    /// require `#[allow_column_dropping]` on the struct itself.
+    pub allow_drops: bool,
+}
+
+/// A row whose enum column could not be parsed back into its `TextEnumCodec` type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidEnumRow {
+    pub id: Uuid,
+    pub value: serde_json::Value,
+    pub error: String,
+}
+
+/// Scans `table.column` and returns every row whose value does not deserialize
+/// into `T` in any of its languages, e.g. double-quoted values left behind by a
+/// bad migration. The table must have a UUID `id` column.
+pub async fn verify_text_enum_column<'e, T, E>(
+    executor: E,
+    table: &str,
+    column: &str,
+) -> anyhow::Result<Vec<InvalidEnumRow>>
+where
+    T: TextEnumCodec + serde::de::DeserializeOwned,
+    E: Executor<'e, Database = Postgres>,
+{
+    use futures::TryStreamExt;
+    use sqlx::Row;
+
+    let query = format!("SELECT \"id\", \"{}\"::jsonb AS value FROM \"{}\" ORDER BY \"id\"", column, table);
+    let mut rows = sqlx::query(&query).fetch(executor);
+
+    let mut invalid = Vec::new();
+    while let Some(row) = rows.try_next().await? {
+        let value: serde_json::Value = row.try_get("value")?;
+        if let Err(e) = serde_json::from_value::<T>(value.clone()) {
+            invalid.push(InvalidEnumRow { id: row.try_get("id")?, value, error: e.to_string() });
+        }
+    }
+
+    Ok(invalid)
+}
+
+/// An enum stored as a native Postgres enum type, implemented by `#[pg_enum]` on a `TextEnum`.
+pub trait PgEnum {
+    /// Name of the Postgres type, e.g. `user_role` for `UserRole`.
+    const TYPE_NAME: &'static str;
+    /// Labels in declaration order.
+    const VARIANTS: &'static [&'static str];
+}
+
+/// What it takes to bring a `PgEnum` type in the database up to date.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PgEnumPlan {
+    /// `CREATE TYPE` for a missing type, otherwise one `ALTER TYPE ... ADD VALUE` per new variant.
+    pub statements: Vec<String>,
+    /// Labels in the database that the enum no longer has. They are never dropped.
+    pub unknown_labels: Vec<String>,
+}
+
+/// Compares `T` with its Postgres enum type. New variants are added after the
+/// variant preceding them in `T`, so the type keeps the enum's order.
+pub async fn plan_pg_enum_migration<T: PgEnum>(pool: &sqlx::PgPool) -> anyhow::Result<PgEnumPlan> {
+    let existing: Option<Vec<String>> = sqlx::query_scalar(
+        "SELECT COALESCE(array_agg(e.enumlabel::text ORDER BY e.enumsortorder) FILTER (WHERE e.enumlabel IS NOT NULL), '{}')
+         FROM pg_type t
+         JOIN pg_namespace n ON n.oid = t.typnamespace
+         LEFT JOIN pg_enum e ON e.enumtypid = t.oid
+         WHERE n.nspname = 'public' AND t.typname = $1
+         GROUP BY t.oid"
+    )
+    .bind(T::TYPE_NAME)
+    .fetch_optional(pool)
+    .await?;
+
+    let quote = |label: &str| format!("'{}'", label.replace('\'', "''"));
+
+    let Some(labels) = existing else {
+        let variants = T::VARIANTS.iter().map(|v| quote(v)).collect::<Vec<_>>().join(", ");
+        return Ok(PgEnumPlan {
+            statements: vec![format!("CREATE TYPE \"{}\" AS ENUM ({})", T::TYPE_NAME, variants)],
+            unknown_labels: Vec::new(),
+        });
+    };
+
+    let mut statements = Vec::new();
+    for (i, variant) in T::VARIANTS.iter().enumerate() {
+        if labels.iter().any(|label| label == variant) {
+            continue;
+        }
+        let position = match (i.checked_sub(1), labels.first()) {
+            (Some(prev), _) => format!(" AFTER {}", quote(T::VARIANTS[prev])),
+            (None, Some(first)) => format!(" BEFORE {}", quote(first)),
+            (None, None) => String::new(),
+        };
+        statements.push(format!("ALTER TYPE \"{}\" ADD VALUE IF NOT EXISTS {}{}", T::TYPE_NAME, quote(variant), position));
+    }
+
+    let unknown_labels = labels.into_iter()
+        .filter(|label| !T::VARIANTS.contains(&label.as_str()))
+        .collect();
+
+    Ok(PgEnumPlan { statements, unknown_labels })
+}
+
 #[async_trait::async_trait]
 pub trait SchemaMigrator {
     /// Compares the struct's schema with the database and applies necessary changes.
-    async fn migrate(pool: &sqlx::PgPool) -> anyhow::Result<()>;
+    async fn migrate(pool: &sqlx::PgPool) -> anyhow::Result<()> {
+        Self::migrate_with(pool, MigrateOptions { dry_run: false, allow_drops: true }).await?;
+        Ok(())
+    }
+
+    /// Like `migrate`, returning the DDL statements that were (or, with
+    /// `dry_run`, would be) executed.
+    async fn migrate_with(pool: &sqlx::PgPool, options: MigrateOptions) -> anyhow::Result<Vec<String>>;
 }
@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use sqlx::{PgPool, Postgres, Transaction};
+
+/// SQLSTATE `serialization_failure`, raised when concurrent `SERIALIZABLE` or
+/// `REPEATABLE READ` transactions conflict and one of them has to start over.
+pub const SERIALIZATION_FAILURE_CODE: &str = "40001";
+
+/// Backoff before the first retry of [`with_retry`]; doubled for every retry after it.
+pub const RETRY_BASE_DELAY: Duration = Duration::from_millis(20);
+
+/// Errors [`with_retry`] can look through for a serialization failure.
+pub trait SerializationFailure {
+    fn is_serialization_failure(&self) -> bool;
+}
+
+impl SerializationFailure for sqlx::Error {
+    fn is_serialization_failure(&self) -> bool {
+        self.as_database_error()
+            .and_then(|e| e.code())
+            .is_some_and(|code| code == SERIALIZATION_FAILURE_CODE)
+    }
+}
+
+impl SerializationFailure for anyhow::Error {
+    fn is_serialization_failure(&self) -> bool {
+        self.chain()
+            .filter_map(|e| e.downcast_ref::<sqlx::Error>())
+            .any(|e| e.is_serialization_failure())
+    }
+}
+
+/// Runs `f` in a transaction on `pool` and commits it, starting over with
+/// exponential backoff whenever `f` or the commit fails with a serialization
+/// failure, at most `max_retries` times. Returns `f`'s value, or the last error.
+///
+/// The transaction uses the session's isolation level; run
+/// `SET TRANSACTION ISOLATION LEVEL ...` first in `f` to raise it. As `f` may
+/// run several times, it should not have side effects outside the transaction.
+pub async fn with_retry<T, E, F>(pool: &PgPool, mut f: F, max_retries: u32) -> Result<T, E>
+where
+    F: for<'c> FnMut(&'c mut Transaction<'static, Postgres>) -> BoxFuture<'c, Result<T, E>>,
+    E: From<sqlx::Error> + SerializationFailure,
+{
+    let mut attempt = 0;
+    loop {
+        let mut tx = pool.begin().await?;
+        let result = match f(&mut tx).await {
+            Ok(value) => tx.commit().await.map(|_| value).map_err(E::from),
+            // Dropping `tx` rolls it back
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Err(e) if attempt < max_retries && e.is_serialization_failure() => {
+                tokio::time::sleep(RETRY_BASE_DELAY * 2u32.pow(attempt)).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
@@ -18,8 +18,8 @@ pub fn generate_row_struct(row_struct_name: &Ident, fields_data: &[FieldData]) -
         let mut row_field_ty = field_ty.clone();
 
         if !is_simple_type(&type_for_analysis) && !is_json_type_for_analysis && !fq_type_str_for_analysis.starts_with("Option<") && !fq_type_str_for_analysis.starts_with("Vec<") {
-            // If the SQL type is JSONB, keep the original enum type (for TextEnum support)
-            if field.sql_type == "JSONB" {
+            // If the SQL type is JSONB or a `#[pg_enum]`, keep the original enum type (for TextEnum support)
+            if field.sql_type == "JSONB" || field.pg_enum {
                 // Keep the original type as it likely has proper SQLx JSONB implementations
                 row_field_ty = field_ty.clone();
             } else {
@@ -45,7 +45,7 @@ pub fn generate_row_struct(row_struct_name: &Ident, fields_data: &[FieldData]) -
     }
 }
 
-pub fn generate_sqlx_schema_impl(struct_name: &Ident, row_struct_name: &Ident, table_name_str: &str, fields_data: &[FieldData]) -> TokenStream {
+pub fn generate_sqlx_schema_impl(struct_name: &Ident, row_struct_name: &Ident, table_name_str: &str, fields_data: &[FieldData], conflict_target: Option<&[String]>, soft_delete: bool) -> TokenStream {
     let active_fields: Vec<_> = fields_data.iter().filter(|f| !f.is_skipped).collect();
     let has_updated_at = active_fields.iter().any(|f| f.name == "updated_at");
 
@@ -54,8 +54,15 @@ pub fn generate_sqlx_schema_impl(struct_name: &Ident, row_struct_name: &Ident, t
         .collect();
 
     let from_row_assignments = generate_from_row_assignments(fields_data);
+    let row_at_assignments: Vec<TokenStream> = active_fields.iter().enumerate().map(|(i, f)| {
+        let ident = format_ident!("{}", f.name);
+        quote! { #ident: ::sqlx::Row::try_get(row, offset + #i)? }
+    }).collect();
 
-    let (create_table_sql_query, create_index_sqls) = generate_create_table_sql(table_name_str, fields_data);
+    let (create_table_sql_query, mut create_index_sqls) = generate_create_table_sql(table_name_str, fields_data);
+    if let Some((_, index_sql)) = conflict_target_index_sql(table_name_str, conflict_target) {
+        create_index_sqls.push(LitStr::new(&index_sql, proc_macro2::Span::call_site()));
+    }
     let drop_table_sql_query = format!("DROP TABLE IF EXISTS \"{}\" CASCADE", table_name_str);
     let insert_sql_query = generate_insert_sql(table_name_str, &active_fields);
     
@@ -70,7 +77,27 @@ pub fn generate_sqlx_schema_impl(struct_name: &Ident, row_struct_name: &Ident, t
         "".to_string()
     };
 
-    let final_pk_id_trait_type: Type = parse_quote!(::sqlx::types::Uuid);
+    let keys = key_fields(fields_data);
+    let key_column_lits: Vec<LitStr> = keys.iter()
+        .map(|f| LitStr::new(&f.name, proc_macro2::Span::call_site()))
+        .collect();
+    let id_column_name = keys.first().map(|f| f.name.clone()).unwrap_or_else(|| "id".to_string());
+    let key_idents: Vec<_> = keys.iter().map(|f| format_ident!("{}", f.name)).collect();
+    let key_types: Vec<_> = keys.iter().map(|f| f.ty.clone()).collect();
+    let (final_pk_id_trait_type, get_id_value_impl): (Type, TokenStream) = if keys.iter().any(|f| f.is_key) {
+        if keys.len() == 1 {
+            let (ty, ident) = (&key_types[0], &key_idents[0]);
+            (parse_quote!(#ty), quote! { self.#ident.clone() })
+        } else {
+            (parse_quote!((#(#key_types),*)), quote! { (#(self.#key_idents.clone()),*) })
+        }
+    } else {
+        (parse_quote!(::sqlx::types::Uuid), quote! { self.id })
+    };
+    let version_column = match lock_field(fields_data) {
+        Some(f) => { let name = &f.name; quote! { Some(#name) } }
+        None => quote! { None },
+    };
 
     quote! {
         #[automatically_derived]
@@ -79,11 +106,14 @@ pub fn generate_sqlx_schema_impl(struct_name: &Ident, row_struct_name: &Ident, t
             type Row = #row_struct_name;
 
             const TABLE_NAME: &'static str = #table_name_str;
-            const ID_COLUMN_NAME: &'static str = "id";
+            const ID_COLUMN_NAME: &'static str = #id_column_name;
+            const PRIMARY_KEY_COLUMNS: &'static [&'static str] = &[#( #key_column_lits ),*];
             const COLUMNS: &'static [&'static str] = &[#( #all_sql_column_names_str_lits ),*];
             const INDEXES_SQL: &'static [&'static str] = &[#( #create_index_sqls ),*];
+            const VERSION_COLUMN: Option<&'static str> = #version_column;
+            const SOFT_DELETE: bool = #soft_delete;
 
-            fn get_id_value(&self) -> Self::Id { self.id }
+            fn get_id_value(&self) -> Self::Id { #get_id_value_impl }
 
             fn from_row(row: Self::Row) -> Self {
                 Self {
@@ -91,6 +121,12 @@ pub fn generate_sqlx_schema_impl(struct_name: &Ident, row_struct_name: &Ident, t
                 }
             }
 
+            fn row_at(row: &::sqlx::postgres::PgRow, offset: usize) -> Result<Self::Row, ::sqlx::Error> {
+                Ok(#row_struct_name {
+                    #(#row_at_assignments),*
+                })
+            }
+
             fn insert_sql() -> String { #insert_sql_query.to_string() }
             fn create_table_sql() -> String { #create_table_sql_query.to_string() }
             fn drop_table_sql() -> String { #drop_table_sql_query.to_string() }
@@ -99,10 +135,47 @@ pub fn generate_sqlx_schema_impl(struct_name: &Ident, row_struct_name: &Ident, t
     }
 }
 
-pub fn generate_sqlx_crud_impl(struct_name: &Ident, table_name_str: &str, fields_data: &[FieldData]) -> TokenStream {
+pub fn generate_sqlx_crud_impl(struct_name: &Ident, table_name_str: &str, fields_data: &[FieldData], soft_delete: bool) -> TokenStream {
     let (insert_bindings, update_bindings) = generate_bind_streams(fields_data);
     let (update_sql, is_select_only) = generate_update_sql(table_name_str, fields_data);
-    let delete_sql = format!("DELETE FROM \"{}\" WHERE \"id\" = $1", table_name_str);
+    let key_bindings = key_bindings(fields_data);
+    let (lock_binding, fetch_updated) = match lock_field(fields_data) {
+        Some(f) => {
+            let ident = format_ident!("{}", f.name);
+            (
+                quote! { .bind(self.#ident) },
+                quote! {
+                    .fetch_optional(executor)
+                    .await?
+                    .map(<Self as ::metastable_database::SqlxSchema>::from_row)
+                    .ok_or_else(|| ::metastable_database::VersionConflict { table: #table_name_str }.into())
+                },
+            )
+        }
+        None => (
+            quote! {},
+            quote! {
+                .fetch_one(executor)
+                .await
+                .map(<Self as ::metastable_database::SqlxSchema>::from_row)
+            },
+        ),
+    };
+    let delete_sql = if soft_delete {
+        format!(
+            "UPDATE \"{}\" SET \"deleted_at\" = floor(extract(epoch from now())) WHERE {} AND \"deleted_at\" IS NULL",
+            table_name_str, key_where_sql(fields_data, 1)
+        )
+    } else {
+        format!("DELETE FROM \"{}\" WHERE {}", table_name_str, key_where_sql(fields_data, 1))
+    };
+    let (delete_by_ids_sql, delete_by_ids_bindings) = generate_delete_by_ids_sql(table_name_str, fields_data, soft_delete);
+    let force_set_timestamp_sql = format!(
+        "UPDATE \"{}\" SET \"created_at\" = $1, \"updated_at\" = $2 WHERE {} RETURNING *",
+        table_name_str, key_where_sql(fields_data, 3)
+    );
+    let (batch_insert_prefix, batch_insert_suffix, insert_column_count) = generate_batch_insert_sql_parts(table_name_str, fields_data);
+    let (restore_sql, restore_skip_sql, restore_overwrite_sql, restore_id_bindings, restore_timestamp_bindings) = generate_restore_sql(table_name_str, fields_data);
     
     quote! {
         #[automatically_derived]
@@ -119,12 +192,9 @@ pub fn generate_sqlx_crud_impl(struct_name: &Ident, table_name_str: &str, fields
                 &self, 
                 query: ::sqlx::query::QueryAs<'q, ::sqlx::Postgres, <Self as ::metastable_database::SqlxSchema>::Row, ::sqlx::postgres::PgArguments>
             ) -> ::sqlx::query::QueryAs<'q, ::sqlx::Postgres, <Self as ::metastable_database::SqlxSchema>::Row, ::sqlx::postgres::PgArguments> {
-                let query = query #(#update_bindings)*;
-                if #is_select_only {
-                    query.bind(self.id)
-                } else {
-                    query.bind(self.id)
-                }
+                // With no updatable columns the statement is a SELECT keyed from $1
+                let query = if #is_select_only { query } else { query #(#update_bindings)* };
+                query #(#key_bindings)* #lock_binding
             }
 
             async fn create<'e, E>(self, executor: E) -> Result<Self, ::sqlx::Error>
@@ -139,6 +209,61 @@ pub fn generate_sqlx_crud_impl(struct_name: &Ident, table_name_str: &str, fields
                     .map(<Self as ::metastable_database::SqlxSchema>::from_row)
             }
 
+            async fn create_many<'a, A>(items: Vec<Self>, conn: A) -> Result<Vec<Self>, ::sqlx::Error>
+            where
+                A: ::sqlx::Acquire<'a, Database = ::sqlx::Postgres> + Send,
+                Self: Send
+            {
+                if items.is_empty() {
+                    return Ok(Vec::new());
+                }
+
+                let rows_per_statement = (::metastable_database::POSTGRES_MAX_BIND_PARAMS / #insert_column_count.max(1)).max(1);
+                let mut conn = conn.acquire().await?;
+                let mut created = Vec::with_capacity(items.len());
+                for chunk in items.chunks(rows_per_statement) {
+                    let values_sql = (0..chunk.len())
+                        .map(|row| {
+                            let placeholders = (1..=#insert_column_count)
+                                .map(|col| format!("${}", row * #insert_column_count + col))
+                                .collect::<Vec<String>>()
+                                .join(", ");
+                            format!("({})", placeholders)
+                        })
+                        .collect::<Vec<String>>()
+                        .join(", ");
+                    let sql = format!("{}{}{}", #batch_insert_prefix, values_sql, #batch_insert_suffix);
+
+                    let mut query = ::sqlx::query_as::<_, <Self as ::metastable_database::SqlxSchema>::Row>(&sql);
+                    for item in chunk {
+                        query = item.bind_insert(query);
+                    }
+
+                    // Postgres returns the rows of a single multi-row VALUES insert in input order
+                    let rows = query.fetch_all(&mut *conn).await?;
+                    created.extend(rows.into_iter().map(<Self as ::metastable_database::SqlxSchema>::from_row));
+                }
+                Ok(created)
+            }
+
+            async fn restore<'e, E>(self, on_conflict: ::metastable_database::OnConflict, executor: E) -> Result<Option<Self>, ::sqlx::Error>
+            where
+                E: ::sqlx::Executor<'e, Database = ::sqlx::Postgres> + Send,
+                Self: Send
+            {
+                let sql = match on_conflict {
+                    ::metastable_database::OnConflict::Fail => #restore_sql,
+                    ::metastable_database::OnConflict::Skip => #restore_skip_sql,
+                    ::metastable_database::OnConflict::Overwrite => #restore_overwrite_sql,
+                };
+                let query = ::sqlx::query_as::<_, <Self as ::metastable_database::SqlxSchema>::Row>(sql)
+                    #(#restore_id_bindings)*;
+                self.bind_insert(query) #(#restore_timestamp_bindings)*
+                    .fetch_optional(executor)
+                    .await
+                    .map(|row| row.map(<Self as ::metastable_database::SqlxSchema>::from_row))
+            }
+
             async fn update<'e, E>(self, executor: E) -> Result<Self, ::sqlx::Error>
             where
                 E: ::sqlx::Executor<'e, Database = ::sqlx::Postgres> + Send,
@@ -146,9 +271,7 @@ pub fn generate_sqlx_crud_impl(struct_name: &Ident, table_name_str: &str, fields
             {
                 let sql = #update_sql;
                 self.bind_update(::sqlx::query_as::<_, <Self as ::metastable_database::SqlxSchema>::Row>(&sql))
-                    .fetch_one(executor)
-                    .await
-                    .map(<Self as ::metastable_database::SqlxSchema>::from_row)
+                    #fetch_updated
             }
 
             async fn delete<'e, E>(self, executor: E) -> Result<u64, ::sqlx::Error>
@@ -157,7 +280,23 @@ pub fn generate_sqlx_crud_impl(struct_name: &Ident, table_name_str: &str, fields
                 Self: Send
             {
                 ::sqlx::query(#delete_sql)
-                    .bind(self.id)
+                    #(#key_bindings)*
+                    .execute(executor)
+                    .await
+                    .map(|done| done.rows_affected())
+            }
+
+            async fn delete_by_ids<'e, E>(ids: &[<Self as ::metastable_database::SqlxSchema>::Id], executor: E) -> Result<u64, ::sqlx::Error>
+            where
+                E: ::sqlx::Executor<'e, Database = ::sqlx::Postgres> + Send,
+                Self: Send
+            {
+                if ids.is_empty() {
+                    return Ok(0);
+                }
+
+                ::sqlx::query(#delete_by_ids_sql)
+                    #delete_by_ids_bindings
                     .execute(executor)
                     .await
                     .map(|done| done.rows_affected())
@@ -180,15 +319,10 @@ pub fn generate_sqlx_crud_impl(struct_name: &Ident, table_name_str: &str, fields
                 E: ::sqlx::Executor<'e, Database = ::sqlx::Postgres> + Send,
                 Self: Send
             {
-                let sql = format!(
-                    "UPDATE \"{}\" SET \"created_at\" = $1, \"updated_at\" = $2 WHERE \"id\" = $3 RETURNING *",
-                    <Self as ::metastable_database::SqlxSchema>::TABLE_NAME
-                );
-
-                ::sqlx::query_as::<_, <Self as ::metastable_database::SqlxSchema>::Row>(&sql)
+                ::sqlx::query_as::<_, <Self as ::metastable_database::SqlxSchema>::Row>(#force_set_timestamp_sql)
                     .bind(created_at)
                     .bind(updated_at)
-                    .bind(self.id)
+                    #(#key_bindings)*
                     .fetch_one(executor)
                     .await
                     .map(<Self as ::metastable_database::SqlxSchema>::from_row)
@@ -197,89 +331,335 @@ pub fn generate_sqlx_crud_impl(struct_name: &Ident, table_name_str: &str, fields
     }
 }
 
-pub fn generate_sqlx_filter_query_impl(struct_name: &Ident, row_struct_name: &Ident) -> TokenStream {
+pub fn generate_sqlx_touch_impl(struct_name: &Ident, table_name_str: &str, fields_data: &[FieldData]) -> TokenStream {
+    let has_updated_at = fields_data.iter().any(|f| !f.is_skipped && f.name == "updated_at");
+    if !has_updated_at {
+        return quote! {};
+    }
+
+    let touch_sql = format!(
+        "UPDATE \"{}\" SET \"updated_at\" = floor(extract(epoch from now())) WHERE {} RETURNING *",
+        table_name_str, key_where_sql(fields_data, 1)
+    );
+    let key_bindings = key_bindings(fields_data);
+
     quote! {
         #[automatically_derived]
         #[::async_trait::async_trait]
-        impl ::metastable_database::SqlxFilterQuery for #struct_name {
-            async fn find_by_criteria<'exe, E>(
-                criteria: ::metastable_database::QueryCriteria,
-                executor: E,
-            ) -> Result<Vec<Self>, ::sqlx::Error>
+        impl ::metastable_database::SqlxTouch for #struct_name {
+            async fn touch<'e, E>(self, executor: E) -> Result<Self, ::sqlx::Error>
             where
-                E: ::sqlx::Executor<'exe, Database = ::sqlx::Postgres> + Send,
-                Self: Send,
+                E: ::sqlx::Executor<'e, Database = ::sqlx::Postgres> + Send,
+                Self: Send
             {
-                let mut sql_query_parts: Vec<String> = Vec::new();
-                let mut arguments = ::sqlx::postgres::PgArguments::default();
-                let mut placeholder_idx = 1;
-                let mut select_columns = (<Self as ::metastable_database::SqlxSchema>::COLUMNS).join(", ");
-                let mut where_clauses: Vec<String> = Vec::new();
+                ::sqlx::query_as::<_, <Self as ::metastable_database::SqlxSchema>::Row>(#touch_sql)
+                    #(#key_bindings)*
+                    .fetch_one(executor)
+                    .await
+                    .map(<Self as ::metastable_database::SqlxSchema>::from_row)
+            }
+        }
+    }
+}
+
+pub fn generate_sqlx_soft_delete_impl(struct_name: &Ident, table_name_str: &str, fields_data: &[FieldData], soft_delete: bool) -> TokenStream {
+    if !soft_delete {
+        return quote! {};
+    }
+
+    let restore_sql = format!(
+        "UPDATE \"{}\" SET \"deleted_at\" = NULL WHERE {} RETURNING *",
+        table_name_str, key_where_sql(fields_data, 1)
+    );
+    let purge_sql = format!("DELETE FROM \"{}\" WHERE {}", table_name_str, key_where_sql(fields_data, 1));
+    let key_bindings = key_bindings(fields_data);
+
+    quote! {
+        #[automatically_derived]
+        #[::async_trait::async_trait]
+        impl ::metastable_database::SqlxSoftDelete for #struct_name {
+            async fn restore_deleted<'e, E>(self, executor: E) -> Result<Self, ::sqlx::Error>
+            where
+                E: ::sqlx::Executor<'e, Database = ::sqlx::Postgres> + Send,
+                Self: Send
+            {
+                ::sqlx::query_as::<_, <Self as ::metastable_database::SqlxSchema>::Row>(#restore_sql)
+                    #(#key_bindings)*
+                    .fetch_one(executor)
+                    .await
+                    .map(<Self as ::metastable_database::SqlxSchema>::from_row)
+            }
 
-                if let Some(ss) = &criteria.similarity_search {
+            async fn purge<'e, E>(self, executor: E) -> Result<u64, ::sqlx::Error>
+            where
+                E: ::sqlx::Executor<'e, Database = ::sqlx::Postgres> + Send,
+                Self: Send
+            {
+                ::sqlx::query(#purge_sql)
+                    #(#key_bindings)*
+                    .execute(executor)
+                    .await
+                    .map(|done| done.rows_affected())
+            }
+        }
+    }
+}
+
+/// Lists the groups of rows sharing the target columns, which would keep the
+/// unique index from being built on a table that predates it. Each row reads
+/// `target | key; key ...` with the rows' key columns. Rows with a NULL target
+/// column are skipped, as the index does not compare them either.
+fn conflict_target_duplicates_sql(table_name_str: &str, conflict_target: &[String], key_columns: &[&str]) -> String {
+    let quoted = |columns: &[&str], suffix: &str, sep: &str| columns.iter()
+        .map(|c| format!("\"{}\"{}", c, suffix))
+        .collect::<Vec<String>>()
+        .join(sep);
+    let target = conflict_target.iter().map(String::as_str).collect::<Vec<_>>();
+    format!(
+        "SELECT concat_ws(', ', {1}) || ' | ' || string_agg(concat_ws(', ', {2}), '; ') FROM \"{0}\" \
+         WHERE {3} GROUP BY {4} HAVING count(*) > 1 LIMIT 20",
+        table_name_str,
+        quoted(&target, "::text", ", "),
+        quoted(key_columns, "::text", ", "),
+        quoted(&target, " IS NOT NULL", " AND "),
+        quoted(&target, "", ", "),
+    )
+}
+
+/// The unique index `ON CONFLICT` needs for the target columns, as `(name, sql)`.
+fn conflict_target_index_sql(table_name_str: &str, conflict_target: Option<&[String]>) -> Option<(String, String)> {
+    let columns = conflict_target?;
+    let index_name = format!("uq_{}_{}", table_name_str, columns.join("_"));
+    let column_list = columns.iter().map(|c| format!("\"{}\"", c)).collect::<Vec<String>>().join(", ");
+    let index_sql = format!(
+        "CREATE UNIQUE INDEX IF NOT EXISTS \"{}\" ON \"{}\"({})",
+        index_name, table_name_str, column_list
+    );
+    Some((index_name, index_sql))
+}
+
+pub fn generate_sqlx_upsert_impl(struct_name: &Ident, table_name_str: &str, fields_data: &[FieldData], conflict_target: Option<&[String]>) -> TokenStream {
+    let Some(conflict_target) = conflict_target else {
+        return quote! {};
+    };
+
+    let active_fields: Vec<_> = fields_data.iter().filter(|f| !f.is_skipped).collect();
+    let insert_sql = generate_insert_sql(table_name_str, &active_fields);
+    let (insert_part, returning_part) = insert_sql.split_once(" RETURNING ")
+        .expect("insert SQL always has a RETURNING clause");
+
+    let mut set_clauses: Vec<String> = active_fields.iter()
+        .filter(|f| f.name != "created_at" && f.name != "updated_at" && !f.is_pk)
+        .filter(|f| !conflict_target.contains(&f.name))
+        .map(|f| format!("\"{0}\" = EXCLUDED.\"{0}\"", f.name))
+        .collect();
+    if active_fields.iter().any(|f| f.name == "updated_at") {
+        set_clauses.push("\"updated_at\" = floor(extract(epoch from now()))".to_string());
+    }
+    if set_clauses.is_empty() {
+        // Nothing to refresh, but DO UPDATE is still needed for RETURNING to yield the row
+        set_clauses.push(format!("\"{0}\" = EXCLUDED.\"{0}\"", conflict_target[0]));
+    }
+
+    let conflict_columns = conflict_target.iter().map(|c| format!("\"{}\"", c)).collect::<Vec<String>>().join(", ");
+    let upsert_sql = format!(
+        "{} ON CONFLICT ({}) DO UPDATE SET {} RETURNING {}",
+        insert_part, conflict_columns, set_clauses.join(", "), returning_part
+    );
+    let insert_if_absent_sql = format!(
+        "{} ON CONFLICT ({}) DO NOTHING RETURNING {}",
+        insert_part, conflict_columns, returning_part
+    );
+
+    quote! {
+        #[automatically_derived]
+        #[::async_trait::async_trait]
+        impl ::metastable_database::SqlxUpsert for #struct_name {
+            async fn upsert<'e, E>(self, executor: E) -> Result<Self, ::sqlx::Error>
+            where
+                E: ::sqlx::Executor<'e, Database = ::sqlx::Postgres> + Send,
+                Self: Send
+            {
+                self.bind_insert(::sqlx::query_as::<_, <Self as ::metastable_database::SqlxSchema>::Row>(#upsert_sql))
+                    .fetch_one(executor)
+                    .await
+                    .map(<Self as ::metastable_database::SqlxSchema>::from_row)
+            }
+
+            async fn create_if_absent<'e, E>(self, executor: E) -> Result<Option<Self>, ::sqlx::Error>
+            where
+                E: ::sqlx::Executor<'e, Database = ::sqlx::Postgres> + Send,
+                Self: Send
+            {
+                self.bind_insert(::sqlx::query_as::<_, <Self as ::metastable_database::SqlxSchema>::Row>(#insert_if_absent_sql))
+                    .fetch_optional(executor)
+                    .await
+                    .map(|row| row.map(<Self as ::metastable_database::SqlxSchema>::from_row))
+            }
+        }
+    }
+}
+
+/// Shared by `find_by_criteria` and `count_by_criteria` so both see the same rows.
+fn criteria_where_clauses(soft_delete: bool) -> TokenStream {
+    quote! {
+        where_clauses.extend(criteria.filter_clauses(<Self as ::metastable_database::SqlxSchema>::PRIMARY_KEY_COLUMNS, &mut arguments, &mut placeholder_idx)?);
+
+        if #soft_delete && !criteria.include_deleted {
+            where_clauses.push("\"deleted_at\" IS NULL".to_string());
+        }
+    }
+}
+
+/// The `find_by_criteria` query over `from_sql`, selecting `select_columns`.
+/// Expects `criteria`, a mutable `select_columns` and `from_sql` in scope, and
+/// leaves `final_sql` and its `arguments` behind.
+fn criteria_select_sql(soft_delete: bool) -> TokenStream {
+    let criteria_where_clauses = criteria_where_clauses(soft_delete);
+    quote! {
+        let mut sql_query_parts: Vec<String> = Vec::new();
+        let mut arguments = ::sqlx::postgres::PgArguments::default();
+        let mut placeholder_idx = 1;
+        let mut where_clauses: Vec<String> = Vec::new();
+
+        if let Some(ss) = &criteria.similarity_search {
+            use ::sqlx::Arguments;
+            arguments.add(ss.vector.clone()).map_err(::sqlx::Error::Encode)?;
+            let vector_placeholder = placeholder_idx;
+            placeholder_idx += 1;
+            select_columns = format!("{}, {} as {}", select_columns, ss.metric.similarity_sql("embedding", vector_placeholder), ss.as_field);
+
+            if let Some(threshold) = ss.threshold {
+                arguments.add(threshold).map_err(::sqlx::Error::Encode)?;
+                let threshold_placeholder = placeholder_idx;
+                placeholder_idx += 1;
+                where_clauses.push(format!("{} >= ${}", ss.metric.similarity_sql("embedding", vector_placeholder), threshold_placeholder));
+            }
+        }
+
+        sql_query_parts.push(format!("SELECT {} FROM {}", select_columns, from_sql));
+
+        #criteria_where_clauses
+
+        if !where_clauses.is_empty() {
+            sql_query_parts.push(format!("WHERE {}", where_clauses.join(" AND ")));
+        }
+
+        if !criteria.order_by.is_empty() {
+            sql_query_parts.push("ORDER BY".to_string());
+            let mut order_clauses: Vec<String> = Vec::with_capacity(criteria.order_by.len() + 1);
+            let mut orders_by_rank = false;
+            for &(col, dir) in &criteria.order_by {
+                let fulltext_rank = match &criteria.fulltext_search {
+                    Some(fs) if col == ::metastable_database::FULLTEXT_RANK => Some(fs),
+                    _ => None,
+                };
+                if let Some(fs) = fulltext_rank {
                     use ::sqlx::Arguments;
-                    arguments.add(ss.vector.clone()).map_err(::sqlx::Error::Encode)?;
-                    let vector_placeholder = placeholder_idx;
+                    arguments.add(fs.query.clone()).map_err(::sqlx::Error::Encode)?;
+                    order_clauses.push(format!("{} {}", fs.rank_sql(placeholder_idx), dir.as_sql()));
                     placeholder_idx += 1;
-                    select_columns = format!("*, 1 - (embedding <=> ${}) as {}", vector_placeholder, ss.as_field);
-
-                    if let Some(threshold) = ss.threshold {
-                        arguments.add(threshold).map_err(::sqlx::Error::Encode)?;
-                        let threshold_placeholder = placeholder_idx;
-                        placeholder_idx += 1;
-                        where_clauses.push(format!("1 - (embedding <=> ${}) >= ${}", vector_placeholder, threshold_placeholder));
+                    orders_by_rank = true;
+                } else if criteria.similarity_search.as_ref().map_or(false, |ssi| ssi.as_field == col) {
+                    order_clauses.push(format!("{} {}", col, dir.as_sql()));
+                } else if col == ::metastable_database::PRIMARY_KEY {
+                    for key in <Self as ::metastable_database::SqlxSchema>::PRIMARY_KEY_COLUMNS {
+                        order_clauses.push(format!("\"{}\" {}", key, dir.as_sql()));
                     }
+                } else {
+                    order_clauses.push(format!("\"{}\" {}", col, dir.as_sql()));
                 }
+            }
 
-                sql_query_parts.push(format!(
-                    "SELECT {} FROM \"{}\"", 
-                    select_columns, 
-                    <Self as ::metastable_database::SqlxSchema>::TABLE_NAME
-                ));
-
-                for condition in &criteria.conditions {
-                    let mut current_condition_sql = format!("\"{}\" {}", condition.column, condition.operator);
-                    if let Some(value) = &condition.value {
-                        value.add_to_args(&mut arguments)?;
-                        if !condition.operator.contains('$') {
-                            current_condition_sql.push_str(&format!(" ${}", placeholder_idx));
-                        }
-                        placeholder_idx += 1;
+            // equal similarity scores or ranks would otherwise come back in arbitrary order
+            let orders_by_similarity = criteria.similarity_search.as_ref()
+                .map_or(false, |ssi| criteria.order_by.iter().any(|&(col, _)| col == ssi.as_field));
+            if (orders_by_similarity || orders_by_rank) && !criteria.order_by.iter().any(|&(col, _)| col == ::metastable_database::PRIMARY_KEY) {
+                for key in <Self as ::metastable_database::SqlxSchema>::PRIMARY_KEY_COLUMNS {
+                    if !criteria.order_by.iter().any(|&(col, _)| col == *key) {
+                        order_clauses.push(format!("\"{}\" ASC", key));
                     }
-                    where_clauses.push(current_condition_sql);
-                }
-                
-                if !where_clauses.is_empty() {
-                    sql_query_parts.push(format!("WHERE {}", where_clauses.join(" AND ")));
                 }
+            }
+            sql_query_parts.push(order_clauses.join(", "));
+        }
 
+        if let Some(limit_val) = criteria.limit {
+            use ::sqlx::Arguments;
+            arguments.add(limit_val).map_err(::sqlx::Error::Encode)?;
+            sql_query_parts.push(format!("LIMIT ${}", placeholder_idx));
+            placeholder_idx += 1;
+        }
 
-                if !criteria.order_by.is_empty() {
-                    sql_query_parts.push("ORDER BY".to_string());
-                    let order_clauses: Vec<String> = criteria.order_by.iter().map(|&(col, dir)| {
-                        if criteria.similarity_search.as_ref().map_or(false, |ssi| ssi.as_field == col) {
-                            format!("{} {}", col, dir.as_sql())
-                        } else {
-                            format!("\"{}\" {}", col, dir.as_sql())
-                        }
-                    }).collect();
-                    sql_query_parts.push(order_clauses.join(", "));
-                }
+        if let Some(offset_val) = criteria.offset {
+            use ::sqlx::Arguments;
+            arguments.add(offset_val).map_err(::sqlx::Error::Encode)?;
+            sql_query_parts.push(format!("OFFSET ${}", placeholder_idx));
+        }
 
-                if let Some(limit_val) = criteria.limit {
-                    use ::sqlx::Arguments;
-                    arguments.add(limit_val).map_err(::sqlx::Error::Encode)?;
-                    sql_query_parts.push(format!("LIMIT ${}", placeholder_idx));
-                    placeholder_idx += 1;
-                }
+        if criteria.for_update {
+            // `OF` keeps joined tables unlocked, and is required when they are outer-joined
+            sql_query_parts.push(format!("FOR UPDATE OF \"{}\"", <Self as ::metastable_database::SqlxSchema>::TABLE_NAME));
+            if criteria.skip_locked {
+                sql_query_parts.push("SKIP LOCKED".to_string());
+            }
+        }
 
-                if let Some(offset_val) = criteria.offset {
-                    use ::sqlx::Arguments;
-                    arguments.add(offset_val).map_err(::sqlx::Error::Encode)?;
-                    sql_query_parts.push(format!("OFFSET ${}", placeholder_idx));
-                }
+        let final_sql = sql_query_parts.join(" ");
+    }
+}
 
-                let final_sql = sql_query_parts.join(" ");
+pub fn generate_sqlx_filter_query_impl(struct_name: &Ident, row_struct_name: &Ident, soft_delete: bool) -> TokenStream {
+    let criteria_where_clauses = criteria_where_clauses(soft_delete);
+    let criteria_select_sql = criteria_select_sql(soft_delete);
+
+    // Shared by `count_by_criteria` and `exists_by_criteria`, which ignore ordering and paging
+    let unordered_where_clauses = quote! {
+        let mut arguments = ::sqlx::postgres::PgArguments::default();
+        let mut placeholder_idx = 1;
+        let mut where_clauses: Vec<String> = Vec::new();
+
+        // Only the threshold filters rows; without one the vector would be an unused parameter
+        if let Some(ss) = &criteria.similarity_search
+            && let Some(threshold) = ss.threshold
+        {
+            use ::sqlx::Arguments;
+            arguments.add(ss.vector.clone()).map_err(::sqlx::Error::Encode)?;
+            arguments.add(threshold).map_err(::sqlx::Error::Encode)?;
+            where_clauses.push(format!("{} >= ${}", ss.metric.similarity_sql("embedding", placeholder_idx), placeholder_idx + 1));
+            placeholder_idx += 2;
+        }
+
+        #criteria_where_clauses
+
+        let mut filtered_sql = format!(
+            "FROM \"{}\"",
+            <Self as ::metastable_database::SqlxSchema>::TABLE_NAME
+        );
+        if !where_clauses.is_empty() {
+            filtered_sql.push_str(&format!(" WHERE {}", where_clauses.join(" AND ")));
+        }
+    };
+
+    quote! {
+        #[automatically_derived]
+        #[::async_trait::async_trait]
+        impl ::metastable_database::SqlxFilterQuery for #struct_name {
+            async fn find_by_criteria<'exe, E>(
+                criteria: ::metastable_database::QueryCriteria,
+                executor: E,
+            ) -> Result<Vec<Self>, ::sqlx::Error>
+            where
+                E: ::sqlx::Executor<'exe, Database = ::sqlx::Postgres> + Send,
+                Self: Send,
+            {
+                let mut select_columns = (<Self as ::metastable_database::SqlxSchema>::COLUMNS)
+                    .iter()
+                    .map(|c| format!("\"{}\"", c))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                let from_sql = format!("\"{}\"", <Self as ::metastable_database::SqlxSchema>::TABLE_NAME);
+                #criteria_select_sql
                 
                 ::sqlx::query_as_with::<_, #row_struct_name, _>(&final_sql, arguments)
                     .fetch_all(executor)
@@ -287,6 +667,36 @@ pub fn generate_sqlx_filter_query_impl(struct_name: &Ident, row_struct_name: &Id
                     .map(|rows| rows.into_iter().map(<Self as ::metastable_database::SqlxSchema>::from_row).collect())
             }
 
+            async fn count_by_criteria<'exe, E>(
+                criteria: ::metastable_database::QueryCriteria,
+                executor: E,
+            ) -> Result<i64, ::sqlx::Error>
+            where
+                E: ::sqlx::Executor<'exe, Database = ::sqlx::Postgres> + Send,
+                Self: Send,
+            {
+                #unordered_where_clauses
+
+                ::sqlx::query_scalar_with::<_, i64, _>(&format!("SELECT COUNT(*) {}", filtered_sql), arguments)
+                    .fetch_one(executor)
+                    .await
+            }
+
+            async fn exists_by_criteria<'exe, E>(
+                criteria: ::metastable_database::QueryCriteria,
+                executor: E,
+            ) -> Result<bool, ::sqlx::Error>
+            where
+                E: ::sqlx::Executor<'exe, Database = ::sqlx::Postgres> + Send,
+                Self: Send,
+            {
+                #unordered_where_clauses
+
+                ::sqlx::query_scalar_with::<_, bool, _>(&format!("SELECT EXISTS (SELECT 1 {})", filtered_sql), arguments)
+                    .fetch_one(executor)
+                    .await
+            }
+
             async fn delete_by_criteria<'exe, E>(
                 criteria: ::metastable_database::QueryCriteria,
                 executor: E,
@@ -299,23 +709,22 @@ pub fn generate_sqlx_filter_query_impl(struct_name: &Ident, row_struct_name: &Id
                 let mut arguments = ::sqlx::postgres::PgArguments::default();
                 let mut placeholder_idx = 1;
                 
-                sql_query_parts.push(format!("DELETE FROM \"{}\"", <Self as ::metastable_database::SqlxSchema>::TABLE_NAME));
-
-                if !criteria.conditions.is_empty() {
-                    sql_query_parts.push("WHERE".to_string());
-                    let mut where_clauses = Vec::new();
-                    for condition in &criteria.conditions { 
-                        let mut current_condition_sql = format!("\"{}\" {}", condition.column, condition.operator);
-                        if let Some(value) = &condition.value {
-                            value.add_to_args(&mut arguments)?;
-                            if !condition.operator.contains('$') {
-                                current_condition_sql.push_str(&format!(" ${}", placeholder_idx));
-                            }
-                            placeholder_idx += 1;
-                        }
-                        where_clauses.push(current_condition_sql);
-                    }
-                    sql_query_parts.push(where_clauses.join(" AND "));
+                // Soft-delete tables only stamp `deleted_at`; use `SqlxSoftDelete::purge` to remove rows
+                if #soft_delete {
+                    sql_query_parts.push(format!(
+                        "UPDATE \"{}\" SET \"deleted_at\" = floor(extract(epoch from now()))",
+                        <Self as ::metastable_database::SqlxSchema>::TABLE_NAME
+                    ));
+                } else {
+                    sql_query_parts.push(format!("DELETE FROM \"{}\"", <Self as ::metastable_database::SqlxSchema>::TABLE_NAME));
+                }
+
+                let mut where_clauses = criteria.filter_clauses(<Self as ::metastable_database::SqlxSchema>::PRIMARY_KEY_COLUMNS, &mut arguments, &mut placeholder_idx)?;
+                if #soft_delete {
+                    where_clauses.push("\"deleted_at\" IS NULL".to_string());
+                }
+                if !where_clauses.is_empty() {
+                    sql_query_parts.push(format!("WHERE {}", where_clauses.join(" AND ")));
                 }
                 
                 let final_sql = sql_query_parts.join(" ");
@@ -329,7 +738,8 @@ pub fn generate_sqlx_filter_query_impl(struct_name: &Ident, row_struct_name: &Id
     }
 }
 
-pub fn generate_fetch_helpers(fields_data: &[FieldData]) -> TokenStream {
+pub fn generate_fetch_helpers(fields_data: &[FieldData], soft_delete: bool) -> TokenStream {
+    let criteria_select_sql = criteria_select_sql(soft_delete);
     let fetch_helper_methods: Vec<TokenStream> = fields_data.iter().filter_map(|field| {
         let field_ident = format_ident!("{}", field.name);
         
@@ -340,8 +750,79 @@ pub fn generate_fetch_helpers(fields_data: &[FieldData]) -> TokenStream {
             
             let id_column_name_of_related_type = quote!{ <#related_type as ::metastable_database::SqlxSchema>::id_column_name() };
 
-            if field.is_option {
-                Some(quote! {
+            let find_with_method_name = format_ident!("find_by_criteria_with_{}", field_ident);
+            let find_with_doc = format!(
+                " Like `find_by_criteria`, with each row's `{}` loaded in the same query through a `LEFT JOIN`.\n Only this one level is joined; the related row's own foreign keys are not loaded.",
+                field.name
+            );
+            let join_alias = format!("__{}", field.name);
+            let field_name = &field.name;
+            let find_with_method = quote! {
+                #[doc = #find_with_doc]
+                pub async fn #find_with_method_name<'exe, E>(
+                    criteria: ::metastable_database::QueryCriteria,
+                    executor: E
+                ) -> Result<Vec<(Self, Option<#related_type>)>, ::sqlx::Error>
+                where
+                    E: ::sqlx::Executor<'exe, Database = ::sqlx::Postgres> + Send,
+                {
+                    let table = <Self as ::metastable_database::SqlxSchema>::TABLE_NAME;
+                    let related_columns = <#related_type as ::metastable_database::SqlxSchema>::COLUMNS;
+                    let related_id = <#related_type as ::metastable_database::SqlxSchema>::ID_COLUMN_NAME;
+
+                    // The joined columns are renamed, so filters and ordering on bare column names keep resolving to this table
+                    let mut select_columns = (<Self as ::metastable_database::SqlxSchema>::COLUMNS)
+                        .iter()
+                        .map(|c| format!("\"{}\".\"{}\"", table, c))
+                        .chain(related_columns.iter().map(|c| format!("\"{0}\".\"{0}_{1}\"", #join_alias, c)))
+                        .collect::<Vec<String>>()
+                        .join(", ");
+                    let related_select = related_columns
+                        .iter()
+                        .map(|c| format!("\"{1}\" AS \"{0}_{1}\"", #join_alias, c))
+                        .collect::<Vec<String>>()
+                        .join(", ");
+                    let related_filter = if <#related_type as ::metastable_database::SqlxSchema>::SOFT_DELETE {
+                        " WHERE \"deleted_at\" IS NULL"
+                    } else {
+                        ""
+                    };
+                    let from_sql = format!(
+                        "\"{table}\" LEFT JOIN (SELECT {related_select} FROM \"{related_table}\"{related_filter}) AS \"{alias}\" ON \"{alias}\".\"{alias}_{related_id}\" = \"{table}\".\"{field}\"",
+                        table = table,
+                        related_select = related_select,
+                        related_table = <#related_type as ::metastable_database::SqlxSchema>::TABLE_NAME,
+                        related_filter = related_filter,
+                        alias = #join_alias,
+                        related_id = related_id,
+                        field = #field_name,
+                    );
+                    #criteria_select_sql
+
+                    let rows = ::sqlx::query_with(&final_sql, arguments)
+                        .fetch_all(executor)
+                        .await?;
+
+                    let related_offset = <Self as ::metastable_database::SqlxSchema>::COLUMNS.len();
+                    let related_id_offset = related_offset + related_columns.iter().position(|c| *c == related_id).unwrap_or(0);
+                    rows.iter().map(|row| {
+                        let item = <Self as ::metastable_database::SqlxSchema>::from_row(
+                            <Self as ::metastable_database::SqlxSchema>::row_at(row, 0)?
+                        );
+                        let related = if ::sqlx::ValueRef::is_null(&::sqlx::Row::try_get_raw(row, related_id_offset)?) {
+                            None
+                        } else {
+                            Some(<#related_type as ::metastable_database::SqlxSchema>::from_row(
+                                <#related_type as ::metastable_database::SqlxSchema>::row_at(row, related_offset)?
+                            ))
+                        };
+                        Ok((item, related))
+                    }).collect()
+                }
+            };
+
+            let fetch_method = if field.is_option {
+                quote! {
                     pub async fn #fetch_method_name<'exe, E>(
                         &self, 
                         executor: E
@@ -357,9 +838,9 @@ pub fn generate_fetch_helpers(fields_data: &[FieldData]) -> TokenStream {
                             Ok(None)
                         }
                     }
-                })
+                }
             } else {
-                Some(quote! {
+                quote! {
                     pub async fn #fetch_method_name<'exe, E>(
                         &self, 
                         executor: E
@@ -371,8 +852,13 @@ pub fn generate_fetch_helpers(fields_data: &[FieldData]) -> TokenStream {
                             .add_valued_filter(#id_column_name_of_related_type, "=", #self_field_access);
                         <#related_type as ::metastable_database::SqlxFilterQuery>::find_one_by_criteria(criteria, executor).await
                     }
-                })
-            }
+                }
+            };
+
+            Some(quote! {
+                #fetch_method
+                #find_with_method
+            })
         } else if let Some(fk_many_info) = &field.foreign_key_many {
             let fetch_method_name = format_ident!("fetch_{}", field_ident);
             let related_type = &fk_many_info.related_rust_type;
@@ -408,6 +894,36 @@ pub fn generate_fetch_helpers(fields_data: &[FieldData]) -> TokenStream {
     quote! { #(#fetch_helper_methods)* }
 }
 
+/// Emits a `Debug` impl that masks `#[sensitive]` fields as `***`.
+/// Structs without sensitive fields keep their own `#[derive(Debug)]`, so nothing is generated for them.
+pub fn generate_debug_impl(struct_name: &Ident, fields_data: &[FieldData]) -> TokenStream {
+    if !fields_data.iter().any(|f| f.sensitive) {
+        return quote! {};
+    }
+
+    let struct_name_str = struct_name.to_string();
+    let debug_fields: Vec<TokenStream> = fields_data.iter().map(|field| {
+        let field_ident = format_ident!("{}", field.name);
+        let field_name_str = &field.name;
+        if field.sensitive {
+            quote! { .field(#field_name_str, &format_args!("***")) }
+        } else {
+            quote! { .field(#field_name_str, &self.#field_ident) }
+        }
+    }).collect();
+
+    quote! {
+        #[automatically_derived]
+        impl ::std::fmt::Debug for #struct_name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.debug_struct(#struct_name_str)
+                    #(#debug_fields)*
+                    .finish()
+            }
+        }
+    }
+}
+
 fn generate_from_row_assignments(fields_data: &[FieldData]) -> Vec<TokenStream> {
     let from_row_sql_field_assignments: Vec<TokenStream> = fields_data.iter()
         .filter(|f| !f.is_skipped)
@@ -422,8 +938,8 @@ fn generate_from_row_assignments(fields_data: &[FieldData]) -> Vec<TokenStream>
         let row_field_name = &field_ident;
 
         if !is_simple_type(&type_for_analysis) && !is_json_type_for_analysis && !fq_type_str_for_analysis.starts_with("Option<") && !fq_type_str_for_analysis.starts_with("Vec<") {
-            // If the SQL type is JSONB, the row struct has the original enum type, so no parsing needed
-            if field.sql_type == "JSONB" {
+            // If the SQL type is JSONB or a `#[pg_enum]`, the row struct has the original enum type, so no parsing needed
+            if field.sql_type == "JSONB" || field.pg_enum {
                 quote! { #field_ident: row.#row_field_name }
             } else {
                 if field_is_option {
@@ -464,42 +980,79 @@ fn generate_from_row_assignments(fields_data: &[FieldData]) -> Vec<TokenStream>
     all_from_row_assignments
 }
 
+/// The generated `tsvector` column of a `#[fulltext]` field: its name and
+/// definition, then its GIN index's name and SQL. The naming and text search
+/// configuration match `metastable_database::fulltext_column` and `FULLTEXT_CONFIG`.
+fn fulltext_column_sql(table_name: &str, column: &str) -> (String, String, String, String) {
+    let tsv_column = format!("{}_tsv", column);
+    let definition = format!(
+        "\"{}\" tsvector GENERATED ALWAYS AS (to_tsvector('simple', coalesce(\"{}\", ''))) STORED",
+        tsv_column, column
+    );
+    let index_name = format!("idx_{}_{}", table_name, tsv_column);
+    let index_sql = format!(
+        "CREATE INDEX IF NOT EXISTS \"{}\" ON \"{}\" USING GIN (\"{}\")",
+        index_name, table_name, tsv_column
+    );
+    (tsv_column, definition, index_name, index_sql)
+}
+
 fn generate_create_table_sql(table_name_str: &str, fields_data: &[FieldData]) -> (String, Vec<LitStr>) {
     let mut create_table_column_defs: Vec<String> = Vec::new();
     let mut foreign_key_clauses_for_create_table: Vec<String> = Vec::new();
     let mut create_index_sqls: Vec<LitStr> = Vec::new();
+    let mut key_columns: Vec<String> = Vec::new();
 
     let active_fields: Vec<_> = fields_data.iter().filter(|f| !f.is_skipped).collect();
 
     for field in active_fields {
         let mut col_def_parts = vec![format!("\"{}\"", field.name), field.sql_type.clone()];
+        if let Some(default) = &field.column_default {
+            col_def_parts.push(format!("DEFAULT {}", default));
+        }
         
         if field.is_pk {
             col_def_parts.push("PRIMARY KEY".to_string());
-            col_def_parts.push("DEFAULT gen_random_uuid()".to_string());
+            if field.column_default.is_none() {
+                col_def_parts.push("DEFAULT gen_random_uuid()".to_string());
+            }
         }
         else if field.name == "created_at" || field.name == "updated_at" {
             let idx = col_def_parts.iter().position(|s| s == &field.sql_type).unwrap();
             col_def_parts[idx] = "BIGINT".to_string();
-            col_def_parts.push("NOT NULL DEFAULT floor(extract(epoch from now()))".to_string());
+            col_def_parts.push("NOT NULL".to_string());
+            if field.column_default.is_none() {
+                col_def_parts.push("DEFAULT floor(extract(epoch from now()))".to_string());
+            }
         }
         else if !field.is_option { col_def_parts.push("NOT NULL".to_string()); }
 
+        if field.is_key {
+            key_columns.push(format!("\"{}\"", field.name));
+        }
+
         if field.unique {
             col_def_parts.push("UNIQUE".to_string());
         }
 
         if field.indexed {
             let index_name = format!("idx_{}_{}", table_name_str, field.name);
+            let using = if field.index_gin { " USING GIN " } else { "" };
             let index_sql = format!(
-                "CREATE INDEX IF NOT EXISTS \"{}\" ON \"{}\"(\"{}\")",
-                index_name, table_name_str, field.name
+                "CREATE INDEX IF NOT EXISTS \"{}\" ON \"{}\"{}(\"{}\")",
+                index_name, table_name_str, using, field.name
             );
             create_index_sqls.push(LitStr::new(&index_sql, proc_macro2::Span::call_site()));
         }
 
         create_table_column_defs.push(col_def_parts.join(" "));
 
+        if field.fulltext {
+            let (_, definition, _, index_sql) = fulltext_column_sql(table_name_str, &field.name);
+            create_table_column_defs.push(definition);
+            create_index_sqls.push(LitStr::new(&index_sql, proc_macro2::Span::call_site()));
+        }
+
         if let Some(fk_info) = &field.foreign_key {
             foreign_key_clauses_for_create_table.push(format!(
                 "FOREIGN KEY (\"{}\") REFERENCES \"{}\"(\"id\") ON DELETE SET NULL ON UPDATE CASCADE",
@@ -509,6 +1062,9 @@ fn generate_create_table_sql(table_name_str: &str, fields_data: &[FieldData]) ->
     }
 
     let mut create_table_parts = create_table_column_defs;
+    if !key_columns.is_empty() {
+        create_table_parts.push(format!("PRIMARY KEY ({})", key_columns.join(", ")));
+    }
     if !foreign_key_clauses_for_create_table.is_empty() {
         create_table_parts.extend(foreign_key_clauses_for_create_table);
     }
@@ -517,6 +1073,72 @@ fn generate_create_table_sql(table_name_str: &str, fields_data: &[FieldData]) ->
     (create_table_sql_query, create_index_sqls)
 }
 
+/// Columns identifying a row: the `#[primary_key]` fields when present,
+/// otherwise the generated `id`.
+fn key_fields(fields_data: &[FieldData]) -> Vec<&FieldData> {
+    let explicit: Vec<_> = fields_data.iter().filter(|f| f.is_key).collect();
+    if explicit.is_empty() {
+        fields_data.iter().filter(|f| f.is_pk).collect()
+    } else {
+        explicit
+    }
+}
+
+/// `"a" = $n AND "b" = $n+1 ...` over the key columns, starting at `first_placeholder`.
+fn key_where_sql(fields_data: &[FieldData], first_placeholder: usize) -> String {
+    key_fields(fields_data).iter()
+        .enumerate()
+        .map(|(i, f)| format!("\"{}\" = ${}", f.name, first_placeholder + i))
+        .collect::<Vec<String>>()
+        .join(" AND ")
+}
+
+/// The `#[optimistic_lock]` field, if any.
+fn lock_field(fields_data: &[FieldData]) -> Option<&FieldData> {
+    fields_data.iter().find(|f| f.optimistic_lock && !f.is_skipped)
+}
+
+/// Binds the key columns in `key_where_sql` order.
+fn key_bindings(fields_data: &[FieldData]) -> Vec<TokenStream> {
+    key_fields(fields_data).iter()
+        .map(|f| {
+            let ident = format_ident!("{}", f.name);
+            if f.is_pk { quote! { .bind(self.#ident) } } else { quote! { .bind(self.#ident.clone()) } }
+        })
+        .collect()
+}
+
+/// `DELETE` (or the soft-delete `UPDATE`) over a slice of ids. A single key is
+/// bound as one array; composite keys bind one array per column and match
+/// against their `UNNEST`.
+fn generate_delete_by_ids_sql(table_name_str: &str, fields_data: &[FieldData], soft_delete: bool) -> (String, TokenStream) {
+    let keys = key_fields(fields_data);
+    let (where_sql, bindings) = if keys.len() == 1 {
+        (format!("\"{}\" = ANY($1)", keys[0].name), quote! { .bind(ids) })
+    } else {
+        let columns: Vec<String> = keys.iter().map(|f| format!("\"{}\"", f.name)).collect();
+        let placeholders: Vec<String> = (1..=keys.len()).map(|i| format!("${}", i)).collect();
+        let bindings = (0..keys.len()).map(|i| {
+            let index = syn::Index::from(i);
+            quote! { .bind(ids.iter().map(|id| id.#index.clone()).collect::<Vec<_>>()) }
+        });
+        (
+            format!("({}) IN (SELECT * FROM UNNEST({}))", columns.join(", "), placeholders.join(", ")),
+            quote! { #(#bindings)* },
+        )
+    };
+
+    let sql = if soft_delete {
+        format!(
+            "UPDATE \"{}\" SET \"deleted_at\" = floor(extract(epoch from now())) WHERE {} AND \"deleted_at\" IS NULL",
+            table_name_str, where_sql
+        )
+    } else {
+        format!("DELETE FROM \"{}\" WHERE {}", table_name_str, where_sql)
+    };
+    (sql, bindings)
+}
+
 fn generate_insert_sql(table_name_str: &str, active_fields: &[&FieldData]) -> String {
     let insert_col_sql_names: Vec<String> = active_fields.iter()
         .filter(|f| f.name != "created_at" && f.name != "updated_at" && !f.is_pk)
@@ -531,24 +1153,103 @@ fn generate_insert_sql(table_name_str: &str, active_fields: &[&FieldData]) -> St
     format!("INSERT INTO \"{}\" ({}) VALUES ({}) RETURNING {}", table_name_str, insert_column_names_joined_sql, insert_bind_placeholders_sql, all_sql_columns_joined_str)
 }
 
-fn generate_update_sql(table_name_str: &str, fields_data: &[FieldData]) -> (String, bool) {
+/// Splits the insert statement around its `VALUES` list so `create_many` can
+/// repeat the placeholder tuple once per row.
+fn generate_batch_insert_sql_parts(table_name_str: &str, fields_data: &[FieldData]) -> (String, String, usize) {
+    let active_fields: Vec<_> = fields_data.iter().filter(|f| !f.is_skipped).collect();
+
+    let insert_col_sql_names: Vec<String> = active_fields.iter()
+        .filter(|f| f.name != "created_at" && f.name != "updated_at" && !f.is_pk)
+        .map(|f| format!("\"{}\"", f.name))
+        .collect();
+    let all_sql_columns_joined_str = active_fields.iter().map(|s| format!("\"{}\"", s.name)).collect::<Vec<String>>().join(", ");
+
+    (
+        format!("INSERT INTO \"{}\" ({}) VALUES ", table_name_str, insert_col_sql_names.join(", ")),
+        format!(" RETURNING {}", all_sql_columns_joined_str),
+        insert_col_sql_names.len(),
+    )
+}
+
+/// Full-row insert used by `restore`: the generated `id` first, then the regular
+/// insert columns in `bind_insert` order (which already include any
+/// `#[primary_key]` fields), then whichever timestamps exist.
+fn generate_restore_sql(table_name_str: &str, fields_data: &[FieldData]) -> (String, String, String, Vec<TokenStream>, Vec<TokenStream>) {
     let active_fields: Vec<_> = fields_data.iter().filter(|f| !f.is_skipped).collect();
 
-    let update_set_clauses_sql: Vec<String> = active_fields.iter()
+    let mut columns: Vec<String> = active_fields.iter()
+        .filter(|f| f.is_pk)
+        .map(|f| f.name.clone())
+        .collect();
+    let id_bindings: Vec<TokenStream> = active_fields.iter()
+        .filter(|f| f.is_pk)
+        .map(|f| {
+            let ident = format_ident!("{}", f.name);
+            quote! { .bind(self.#ident) }
+        })
+        .collect();
+    let key_names: Vec<String> = key_fields(fields_data).iter().map(|f| f.name.clone()).collect();
+    let conflict_target = key_names.iter().map(|c| format!("\"{}\"", c)).collect::<Vec<String>>().join(", ");
+    columns.extend(active_fields.iter()
         .filter(|f| f.name != "created_at" && f.name != "updated_at" && !f.is_pk)
+        .map(|f| f.name.clone()));
+
+    let mut timestamp_bindings = Vec::new();
+    for timestamp in ["created_at", "updated_at"] {
+        if active_fields.iter().any(|f| f.name == timestamp) {
+            let ident = format_ident!("{}", timestamp);
+            timestamp_bindings.push(quote! { .bind(self.#ident) });
+            columns.push(timestamp.to_string());
+        }
+    }
+
+    let column_list = columns.iter().map(|c| format!("\"{}\"", c)).collect::<Vec<String>>().join(", ");
+    let placeholders = (1..=columns.len()).map(|i| format!("${}", i)).collect::<Vec<String>>().join(", ");
+    let returning = active_fields.iter().map(|f| format!("\"{}\"", f.name)).collect::<Vec<String>>().join(", ");
+    let overwrite_set = columns.iter()
+        .filter(|c| !key_names.contains(c))
+        .map(|c| format!("\"{}\" = EXCLUDED.\"{}\"", c, c))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    let insert = format!("INSERT INTO \"{}\" ({}) VALUES ({})", table_name_str, column_list, placeholders);
+    (
+        format!("{} RETURNING {}", insert, returning),
+        format!("{} ON CONFLICT ({}) DO NOTHING RETURNING {}", insert, conflict_target, returning),
+        format!("{} ON CONFLICT ({}) DO UPDATE SET {} RETURNING {}", insert, conflict_target, overwrite_set, returning),
+        id_bindings,
+        timestamp_bindings,
+    )
+}
+
+fn generate_update_sql(table_name_str: &str, fields_data: &[FieldData]) -> (String, bool) {
+    let active_fields: Vec<_> = fields_data.iter().filter(|f| !f.is_skipped).collect();
+
+    let mut update_set_clauses_sql: Vec<String> = active_fields.iter()
+        .filter(|f| f.name != "created_at" && f.name != "updated_at" && !f.is_pk && !f.is_key && !f.optimistic_lock)
         .enumerate()
         .map(|(i, f)| format!("\"{}\" = ${}", f.name, i + 1))
         .collect();
-    
+    let bound_set_clauses = update_set_clauses_sql.len();
+    let lock = lock_field(fields_data);
+    if let Some(lock) = lock {
+        update_set_clauses_sql.push(format!("\"{0}\" = \"{0}\" + 1", lock.name));
+    }
+
     let all_sql_columns_joined_str = active_fields.iter().map(|s| format!("\"{}\"", s.name)).collect::<Vec<String>>().join(", ");
 
     let is_select_only = update_set_clauses_sql.is_empty();
     let sql = if is_select_only {
-        format!("SELECT {} FROM \"{}\" WHERE \"id\" = $1", all_sql_columns_joined_str, table_name_str) 
+        format!("SELECT {} FROM \"{}\" WHERE {}", all_sql_columns_joined_str, table_name_str, key_where_sql(fields_data, 1))
     } else {
         let update_set_str_sql = update_set_clauses_sql.join(", ");
-        let pk_placeholder_idx = update_set_clauses_sql.len() + 1;
-        format!("UPDATE \"{}\" SET {} WHERE \"id\" = ${} RETURNING {}", table_name_str, update_set_str_sql, pk_placeholder_idx, all_sql_columns_joined_str)
+        let pk_placeholder_idx = bound_set_clauses + 1;
+        let mut where_sql = key_where_sql(fields_data, pk_placeholder_idx);
+        // Matches only if nobody updated the row since it was read
+        if let Some(lock) = lock {
+            where_sql.push_str(&format!(" AND \"{}\" = ${}", lock.name, pk_placeholder_idx + key_fields(fields_data).len()));
+        }
+        format!("UPDATE \"{}\" SET {} WHERE {} RETURNING {}", table_name_str, update_set_str_sql, where_sql, all_sql_columns_joined_str)
     };
 
     (sql, is_select_only)
@@ -591,7 +1292,9 @@ fn generate_bind_streams(fields_data: &[FieldData]) -> (Vec<TokenStream>, Vec<To
         };
         
         insert_bindings_streams.push(bind_stream.clone());
-        update_bindings_streams.push(bind_stream);
+        if !field.is_key && !field.optimistic_lock {
+            update_bindings_streams.push(bind_stream);
+        }
     }
 
     (insert_bindings_streams, update_bindings_streams)
@@ -629,9 +1332,93 @@ pub fn generate_migrate_fn(
     fields_data: &[FieldData],
     allow_column_dropping: bool,
     allow_type_change: bool,
+    conflict_target: Option<&[String]>,
 ) -> TokenStream {
     let active_fields: Vec<_> = fields_data.iter().filter(|f| !f.is_skipped).collect();
 
+    // Tables created before `#[conflict_target]` was declared still need its unique index
+    let ensure_conflict_index = match (conflict_target, conflict_target_index_sql(table_name, conflict_target)) {
+        (Some(target), Some((index_name, index_sql))) => {
+            let key_columns = key_fields(fields_data).iter().map(|f| f.name.as_str()).collect::<Vec<_>>();
+            let duplicates_sql = conflict_target_duplicates_sql(table_name, target, &key_columns);
+            let target_desc = target.join(", ");
+            let key_desc = key_columns.join(", ");
+            quote! {
+                let conflict_index_exists: bool = sqlx::query_scalar(
+                    "SELECT EXISTS (SELECT FROM pg_indexes WHERE schemaname = 'public' AND tablename = $1 AND indexname = $2)"
+                )
+                .bind(#table_name)
+                .bind(#index_name)
+                .fetch_one(pool)
+                .await?;
+                if !conflict_index_exists {
+                    // Never resolved here: which duplicate to keep is not the migration's call
+                    let duplicates: Vec<String> = sqlx::query_scalar(#duplicates_sql).fetch_all(pool).await?;
+                    if !duplicates.is_empty() {
+                        return Err(anyhow::anyhow!(
+                            "[MIGRATE] Table '{}': cannot create unique index '{}', rows share the conflict target ({}). Duplicates as `{} | {}; ...`:\n{}",
+                            #table_name, #index_name, #target_desc, #target_desc, #key_desc, duplicates.join("\n")
+                        ));
+                    }
+                    tracing::info!("[MIGRATE][ACTION] Table '{}': Creating unique index '{}' for the conflict target.", #table_name, #index_name);
+                    alter_statements.push(#index_sql.to_string());
+                }
+            }
+        },
+        _ => quote! {},
+    };
+
+    // Renames are planned first and applied to `db_columns`, so the diffing below
+    // sees the column under its new name instead of adding it and orphaning the old one
+    let rename_column_logics = active_fields
+        .iter()
+        .filter_map(|field| field.renamed_from.as_ref().map(|old_name| (&field.name, old_name)))
+        .map(|(col_name, old_name)| {
+            let rename_sql = format!(
+                "ALTER TABLE \"{}\" RENAME COLUMN \"{}\" TO \"{}\"",
+                table_name, old_name, col_name
+            );
+            quote! {
+                if !db_columns.contains_key(#col_name) {
+                    if let Some(column) = db_columns.remove(#old_name) {
+                        tracing::info!("[MIGRATE][ACTION] Table '{}': Renaming column '{}' to '{}'.", #table_name, #old_name, #col_name);
+                        alter_statements.push(#rename_sql.to_string());
+                        db_columns.insert(#col_name.to_string(), column);
+                    }
+                }
+            }
+        });
+    // After the columns are added, since the generated column reads its source column
+    let fulltext_logics = active_fields
+        .iter()
+        .filter(|field| field.fulltext)
+        .map(|field| {
+            let (tsv_column, definition, index_name, index_sql) = fulltext_column_sql(table_name, &field.name);
+            let add_sql = format!("ALTER TABLE \"{}\" ADD COLUMN {}", table_name, definition);
+            quote! {
+                if !db_columns.contains_key(#tsv_column) {
+                    tracing::info!("[MIGRATE][ACTION] Table '{}': Adding full-text column '{}'.", #table_name, #tsv_column);
+                    alter_statements.push(#add_sql.to_string());
+                }
+                let fulltext_index_exists: bool = sqlx::query_scalar(
+                    "SELECT EXISTS (SELECT FROM pg_indexes WHERE schemaname = 'public' AND tablename = $1 AND indexname = $2)"
+                )
+                .bind(#table_name)
+                .bind(#index_name)
+                .fetch_one(pool)
+                .await?;
+                if !fulltext_index_exists {
+                    tracing::info!("[MIGRATE][ACTION] Table '{}': Creating full-text index '{}'.", #table_name, #index_name);
+                    alter_statements.push(#index_sql.to_string());
+                }
+            }
+        });
+
+    let db_columns_mut = match active_fields.iter().any(|f| f.renamed_from.is_some()) {
+        true => quote! { mut },
+        false => quote! {},
+    };
+
     let add_column_logics = active_fields
         .iter()
         .map(|field| {
@@ -646,9 +1433,15 @@ pub fn generate_migrate_fn(
                 sql_type.clone(),
             ];
 
-            if !is_nullable {
+            if let Some(default) = &field.column_default {
+                add_sql_parts.push(format!("DEFAULT {}", default));
+                if !is_nullable {
+                    add_sql_parts.push("NOT NULL".to_string());
+                }
+            } else if !is_nullable {
                 add_sql_parts.push("NOT NULL".to_string());
-                let default_clause = get_sql_default_value(field);
+                // A `#[pg_enum]` type name can look like any other SQL type to `get_sql_default_value`
+                let default_clause = if field.pg_enum { String::new() } else { get_sql_default_value(field) };
                 if !default_clause.is_empty() {
                     add_sql_parts.push(default_clause);
                 }
@@ -671,19 +1464,40 @@ pub fn generate_migrate_fn(
             let sql_type = &f.sql_type;
             let is_nullable = f.is_option;
             let vector_dim = f.vector_dimension.unwrap_or(0);
+            let column_default = f.column_default.clone().unwrap_or_default();
             quote! {
-                ( #column_name, #sql_type, #is_nullable, #vector_dim )
+                ( #column_name, #sql_type, #is_nullable, #vector_dim, #column_default )
             }
         })
+        // Generated full-text columns belong to the struct too, so they are not reported as orphaned
+        .chain(active_fields.iter().filter(|f| f.fulltext).map(|f| {
+            let tsv_column = fulltext_column_sql(table_name, &f.name).0;
+            quote! {
+                ( #tsv_column, "TSVECTOR", true, 0usize, "" )
+            }
+        }))
         .collect();
     
     let has_updated_at = active_fields.iter().any(|f| f.name == "updated_at");
     let trigger_name = format!("set_updated_at_{}", table_name);
 
+    let pg_enum_columns: Vec<&str> = active_fields.iter().filter(|f| f.pg_enum).map(|f| f.name.as_str()).collect();
+    let mut pg_enum_types: Vec<Type> = Vec::new();
+    for field in active_fields.iter().filter(|f| f.pg_enum) {
+        let ty = get_option_inner_type(&field.ty).unwrap_or_else(|| field.ty.clone());
+        let ty_str = get_fully_qualified_type_string(&ty);
+        if !pg_enum_types.iter().any(|t| get_fully_qualified_type_string(t) == ty_str) {
+            pg_enum_types.push(ty);
+        }
+    }
+
     quote! {
         #[async_trait::async_trait]
         impl ::metastable_database::SchemaMigrator for #struct_name {
-            async fn migrate(pool: &::sqlx::PgPool) -> anyhow::Result<()> {
+            async fn migrate_with(
+                pool: &::sqlx::PgPool,
+                options: ::metastable_database::MigrateOptions,
+            ) -> anyhow::Result<Vec<String>> {
                 use sqlx::Row;
                 
                 fn get_sql_default_value(sql_type: &str, vector_dimension: usize) -> String {
@@ -711,11 +1525,14 @@ pub fn generate_migrate_fn(
                     }
                 }
 
-                fn generate_casting_expression(col_name: &str, db_type: &str, struct_type: &str) -> String {
+                fn generate_casting_expression(col_name: &str, db_type: &str, struct_type: &str, is_pg_enum: bool) -> String {
                     let db_type_upper = db_type.to_uppercase();
                     let struct_type_upper = struct_type.to_uppercase();
                 
-                    if (db_type_upper.starts_with('_') || db_type_upper.ends_with("[]")) && struct_type_upper == "JSONB" {
+                    if is_pg_enum && db_type_upper == "JSONB" {
+                        // Enum values were stored as JSON strings
+                        format!("(\"{}\" #>> '{{}}')::{}", col_name, struct_type)
+                    } else if (db_type_upper.starts_with('_') || db_type_upper.ends_with("[]")) && struct_type_upper == "JSONB" {
                         format!("array_to_json(\"{}\")::jsonb", col_name)
                     } else {
                         format!("\"{}\"::{}", col_name, struct_type)
@@ -749,8 +1566,28 @@ pub fn generate_migrate_fn(
                     }
                 }
 
+                const PG_ENUM_COLUMNS: &[&str] = &[#(#pg_enum_columns),*];
+
                 tracing::info!("[MIGRATE][INFO] Starting migration check for table '{}'...", #table_name);
 
+                // Enum types are created or extended first, outside the table's transaction,
+                // so the columns below can use their new labels.
+                let mut enum_statements: Vec<String> = Vec::new();
+                #(
+                    let plan = ::metastable_database::plan_pg_enum_migration::<#pg_enum_types>(pool).await?;
+                    for label in &plan.unknown_labels {
+                        tracing::warn!("[MIGRATE][WARNING] Type '{}': Label '{}' exists in the database but not in the enum. It will NOT be dropped.", <#pg_enum_types as ::metastable_database::PgEnum>::TYPE_NAME, label);
+                    }
+                    enum_statements.extend(plan.statements);
+                )*
+                if !options.dry_run {
+                    for statement in &enum_statements {
+                        tracing::info!("[MIGRATE][ACTION] Table '{}': Running '{}'.", #table_name, statement);
+                        sqlx::query(statement).execute(pool).await
+                            .map_err(|e| anyhow::anyhow!("Failed to execute statement '{}': {}", statement, e))?;
+                    }
+                }
+
                 let table_exists: bool = sqlx::query_scalar(
                     "SELECT EXISTS (
                         SELECT FROM information_schema.tables 
@@ -762,8 +1599,21 @@ pub fn generate_migrate_fn(
                 .await?;
 
                 if !table_exists {
-                    tracing::info!("[MIGRATE][ACTION] Table '{}' does not exist. Creating it now.", #table_name);
                     let create_sql = Self::create_table_sql();
+                    let trigger_sql = Self::trigger_sql();
+                    let mut statements = vec![create_sql];
+                    statements.extend(Self::INDEXES_SQL.iter().map(|s| s.to_string()));
+                    statements.extend(
+                        trigger_sql.split(';').filter(|s| !s.trim().is_empty()).map(|s| s.to_string())
+                    );
+
+                    enum_statements.extend(statements.iter().cloned());
+                    if options.dry_run {
+                        tracing::info!("[MIGRATE][DRY RUN] Table '{}' does not exist. Would run: {:?}", #table_name, enum_statements);
+                        return Ok(enum_statements);
+                    }
+
+                    tracing::info!("[MIGRATE][ACTION] Table '{}' does not exist. Creating it now.", #table_name);
                     let trigger_func_sql = r#"
                     CREATE OR REPLACE FUNCTION set_updated_at_unix_timestamp()
                     RETURNS TRIGGER AS $$
@@ -772,24 +1622,17 @@ pub fn generate_migrate_fn(
                     "#;
                     let mut tx = pool.begin().await?;
                     sqlx::query(trigger_func_sql).execute(&mut *tx).await.ok();
-                    sqlx::query(&create_sql).execute(&mut *tx).await?;
-                    for index_sql in Self::INDEXES_SQL {
-                        sqlx::query(index_sql).execute(&mut *tx).await?;
-                    }
-                    let trigger_sql = Self::trigger_sql();
-                     if !trigger_sql.is_empty() {
-                        for statement in trigger_sql.split(';').filter(|s| !s.trim().is_empty()) {
-                            sqlx::query(statement).execute(&mut *tx).await
-                                .map_err(|e| anyhow::anyhow!("Failed to execute trigger statement '{}': {}", statement, e))?;
-                        }
+                    for statement in &statements {
+                        sqlx::query(statement).execute(&mut *tx).await
+                            .map_err(|e| anyhow::anyhow!("Failed to execute statement '{}': {}", statement, e))?;
                     }
                     tx.commit().await?;
 
                     tracing::info!("[MIGRATE][SUCCESS] Table '{}' created.", #table_name);
-                    return Ok(());
+                    return Ok(enum_statements);
                 }
 
-                let db_columns: std::collections::HashMap<String, (String, bool)> = sqlx::query(
+                let #db_columns_mut db_columns: std::collections::HashMap<String, (String, bool)> = sqlx::query(
                     "SELECT column_name, udt_name, is_nullable 
                      FROM information_schema.columns 
                      WHERE table_name = $1 AND table_schema = 'public'"
@@ -806,22 +1649,25 @@ pub fn generate_migrate_fn(
                 })
                 .collect();
 
-                let struct_columns: std::collections::HashMap<String, (String, bool, usize)> = {
+                let struct_columns: std::collections::HashMap<String, (String, bool, usize, &str)> = {
                     let mut map = std::collections::HashMap::new();
                     #(
-                        let (col_name, sql_type, is_nullable, vector_dim) = #struct_column_definitions;
-                        map.insert(col_name.to_string(), (sql_type.to_string(), is_nullable, vector_dim));
+                        let (col_name, sql_type, is_nullable, vector_dim, column_default) = #struct_column_definitions;
+                        map.insert(col_name.to_string(), (sql_type.to_string(), is_nullable, vector_dim, column_default));
                     )*
                     map
                 };
 
                 let mut alter_statements = Vec::new();
 
+                #(#rename_column_logics)*
                 #(#add_column_logics)*
+                #(#fulltext_logics)*
+                #ensure_conflict_index
                 
                 for (col_name, _) in &db_columns {
                     if !struct_columns.contains_key(col_name) {
-                        if #allow_column_dropping {
+                        if #allow_column_dropping && options.allow_drops {
                             let drop_sql = format!("ALTER TABLE \"{}\" DROP COLUMN \"{}\"", #table_name, col_name);
                             tracing::info!("[MIGRATE][ACTION] Table '{}': Dropping column '{}' as 'allow_column_dropping' is enabled.", #table_name, col_name);
                             alter_statements.push(drop_sql);
@@ -832,7 +1678,7 @@ pub fn generate_migrate_fn(
                 }
 
                 for (col_name, (db_type, db_nullable)) in &db_columns {
-                    if let Some((struct_type, struct_nullable, vector_dim)) = struct_columns.get(col_name) {
+                    if let Some((struct_type, struct_nullable, vector_dim, column_default)) = struct_columns.get(col_name) {
                         if !are_sql_types_equivalent(struct_type, db_type) {
                             if #allow_type_change {
                                 tracing::info!("[MIGRATE][ACTION] Table '{}': Changing type of column '{}' to '{}' as 'allow_type_change' is enabled.", #table_name, col_name, struct_type);
@@ -840,14 +1686,21 @@ pub fn generate_migrate_fn(
                                 let drop_default_sql = format!("ALTER TABLE \"{}\" ALTER COLUMN \"{}\" DROP DEFAULT", #table_name, col_name);
                                 alter_statements.push(drop_default_sql);
                                 
-                                let using_clause = generate_casting_expression(col_name, db_type, struct_type);
+                                let is_pg_enum = PG_ENUM_COLUMNS.contains(&col_name.as_str());
+                                let using_clause = generate_casting_expression(col_name, db_type, struct_type, is_pg_enum);
                                 let alter_type_sql = format!(
                                     "ALTER TABLE \"{}\" ALTER COLUMN \"{}\" TYPE {} USING {}", 
                                     #table_name, col_name, struct_type, using_clause
                                 );
                                 alter_statements.push(alter_type_sql);
 
-                                let new_default_clause = get_sql_default_value(struct_type, *vector_dim);
+                                let new_default_clause = if !column_default.is_empty() {
+                                    format!("DEFAULT {}", column_default)
+                                } else if is_pg_enum {
+                                    String::new()
+                                } else {
+                                    get_sql_default_value(struct_type, *vector_dim)
+                                };
                                 if !new_default_clause.is_empty() {
                                     let set_default_sql = format!("ALTER TABLE \"{}\" ALTER COLUMN \"{}\" SET {}", #table_name, col_name, new_default_clause);
                                     alter_statements.push(set_default_sql);
@@ -865,7 +1718,9 @@ pub fn generate_migrate_fn(
                     }
                 }
 
-                if !alter_statements.is_empty() {
+                if !alter_statements.is_empty() && options.dry_run {
+                    tracing::info!("[MIGRATE][DRY RUN] Table '{}' would run: {:?}", #table_name, alter_statements);
+                } else if !alter_statements.is_empty() {
                     let mut tx = pool.begin().await?;
 
                     if #has_updated_at {
@@ -873,8 +1728,8 @@ pub fn generate_migrate_fn(
                         sqlx::query(&disable_trigger_sql).execute(&mut *tx).await.ok();
                     }
 
-                    for stmt in alter_statements {
-                        sqlx::query(&stmt).execute(&mut *tx).await?;
+                    for stmt in &alter_statements {
+                        sqlx::query(stmt).execute(&mut *tx).await?;
                     }
 
                     if #has_updated_at {
@@ -888,7 +1743,8 @@ pub fn generate_migrate_fn(
                     tracing::info!("[MIGRATE][INFO] Table '{}' is already up-to-date.", #table_name);
                 }
 
-                Ok(())
+                enum_statements.extend(alter_statements);
+                Ok(enum_statements)
             }
         }
     }
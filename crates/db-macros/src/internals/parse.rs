@@ -1,9 +1,10 @@
 use quote::format_ident;
-use syn::Field;
+use syn::{Field, Type};
 use super::types::{
     FieldData, ForeignKeyInfo, ForeignKeyManyInfo, get_fully_qualified_type_string, 
-    get_option_inner_type, is_option_type, map_rust_type_to_sql
+    get_option_inner_type, get_vec_inner_type, is_option_type, is_simple_type, map_rust_type_to_sql
 };
+use crate::text_codec_internals::parse::pg_enum_type_name;
 
 // Functions for parsing attributes from fields
 pub fn parse_foreign_key_attr(field: &Field) -> Option<ForeignKeyInfo> {
@@ -87,6 +88,56 @@ pub fn parse_vector_dimension_attr(field: &Field) -> Option<usize> {
     None
 }
 
+/// The string of a `#[name = "..."]` field attribute, if present.
+fn parse_str_attr(field: &Field, name: &str, example: &str) -> Option<String> {
+    for attr in field.attrs.iter() {
+        if attr.path.is_ident(name) {
+            if let Ok(syn::Meta::NameValue(syn::MetaNameValue { lit: syn::Lit::Str(lit_str), .. })) = attr.parse_meta() {
+                return Some(lit_str.value());
+            }
+            panic!("`#[{}]` expects a string, e.g. `#[{} = \"{}\"]`.", name, name, example);
+        }
+    }
+    None
+}
+
+/// The SQL expression given in `#[column_default = "..."]`, if any.
+pub fn parse_column_default_attr(field: &Field) -> Option<String> {
+    let default = parse_str_attr(field, "column_default", "true")?;
+    if default.trim().is_empty() {
+        panic!("`#[column_default]` on field '{}' needs a non-empty SQL expression.", field.ident.as_ref().unwrap());
+    }
+    Some(default)
+}
+
+/// The column's previous name given in `#[renamed_from = "..."]`, if any.
+pub fn parse_renamed_from_attr(field: &Field) -> Option<String> {
+    parse_str_attr(field, "renamed_from", "old_name")
+}
+
+/// Column names listed in a struct-level `#[conflict_target(a, b)]`, or `None`
+/// when the attribute is absent. A malformed attribute is an error.
+pub fn parse_conflict_target_attr(attrs: &[syn::Attribute]) -> syn::Result<Option<Vec<String>>> {
+    let Some(attr) = attrs.iter().find(|attr| attr.path.is_ident("conflict_target")) else {
+        return Ok(None);
+    };
+    let columns = match attr.parse_meta()? {
+        syn::Meta::List(meta_list) => meta_list.nested.iter()
+            .map(|nested| match nested {
+                syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.get_ident().is_some() => {
+                    Ok(path.get_ident().unwrap().to_string())
+                }
+                other => Err(syn::Error::new_spanned(other, "expected a column name")),
+            })
+            .collect::<syn::Result<Vec<String>>>()?,
+        other => return Err(syn::Error::new_spanned(other, "expected `#[conflict_target(column, ...)]`")),
+    };
+    if columns.is_empty() {
+        return Err(syn::Error::new_spanned(attr, "`#[conflict_target(...)]` needs at least one column"));
+    }
+    Ok(Some(columns))
+}
+
 pub fn has_unique_attr(field: &Field) -> bool {
     field.attrs.iter().any(|attr| attr.path.is_ident("unique"))
 }
@@ -95,6 +146,51 @@ pub fn has_indexed_attr(field: &Field) -> bool {
     field.attrs.iter().any(|attr| attr.path.is_ident("indexed"))
 }
 
+/// `#[indexed(gin)]` asks for a GIN index instead of the default B-tree.
+pub fn has_gin_index_attr(field: &Field) -> bool {
+    let Some(attr) = field.attrs.iter().find(|attr| attr.path.is_ident("indexed")) else {
+        return false;
+    };
+    match attr.parse_meta() {
+        Ok(syn::Meta::Path(_)) => false,
+        Ok(syn::Meta::List(list)) => match list.nested.iter().collect::<Vec<_>>().as_slice() {
+            [syn::NestedMeta::Meta(syn::Meta::Path(path))] if path.is_ident("gin") => true,
+            _ => panic!("Expected `#[indexed]` or `#[indexed(gin)]` on '{}'.", field.ident.as_ref().unwrap()),
+        },
+        _ => panic!("Expected `#[indexed]` or `#[indexed(gin)]` on '{}'.", field.ident.as_ref().unwrap()),
+    }
+}
+
+pub fn has_sensitive_attr(field: &Field) -> bool {
+    field.attrs.iter().any(|attr| attr.path.is_ident("sensitive"))
+}
+
+pub fn has_primary_key_attr(field: &Field) -> bool {
+    field.attrs.iter().any(|attr| attr.path.is_ident("primary_key"))
+}
+
+pub fn has_optimistic_lock_attr(field: &Field) -> bool {
+    field.attrs.iter().any(|attr| attr.path.is_ident("optimistic_lock"))
+}
+
+pub fn has_pg_enum_attr(field: &Field) -> bool {
+    field.attrs.iter().any(|attr| attr.path.is_ident("pg_enum"))
+}
+
+/// The Postgres enum type of a `#[pg_enum]` field, named after the enum like `#[pg_enum]` on a `TextEnum`.
+fn pg_enum_sql_type(ty: &Type) -> String {
+    match ty {
+        Type::Path(type_path) if !is_simple_type(ty) && get_vec_inner_type(ty).is_none() => {
+            pg_enum_type_name(&type_path.path.segments.last().unwrap().ident)
+        }
+        _ => panic!("`#[pg_enum]` requires a field whose type is a `#[pg_enum]` TextEnum, found '{}'.", get_fully_qualified_type_string(ty)),
+    }
+}
+
+pub fn has_fulltext_attr(field: &Field) -> bool {
+    field.attrs.iter().any(|attr| attr.path.is_ident("fulltext"))
+}
+
 pub fn has_sqlx_skip_column_attr(field: &Field) -> bool {
     field.attrs.iter().any(|attr| attr.path.is_ident("sqlx_skip_column"))
 }
@@ -107,6 +203,8 @@ pub fn get_fields_data(fields: &syn::punctuated::Punctuated<syn::Field, syn::Tok
         let field_is_option = is_option_type(field_ty);
         let field_is_pk = field_ident == "id";
         let field_is_skipped = has_sqlx_skip_column_attr(field);
+        let field_is_pg_enum = has_pg_enum_attr(field);
+        let field_is_fulltext = has_fulltext_attr(field);
 
         let type_for_analysis = get_option_inner_type(field_ty).unwrap_or_else(|| field_ty.clone());
         let fq_type_str_for_analysis = get_fully_qualified_type_string(&type_for_analysis);
@@ -116,25 +214,42 @@ pub fn get_fields_data(fields: &syn::punctuated::Punctuated<syn::Field, syn::Tok
         
         let sql_type_str = if field_is_skipped {
             "SKIP".to_string() 
+        } else if field_is_pg_enum {
+            pg_enum_sql_type(&type_for_analysis)
         } else if is_json_type_for_analysis {
             "JSONB".to_string()
         } else {
             let actual_type_for_sql_map = if field_is_option { type_for_analysis.clone() } else { field_ty.clone() };
             map_rust_type_to_sql(&actual_type_for_sql_map, field_is_pk, false, vector_dimension)
         };
+        if field_is_fulltext && sql_type_str != "TEXT" {
+            panic!("`#[fulltext]` requires a `String` or `Option<String>` field, found '{}' on '{}'.", get_fully_qualified_type_string(field_ty), field_ident);
+        }
+        let index_gin = has_gin_index_attr(field);
+        if index_gin && !(sql_type_str.ends_with("[]") || sql_type_str == "JSONB") {
+            panic!("`#[indexed(gin)]` requires an array or JSON field, found '{}' on '{}'.", get_fully_qualified_type_string(field_ty), field_ident);
+        }
 
         FieldData {
             name: field_ident.to_string(),
             ty: field_ty.clone(),
             is_option: field_is_option,
             is_pk: field_is_pk,
+            is_key: has_primary_key_attr(field),
             is_skipped: field_is_skipped,
             sql_type: sql_type_str,
             foreign_key: parse_foreign_key_attr(field),
             foreign_key_many: parse_foreign_key_many_attr(field),
             unique: has_unique_attr(field),
             indexed: has_indexed_attr(field),
+            index_gin,
             vector_dimension: vector_dimension,
+            sensitive: has_sensitive_attr(field),
+            optimistic_lock: has_optimistic_lock_attr(field),
+            pg_enum: field_is_pg_enum,
+            column_default: parse_column_default_attr(field),
+            renamed_from: parse_renamed_from_attr(field),
+            fulltext: field_is_fulltext,
         }
     }).collect()
 } 
\ No newline at end of file
@@ -19,13 +19,28 @@ pub struct FieldData {
     pub ty: syn::Type,
     pub is_option: bool,
     pub is_pk: bool,
+    /// Marked `#[primary_key]`: part of a caller-supplied (possibly composite) key.
+    pub is_key: bool,
     pub is_skipped: bool,
     pub sql_type: String,
     pub foreign_key: Option<ForeignKeyInfo>,
     pub foreign_key_many: Option<ForeignKeyManyInfo>,
     pub unique: bool,
     pub indexed: bool,
+    /// Marked `#[indexed(gin)]`: the index is GIN, for `@>` on arrays and JSONB.
+    pub index_gin: bool,
     pub vector_dimension: Option<usize>,
+    pub sensitive: bool,
+    /// Marked `#[optimistic_lock]`: checked and bumped by `update()`.
+    pub optimistic_lock: bool,
+    /// Marked `#[pg_enum]`: a column of the enum's native Postgres type.
+    pub pg_enum: bool,
+    /// `#[column_default = "..."]`: a SQL default used verbatim instead of the one derived from its type.
+    pub column_default: Option<String>,
+    /// `#[renamed_from = "..."]`: the column's previous name, renamed in place by the migrator.
+    pub renamed_from: Option<String>,
+    /// Marked `#[fulltext]`: backed by a generated `tsvector` column with a GIN index.
+    pub fulltext: bool,
 }
 
 impl std::fmt::Debug for FieldData {
@@ -35,13 +50,21 @@ impl std::fmt::Debug for FieldData {
             .field("ty", &self.ty.to_token_stream().to_string())
             .field("is_option", &self.is_option)
             .field("is_pk", &self.is_pk)
+            .field("is_key", &self.is_key)
             .field("is_skipped", &self.is_skipped)
             .field("sql_type", &self.sql_type)
             .field("foreign_key", &self.foreign_key)
             .field("foreign_key_many", &self.foreign_key_many)
             .field("unique", &self.unique)
             .field("indexed", &self.indexed)
+            .field("index_gin", &self.index_gin)
             .field("vector_dimension", &self.vector_dimension)
+            .field("sensitive", &self.sensitive)
+            .field("optimistic_lock", &self.optimistic_lock)
+            .field("pg_enum", &self.pg_enum)
+            .field("column_default", &self.column_default)
+            .field("renamed_from", &self.renamed_from)
+            .field("fulltext", &self.fulltext)
             .finish()
     }
 }
@@ -5,11 +5,11 @@ use syn::{parse_macro_input, Data, DataStruct, DeriveInput, Fields, Lit, Meta};
 mod internals;
 mod text_codec_internals;
 use internals::{
-    codegen::{generate_migrate_fn, generate_row_struct, generate_sqlx_schema_impl, generate_sqlx_crud_impl, generate_sqlx_filter_query_impl, generate_fetch_helpers},
-    parse::get_fields_data,
+    codegen::{generate_migrate_fn, generate_row_struct, generate_sqlx_schema_impl, generate_sqlx_crud_impl, generate_sqlx_filter_query_impl, generate_fetch_helpers, generate_debug_impl, generate_sqlx_touch_impl, generate_sqlx_soft_delete_impl, generate_sqlx_upsert_impl},
+    parse::{get_fields_data, parse_conflict_target_attr},
 };
 
-#[proc_macro_derive(SqlxObject, attributes(table_name, foreign_key, foreign_key_many, sqlx_skip_column, unique, vector_dimension, indexed, allow_column_dropping, allow_type_change))]
+#[proc_macro_derive(SqlxObject, attributes(table_name, foreign_key, foreign_key_many, sqlx_skip_column, unique, vector_dimension, indexed, sensitive, allow_column_dropping, allow_type_change, soft_delete, primary_key, conflict_target, optimistic_lock, pg_enum, column_default, renamed_from, fulltext))]
 pub fn sqlx_object_derive(input: TokenStream) -> TokenStream {
     let input_ast = parse_macro_input!(input as DeriveInput);
     let struct_name = &input_ast.ident;
@@ -43,18 +43,72 @@ pub fn sqlx_object_derive(input: TokenStream) -> TokenStream {
 
     let fields_data = get_fields_data(all_fields_in_struct);
 
+    let key_fields: Vec<_> = fields_data.iter().filter(|f| f.is_key).collect();
+    if !key_fields.is_empty() {
+        if fields_data.iter().any(|f| f.is_pk) {
+            return syn::Error::new_spanned(struct_name, "#[derive(SqlxObject)] cannot combine the default `id` primary key with `#[primary_key]` fields; rename `id` or drop `#[primary_key]`.")
+                .to_compile_error()
+                .into();
+        }
+        if let Some(field) = key_fields.iter().find(|f| f.is_option || f.is_skipped) {
+            return syn::Error::new_spanned(struct_name, format!("`#[primary_key]` field `{}` must be a non-optional column.", field.name))
+                .to_compile_error()
+                .into();
+        }
+    }
+
+    let lock_fields: Vec<_> = fields_data.iter().filter(|f| f.optimistic_lock).collect();
+    if lock_fields.len() > 1 {
+        return syn::Error::new_spanned(struct_name, "#[derive(SqlxObject)] allows only one `#[optimistic_lock]` field.")
+            .to_compile_error()
+            .into();
+    }
+    if let Some(field) = lock_fields.first()
+        && (field.is_option || field.is_skipped || field.is_pk || field.is_key || !matches!(field.sql_type.as_str(), "BIGINT" | "INTEGER"))
+    {
+        return syn::Error::new_spanned(struct_name, format!("`#[optimistic_lock]` field `{}` must be a non-optional integer column outside the primary key.", field.name))
+            .to_compile_error()
+            .into();
+    }
+
     // --- Code Generation ---
     let row_struct_name = format_ident!("{}RowSqlx", struct_name);
     let allow_column_dropping = input_ast.attrs.iter().any(|attr| attr.path.is_ident("allow_column_dropping"));
     let allow_type_change = input_ast.attrs.iter().any(|attr| attr.path.is_ident("allow_type_change"));
-    
+    let soft_delete = input_ast.attrs.iter().any(|attr| attr.path.is_ident("soft_delete"));
+    if soft_delete {
+        let has_deleted_at = fields_data.iter()
+            .any(|f| f.name == "deleted_at" && f.is_option && !f.is_skipped && f.sql_type == "BIGINT");
+        if !has_deleted_at {
+            return syn::Error::new_spanned(struct_name, "#[soft_delete] requires a `deleted_at: Option<i64>` field.")
+                .to_compile_error()
+                .into();
+        }
+    }
+    let conflict_target = match parse_conflict_target_attr(&input_ast.attrs) {
+        Ok(target) => target,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    if let Some(target) = &conflict_target
+        && let Some(column) = target.iter().find(|c| !fields_data.iter().any(|f| &f.name == *c && !f.is_skipped))
+    {
+        return syn::Error::new_spanned(struct_name, format!("`#[conflict_target]` column `{}` is not a field of this struct.", column))
+            .to_compile_error()
+            .into();
+    }
+    let conflict_target = conflict_target.as_deref();
+
     let row_struct_def = generate_row_struct(&row_struct_name, &fields_data);
-    let sqlx_schema_impl = generate_sqlx_schema_impl(struct_name, &row_struct_name, &table_name_str, &fields_data);
-    let sqlx_crud_impl = generate_sqlx_crud_impl(struct_name, &table_name_str, &fields_data);
-    let sqlx_filter_query_impl = generate_sqlx_filter_query_impl(struct_name, &row_struct_name);
+    let sqlx_schema_impl = generate_sqlx_schema_impl(struct_name, &row_struct_name, &table_name_str, &fields_data, conflict_target, soft_delete);
+    let sqlx_crud_impl = generate_sqlx_crud_impl(struct_name, &table_name_str, &fields_data, soft_delete);
+    let sqlx_filter_query_impl = generate_sqlx_filter_query_impl(struct_name, &row_struct_name, soft_delete);
+    let sqlx_touch_impl = generate_sqlx_touch_impl(struct_name, &table_name_str, &fields_data);
+    let sqlx_soft_delete_impl = generate_sqlx_soft_delete_impl(struct_name, &table_name_str, &fields_data, soft_delete);
+    let sqlx_upsert_impl = generate_sqlx_upsert_impl(struct_name, &table_name_str, &fields_data, conflict_target);
     
-    let fetch_helpers = generate_fetch_helpers(&fields_data);
-    let migrate_impl = generate_migrate_fn(struct_name, &table_name_str, &fields_data, allow_column_dropping, allow_type_change);
+    let fetch_helpers = generate_fetch_helpers(&fields_data, soft_delete);
+    let debug_impl = generate_debug_impl(struct_name, &fields_data);
+    let migrate_impl = generate_migrate_fn(struct_name, &table_name_str, &fields_data, allow_column_dropping, allow_type_change, conflict_target);
 
     let expanded = quote! {
         use ::metastable_database::{SqlxSchema, SqlxCrud, SqlxFilterQuery, QueryCriteria};
@@ -63,6 +117,10 @@ pub fn sqlx_object_derive(input: TokenStream) -> TokenStream {
         #sqlx_schema_impl
         #sqlx_crud_impl
         #sqlx_filter_query_impl
+        #sqlx_touch_impl
+        #sqlx_soft_delete_impl
+        #sqlx_upsert_impl
+        #debug_impl
         
         #[automatically_derived]
         impl #struct_name {
@@ -75,7 +133,7 @@ pub fn sqlx_object_derive(input: TokenStream) -> TokenStream {
     TokenStream::from(expanded)
 }
 
-#[proc_macro_derive(TextEnum, attributes(text_enum, prefix, catch_all))]
+#[proc_macro_derive(TextEnum, attributes(text_enum, prefix, catch_all, format, collection, pg_enum))]
 pub fn text_enum_derive(input: TokenStream) -> TokenStream {
     let input_ast = parse_macro_input!(input as DeriveInput);
 
@@ -2,7 +2,7 @@ use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 use syn::Ident;
 
-use super::parse::{TextEnumCodec, TextEnumVariant, VariantKind};
+use super::parse::{pg_enum_type_name, CollectionFormat, TextEnumCodec, TextEnumVariant, TextFormat, VariantKind};
 
 pub fn generate_text_enum_impl(parsed_enum: &TextEnumCodec) -> TokenStream {
     let enum_ident = &parsed_enum.ident;
@@ -12,8 +12,12 @@ pub fn generate_text_enum_impl(parsed_enum: &TextEnumCodec) -> TokenStream {
     let display_impl = generate_display_impl(enum_ident);
     let from_str_impl = generate_from_str_impl(enum_ident);
     let serialize_impl = generate_serialize_impl(enum_ident, &parsed_enum.variants);
-    let deserialize_impl = generate_deserialize_impl(enum_ident, &parsed_enum.variants);
-    let sqlx_impls = generate_sqlx_impls(enum_ident);
+    let deserialize_impl = generate_deserialize_impl(parsed_enum);
+    let sqlx_impls = if parsed_enum.pg_enum {
+        generate_pg_enum_impls(enum_ident, &parsed_enum.variants)
+    } else {
+        generate_sqlx_impls(enum_ident)
+    };
 
     quote! {
         #text_enum_codec_impl
@@ -31,7 +35,8 @@ fn generate_text_enum_codec_trait_impl(parsed_enum: &TextEnumCodec) -> TokenStre
     let type_lang = &parsed_enum.type_lang;
     let schema_lang = &parsed_enum.schema_lang;
 
-    let to_prompt_text_impl = generate_to_prompt_text_impl(type_lang, &parsed_enum.variants);
+    let to_prompt_text_impl = generate_to_prompt_text_impl(parsed_enum);
+    let from_prompt_text_impl = generate_from_prompt_text_impl(parsed_enum);
     let schema_impl = generate_schema_impl(parsed_enum);
 
     quote! {
@@ -40,6 +45,10 @@ fn generate_text_enum_codec_trait_impl(parsed_enum: &TextEnumCodec) -> TokenStre
                 #to_prompt_text_impl
             }
 
+            fn from_prompt_text(text: &str) -> anyhow::Result<Self> {
+                #from_prompt_text_impl
+            }
+
             fn schema(lang: Option<&str>) -> serde_json::Value {
                 #schema_impl
             }
@@ -84,30 +93,101 @@ fn generate_sqlx_impls(enum_ident: &Ident) -> TokenStream {
     }
 }
 
-fn generate_to_prompt_text_impl(type_lang: &str, variants: &[TextEnumVariant]) -> TokenStream {
-    let arms = variants.iter().map(|v| {
+/// Encodes unit variants as labels of a native Postgres enum named by `pg_enum_type_name`.
+fn generate_pg_enum_impls(enum_ident: &Ident, variants: &[TextEnumVariant]) -> TokenStream {
+    let type_name = pg_enum_type_name(enum_ident);
+    let variant_idents: Vec<_> = variants.iter().map(|v| &v.ident).collect();
+    let labels: Vec<String> = variant_idents.iter().map(|ident| ident.to_string()).collect();
+
+    quote! {
+        impl ::metastable_database::PgEnum for #enum_ident {
+            const TYPE_NAME: &'static str = #type_name;
+            const VARIANTS: &'static [&'static str] = &[#(#labels),*];
+        }
+
+        impl ::sqlx::Type<::sqlx::Postgres> for #enum_ident {
+            fn type_info() -> ::sqlx::postgres::PgTypeInfo {
+                ::sqlx::postgres::PgTypeInfo::with_name(#type_name)
+            }
+        }
+
+        impl<'q> ::sqlx::Encode<'q, ::sqlx::Postgres> for #enum_ident {
+            fn encode_by_ref(
+                &self,
+                buf: &mut ::sqlx::postgres::PgArgumentBuffer,
+            ) -> Result<::sqlx::encode::IsNull, Box<dyn std::error::Error + Send + Sync>> {
+                let label = match self {
+                    #(Self::#variant_idents => #labels,)*
+                };
+                <&str as ::sqlx::Encode<::sqlx::Postgres>>::encode_by_ref(&label, buf)
+            }
+        }
+
+        impl<'r> ::sqlx::Decode<'r, ::sqlx::Postgres> for #enum_ident {
+            fn decode(
+                value: ::sqlx::postgres::PgValueRef<'r>,
+            ) -> Result<Self, Box<dyn std::error::Error + 'static + Send + Sync>> {
+                match <&str as ::sqlx::Decode<::sqlx::Postgres>>::decode(value)? {
+                    #(#labels => Ok(Self::#variant_idents),)*
+                    label => Err(format!("Unknown {} label: {}", #type_name, label).into()),
+                }
+            }
+        }
+    }
+}
+
+/// The `TextListFormat` a `Vec<String>` variant's items are written with.
+fn list_format(variant: &TextEnumVariant) -> TokenStream {
+    match &variant.collection {
+        Some(CollectionFormat { open, sep, close }) => quote! {
+            ::metastable_database::TextListFormat { open: #open, sep: #sep, close: #close }
+        },
+        None => quote! { ::metastable_database::TextListFormat::DEFAULT },
+    }
+}
+
+fn generate_to_prompt_text_impl(parsed_enum: &TextEnumCodec) -> TokenStream {
+    let arms = parsed_enum.variants.iter().map(|v| {
         let variant_ident = &v.ident;
         let default_type = v.ident.to_string();
 
         // Get the type name for this language, fallback to default
-        let type_name = v.prefixes.get(type_lang).cloned().unwrap_or(default_type);
+        let type_name = v.prefixes.get(&parsed_enum.type_lang).cloned().unwrap_or(default_type);
+        let format = v.format.unwrap_or(parsed_enum.format);
+        let template = match format {
+            TextFormat::Colon => "{}: {}",
+            TextFormat::Paren => "{}({})",
+        };
 
         match v.kind {
             VariantKind::Unit => {
                 quote! { Self::#variant_ident => #type_name.to_string() }
             },
+            // Paren content is escaped, so its own delimiters cannot end or split it
             VariantKind::String => {
-                if v.is_catch_all {
+                if v.is_catch_all && parsed_enum.format == TextFormat::Paren {
+                    quote! { Self::#variant_ident(content) => ::metastable_database::escape_text(content) }
+                } else if v.is_catch_all {
                     quote! { Self::#variant_ident(content) => content.clone() }
+                } else if format == TextFormat::Paren {
+                    quote! { Self::#variant_ident(content) => format!(#template, #type_name, ::metastable_database::escape_text(content)) }
                 } else {
-                    quote! { Self::#variant_ident(content) => format!("{}: {}", #type_name, content) }
+                    quote! { Self::#variant_ident(content) => format!(#template, #type_name, content) }
                 }
             },
+            VariantKind::VecString if format == TextFormat::Paren => {
+                let list_format = list_format(v);
+                quote! { Self::#variant_ident(items) => format!(#template, #type_name, #list_format.encode_paren(items)) }
+            },
             VariantKind::VecString => {
-                quote! { Self::#variant_ident(items) => format!("{}: {}", #type_name, items.join(",")) }
+                let list_format = list_format(v);
+                quote! { Self::#variant_ident(items) => format!(#template, #type_name, #list_format.encode(items)) }
             },
             VariantKind::Uuid => {
-                quote! { Self::#variant_ident(id) => format!("{}: {}", #type_name, id) }
+                quote! { Self::#variant_ident(id) => format!(#template, #type_name, id) }
+            },
+            VariantKind::I64 | VariantKind::F64 | VariantKind::Bool => {
+                quote! { Self::#variant_ident(value) => format!(#template, #type_name, value) }
             },
             VariantKind::Unsupported => quote! { Self::#variant_ident => unreachable!() },
         }
@@ -120,6 +200,83 @@ fn generate_to_prompt_text_impl(type_lang: &str, variants: &[TextEnumVariant]) -
     }
 }
 
+/// Parses text produced by `to_prompt_text`, trying each variant's type names
+/// in its own format. Unmatched text falls into the catch-all variant, if any,
+/// exactly as given (unescaped, for paren enums); scalar content that does not
+/// parse is an error.
+fn generate_from_prompt_text_impl(parsed_enum: &TextEnumCodec) -> TokenStream {
+    let resolved_names = resolve_type_names(parsed_enum);
+
+    let attempts = parsed_enum.variants.iter().zip(&resolved_names).filter(|(v, _)| !v.is_catch_all).map(|(v, type_names)| {
+        let variant_ident = &v.ident;
+
+        let format = v.format.unwrap_or(parsed_enum.format);
+        let strip_format = match format {
+            TextFormat::Colon => quote! {
+                rest.strip_prefix(':').map(str::trim)
+            },
+            TextFormat::Paren => quote! {
+                rest.strip_prefix('(').and_then(|r| r.strip_suffix(')'))
+            },
+        };
+
+        let build = match v.kind {
+            VariantKind::Unit => return quote! {
+                #( if text == #type_names { return Ok(Self::#variant_ident); } )*
+            },
+            VariantKind::String if format == TextFormat::Paren => quote! {
+                Some(Self::#variant_ident(::metastable_database::unescape_text(content)))
+            },
+            VariantKind::String => quote! { Some(Self::#variant_ident(content.to_string())) },
+            VariantKind::VecString => {
+                let list_format = list_format(v);
+                quote! { Some(Self::#variant_ident(#list_format.decode(content))) }
+            },
+            VariantKind::Uuid => quote! { content.parse().ok().map(Self::#variant_ident) },
+            // A matching type name with a malformed value is an error, not a catch-all
+            VariantKind::I64 | VariantKind::F64 | VariantKind::Bool => {
+                let variant_name = variant_ident.to_string();
+                quote! {
+                    Some(Self::#variant_ident(content.trim().parse().map_err(|e| anyhow::anyhow!(
+                        "Invalid content for {}: {:?} ({})", #variant_name, content, e
+                    ))?))
+                }
+            },
+            VariantKind::Unsupported => return quote! {},
+        };
+
+        quote! {
+            #(
+                if let Some(rest) = text.strip_prefix(#type_names)
+                    && let Some(content) = #strip_format
+                    && let Some(parsed) = #build
+                {
+                    return Ok(parsed);
+                }
+            )*
+        }
+    });
+
+    let fallback = if let Some(catch_all) = parsed_enum.variants.iter().find(|v| v.is_catch_all) {
+        let variant_ident = &catch_all.ident;
+        if parsed_enum.format == TextFormat::Paren {
+            quote! { Ok(Self::#variant_ident(::metastable_database::unescape_text(original))) }
+        } else {
+            quote! { Ok(Self::#variant_ident(original.to_string())) }
+        }
+    } else {
+        quote! { Err(anyhow::anyhow!("Unknown prompt text: {}", original)) }
+    };
+
+    // Matching ignores surrounding whitespace, the catch-all keeps the input verbatim
+    quote! {
+        let original = text;
+        let text = text.trim();
+        #(#attempts)*
+        #fallback
+    }
+}
+
 fn generate_serialize_impl(enum_ident: &Ident, variants: &[TextEnumVariant]) -> TokenStream {
     let ser_repr_ident = format_ident!("{}SerRepr", enum_ident);
     let ser_struct_ident = format_ident!("{}SerStruct", enum_ident);
@@ -149,10 +306,11 @@ fn generate_serialize_impl(enum_ident: &Ident, variants: &[TextEnumVariant]) ->
                 }
             },
             VariantKind::VecString => {
+                let list_format = list_format(v);
                 quote! {
                     Self::#variant_ident(items) => #ser_repr_ident::Content(#ser_struct_ident {
                         typ: #type_name.into(),
-                        content: serde_json::json!(items.join(",")),
+                        content: serde_json::json!(#list_format.encode(items)),
                     })
                 }
             },
@@ -164,6 +322,14 @@ fn generate_serialize_impl(enum_ident: &Ident, variants: &[TextEnumVariant]) ->
                     })
                 }
             },
+            VariantKind::I64 | VariantKind::F64 | VariantKind::Bool => {
+                quote! {
+                    Self::#variant_ident(value) => #ser_repr_ident::Content(#ser_struct_ident {
+                        typ: #type_name.into(),
+                        content: serde_json::json!(value),
+                    })
+                }
+            },
             VariantKind::Unsupported => quote! { Self::#variant_ident => unreachable!() },
         }
     });
@@ -197,40 +363,71 @@ fn generate_serialize_impl(enum_ident: &Ident, variants: &[TextEnumVariant]) ->
     }
 }
 
-fn generate_deserialize_impl(enum_ident: &Ident, variants: &[TextEnumVariant]) -> TokenStream {
+/// Assigns every accepted type name to exactly one variant. Variant idents are
+/// claimed first, then prefixes language by language in `lang_priority` order,
+/// so a prefix shared across languages resolves to the higher-priority one.
+fn resolve_type_names(parsed_enum: &TextEnumCodec) -> Vec<Vec<String>> {
+    let variants = &parsed_enum.variants;
+    let mut claimed = std::collections::HashSet::new();
+    let mut names = vec![Vec::new(); variants.len()];
+
+    for (i, v) in variants.iter().enumerate() {
+        if claimed.insert(v.ident.to_string()) {
+            names[i].push(v.ident.to_string());
+        }
+    }
+    for lang in &parsed_enum.lang_priority {
+        for (i, v) in variants.iter().enumerate() {
+            if let Some(prefix) = v.prefixes.get(lang)
+                && claimed.insert(prefix.clone()) {
+                names[i].push(prefix.clone());
+            }
+        }
+    }
+    names
+}
+
+fn generate_deserialize_impl(parsed_enum: &TextEnumCodec) -> TokenStream {
+    let enum_ident = &parsed_enum.ident;
+    let variants = &parsed_enum.variants;
     let de_repr_ident = format_ident!("{}DeRepr", enum_ident);
     let de_struct_ident = format_ident!("{}DeStruct", enum_ident);
+    let resolved_names = resolve_type_names(parsed_enum);
 
-    let unit_arms = variants.iter().filter(|v| v.kind == VariantKind::Unit).map(|v| {
+    let unit_arms = variants.iter().zip(&resolved_names).filter(|(v, _)| v.kind == VariantKind::Unit).map(|(v, type_names)| {
         let variant_ident = &v.ident;
-        let mut type_names = vec![v.ident.to_string()];
-        type_names.extend(v.prefixes.values().cloned());
         quote! {
             #( #type_names => Ok(Self::#variant_ident), )*
         }
     });
 
-    let content_arms = variants.iter().filter(|v| v.kind != VariantKind::Unit && (!v.is_catch_all || v.catch_all_include_prefix)).map(|v| {
+    let content_arms = variants.iter().zip(&resolved_names).filter(|(v, _)| v.kind != VariantKind::Unit && (!v.is_catch_all || v.catch_all_include_prefix)).map(|(v, type_names)| {
         let variant_ident = &v.ident;
-        let mut type_names = vec![v.ident.to_string()];
-        type_names.extend(v.prefixes.values().cloned());
 
         let content_parsing = match v.kind {
             VariantKind::String => quote! { content.as_str().map(|s| Self::#variant_ident(s.to_string())) },
-            VariantKind::VecString => quote! {
-                content.as_str().map(|s| {
-                    let items = if s.trim().is_empty() { vec![] } else { s.split(',').map(|i| i.trim().to_string()).collect() };
-                    Self::#variant_ident(items)
-                })
+            VariantKind::VecString => {
+                let list_format = list_format(v);
+                quote! { content.as_str().map(|s| Self::#variant_ident(#list_format.decode(s))) }
             },
             VariantKind::Uuid => quote! {
                 content.as_str().and_then(|s| s.parse().ok()).map(Self::#variant_ident)
             },
+            // Scalars also accept their text form, as models tend to quote them
+            VariantKind::I64 => quote! {
+                content.as_i64().or_else(|| content.as_str().and_then(|s| s.trim().parse().ok())).map(Self::#variant_ident)
+            },
+            VariantKind::F64 => quote! {
+                content.as_f64().or_else(|| content.as_str().and_then(|s| s.trim().parse().ok())).map(Self::#variant_ident)
+            },
+            VariantKind::Bool => quote! {
+                content.as_bool().or_else(|| content.as_str().and_then(|s| s.trim().parse().ok())).map(Self::#variant_ident)
+            },
             _ => quote! { None },
         };
 
         quote! {
-            #( #type_names => #content_parsing.ok_or_else(|| anyhow::anyhow!("Failed to parse content for type '{}'", typ)), )*
+            #( #type_names => #content_parsing.ok_or_else(|| anyhow::anyhow!("Failed to parse content for type '{}': {}", typ, content)), )*
         }
     });
 
@@ -247,6 +444,7 @@ fn generate_deserialize_impl(enum_ident: &Ident, variants: &[TextEnumVariant]) -
 
     let content_catch_all_arm = if let Some(catch_all) = variants.iter().find(|v| v.is_catch_all) {
         let variant_ident = &catch_all.ident;
+        // The content is what users see; an unknown type tag is not shown
         quote! {
             _ => Ok(Self::#variant_ident(content.as_str().unwrap_or("").to_string())),
         }
@@ -301,24 +499,27 @@ fn generate_deserialize_impl(enum_ident: &Ident, variants: &[TextEnumVariant]) -
 fn generate_schema_impl(parsed_enum: &TextEnumCodec) -> TokenStream {
     // Determine schema structure at compile time
     let has_structured_variants = parsed_enum.variants.iter()
-        .any(|v| matches!(v.kind, VariantKind::String | VariantKind::VecString | VariantKind::Uuid) && !v.is_catch_all);
+        .any(|v| !matches!(v.kind, VariantKind::Unit | VariantKind::Unsupported) && !v.is_catch_all);
     let has_unit_variants = parsed_enum.variants.iter()
         .any(|v| v.kind == VariantKind::Unit);
 
-    // Pre-compute unique, sorted variant names for all available languages at compile time
-    let mut lang_to_variants = std::collections::BTreeMap::<String, Vec<String>>::new();
-    for v in &parsed_enum.variants {        
+    // Pre-compute unique, sorted variant names for all available languages at compile time,
+    // each with the schema type of its content
+    let mut lang_to_variants = std::collections::BTreeMap::<String, Vec<(String, &'static str)>>::new();
+    for v in &parsed_enum.variants {
+        let content_type = v.kind.content_schema_type();
+
         // Add variant's default name to the default schema language
         let default_lang_variants = lang_to_variants.entry(parsed_enum.schema_lang.clone()).or_default();
-        if !default_lang_variants.contains(&v.ident.to_string()) {
-            default_lang_variants.push(v.ident.to_string());
+        if !default_lang_variants.iter().any(|(name, _)| v.ident == name) {
+            default_lang_variants.push((v.ident.to_string(), content_type));
         }
 
         // Add prefixed names for each language
         for (lang, prefix) in &v.prefixes {
             let lang_variants = lang_to_variants.entry(lang.clone()).or_default();
-            if !lang_variants.contains(prefix) {
-                lang_variants.push(prefix.clone());
+            if !lang_variants.iter().any(|(name, _)| name == prefix) {
+                lang_variants.push((prefix.clone(), content_type));
             }
         }
     }
@@ -344,27 +545,46 @@ fn generate_schema_impl(parsed_enum: &TextEnumCodec) -> TokenStream {
     }
 }
 
-fn generate_enum_schema(has_structured_variants: bool, has_unit_variants: bool, variants: &[String]) -> TokenStream {
+fn generate_enum_schema(has_structured_variants: bool, has_unit_variants: bool, variants: &[(String, &'static str)]) -> TokenStream {
+    let names: Vec<&String> = variants.iter().map(|(name, _)| name).collect();
     if has_structured_variants && has_unit_variants {
         quote! {
-            serde_json::json!({"type": "string", "enum": [#(#variants),*]})
+            serde_json::json!({"type": "string", "enum": [#(#names),*]})
         }
     } else if has_structured_variants {
-        quote! {
-            serde_json::json!({
-                "type": "object",
-                "properties": {
-                    "content": {"type": "string"},
-                    "type": {"type": "string", "enum": [#(#variants),*]}
-                },
-                "required": ["type", "content"]
-            })
+        // One object schema per content type, so scalar payloads keep their JSON type
+        let mut content_types: Vec<&'static str> = Vec::new();
+        for (_, content_type) in variants {
+            if !content_types.contains(content_type) {
+                content_types.push(content_type);
+            }
+        }
+        let schemas = content_types.iter().map(|content_type| {
+            let names = variants.iter().filter(|(_, t)| t == content_type).map(|(name, _)| name);
+            quote! {
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "content": {"type": #content_type},
+                        "type": {"type": "string", "enum": [#(#names),*]}
+                    },
+                    "required": ["type", "content"]
+                })
+            }
+        }).collect::<Vec<_>>();
+
+        if schemas.len() == 1 {
+            schemas.into_iter().next().unwrap()
+        } else {
+            quote! {
+                serde_json::json!({ "oneOf": [#(#schemas),*] })
+            }
         }
     } else {
         quote! {
             serde_json::json!({
                 "type": "string",
-                "enum": [#(#variants),*]
+                "enum": [#(#names),*]
             })
         }
     }
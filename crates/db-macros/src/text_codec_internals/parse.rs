@@ -7,9 +7,34 @@ pub struct TextEnumCodec {
     pub ident: Ident,
     pub type_lang: String,
     pub schema_lang: String,
+    /// Order in which prefix languages claim a type name when deserializing.
+    /// Always covers every language used by a `prefix` attribute.
+    pub lang_priority: Vec<String>,
+    /// Default prompt text format for content variants.
+    pub format: TextFormat,
+    /// Marked `#[pg_enum]`: stored as a native Postgres enum instead of JSONB.
+    pub pg_enum: bool,
     pub variants: Vec<TextEnumVariant>,
 }
 
+/// How content variants are written in prompt text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextFormat {
+    #[default]
+    Colon,          // Like: Action: wave
+    Paren,          // Like: Action(wave)
+}
+
+impl TextFormat {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "colon" => Some(Self::Colon),
+            "paren" => Some(Self::Paren),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct TextEnumVariant {
     pub ident: Ident,
@@ -17,6 +42,19 @@ pub struct TextEnumVariant {
     pub prefixes: HashMap<String, String>,
     pub is_catch_all: bool,
     pub catch_all_include_prefix: bool,
+    /// Per-variant override of the enum-level `format`.
+    pub format: Option<TextFormat>,
+    /// `#[collection(...)]` delimiters of a `Vec<String>` variant.
+    pub collection: Option<CollectionFormat>,
+}
+
+/// Delimiters of a `Vec<String>` variant, as `#[collection(open = "[", sep = "; ", close = "]")]`.
+/// Omitted keys keep `TextListFormat::DEFAULT`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollectionFormat {
+    pub open: String,
+    pub sep: String,
+    pub close: String,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -25,12 +63,28 @@ pub enum VariantKind {
     String,         // Like: Scenario(String)
     VecString,      // Like: Items(Vec<String>)
     Uuid,          // Like: Entity(Uuid)
+    I64,           // Like: Count(i64)
+    F64,           // Like: Score(f64)
+    Bool,          // Like: Flag(bool)
     Unsupported,   // Other types
 }
 
+impl VariantKind {
+    /// JSON schema type of the payload as it appears in `content`.
+    pub fn content_schema_type(&self) -> &'static str {
+        match self {
+            Self::I64 | Self::F64 => "number",
+            Self::Bool => "boolean",
+            _ => "string",
+        }
+    }
+}
+
 pub fn parse_text_enum(input: &DeriveInput) -> Result<TextEnumCodec, syn::Error> {
     let mut type_lang = "en".to_string();
     let mut schema_lang = "en".to_string();
+    let mut lang_priority = Vec::new();
+    let mut format = TextFormat::default();
 
     for attr in &input.attrs {
         if attr.path.is_ident("text_enum") {
@@ -45,6 +99,19 @@ pub fn parse_text_enum(input: &DeriveInput) -> Result<TextEnumCodec, syn::Error>
                             if let Lit::Str(s) = &nv.lit {
                                 schema_lang = s.value();
                             }
+                        } else if nv.path.is_ident("format") {
+                            if let Lit::Str(s) = &nv.lit {
+                                format = TextFormat::from_name(&s.value()).ok_or_else(|| syn::Error::new_spanned(
+                                    &nv.lit, "`format` must be \"colon\" or \"paren\"",
+                                ))?;
+                            }
+                        } else if nv.path.is_ident("lang_priority") {
+                            if let Lit::Str(s) = &nv.lit {
+                                lang_priority = s.value().split(',')
+                                    .map(|lang| lang.trim().to_string())
+                                    .filter(|lang| !lang.is_empty())
+                                    .collect();
+                            }
                         }
                     }
                 }
@@ -58,18 +125,63 @@ pub fn parse_text_enum(input: &DeriveInput) -> Result<TextEnumCodec, syn::Error>
         return Err(syn::Error::new_spanned(&input.ident, "TextEnum can only be derived for enums"));
     };
 
+    let pg_enum = input.attrs.iter().any(|attr| attr.path.is_ident("pg_enum"));
+    if pg_enum
+        && let Some(variant) = variants.iter().find(|v| v.kind != VariantKind::Unit)
+    {
+        return Err(syn::Error::new_spanned(&variant.ident, "`#[pg_enum]` only supports unit variants"));
+    }
+
+    // Without an explicit order the prompt language wins, then the schema
+    // language; any remaining languages follow alphabetically.
+    if lang_priority.is_empty() {
+        lang_priority = vec![type_lang.clone(), schema_lang.clone()];
+    }
+    let mut remaining_langs = variants.iter()
+        .flat_map(|v| v.prefixes.keys().cloned())
+        .collect::<Vec<_>>();
+    remaining_langs.sort();
+    for lang in remaining_langs {
+        if !lang_priority.contains(&lang) {
+            lang_priority.push(lang);
+        }
+    }
+    lang_priority.dedup();
+
     Ok(TextEnumCodec {
         ident: input.ident.clone(),
         type_lang,
         schema_lang,
+        lang_priority,
+        format,
+        pg_enum,
         variants,
     })
 }
 
+/// Name of the Postgres enum type backing a `#[pg_enum]` enum: `UserRole` becomes `user_role`.
+pub fn pg_enum_type_name(ident: &Ident) -> String {
+    let chars: Vec<char> = ident.to_string().chars().collect();
+    let mut name = String::with_capacity(chars.len() + 4);
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_is_lower) {
+                name.push('_');
+            }
+        }
+        name.extend(c.to_lowercase());
+    }
+    name
+}
+
 fn parse_variant(variant: &Variant) -> Result<TextEnumVariant, syn::Error> {
     let mut prefixes = HashMap::new();
     let mut is_catch_all = false;
     let mut catch_all_include_prefix = false;
+    let mut format = None;
+    let mut collection = None;
 
     for attr in &variant.attrs {
         if attr.path.is_ident("prefix") {
@@ -77,6 +189,10 @@ fn parse_variant(variant: &Variant) -> Result<TextEnumVariant, syn::Error> {
                 let (lang, content) = parse_prefix_attribute(&list)?;
                 prefixes.insert(lang, content);
             }
+        } else if attr.path.is_ident("format") {
+            format = Some(parse_format_attribute(attr)?);
+        } else if attr.path.is_ident("collection") {
+            collection = Some(parse_collection_attribute(attr)?);
         } else if attr.path.is_ident("catch_all") {
             is_catch_all = true;
             if let Ok(Meta::List(list)) = attr.parse_meta() {
@@ -104,6 +220,12 @@ fn parse_variant(variant: &Variant) -> Result<TextEnumVariant, syn::Error> {
                 VariantKind::VecString
             } else if ty_str == "Uuid" || ty_str == "sqlx::types::Uuid" {
                 VariantKind::Uuid
+            } else if ty_str == "i64" {
+                VariantKind::I64
+            } else if ty_str == "f64" {
+                VariantKind::F64
+            } else if ty_str == "bool" {
+                VariantKind::Bool
             } else {
                 VariantKind::Unsupported
             }
@@ -111,15 +233,65 @@ fn parse_variant(variant: &Variant) -> Result<TextEnumVariant, syn::Error> {
         _ => VariantKind::Unsupported,
     };
 
+    if collection.is_some() && kind != VariantKind::VecString {
+        return Err(syn::Error::new_spanned(&variant.ident, "`collection` only applies to `Vec<String>` variants"));
+    }
+
     Ok(TextEnumVariant {
         ident: variant.ident.clone(),
         kind,
         prefixes,
         is_catch_all,
         catch_all_include_prefix,
+        format,
+        collection,
     })
 }
 
+fn parse_collection_attribute(attr: &syn::Attribute) -> Result<CollectionFormat, syn::Error> {
+    let invalid = || syn::Error::new_spanned(attr, "Expected `#[collection(open = \"..\", sep = \"..\", close = \"..\")]`");
+    let Meta::List(list) = attr.parse_meta()? else {
+        return Err(invalid());
+    };
+
+    let mut collection = CollectionFormat { open: String::new(), sep: ",".to_string(), close: String::new() };
+    for nested in list.nested.iter() {
+        let NestedMeta::Meta(Meta::NameValue(nv)) = nested else {
+            return Err(invalid());
+        };
+        let Lit::Str(value) = &nv.lit else {
+            return Err(invalid());
+        };
+        if nv.path.is_ident("open") {
+            collection.open = value.value();
+        } else if nv.path.is_ident("sep") {
+            collection.sep = value.value();
+        } else if nv.path.is_ident("close") {
+            collection.close = value.value();
+        } else {
+            return Err(invalid());
+        }
+    }
+
+    if collection.sep.is_empty() {
+        return Err(syn::Error::new_spanned(attr, "`sep` in `collection` cannot be empty"));
+    }
+    Ok(collection)
+}
+
+fn parse_format_attribute(attr: &syn::Attribute) -> Result<TextFormat, syn::Error> {
+    let invalid = || syn::Error::new_spanned(attr, "Expected `#[format(colon)]` or `#[format(paren)]`");
+    match attr.parse_meta()? {
+        Meta::List(list) if list.nested.len() == 1 => match &list.nested[0] {
+            NestedMeta::Meta(Meta::Path(path)) => path.get_ident()
+                .and_then(|ident| TextFormat::from_name(&ident.to_string()))
+                .ok_or_else(invalid),
+            _ => Err(invalid()),
+        },
+        _ => Err(invalid()),
+    }
+}
+
 fn parse_prefix_attribute(list: &syn::MetaList) -> Result<(String, String), syn::Error> {
     let mut lang = None;
     let mut content = None;
@@ -23,6 +23,75 @@ pub enum SimpleEnum {
     OptionB,                     // Should output: "OptionB"
 }
 
+// "Act" is the English prefix of Act and the Japanese prefix of Scene
+#[derive(Debug, Clone, PartialEq, TextEnum)]
+#[text_enum(type_lang = "en", schema_lang = "en", lang_priority = "en,ja")]
+pub enum EnFirstEnum {
+    #[prefix(lang = "en", content = "Act")]
+    Action(String),
+    #[prefix(lang = "ja", content = "Act")]
+    Scene(String),
+}
+
+#[derive(Debug, Clone, PartialEq, TextEnum)]
+#[text_enum(type_lang = "en", schema_lang = "en", lang_priority = "ja,en")]
+pub enum JaFirstEnum {
+    #[prefix(lang = "en", content = "Act")]
+    Action(String),
+    #[prefix(lang = "ja", content = "Act")]
+    Scene(String),
+}
+
+#[derive(Debug, Clone, PartialEq, TextEnum)]
+#[text_enum(type_lang = "en", schema_lang = "en")]
+pub enum ListEnum {
+    Items(Vec<String>),
+    Empty,
+}
+
+#[derive(Debug, Clone, PartialEq, TextEnum)]
+#[text_enum(type_lang = "en", schema_lang = "en", format = "paren")]
+pub enum MixedFormatEnum {
+    Action(String),
+    Tags(Vec<String>),
+    #[format(colon)]
+    Legacy(String),
+    Idle,
+}
+
+#[derive(Debug, Clone, PartialEq, TextEnum)]
+#[text_enum(type_lang = "en", schema_lang = "en", format = "paren")]
+pub enum ParenEnum {
+    Action(String),
+    Tags(Vec<String>),
+    Idle,
+    #[catch_all(include_prefix = false)]
+    Other(String),
+}
+
+#[derive(Debug, Clone, PartialEq, TextEnum)]
+#[text_enum(type_lang = "en", schema_lang = "en")]
+pub enum ScalarEnum {
+    Score(f64),
+    Count(i64),
+    #[format(paren)]
+    Flag(bool),
+    Note(String),
+}
+
+#[derive(Debug, Clone, PartialEq, TextEnum)]
+#[text_enum(type_lang = "en", schema_lang = "en")]
+pub enum CollectionEnum {
+    #[collection(sep = "; ")]
+    Steps(Vec<String>),
+    #[collection(open = "[", sep = ", ", close = "]")]
+    Tags(Vec<String>),
+    #[collection(sep = "\n")]
+    #[format(paren)]
+    Lines(Vec<String>),
+    Plain(Vec<String>),
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -204,4 +273,216 @@ mod tests {
         let parsed: TestMessageType = display_str.parse().unwrap();
         assert_eq!(original_text, parsed);
     }
+
+    #[test]
+    fn test_lang_priority_resolves_shared_prefix() {
+        let json = serde_json::json!({"type": "Act", "content": "wave"});
+
+        let en_first: EnFirstEnum = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(en_first, EnFirstEnum::Action("wave".to_string()));
+
+        let ja_first: JaFirstEnum = serde_json::from_value(json).unwrap();
+        assert_eq!(ja_first, JaFirstEnum::Scene("wave".to_string()));
+
+        // Variant idents always resolve to their own variant
+        let scene: EnFirstEnum = serde_json::from_value(serde_json::json!({"type": "Scene", "content": "x"})).unwrap();
+        assert_eq!(scene, EnFirstEnum::Scene("x".to_string()));
+    }
+
+    #[test]
+    fn test_vec_string_separators_round_trip() {
+        let original = ListEnum::Items(vec![
+            "a,b".to_string(),
+            "[bracketed]".to_string(),
+            "back\\slash\\".to_string(),
+            "plain".to_string(),
+        ]);
+
+        let encoded = original.to_string();
+        let parsed: ListEnum = encoded.parse().unwrap();
+        assert_eq!(parsed, original);
+
+        // Unescaped lists written by hand still parse
+        let parsed: ListEnum = r#"{"type": "Items", "content": "x, y"}"#.parse().unwrap();
+        assert_eq!(parsed, ListEnum::Items(vec!["x".to_string(), "y".to_string()]));
+    }
+
+    #[test]
+    fn test_mixed_format_prompt_text() {
+        let cases = [
+            (MixedFormatEnum::Action("wave".to_string()), "Action(wave)"),
+            (MixedFormatEnum::Tags(vec!["a".to_string(), "b".to_string()]), "Tags(a,b)"),
+            (MixedFormatEnum::Legacy("old style".to_string()), "Legacy: old style"),
+            (MixedFormatEnum::Idle, "Idle"),
+        ];
+        for (variant, text) in cases {
+            assert_eq!(variant.to_prompt_text("en"), text);
+            assert_eq!(MixedFormatEnum::from_prompt_text(text).unwrap(), variant);
+        }
+
+        // Each variant only parses in its own format
+        assert!(MixedFormatEnum::from_prompt_text("Action: wave").is_err());
+        assert!(MixedFormatEnum::from_prompt_text("Legacy(old style)").is_err());
+
+        // Catch-all enums keep unmatched text
+        let parsed = TestMessageType::from_prompt_text("动作: 挥手").unwrap();
+        assert_eq!(parsed, TestMessageType::Action("挥手".to_string()));
+        let parsed = TestMessageType::from_prompt_text("just talking").unwrap();
+        assert_eq!(parsed, TestMessageType::Text("just talking".to_string()));
+    }
+
+    #[test]
+    fn test_catch_all_preserves_unknown_type() {
+        for input in ["Whisper: hello", "  耳语：  你好 "] {
+            let parsed: TestMessageType = input.parse().unwrap();
+            assert_eq!(parsed.to_string(), input);
+
+            let parsed = TestMessageType::from_prompt_text(input).unwrap();
+            assert_eq!(parsed.to_prompt_text("zh"), input);
+        }
+
+    }
+
+    #[test]
+    fn test_round_trip_unknown_structured_type() {
+        // Structured input with an unknown type keeps only its content
+        let parsed: TestMessageType = serde_json::from_value(serde_json::json!({"type": "Whisper", "content": "hello"})).unwrap();
+        assert_eq!(parsed, TestMessageType::Text("hello".to_string()));
+
+        let value = serde_json::to_value(&parsed).unwrap();
+        assert_eq!(value, serde_json::json!("hello"));
+        let reparsed: TestMessageType = serde_json::from_value(value).unwrap();
+        assert_eq!(reparsed, parsed);
+    }
+
+    /// Deterministic pseudo-random strings over an alphabet heavy in delimiters.
+    fn arbitrary_strings(count: usize) -> Vec<String> {
+        const ALPHABET: &[char] = &['(', ')', ',', '[', ']', '\\', ' ', 'a', 'b', 'I', 'd', 'l', 'e', ':', '动'];
+        let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+        let mut next = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 33) as usize
+        };
+        (0..count)
+            .map(|_| {
+                let len = next() % 12;
+                (0..len).map(|_| ALPHABET[next() % ALPHABET.len()]).collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_paren_content_delimiters_round_trip() {
+        let action = ParenEnum::Action("a (b), [c]\\".to_string());
+        assert_eq!(action.to_prompt_text("en"), "Action(a \\(b\\)\\, \\[c\\]\\\\)");
+
+        let strings = arbitrary_strings(2000);
+        for s in &strings {
+            let action = ParenEnum::Action(s.clone());
+            assert_eq!(ParenEnum::from_prompt_text(&action.to_prompt_text("en")).unwrap(), action, "{:?}", s);
+
+            // A catch-all that reads like another variant must stay a catch-all
+            if s.trim() != "Idle" {
+                let other = ParenEnum::Other(s.clone());
+                assert_eq!(ParenEnum::from_prompt_text(&other.to_prompt_text("en")).unwrap(), other, "{:?}", s);
+            }
+        }
+
+        // List items are trimmed when parsed, and an empty item is an empty list
+        let items: Vec<String> = strings.iter()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        for chunk in items.chunks(3) {
+            let tags = ParenEnum::Tags(chunk.to_vec());
+            assert_eq!(ParenEnum::from_prompt_text(&tags.to_prompt_text("en")).unwrap(), tags, "{:?}", chunk);
+        }
+
+        // Unescaped hand-written text still parses, backslashes before other characters are kept
+        assert_eq!(ParenEnum::from_prompt_text("Action(wave)").unwrap(), ParenEnum::Action("wave".to_string()));
+        assert_eq!(ParenEnum::from_prompt_text("C:\\temp").unwrap(), ParenEnum::Other("C:\\temp".to_string()));
+        assert_eq!(ParenEnum::Other("Action(x)".to_string()).to_prompt_text("en"), "Action\\(x\\)");
+    }
+
+    #[test]
+    fn test_scalar_variants_round_trip() {
+        let values = [
+            ScalarEnum::Score(0.87),
+            ScalarEnum::Score(-1e-9),
+            ScalarEnum::Count(-42),
+            ScalarEnum::Count(i64::MAX),
+            ScalarEnum::Flag(true),
+            ScalarEnum::Note("kept as text".to_string()),
+        ];
+        for value in &values {
+            assert_eq!(ScalarEnum::from_prompt_text(&value.to_prompt_text("en")).unwrap(), *value);
+            assert_eq!(serde_json::from_value::<ScalarEnum>(serde_json::to_value(value).unwrap()).unwrap(), *value);
+            assert_eq!(value.to_string().parse::<ScalarEnum>().unwrap(), *value);
+        }
+
+        // Scalars travel as native JSON values, but their text form is accepted too
+        assert_eq!(ScalarEnum::Score(0.87).to_prompt_text("en"), "Score: 0.87");
+        assert_eq!(ScalarEnum::Flag(false).to_prompt_text("en"), "Flag(false)");
+        assert_eq!(serde_json::to_value(ScalarEnum::Count(3)).unwrap(), serde_json::json!({"type": "Count", "content": 3}));
+        assert_eq!(
+            serde_json::from_value::<ScalarEnum>(serde_json::json!({"type": "Score", "content": "0.5"})).unwrap(),
+            ScalarEnum::Score(0.5)
+        );
+
+        // Malformed content is an error rather than a silent mismatch
+        let err = ScalarEnum::from_prompt_text("Count: lots").unwrap_err();
+        assert!(err.to_string().contains("Count"), "{}", err);
+        assert!(ScalarEnum::from_prompt_text("Flag(maybe)").is_err());
+        assert!(serde_json::from_value::<ScalarEnum>(serde_json::json!({"type": "Count", "content": 1.5})).is_err());
+
+        let schema = ScalarEnum::schema(None);
+        let variants = schema["oneOf"].as_array().unwrap();
+        let content_type = |name: &str| variants.iter()
+            .find(|v| v["properties"]["type"]["enum"].as_array().unwrap().iter().any(|n| n == name))
+            .map(|v| v["properties"]["content"]["type"].clone())
+            .unwrap();
+        assert_eq!(content_type("Score"), "number");
+        assert_eq!(content_type("Count"), "number");
+        assert_eq!(content_type("Flag"), "boolean");
+        assert_eq!(content_type("Note"), "string");
+    }
+
+    #[test]
+    fn test_collection_separators_round_trip() {
+        let strings = |items: &[&str]| items.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        let cases = [
+            (CollectionEnum::Steps(strings(&["boil water", "add tea"])), "Steps: boil water; add tea"),
+            (CollectionEnum::Tags(strings(&["a", "b"])), "Tags: [a, b]"),
+            (CollectionEnum::Lines(strings(&["first", "second"])), "Lines(first\nsecond)"),
+            (CollectionEnum::Plain(strings(&["x", "y"])), "Plain: x,y"),
+            // Empty and single-element collections
+            (CollectionEnum::Steps(vec![]), "Steps: "),
+            (CollectionEnum::Tags(vec![]), "Tags: []"),
+            (CollectionEnum::Lines(vec![]), "Lines()"),
+            (CollectionEnum::Steps(strings(&["only"])), "Steps: only"),
+            (CollectionEnum::Tags(strings(&["only"])), "Tags: [only]"),
+            (CollectionEnum::Lines(strings(&["only"])), "Lines(only)"),
+        ];
+        for (value, text) in &cases {
+            assert_eq!(value.to_prompt_text("en"), *text);
+            assert_eq!(CollectionEnum::from_prompt_text(text).unwrap(), *value, "{:?}", text);
+            assert_eq!(value.to_string().parse::<CollectionEnum>().unwrap(), *value);
+        }
+
+        // Separator characters inside items are escaped, other ones are left alone
+        let tricky = CollectionEnum::Steps(strings(&["a; b", "c,d", "[e]", "f\\", ";"]));
+        assert_eq!(tricky.to_prompt_text("en"), "Steps: a\\; b; c,d; \\[e\\]; f\\\\; \\;");
+        assert_eq!(CollectionEnum::from_prompt_text(&tricky.to_prompt_text("en")).unwrap(), tricky);
+        let tricky = CollectionEnum::Tags(strings(&["]", "x, y", "z]"]));
+        assert_eq!(CollectionEnum::from_prompt_text(&tricky.to_prompt_text("en")).unwrap(), tricky);
+        let tricky = CollectionEnum::Lines(strings(&["(a)", "b,c"]));
+        assert_eq!(CollectionEnum::from_prompt_text(&tricky.to_prompt_text("en")).unwrap(), tricky);
+
+        // The JSON content uses the same delimiters
+        assert_eq!(
+            serde_json::to_value(CollectionEnum::Tags(strings(&["a", "b"]))).unwrap(),
+            serde_json::json!({"type": "Tags", "content": "[a, b]"})
+        );
+    }
 }
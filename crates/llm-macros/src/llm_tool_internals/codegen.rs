@@ -249,6 +249,7 @@ fn get_schema_for_type(
             } else if type_name == "i64"
                 || type_name == "u64"
                 || type_name == "f64"
+                || type_name == "f32"
                 || type_name == "isize"
                 || type_name == "usize"
             {
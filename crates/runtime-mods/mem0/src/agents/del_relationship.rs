@@ -80,20 +80,16 @@ impl Agent for DeleteRelationshipsAgent {
         ])
     }
 
-    async fn handle_output(&self, input: &Self::Input, _message: &Message, tool: &Self::Tool) -> Result<Option<Value>> {
+    async fn handle_output(&self, input: &Self::Input, message: &Message, tool: &Self::Tool) -> Result<(Message, Option<Value>)> {
         let relationships = tool.relationships.clone();
         if relationships.is_empty() {
-            return Ok(Some(serde_json::to_value(0)?));
+            return Ok((message.clone(), Some(serde_json::to_value(0)?)));
         }
 
-        let graph_entities = GraphEntities {
-            relationships,
-            entity_tags: input.type_mapping.iter().map(|e| (e.entity_name.clone(), e.entity_tag.clone())).collect(),
-            filter: input.filter.clone(),
-        };
+        let graph_entities = GraphEntities::new(relationships, input.type_mapping.clone(), input.filter.clone())?;
 
         let delete_size = self.mem0_engine.graph_db.delete(&graph_entities).await?;
-        Ok(Some(serde_json::to_value(delete_size)?))
+        Ok((message.clone(), Some(serde_json::to_value(delete_size)?)))
     }
 
     fn system_prompt() ->  &'static str {
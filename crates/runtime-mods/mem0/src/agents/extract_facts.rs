@@ -69,8 +69,8 @@ impl Agent for ExtractFactsAgent {
         ])
     }
 
-    async fn handle_output(&self, _input: &Self::Input, _message: &Message, _tool: &Self::Tool) -> Result<Option<Value>> {
-        Ok(None)
+    async fn handle_output(&self, _input: &Self::Input, message: &Message, _tool: &Self::Tool) -> Result<(Message, Option<Value>)> {
+        Ok((message.clone(), None))
     }
 
     fn system_prompt() ->  &'static str {
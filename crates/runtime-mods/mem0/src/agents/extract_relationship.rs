@@ -17,12 +17,38 @@ pub struct ExtractRelationshipsOutput {
     pub relationships: Vec<Relationship>,
 }
 
+/// What relationships are extracted from: the raw message, or the facts
+/// `ExtractFactsAgent` already pulled out of it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RelationshipSource {
+    Message(String),
+    Facts(Vec<String>),
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExtractRelationshipsInput {
     pub filter: Mem0Filter,
     pub entities: Vec<EntityTag>,
     pub user_aka: String,
-    pub new_message: String,
+    pub source: RelationshipSource,
+    pub max_relationships: usize,
+}
+
+impl ExtractRelationshipsInput {
+    /// The user turn of the prompt.
+    pub fn user_message(&self) -> String {
+        let entities = self.entities.iter().map(|e| e.entity_name.clone()).collect::<Vec<_>>().join(", ");
+        match &self.source {
+            RelationshipSource::Message(message) => format!(
+                "List of entities: {}\nNew information: {}", entities, message
+            ),
+            RelationshipSource::Facts(facts) => format!(
+                "List of entities: {}\nExtracted facts:\n{}",
+                entities,
+                facts.iter().map(|fact| format!("- {}", fact)).collect::<Vec<_>>().join("\n")
+            ),
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -58,38 +84,29 @@ impl Agent for ExtractRelationshipsAgent {
         let system_prompt = Self::system_prompt()
             .replace("{{user}}", &input.user_aka);
 
-        let user_message = format!(
-            "List of entities: {}\nNew information: {}", 
-            input.entities.iter().map(|e| e.entity_name.clone()).collect::<Vec<_>>().join(", "), 
-            input.new_message.clone()
-        );
-
         Ok(vec![
             Prompt::new_system(&system_prompt),
             Prompt {
                 role: MessageRole::User,
                 content_type: MessageType::Text,
-                content: user_message,
+                content: input.user_message(),
                 toolcall: None,
                 created_at: get_current_timestamp(),
             }
         ])
     }
 
-    async fn handle_output(&self, input: &Self::Input, _message: &Message, tool: &Self::Tool) -> Result<Option<Value>> {
+    async fn handle_output(&self, input: &Self::Input, message: &Message, tool: &Self::Tool) -> Result<(Message, Option<Value>)> {
         let relationships = tool.relationships.clone();
         if relationships.is_empty() {
-            return Ok(Some(serde_json::to_value(0)?));
+            return Ok((message.clone(), Some(serde_json::to_value(0)?)));
         }
 
-        let graph_entities = GraphEntities {
-            relationships,
-            entity_tags: input.entities.iter().map(|e| (e.entity_name.clone(), e.entity_tag.clone())).collect(),
-            filter: input.filter.clone(),
-        };
+        let mut graph_entities = GraphEntities::new(relationships, input.entities.clone(), input.filter.clone())?;
+        graph_entities.cap_relationships(input.max_relationships);
 
         let add_size = self.mem0_engine.graph_db.add(&graph_entities, &self.mem0_engine.embeder).await?;
-        Ok(Some(serde_json::to_value(add_size)?))
+        Ok((message.clone(), Some(serde_json::to_value(add_size)?)))
     }
 
     fn system_prompt() ->  &'static str {
@@ -98,6 +115,8 @@ impl Agent for ExtractRelationshipsAgent {
 1. Extract only explicitly stated information from the text.
 2. Establish relationships among the entities provided.
 3. Use "{{user}}" as the source entity for any self-references (e.g., "I," "me," "my," etc.) in user messages.
+4. Give every relationship a confidence between 0 and 1 reflecting how clearly the text states it.
+5. When given extracted facts instead of new information, build relationships from those facts alone.
 
 Relationships:
     - Use consistent, general, and timeless relationship types.
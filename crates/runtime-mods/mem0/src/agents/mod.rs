@@ -11,6 +11,6 @@ pub use update_memory::{UpdateMemoryAgent, UpdateMemoryInput, UpdateMemory};
 #[cfg(feature = "graph")]
 pub use extract_entities::{ExtractEntitiesAgent, ExtractEntitiesInput};
 #[cfg(feature = "graph")]
-pub use extract_relationship::{ExtractRelationshipsAgent, ExtractRelationshipsInput};
+pub use extract_relationship::{ExtractRelationshipsAgent, ExtractRelationshipsInput, RelationshipSource};
 #[cfg(feature = "graph")]
 pub use del_relationship::{DeleteRelationshipsAgent, DeleteRelationshipsInput};
\ No newline at end of file
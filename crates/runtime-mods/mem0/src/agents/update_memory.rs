@@ -9,7 +9,7 @@ use metastable_clients::{LlmClient, PostgresClient};
 use serde_json::{json, Value};
 use sqlx::types::Uuid;
 
-use crate::{init_mem0, EmbeddingMessage, Mem0Engine, Mem0Filter};
+use crate::{init_mem0, merge_contradictions, EmbeddingMessage, Mem0Engine, Mem0Filter};
 use crate::pgvector::{MemoryEvent, MemoryUpdateEntry};
 
 init_mem0!();
@@ -22,6 +22,8 @@ pub struct MemoryEntrySimplified {
     pub content: String,
     #[llm_tool(description = "Operation to be performed.", is_enum = true)]
     pub event: MemoryEvent,
+    #[llm_tool(description = "ID of the existing memory this fact contradicts, if any.")]
+    pub contradicts: Option<Uuid>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, LlmTool)]
@@ -102,18 +104,20 @@ impl Agent for UpdateMemoryAgent {
         ])
     }
 
-    async fn handle_output(&self, input: &Self::Input, _message: &Message, tool: &Self::Tool) -> Result<Option<Value>> {
+    async fn handle_output(&self, input: &Self::Input, message: &Message, tool: &Self::Tool) -> Result<(Message, Option<Value>)> {
         let memory_updates = tool.memory.iter().map(|entry| {
             MemoryUpdateEntry {
                 id: entry.id,
                 filter: input.filter.clone(),
                 event: entry.event.clone(),
                 content: entry.content.clone(),
+                contradicts: entry.contradicts,
             }
         }).collect::<Vec<_>>();
+        let memory_updates = merge_contradictions(memory_updates, self.mem0_engine.content_hash);
 
         let summary = self.mem0_engine.vector_db_batch_update(memory_updates).await?;
-        Ok(Some(serde_json::to_value(summary)?))
+        Ok((message.clone(), Some(serde_json::to_value(summary)?)))
     }
 
     fn system_prompt() ->  &'static str {
@@ -203,7 +207,9 @@ Please note to return the UUIDs in the output from the input UUIDs only and do n
         }}
 
 
-3. **Delete**: If the retrieved facts contain information that contradicts the information present in the memory, then you have to delete it. Or if the direction is to delete the memory, then you have to delete it.
+3. **Contradiction**: If a retrieved fact contradicts a memory (e.g. "Lives in Beijing" and "Lives in Shanghai"), then you have to UPDATE that memory with the retrieved fact, keeping its ID. Do not ADD the new fact next to the old one.
+If you ADD a fact that contradicts a memory instead, set `contradicts` to that memory's ID.
+Only DELETE a memory if the direction is to delete or forget it.
 Please note to return the UUIDs in the output from the input UUIDs only and do not generate any new UUIDs.
 - **Example**:
     - Old Memory:
@@ -228,8 +234,8 @@ Please note to return the UUIDs in the output from the input UUIDs only and do n
                 }},
                 {{
                     "id" : "123e4567-e89b-12d3-a456-426614174005",
-                    "content" : "Loves cheese pizza",
-                    "event" : "DELETE"
+                    "content" : "Dislikes cheese pizza",
+                    "event" : "UPDATE"
                 }}
         ]
         }}
@@ -281,6 +287,7 @@ You must call the `update_memory` tool to perform the memory updates. The argume
             "id" : "<ID of the memory>",                # Use existing ID for updates/deletes, or new ID for additions
             "content" : "<Content of the memory>",         # Content of the memory
             "event" : "<Operation to be performed>",    # Must be "ADD", "UPDATE", "DELETE", or "NONE"
+            "contradicts" : "<ID of the contradicted memory>",  # Optional, only for facts contradicting a memory
         }},
         ...
     ]
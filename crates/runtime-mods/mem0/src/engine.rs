@@ -1,7 +1,9 @@
 use anyhow::Result;
 use metastable_runtime::{Agent, MessageRole, MessageType, Prompt};
+use tokio::sync::oneshot;
 
-use crate::{EmbeddingMessage, Mem0Engine, Mem0Filter};
+use crate::{scrub_facts, scrub_message, EmbeddingMessage, Mem0Engine, Mem0Filter, RecallResult, RecallSource};
+use crate::recall::cosine_similarity;
 use crate::agents::{
     ExtractFactsAgent, ExtractFactsInput, 
     UpdateMemoryAgent, UpdateMemoryInput
@@ -10,13 +12,16 @@ use crate::agents::{
 #[cfg(feature = "graph")]
 use crate::agents::{
     ExtractEntitiesAgent, ExtractEntitiesInput,
-    ExtractRelationshipsAgent, ExtractRelationshipsInput,
+    ExtractRelationshipsAgent, ExtractRelationshipsInput, RelationshipSource,
     DeleteRelationshipsAgent, DeleteRelationshipsInput,
 };
 type AsyncTask = tokio::task::JoinHandle<Result<()>>;
 
 impl Mem0Engine {
-    async fn add_memory(&self, message: String, filter: &Mem0Filter) -> Result<()> {
+    /// Extracts facts from the message and merges them into memory. The scrubbed
+    /// facts are also sent on `facts_tx`, as soon as they are known, even when
+    /// scrubbing left none.
+    async fn add_memory(&self, message: String, filter: &Mem0Filter, facts_tx: Option<oneshot::Sender<Vec<String>>>) -> Result<()> {
         let fact_extract_agent = ExtractFactsAgent::new().await?;
         let memory_update_agent = UpdateMemoryAgent::new().await?;
 
@@ -24,9 +29,16 @@ impl Mem0Engine {
             filter: filter.clone(),
             new_message: message.clone(),
         };
-        let (_, output, _) = fact_extract_agent.call(&filter.user_id, &facts_tool_input).await?;
+        let Some((_, output, _)) = self.extraction_gate.run("fact extraction", &message, || {
+            fact_extract_agent.call(&filter.user_id, &facts_tool_input)
+        }).await? else { return Ok(()) };
+        let facts = scrub_facts(output.facts, self.pii_scrub_mode);
+        if let Some(facts_tx) = facts_tx {
+            let _ = facts_tx.send(facts.clone());
+        }
+        if facts.is_empty() { return Ok(()); }
         let embedding_messages = EmbeddingMessage::batch_create(
-            &self, &output.facts, &filter
+            &self, &facts, &filter
         ).await?;
         let update_memory_input = UpdateMemoryInput {
             filter: filter.clone(),
@@ -38,7 +50,9 @@ impl Mem0Engine {
     }
 
     #[cfg(feature = "graph")]
-    async fn add_graph(&self, message: String, filter: &Mem0Filter) -> Result<()> {
+    async fn add_graph(&self, message: String, filter: &Mem0Filter, facts_rx: oneshot::Receiver<Vec<String>>) -> Result<()> {
+        // Every graph agent sees the message, so it is scrubbed like the facts are
+        let Some(message) = scrub_message(message, self.pii_scrub_mode) else { return Ok(()) };
         let entity_extract_agent = ExtractEntitiesAgent::new().await?;
         let relationship_extract_agent = ExtractRelationshipsAgent::new().await?;
         let delete_relationship_agent = DeleteRelationshipsAgent::new().await?;
@@ -48,18 +62,30 @@ impl Mem0Engine {
             new_message: message.clone(),
             user_aka: filter.user_aka.clone(),
         };
-        let (_, output, _) = entity_extract_agent.call(&filter.user_id, &entity_tool_input).await?;
+        let Some((_, output, _)) = self.extraction_gate.run("entity extraction", &message, || {
+            entity_extract_agent.call(&filter.user_id, &entity_tool_input)
+        }).await? else { return Ok(()) };
 
         // insert operations
         let insert_cloned_filter = filter.clone();
         let insert_cloned_messages = message.clone();
         let insert_output = output.clone();
+        let max_relationships = self.max_relationships;
         let graph_insert_operations: AsyncTask = tokio::spawn(async move {
+            // Reuse the facts `add_memory` extracted; none left after scrubbing
+            // means nothing to relate. Without them (skipped or failed) fall back
+            // to the scrubbed message.
+            let source = match facts_rx.await {
+                Ok(facts) if facts.is_empty() => return Ok(()),
+                Ok(facts) => RelationshipSource::Facts(facts),
+                Err(_) => RelationshipSource::Message(insert_cloned_messages.clone()),
+            };
             let extract_relationship_tool_input = ExtractRelationshipsInput {
                 filter: insert_cloned_filter.clone(),
                 entities: insert_output.entities.clone(),
-                new_message: insert_cloned_messages.clone(),
+                source,
                 user_aka: insert_cloned_filter.user_aka.clone(),
+                max_relationships,
             };
             let (_, _, summary) = relationship_extract_agent.call(&insert_cloned_filter.user_id, &extract_relationship_tool_input).await?;
             tracing::info!("Relationship extraction summary: {:?}", summary);
@@ -99,11 +125,18 @@ impl Mem0Engine {
     pub async fn add(&self, messages: Vec<Prompt>, filter: &Mem0Filter) -> Result<()> {
         let messages = Prompt::pack_flat_messages(messages)?;
 
+        #[cfg(feature = "graph")]
+        let (facts_tx, facts_rx) = oneshot::channel();
+        #[cfg(feature = "graph")]
+        let facts_tx = Some(facts_tx);
+        #[cfg(not(feature = "graph"))]
+        let facts_tx = None;
+
         let cloned_filter = filter.clone();
         let cloned_messages = messages.clone();
         let cloned_self = self.clone();
         let memories_operations: AsyncTask = tokio::spawn(async move {
-            cloned_self.add_memory(cloned_messages, &cloned_filter).await
+            cloned_self.add_memory(cloned_messages, &cloned_filter, facts_tx).await
         });
 
         #[cfg(feature = "graph")]
@@ -112,7 +145,7 @@ impl Mem0Engine {
             let cloned_messages = messages.clone();
             let cloned_self = self.clone();
             let graph_operations: AsyncTask = tokio::spawn(async move {
-                cloned_self.add_graph(cloned_messages, &cloned_filter).await
+                cloned_self.add_graph(cloned_messages, &cloned_filter, facts_rx).await
             });
 
             let (memories_result, graph_result) = futures::future::join(
@@ -139,6 +172,54 @@ impl Mem0Engine {
         Ok(())
     }
 
+    /// Recalls memories for the message, tagging each with whether it came
+    /// from vector similarity or graph traversal.
+    pub async fn recall(&self, message: Prompt, filter: &Mem0Filter) -> Result<Vec<RecallResult>> {
+        let query = EmbeddingMessage::batch_create(self, &[message.content], filter).await?;
+        let vector_db_search_fut = EmbeddingMessage::batch_search(self, filter, &query, 10);
+
+        #[cfg(feature = "graph")]
+        let (vector_db_search, graph_db_search) = {
+            let graph_db_search_fut = self.graph_db.search(
+                query.iter().map(|q| q.embedding.to_vec()).collect::<Vec<_>>(),
+                filter
+            );
+            let (vector_db_search_res, graph_db_search_res) = futures::future::join(
+                vector_db_search_fut,
+                graph_db_search_fut
+            ).await;
+            (vector_db_search_res?, graph_db_search_res?)
+        };
+
+        #[cfg(not(feature = "graph"))]
+        let vector_db_search = vector_db_search_fut.await?;
+
+        let mut results = Vec::new();
+        for (q, hits) in query.iter().zip(vector_db_search) {
+            for hit in hits {
+                results.push(RecallResult {
+                    source: RecallSource::Vector {
+                        score: cosine_similarity(q.embedding.as_slice(), hit.embedding.as_slice()),
+                    },
+                    content: hit.content,
+                });
+            }
+        }
+
+        #[cfg(feature = "graph")]
+        for hit in graph_db_search {
+            results.push(RecallResult {
+                content: format!("{} - {} - {}", hit.source, hit.relationship, hit.destination),
+                source: RecallSource::Graph { path: vec![hit.source, hit.relationship, hit.destination] },
+            });
+        }
+
+        for result in &results {
+            tracing::debug!("[Mem0Engine::recall] {}", result);
+        }
+        Ok(results)
+    }
+
     pub async fn search(&self, message: Prompt, filter: &Mem0Filter) -> Result<Vec<Prompt>> {
         // Create embedding for the query message
         let query = EmbeddingMessage::batch_create(self, &[message.content], filter).await?;
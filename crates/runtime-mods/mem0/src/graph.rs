@@ -1,19 +1,28 @@
-use std::{collections::{HashMap, HashSet}, env};
+use std::{collections::{HashMap, HashSet}, env, future::Future, sync::Arc, time::Duration};
 
-use anyhow::Result;
-use metastable_common::{define_module_client, ModuleClient};
+use anyhow::{anyhow, Result};
+use metastable_common::{uri_from_env, ModuleClient};
 use metastable_runtime::LlmTool;
-use neo4rs::{query, ConfigBuilder, Graph};
+use neo4rs::{query, BoltMap, BoltType, ConfigBuilder, Graph};
 use serde::{Deserialize, Serialize};
 
-use metastable_clients::{Embedding, EmbederClient, EMBEDDING_DIMS, DEFAULT_GRAPH_DB_TEXT_SEARCH_THRESHOLD, DEFAULT_GRAPH_DB_SEARCH_LIMIT, DEFAULT_GRAPH_DB_VECTOR_SEARCH_THRESHOLD};
+use metastable_clients::{Embedding, EmbederClient, EMBEDDING_DIMS, DEFAULT_GRAPH_DB_TEXT_SEARCH_THRESHOLD, DEFAULT_GRAPH_DB_SEARCH_LIMIT, DEFAULT_GRAPH_DB_QUERY_TIMEOUT_MS, DEFAULT_GRAPH_DB_COMMIT_BATCH_SIZE, DEFAULT_GRAPH_DB_VECTOR_SEARCH_THRESHOLD, DEFAULT_GRAPH_DB_RECENCY_HALF_LIFE_MS, DEFAULT_GRAPH_DB_VECTOR_INDEX, DEFAULT_GRAPH_DB_MAX_RELATIONSHIPS, MAX_GRAPH_DB_TRAVERSAL_DEPTH};
 use crate::Mem0Filter;
 
+/// Applied after every relationship MERGE: new edges start at the row's weight
+/// and each re-observation refreshes `updated_at` and adds its weight again.
+const RELATIONSHIP_MERGE_SET: &str = "ON CREATE SET r.created_at = timestamp(), r.updated_at = timestamp(), r.weight = row.weight \
+    ON MATCH SET r.updated_at = timestamp(), r.weight = coalesce(r.weight, 0) + row.weight";
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, LlmTool)]
 pub struct Relationship { // TOOLCALL Return
     pub source: String,
     pub relationship: String,
     pub destination: String,
+    /// How confident the extractor is in this relationship, from 0 to 1.
+    pub confidence: Option<f32>,
+    /// How much this mention reinforces the edge; 1 when unset.
+    pub weight: Option<f32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,28 +38,262 @@ pub struct EntityTag { // TOOLCALL Return
     pub entity_tag: String,
 }
 
+/// Reads `MEM0_GRAPH_MAX_RELATIONSHIPS`, falling back to `DEFAULT_GRAPH_DB_MAX_RELATIONSHIPS`.
+pub fn max_relationships_from_env() -> usize {
+    parse_max_relationships(env::var("MEM0_GRAPH_MAX_RELATIONSHIPS").ok().as_deref())
+}
+
+/// Parses a relationship cap; unset, invalid or zero values use the default.
+pub fn parse_max_relationships(value: Option<&str>) -> usize {
+    value
+        .and_then(|v| v.trim().parse().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(DEFAULT_GRAPH_DB_MAX_RELATIONSHIPS)
+}
+
 impl GraphEntities {
-    pub fn _new(relationships: Vec<Relationship>, entity_tags: Vec<EntityTag>, filter: Mem0Filter) -> Self {
-        let entity_tags = entity_tags
-            .into_iter()
-            .map(|tag| (tag.entity_name, tag.entity_tag))
-            .collect();
+    /// Keeps only the `max` most confident relationships so a long message
+    /// can't flood the graph. Relationships without a confidence rank last.
+    pub fn cap_relationships(&mut self, max: usize) -> usize {
+        if self.relationships.len() <= max {
+            return 0;
+        }
 
-        
-        Self {
+        self.relationships.sort_by(|a, b| {
+            b.confidence.unwrap_or(0.0).total_cmp(&a.confidence.unwrap_or(0.0))
+        });
+        let dropped = self.relationships.len() - max;
+        self.relationships.truncate(max);
+        tracing::info!("[GraphEntities::cap_relationships] Dropped {} low-confidence relationships", dropped);
+        dropped
+    }
+
+    /// Builds the graph payload from extractor output, checked by `validate`.
+    pub fn new(relationships: Vec<Relationship>, entity_tags: Vec<EntityTag>, filter: Mem0Filter) -> Result<Self> {
+        Self::check_tag_conflicts(entity_tags.iter().map(|t| (&t.entity_name, &t.entity_tag)))?;
+
+        let mut entities = Self {
             relationships,
-            entity_tags,
+            entity_tags: entity_tags.into_iter().map(|t| (t.entity_name, t.entity_tag)).collect(),
             filter,
+        };
+        entities.fill_default_tags();
+        Ok(entities)
+    }
+
+    /// Makes sure every entity referenced by a relationship carries exactly one
+    /// tag. Untagged entities default to `Entity` (returned so callers can see
+    /// what was filled in); names that differ only by case or surrounding
+    /// whitespace but carry different tags are an error.
+    pub fn validate(&mut self) -> Result<Vec<String>> {
+        Self::check_tag_conflicts(self.entity_tags.iter())?;
+        Ok(self.fill_default_tags())
+    }
+
+    fn check_tag_conflicts<'a>(tags: impl Iterator<Item = (&'a String, &'a String)>) -> Result<()> {
+        let mut normalized: HashMap<String, (&String, &String)> = HashMap::new();
+        for (name, tag) in tags {
+            let key = name.trim().to_lowercase();
+            if let Some((other_name, other_tag)) = normalized.get(&key) {
+                if *other_tag != tag {
+                    return Err(anyhow!("[GraphEntities::validate] Conflicting tags for entity {}: {} ({}) vs {} ({})",
+                        key, other_tag, other_name, tag, name));
+                }
+            }
+            normalized.insert(key, (name, tag));
+        }
+        Ok(())
+    }
+
+    fn fill_default_tags(&mut self) -> Vec<String> {
+        let mut defaulted = Vec::new();
+        for relationship in &self.relationships {
+            for name in [&relationship.source, &relationship.destination] {
+                if !self.entity_tags.contains_key(name) && !defaulted.contains(name) {
+                    defaulted.push(name.clone());
+                }
+            }
         }
+        for name in &defaulted {
+            tracing::warn!("[GraphEntities::validate] Entity {} has no tag, defaulting to Entity", name);
+            self.entity_tags.insert(name.clone(), "Entity".to_string());
+        }
+        defaulted
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityCandidate {
+    pub id: String,
+    pub name: String,
+    pub similarity: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoredRelationship {
+    pub relationship: Relationship,
+    /// Similarity (-1 to 1) of the matched entity to the query; 1 when the
+    /// relationship was not found through a similarity match.
+    pub similarity: f64,
+    pub weight: f64,
+    pub updated_at: i64,
+}
+
+/// A relationship returned by graph search, with the ranking score it was ordered by.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelationHit {
+    pub source: String,
+    pub relationship: String,
+    pub destination: String,
+    /// Accumulated weight of the edge.
+    pub weight: f64,
+    pub score: f64,
+}
+
+impl RelationHit {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("[RelationHit::to_json] RelationHit is always serializable")
     }
 }
 
-define_module_client! {
-    (struct GraphClient, "graph")
-    client_type: Graph,
-    env: ["GRAPH_URI", "GRAPH_USER", "GRAPH_PASSWORD"],
-    setup: async {
-        let graph_uri = env::var("GRAPH_URI").expect("GRAPH_URI is not set");
+/// Orders graph search results by how well the matched entity fits the query,
+/// times the edge weight decayed by the age of the last observation.
+#[derive(Debug, Clone, Copy)]
+pub struct GraphRanking {
+    pub recency_half_life_ms: i64,
+}
+
+impl Default for GraphRanking {
+    fn default() -> Self {
+        Self { recency_half_life_ms: DEFAULT_GRAPH_DB_RECENCY_HALF_LIFE_MS }
+    }
+}
+
+impl GraphRanking {
+    /// `similarity` is on the -1 to 1 scale used by search and is mapped to 0..1,
+    /// so an exact match keeps the full decayed weight.
+    pub fn score(&self, similarity: f64, weight: f64, updated_at: i64, now: i64) -> f64 {
+        let relevance = ((similarity + 1.0) / 2.0).clamp(0.0, 1.0);
+        let age = (now - updated_at).max(0) as f64;
+        let decay = if self.recency_half_life_ms > 0 {
+            0.5f64.powf(age / self.recency_half_life_ms as f64)
+        } else {
+            1.0
+        };
+        relevance * weight.max(0.0) * decay
+    }
+
+    pub fn rank(&self, relations: Vec<ScoredRelationship>, now: i64) -> Vec<RelationHit> {
+        let mut hits = relations.into_iter()
+            .map(|r| RelationHit {
+                score: self.score(r.similarity, r.weight, r.updated_at, now),
+                weight: r.weight,
+                source: r.relationship.source,
+                relationship: r.relationship.relationship,
+                destination: r.relationship.destination,
+            })
+            .collect::<Vec<_>>();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GraphSearchOptions {
+    /// Minimum name similarity (-1 to 1) for an entity to match the query.
+    /// Lower values let partial names like "Elon" match "Elon Musk" but
+    /// risk pulling in unrelated entities.
+    pub fuzzy_threshold: f32,
+    pub ranking: GraphRanking,
+    /// Maximum number of relationships returned, applied both as the Cypher
+    /// `LIMIT` per query embedding and to the final ranked list.
+    pub limit: usize,
+    pub timeout: Duration,
+    /// Only relationships created at or after this time, in milliseconds since the epoch.
+    pub since: Option<i64>,
+    /// Only relationships created at or before this time, in milliseconds since the epoch.
+    pub until: Option<i64>,
+}
+
+impl Default for GraphSearchOptions {
+    fn default() -> Self {
+        Self {
+            fuzzy_threshold: DEFAULT_GRAPH_DB_TEXT_SEARCH_THRESHOLD,
+            ranking: GraphRanking::default(),
+            limit: DEFAULT_GRAPH_DB_SEARCH_LIMIT,
+            timeout: Duration::from_millis(DEFAULT_GRAPH_DB_QUERY_TIMEOUT_MS),
+            since: None,
+            until: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GraphAddOptions {
+    /// Relationships written per transaction, as a single statement. Each batch is committed on its
+    /// own, so earlier batches stay durable if a later one fails.
+    pub batch_size: usize,
+    /// Applies to the whole add, across all batches.
+    pub timeout: Duration,
+}
+
+impl Default for GraphAddOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: DEFAULT_GRAPH_DB_COMMIT_BATCH_SIZE,
+            timeout: Duration::from_millis(DEFAULT_GRAPH_DB_QUERY_TIMEOUT_MS),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GraphAddSummary {
+    pub relationships: usize,
+    pub commits: usize,
+}
+
+/// Connection to the graph database, plus the name of the vector index used to
+/// resolve entities. Written out by hand instead of with `define_module_client!`
+/// so it can carry the index name; `GRAPH_VECTOR_INDEX` overrides the default.
+/// Needs Neo4j 5.18 or later, for vector indexes and `vector.similarity.cosine`.
+#[derive(Clone)]
+pub struct GraphClient {
+    client: Option<Arc<Graph>>,
+    vector_index: String,
+}
+
+impl Default for GraphClient {
+    fn default() -> Self {
+        Self {
+            client: None,
+            vector_index: DEFAULT_GRAPH_DB_VECTOR_INDEX.to_string(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ModuleClient for GraphClient {
+    const NAME: &'static str = "graph";
+    type Client = Arc<Graph>;
+
+    fn validate_env() -> bool {
+        const ENV_VARS: &[&str] = &["GRAPH_URI", "GRAPH_USER", "GRAPH_PASSWORD"];
+        let missing_vars: Vec<&str> = ENV_VARS.iter().cloned().filter(|var| uri_from_env(var).is_none()).collect();
+
+        if missing_vars.is_empty() {
+            return true;
+        }
+
+        tracing::error!("[Client: {}] Required environment variables are not set: [{}]", Self::NAME, missing_vars.join(", "));
+        false
+    }
+
+    async fn setup_connection() -> Self {
+        if !Self::validate_env() {
+            panic!("[Client: {}] Required environment variables are not set. Check logs for details. Cannot setup connection.", Self::NAME);
+        }
+
+        let graph_uri = uri_from_env("GRAPH_URI").expect("GRAPH_URI is not set");
         let graph_user = env::var("GRAPH_USER").expect("GRAPH_USER is not set");
         let graph_password = env::var("GRAPH_PASSWORD").expect("GRAPH_PASSWORD is not set");
         let graph_config  = ConfigBuilder::default()
@@ -64,17 +307,45 @@ define_module_client! {
         let graph = Graph::connect(graph_config).await
             .expect("[GraphClient::setup] Failed to connect to graph");
 
-        graph
+        let vector_index = env::var("GRAPH_VECTOR_INDEX")
+            .ok()
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_GRAPH_DB_VECTOR_INDEX.to_string());
+
+        Self { client: Some(Arc::new(graph)), vector_index }
+    }
+
+    fn get_client(&self) -> &Self::Client {
+        self.client.as_ref().expect("Client not connected. Did you call setup_connection?")
     }
 }
 
 impl GraphClient {
+    /// Runs a graph operation, failing with an error instead of hanging once
+    /// `timeout` elapses. The dropped future abandons the in-flight query.
+    pub async fn with_timeout<T>(
+        timeout: Duration, op: &str, fut: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        tokio::time::timeout(timeout, fut).await
+            .map_err(|_| anyhow!("[GraphClient::{}] Query timed out after {:?}", op, timeout))?
+    }
+
+    /// Uses `vector_index` instead of the configured index, for `initialize` and every entity search.
+    pub fn with_vector_index(mut self, vector_index: impl Into<String>) -> Self {
+        self.vector_index = vector_index.into();
+        self
+    }
+
+    pub fn vector_index(&self) -> &str {
+        &self.vector_index
+    }
+
     pub async fn initialize(&self) -> Result<()> {
         let mut tx = self.get_client().start_txn().await?;
 
         let create_vector_index = format!(
-            "CREATE VECTOR INDEX memzero IF NOT EXISTS FOR (n:Entity) ON (n.embedding) OPTIONS {{indexConfig: {{`vector.dimensions`: {}, `vector.similarity_function`: 'cosine'}}}}",
-            EMBEDDING_DIMS
+            "CREATE VECTOR INDEX {} IF NOT EXISTS FOR (n:Entity) ON (n.embedding) OPTIONS {{indexConfig: {{`vector.dimensions`: {}, `vector.similarity_function`: 'cosine'}}}}",
+            self.vector_index, EMBEDDING_DIMS
         );
         let _ = tx.run(query(&create_vector_index)).await;
 
@@ -88,6 +359,15 @@ impl GraphClient {
     pub async fn search_entity_with_similarity(&self,
         embedding: &Embedding, filter: &Mem0Filter,
     ) -> Result<Option<String>> {
+        let candidates = self.search_entities_with_similarity(embedding, filter, 1).await?;
+        Ok(candidates.into_iter().next().map(|c| c.id))
+    }
+
+    /// Up to `top_k` entities from the configured vector index above the
+    /// similarity threshold, most similar first.
+    pub async fn search_entities_with_similarity(&self,
+        embedding: &Embedding, filter: &Mem0Filter, top_k: usize,
+    ) -> Result<Vec<EntityCandidate>> {
         let character_id_filter = if let Some(character_id) = filter.character_id {
             format!("AND candidate.character_id = '{}'", character_id)
         } else {
@@ -100,33 +380,58 @@ impl GraphClient {
         };
 
         let q = format!(r#"
-            CALL db.index.vector.queryNodes("memzero", 1, $source_embedding)
+            CALL db.index.vector.queryNodes($vector_index, $top_k, $source_embedding)
             YIELD node AS candidate, score AS similarity
             WHERE candidate.user_id = $user_id
             {character_id_filter}
             {session_id_filter}
             AND similarity >= {DEFAULT_GRAPH_DB_VECTOR_SEARCH_THRESHOLD}
-            RETURN id(candidate);
+            RETURN elementId(candidate) AS id, candidate.name AS name, similarity
+            ORDER BY similarity DESC;
         "#);
 
         let q = query(&q)
+            .param("vector_index", self.vector_index.as_str())
+            .param("top_k", top_k as i64)
             .param("source_embedding", embedding.clone())
             .param("user_id", filter.user_id.to_string());
 
         let mut result = self.get_client().execute(q).await?;
-        let mut results = Vec::new();
+        let mut candidates = Vec::new();
         while let Some(row) = result.next().await? {
-            results.push(row);
+            candidates.push(EntityCandidate {
+                id: row.get("id").unwrap_or_default(),
+                name: row.get("name").unwrap_or_default(),
+                similarity: row.get("similarity").unwrap_or_default(),
+            });
         }
 
-        let maybe_id = results
-            .first()
-            .and_then(|row| row.get("id(candidate)").unwrap_or(None));
-
-        Ok(maybe_id)
+        Ok(candidates)
     }
 
     pub async fn add(&self, message: &GraphEntities, embeder: &EmbederClient) -> Result<usize> {
+        let summary = self.add_with_options(message, embeder, &GraphAddOptions::default()).await?;
+        Ok(summary.relationships)
+    }
+
+    pub async fn add_with_timeout(&self,
+        message: &GraphEntities, embeder: &EmbederClient, timeout: Duration,
+    ) -> Result<usize> {
+        let options = GraphAddOptions { timeout, ..Default::default() };
+        let summary = self.add_with_options(message, embeder, &options).await?;
+        Ok(summary.relationships)
+    }
+
+    pub async fn add_with_options(&self,
+        message: &GraphEntities, embeder: &EmbederClient, options: &GraphAddOptions,
+    ) -> Result<GraphAddSummary> {
+        if options.batch_size == 0 {
+            return Err(anyhow!("[GraphClient::add_with_options] batch_size must be positive"));
+        }
+        Self::with_timeout(options.timeout, "add", self.add_inner(message, embeder, options.batch_size)).await
+    }
+
+    async fn add_inner(&self, message: &GraphEntities, embeder: &EmbederClient, batch_size: usize) -> Result<GraphAddSummary> {
         tracing::debug!("[Mem0Engine::graph_db_add] Adding graph entities: {:?}", message);
         let user_id = message.filter.user_id;
         let character_id = message.filter.character_id;
@@ -138,7 +443,7 @@ impl GraphClient {
             entity_names.insert(relationship.destination.clone());
         }
         let entity_names: Vec<String> = entity_names.into_iter().collect();
-        if entity_names.is_empty() { return Ok(0); }
+        if entity_names.is_empty() { return Ok(GraphAddSummary::default()); }
 
         let embeddings = embeder.embed(entity_names.clone()).await?;
         let name_to_embedding: HashMap<String, Vec<f32>> = entity_names.iter().cloned().zip(embeddings).collect();
@@ -152,143 +457,108 @@ impl GraphClient {
             }
         }
 
-        let mut tx = self.get_client().start_txn().await?;
-        let mut count = 0;
-        for relationship in &message.relationships {
-            let source_id = name_to_id.get(&relationship.source);
-            let dest_id = name_to_id.get(&relationship.destination);
-
-            let source_embed = name_to_embedding.get(&relationship.source).unwrap();
-            let dest_embed = name_to_embedding.get(&relationship.destination).unwrap();
-
-            let query = match (source_id, dest_id) {
-                (Some(source_id), Some(dest_id)) => {
-                    let cypher = format!(
-                        "MATCH (source:Entity {{id: $source_id}}), (destination:Entity {{id: $dest_id}}) \
-                        MERGE (source)-[r:`{}`]->(destination) \
-                        ON CREATE SET r.created_at = timestamp(), r.updated_at = timestamp()",
-                        relationship.relationship
-                    );
-                    query(&cypher)
-                        .param("source_id", source_id.clone())
-                        .param("dest_id", dest_id.clone())
-                }
-                (Some(source_id), None) => {
-                    let dest_type = message.entity_tags
-                        .get(&relationship.destination)
-                        .cloned()
-                        .unwrap_or_else(|| "Entity".to_string());
-                    
-                    let mut merge_properties = vec!["name: $destination_name", "user_id: $user_id"];
-                    if character_id.is_some() { merge_properties.push("character_id: $character_id"); }
-                    if session_id.is_some() { merge_properties.push("session_id: $session_id"); }
-
-                    let cypher = format!(
-                        "MATCH (source:Entity {{id: $source_id}}) \
-                        MERGE (destination:`{}`:Entity {{{}}}) \
-                        ON CREATE SET destination.created_at = timestamp(), destination.embedding = $destination_embedding \
-                        MERGE (source)-[r:`{}`]->(destination) \
-                        ON CREATE SET r.created_at = timestamp(), r.updated_at = timestamp()",
-                        dest_type, merge_properties.join(", "), relationship.relationship
-                    );
-
-                    let mut q = query(&cypher)
-                        .param("source_id", source_id.clone())
-                        .param("destination_name", relationship.destination.clone())
-                        .param("user_id", user_id.to_string())
-                        .param("destination_embedding", dest_embed.clone());
-                    
-                    if let Some(cid) = character_id { q = q.param("character_id", cid.to_string()); }
-                    if let Some(sid) = session_id { q = q.param("session_id", sid.to_string()); }
-                    q
-                }
-                (None, Some(dest_id)) => {
-                    let source_type = message.entity_tags
-                        .get(&relationship.source)
-                        .cloned()
-                        .unwrap_or_else(|| "Entity".to_string());
-
-                    let mut merge_properties = vec!["name: $source_name", "user_id: $user_id"];
-                    if character_id.is_some() { merge_properties.push("character_id: $character_id"); }
-                    if session_id.is_some() { merge_properties.push("session_id: $session_id"); }
-
-                    let cypher = format!(
-                        "MATCH (destination:Entity {{id: $dest_id}}) \
-                        MERGE (source:`{}`:Entity {{{}}}) \
-                        ON CREATE SET source.created_at = timestamp(), source.embedding = $source_embedding \
-                        MERGE (source)-[r:`{}`]->(destination) \
-                        ON CREATE SET r.created_at = timestamp(), r.updated_at = timestamp()",
-                        source_type, merge_properties.join(", "), relationship.relationship
-                    );
-                    
-                    let mut q = query(&cypher)
-                        .param("dest_id", dest_id.clone())
-                        .param("source_name", relationship.source.clone())
-                        .param("user_id", user_id.to_string())
-                        .param("source_embedding", source_embed.clone());
-
-                    if let Some(cid) = character_id { q = q.param("character_id", cid.to_string()); }
-                    if let Some(sid) = session_id { q = q.param("session_id", sid.to_string()); }
-                    q
-                }
-                (None, None) => {
-                    let source_type = message.entity_tags
-                        .get(&relationship.source)
-                        .cloned()
-                        .unwrap_or_else(|| "Entity".to_string());
-                    let dest_type = message.entity_tags
-                        .get(&relationship.destination)
-                        .cloned()
-                        .unwrap_or_else(|| "Entity".to_string());
-
-                    let mut source_merge_props = vec!["name: $source_name", "user_id: $user_id"];
-                    let mut dest_merge_props = vec!["name: $dest_name", "user_id: $user_id"];
-
-                    if character_id.is_some() {
-                        source_merge_props.push("character_id: $character_id");
-                        dest_merge_props.push("character_id: $character_id");
-                    }
-                    if session_id.is_some() {
-                        source_merge_props.push("session_id: $session_id");
-                        dest_merge_props.push("session_id: $session_id");
-                    }
-
-                    let cypher = format!(
-                        "MERGE (source:`{}`:Entity {{{}}}) \
-                        ON CREATE SET source.created_at = timestamp(), source.embedding = $source_embedding \
-                        MERGE (destination:`{}`:Entity {{{}}}) \
-                        ON CREATE SET destination.created_at = timestamp(), destination.embedding = $dest_embedding \
-                        MERGE (source)-[r:`{}`]->(destination) \
-                        ON CREATE SET r.created_at = timestamp(), r.updated_at = timestamp()",
-                        source_type, source_merge_props.join(", "), dest_type, dest_merge_props.join(", "), relationship.relationship
-                    );
-
-                    let mut q = query(&cypher)
-                        .param("source_name", relationship.source.clone())
-                        .param("dest_name", relationship.destination.clone())
-                        .param("source_embedding", source_embed.clone())
-                        .param("dest_embedding", dest_embed.clone())
-                        .param("user_id", user_id.to_string());
-                    
-                    if let Some(cid) = character_id { q = q.param("character_id", cid.to_string()); }
-                    if let Some(sid) = session_id { q = q.param("session_id", sid.to_string()); }
-                    q
-                }
-            };
+        let mut scope_properties = vec!["user_id: $user_id"];
+        if character_id.is_some() { scope_properties.push("character_id: $character_id"); }
+        if session_id.is_some() { scope_properties.push("session_id: $session_id"); }
+        let scope_properties = scope_properties.join(", ");
+
+        // Endpoints resolved to an existing entity are matched by id, the rest are
+        // merged within the filter's scope. Labels and relationship types can't be
+        // parameters, so a batch runs one statement per (source type, destination
+        // type, relationship) it contains, all in the batch's transaction.
+        let merge_cypher = |source_type: &str, dest_type: &str, relationship: &str| format!(
+            "UNWIND $rows AS row \
+            CALL {{ \
+                WITH row \
+                WITH row WHERE row.source_id IS NOT NULL \
+                MATCH (source:Entity) WHERE elementId(source) = row.source_id \
+                RETURN source \
+                UNION \
+                WITH row \
+                WITH row WHERE row.source_id IS NULL \
+                MERGE (source:`{source_type}`:Entity {{name: row.source_name, {scope_properties}}}) \
+                ON CREATE SET source.created_at = timestamp(), source.embedding = row.source_embedding \
+                RETURN source \
+            }} \
+            CALL {{ \
+                WITH row \
+                WITH row WHERE row.dest_id IS NOT NULL \
+                MATCH (destination:Entity) WHERE elementId(destination) = row.dest_id \
+                RETURN destination \
+                UNION \
+                WITH row \
+                WITH row WHERE row.dest_id IS NULL \
+                MERGE (destination:`{dest_type}`:Entity {{name: row.dest_name, {scope_properties}}}) \
+                ON CREATE SET destination.created_at = timestamp(), destination.embedding = row.dest_embedding \
+                RETURN destination \
+            }} \
+            MERGE (source)-[r:`{relationship}`]->(destination) \
+            {RELATIONSHIP_MERGE_SET} \
+            RETURN count(r) AS relationships"
+        );
 
-            let mut result = tx.execute(query).await?;
-            while let Ok(Some(_)) = result.next(&mut tx.handle()).await { count += 1; }
-        }
+        let entity_type = |name: &String| message.entity_tags.get(name).cloned().unwrap_or_else(|| "Entity".to_string());
+
+        let mut summary = GraphAddSummary::default();
+        for batch in message.relationships.chunks(batch_size) {
+            let mut groups: HashMap<(String, String, &str), Vec<BoltType>> = HashMap::new();
+            for relationship in batch {
+                let mut row = BoltMap::new();
+                row.put("weight".into(), (relationship.weight.unwrap_or(1.0) as f64).into());
+                row.put("source_id".into(), name_to_id.get(&relationship.source).cloned().into());
+                row.put("source_name".into(), relationship.source.clone().into());
+                row.put("source_embedding".into(), name_to_embedding[&relationship.source].clone().into());
+                row.put("dest_id".into(), name_to_id.get(&relationship.destination).cloned().into());
+                row.put("dest_name".into(), relationship.destination.clone().into());
+                row.put("dest_embedding".into(), name_to_embedding[&relationship.destination].clone().into());
+                groups.entry((entity_type(&relationship.source), entity_type(&relationship.destination), relationship.relationship.as_str()))
+                    .or_default()
+                    .push(BoltType::Map(row));
+            }
 
-        tx.commit().await?;
+            let mut tx = self.get_client().start_txn().await?;
+            for ((source_type, dest_type, relationship), rows) in groups {
+                let mut q = query(&merge_cypher(&source_type, &dest_type, relationship))
+                    .param("rows", rows)
+                    .param("user_id", user_id.to_string());
+                if let Some(cid) = character_id { q = q.param("character_id", cid.to_string()); }
+                if let Some(sid) = session_id { q = q.param("session_id", sid.to_string()); }
+
+                let mut result = tx.execute(q).await?;
+                if let Some(row) = result.next(&mut tx.handle()).await? {
+                    summary.relationships += row.get::<i64>("relationships")? as usize;
+                }
+            }
+            tx.commit().await?;
+            summary.commits += 1;
+        }
 
-        Ok(count)
+        tracing::debug!("[GraphClient::add] Added {} relationships in {} commits", summary.relationships, summary.commits);
+        Ok(summary)
     }
 
     pub async fn search(&self,
         nodes_embeddings: Vec<Embedding>,
         filter: &Mem0Filter,
-    ) -> Result<Vec<Relationship>> {
+    ) -> Result<Vec<RelationHit>> {
+        self.search_with_options(nodes_embeddings, filter, &GraphSearchOptions::default()).await
+    }
+
+    pub async fn search_with_options(&self,
+        nodes_embeddings: Vec<Embedding>,
+        filter: &Mem0Filter,
+        options: &GraphSearchOptions,
+    ) -> Result<Vec<RelationHit>> {
+        Self::with_timeout(options.timeout, "search", self.search_inner(nodes_embeddings, filter, options)).await
+    }
+
+    async fn search_inner(&self,
+        nodes_embeddings: Vec<Embedding>,
+        filter: &Mem0Filter,
+        options: &GraphSearchOptions,
+    ) -> Result<Vec<RelationHit>> {
+        let fuzzy_threshold = options.fuzzy_threshold;
+        let limit = options.limit;
         let (character_id_filter_n, character_id_filter_m) = if let Some(character_id) = filter.character_id {
             (
                 format!("AND n.character_id = '{}'", character_id),
@@ -306,6 +576,10 @@ impl GraphClient {
             ("".to_string(), "".to_string())
         };
 
+        let mut created_at_filter = String::new();
+        if options.since.is_some() { created_at_filter.push_str("AND r.created_at >= $since "); }
+        if options.until.is_some() { created_at_filter.push_str("AND r.created_at <= $until "); }
+
         let mut all_relations = Vec::new();
 
         for embedding in nodes_embeddings {
@@ -313,26 +587,28 @@ impl GraphClient {
                 MATCH (n:Entity)
                 WHERE n.embedding IS NOT NULL AND n.user_id = $user_id {character_id_filter_n} {session_id_filter_n}
                 WITH n, round(2 * vector.similarity.cosine(n.embedding, $embedding) - 1, 4) AS similarity
-                WHERE similarity >= {DEFAULT_GRAPH_DB_TEXT_SEARCH_THRESHOLD}
+                WHERE similarity >= {fuzzy_threshold}
                 CALL {{
                     WITH n
                     MATCH (n)-[r]->(m:Entity)
-                    WHERE m.user_id = $user_id {character_id_filter_m} {session_id_filter_m}
-                    RETURN n.name AS source, elementId(n) AS source_id, type(r) AS relationship, elementId(r) AS relation_id, m.name AS destination, elementId(m) AS destination_id
+                    WHERE m.user_id = $user_id {character_id_filter_m} {session_id_filter_m} {created_at_filter}
+                    RETURN n.name AS source, elementId(n) AS source_id, type(r) AS relationship, elementId(r) AS relation_id, m.name AS destination, elementId(m) AS destination_id, toFloat(coalesce(r.weight, 1)) AS weight, coalesce(r.updated_at, r.created_at, 0) AS updated_at
                     UNION
                     WITH n
                     MATCH (m:Entity)-[r]->(n)
-                    WHERE m.user_id = $user_id {character_id_filter_m} {session_id_filter_m}
-                    RETURN m.name AS source, elementId(m) AS source_id, type(r) AS relationship, elementId(r) AS relation_id, n.name AS destination, elementId(n) AS destination_id
+                    WHERE m.user_id = $user_id {character_id_filter_m} {session_id_filter_m} {created_at_filter}
+                    RETURN m.name AS source, elementId(m) AS source_id, type(r) AS relationship, elementId(r) AS relation_id, n.name AS destination, elementId(n) AS destination_id, toFloat(coalesce(r.weight, 1)) AS weight, coalesce(r.updated_at, r.created_at, 0) AS updated_at
                 }}
-                WITH distinct source, source_id, relationship, relation_id, destination, destination_id, similarity
+                WITH distinct source, source_id, relationship, relation_id, destination, destination_id, weight, updated_at, similarity
                 RETURN
                     source,
                     relationship,
                     destination,
+                    weight,
+                    updated_at,
                     similarity
                 ORDER BY similarity DESC
-                LIMIT {DEFAULT_GRAPH_DB_SEARCH_LIMIT}
+                LIMIT {limit}
             "#,
             character_id_filter_n = character_id_filter_n,
             character_id_filter_m = character_id_filter_m,
@@ -340,9 +616,11 @@ impl GraphClient {
             session_id_filter_m = session_id_filter_m
             );
 
-            let q = query(&query_str)
+            let mut q = query(&query_str)
                 .param("embedding", embedding)
                 .param("user_id", filter.user_id.to_string());
+            if let Some(since) = options.since { q = q.param("since", since); }
+            if let Some(until) = options.until { q = q.param("until", until); }
 
             let mut result = self.get_client().execute(q).await?;
             while let Some(row) = result.next().await? {
@@ -350,15 +628,156 @@ impl GraphClient {
                     source: row.get("source").unwrap_or_default(),
                     relationship: row.get("relationship").unwrap_or_default(),
                     destination: row.get("destination").unwrap_or_default(),
+                    confidence: None,
+                    weight: None,
                 };
-                all_relations.push(relation_info);
+                all_relations.push(ScoredRelationship {
+                    relationship: relation_info,
+                    similarity: row.get("similarity").unwrap_or_default(),
+                    weight: row.get("weight").unwrap_or(1.0),
+                    updated_at: row.get("updated_at").unwrap_or_default(),
+                });
+            }
+        }
+
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut ranked = options.ranking.rank(all_relations, now);
+        ranked.truncate(limit);
+        Ok(ranked)
+    }
+
+    /// Relationships on paths of up to `depth` hops from the entities most similar
+    /// to `entities`, in either direction and within the filter's scope. `depth`
+    /// is capped at `MAX_GRAPH_DB_TRAVERSAL_DEPTH`; each relationship is returned
+    /// once, ranked like `search`.
+    pub async fn search_subgraph(&self,
+        entities: Vec<String>, embeder: &EmbederClient, filter: &Mem0Filter, depth: u8,
+    ) -> Result<Vec<RelationHit>> {
+        Self::with_timeout(
+            Duration::from_millis(DEFAULT_GRAPH_DB_QUERY_TIMEOUT_MS),
+            "search_subgraph",
+            self.search_subgraph_inner(entities, embeder, filter, depth),
+        ).await
+    }
+
+    async fn search_subgraph_inner(&self,
+        entities: Vec<String>, embeder: &EmbederClient, filter: &Mem0Filter, depth: u8,
+    ) -> Result<Vec<RelationHit>> {
+        if entities.is_empty() { return Ok(vec![]); }
+        let depth = depth.clamp(1, MAX_GRAPH_DB_TRAVERSAL_DEPTH);
+
+        let embeddings = embeder.embed(entities).await?;
+        let mut seed_ids = HashSet::new();
+        for embedding in &embeddings {
+            if let Some(id) = self.search_entity_with_similarity(embedding, filter).await? {
+                seed_ids.insert(id);
             }
         }
+        if seed_ids.is_empty() { return Ok(vec![]); }
+
+        let mut scope = vec!["node.user_id = $user_id"];
+        if filter.character_id.is_some() { scope.push("node.character_id = $character_id"); }
+        if filter.session_id.is_some() { scope.push("node.session_id = $session_id"); }
+
+        let cypher = format!(r#"
+            MATCH (seed:Entity) WHERE elementId(seed) IN $seed_ids
+            MATCH path = (seed)-[*1..{depth}]-(:Entity)
+            WHERE all(node IN nodes(path) WHERE {scope})
+            UNWIND relationships(path) AS r
+            WITH DISTINCT r
+            RETURN
+                startNode(r).name AS source,
+                type(r) AS relationship,
+                endNode(r).name AS destination,
+                toFloat(coalesce(r.weight, 1)) AS weight,
+                coalesce(r.updated_at, r.created_at, 0) AS updated_at
+            LIMIT {DEFAULT_GRAPH_DB_SEARCH_LIMIT}
+        "#, scope = scope.join(" AND "));
+
+        let mut q = query(&cypher)
+            .param("seed_ids", seed_ids.into_iter().collect::<Vec<_>>())
+            .param("user_id", filter.user_id.to_string());
+        if let Some(cid) = filter.character_id { q = q.param("character_id", cid.to_string()); }
+        if let Some(sid) = filter.session_id { q = q.param("session_id", sid.to_string()); }
+
+        let mut relations = Vec::new();
+        let mut result = self.get_client().execute(q).await?;
+        while let Some(row) = result.next().await? {
+            relations.push(ScoredRelationship {
+                relationship: Relationship {
+                    source: row.get("source").unwrap_or_default(),
+                    relationship: row.get("relationship").unwrap_or_default(),
+                    destination: row.get("destination").unwrap_or_default(),
+                    confidence: None,
+                    weight: None,
+                },
+                similarity: 1.0,
+                weight: row.get("weight").unwrap_or(1.0),
+                updated_at: row.get("updated_at").unwrap_or_default(),
+            });
+        }
 
-        Ok(all_relations)
+        let now = chrono::Utc::now().timestamp_millis();
+        Ok(GraphRanking::default().rank(relations, now))
+    }
+
+    /// Number of entity nodes owned by the user, narrowed to the character and
+    /// session when the filter sets them.
+    pub async fn count_nodes(&self, filter: &Mem0Filter) -> Result<i64> {
+        let mut match_props = vec!["user_id: $user_id"];
+        if filter.character_id.is_some() { match_props.push("character_id: $character_id"); }
+        if filter.session_id.is_some() { match_props.push("session_id: $session_id"); }
+
+        let cypher = format!("MATCH (n:Entity {{{}}}) RETURN count(n) AS nodes", match_props.join(", "));
+        let mut q = query(&cypher).param("user_id", filter.user_id.to_string());
+        if let Some(cid) = filter.character_id { q = q.param("character_id", cid.to_string()); }
+        if let Some(sid) = filter.session_id { q = q.param("session_id", sid.to_string()); }
+
+        Self::with_timeout(Duration::from_millis(DEFAULT_GRAPH_DB_QUERY_TIMEOUT_MS), "count_nodes", async {
+            let mut result = self.get_client().execute(q).await?;
+            let row = result.next().await?
+                .ok_or_else(|| anyhow!("[GraphClient::count_nodes] count returned no rows"))?;
+            Ok(row.get::<i64>("nodes")?)
+        }).await
+    }
+
+    /// Deletes every entity node in the filter's scope together with its
+    /// relationships, in one transaction. Returns the number of nodes deleted.
+    pub async fn delete_all(&self, filter: &Mem0Filter) -> Result<usize> {
+        Self::with_timeout(Duration::from_millis(DEFAULT_GRAPH_DB_QUERY_TIMEOUT_MS), "delete_all", self.delete_all_inner(filter)).await
+    }
+
+    async fn delete_all_inner(&self, filter: &Mem0Filter) -> Result<usize> {
+        let mut match_props = vec!["user_id: $user_id"];
+        if filter.character_id.is_some() { match_props.push("character_id: $character_id"); }
+        if filter.session_id.is_some() { match_props.push("session_id: $session_id"); }
+
+        let cypher = format!("MATCH (n:Entity {{{}}}) DETACH DELETE n RETURN count(n) AS deleted", match_props.join(", "));
+        let mut q = query(&cypher).param("user_id", filter.user_id.to_string());
+        if let Some(cid) = filter.character_id { q = q.param("character_id", cid.to_string()); }
+        if let Some(sid) = filter.session_id { q = q.param("session_id", sid.to_string()); }
+
+        let mut tx = self.get_client().start_txn().await?;
+        let mut result = tx.execute(q).await?;
+        let deleted = match result.next(&mut tx.handle()).await? {
+            Some(row) => row.get::<i64>("deleted")? as usize,
+            None => 0,
+        };
+        tx.commit().await?;
+
+        tracing::debug!("[GraphClient::delete_all] Deleted {} nodes", deleted);
+        Ok(deleted)
     }
 
     pub async fn delete(&self, message: &GraphEntities) -> Result<usize> {
+        self.delete_with_timeout(message, Duration::from_millis(DEFAULT_GRAPH_DB_QUERY_TIMEOUT_MS)).await
+    }
+
+    pub async fn delete_with_timeout(&self, message: &GraphEntities, timeout: Duration) -> Result<usize> {
+        Self::with_timeout(timeout, "delete", self.delete_inner(message)).await
+    }
+
+    async fn delete_inner(&self, message: &GraphEntities) -> Result<usize> {
         let mut count = 0;
         let mut tx = self.get_client().start_txn().await?;
         for relationship in &message.relationships {
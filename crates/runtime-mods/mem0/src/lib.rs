@@ -1,17 +1,23 @@
 mod pgvector;
 pub mod agents;
 mod engine;
+mod merge;
+mod recall;
+mod scrub;
 #[cfg(feature = "graph")]
 mod graph;
 
-pub use pgvector::EmbeddingMessage;
+pub use pgvector::{EmbeddingMessage, MemoryEvent, MemoryUpdateEntry};
+pub use merge::{merge_contradictions, ContentHash};
+pub use recall::{RecallResult, RecallSource};
+pub use scrub::{redact_pii, scrub_facts, scrub_message, PiiScrubMode};
 use anyhow::Result;
 
-use metastable_clients::{EmbederClient, LlmClient, PgvectorClient, PostgresClient};
+use metastable_clients::{EmbederClient, ExtractionGate, LlmClient, PgVectorConfig, PgvectorClient, PostgresClient};
 use metastable_common::ModuleClient;
 
 #[cfg(feature = "graph")]
-use graph::{GraphClient, EntityTag};
+pub use graph::{max_relationships_from_env, parse_max_relationships, EntityCandidate, EntityTag, GraphAddOptions, GraphAddSummary, GraphClient, GraphEntities, GraphRanking, GraphSearchOptions, RelationHit, Relationship, ScoredRelationship};
 use serde::{Deserialize, Serialize};
 use sqlx::types::Uuid;
 
@@ -32,6 +38,14 @@ pub struct Mem0Engine {
     #[cfg(feature = "graph")]
     pub(crate) graph_db: GraphClient,
     pub(crate) embeder: EmbederClient,
+    /// Distance function of `EmbeddingMessage::batch_search`, from `PGVECTOR_METRIC`.
+    pub(crate) vector_config: PgVectorConfig,
+    pub(crate) pii_scrub_mode: PiiScrubMode,
+    pub(crate) extraction_gate: ExtractionGate,
+    pub(crate) content_hash: ContentHash,
+    /// Relationships kept from one extraction, see `GraphEntities::cap_relationships`.
+    #[cfg(feature = "graph")]
+    pub(crate) max_relationships: usize,
 }
 
 impl Mem0Engine {
@@ -42,11 +56,21 @@ impl Mem0Engine {
         let graph_db = GraphClient::setup_connection().await;
         let embeder = EmbederClient::setup_connection().await;
         let llm = LlmClient::setup_connection().await;
+        let vector_config = PgVectorConfig::from_env()?;
+        let pii_scrub_mode = PiiScrubMode::from_env();
+        let extraction_gate = ExtractionGate::from_env();
+        let content_hash = ContentHash::from_env();
+        #[cfg(feature = "graph")]
+        let max_relationships = max_relationships_from_env();
 
-        Ok(Self {  data_db, vector_db,  #[cfg(feature = "graph")] graph_db, embeder, llm })
+        Ok(Self {
+            data_db, vector_db,  #[cfg(feature = "graph")] graph_db, embeder, vector_config, llm, pii_scrub_mode, extraction_gate, content_hash,
+            #[cfg(feature = "graph")] max_relationships,
+        })
     }
 
     pub async fn init(&self) -> Result<()> {
+        EmbeddingMessage::create_vector_index(self).await?;
         #[cfg(feature = "graph")]
         self.graph_db.initialize().await?;
         Ok(())
@@ -0,0 +1,83 @@
+use std::collections::HashSet;
+use std::collections::hash_map::DefaultHasher;
+use std::env;
+use std::hash::{Hash, Hasher};
+
+use crate::pgvector::{MemoryEvent, MemoryUpdateEntry};
+
+/// How fact contents are hashed when merging the memory operations of one
+/// `update_memory` call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ContentHash {
+    /// Apply the operations as the model returned them.
+    Off,
+    /// Hash contents byte for byte.
+    Exact,
+    /// Hash contents ignoring case, surrounding punctuation and repeated whitespace.
+    #[default]
+    Normalized,
+}
+
+impl ContentHash {
+    /// Reads `MEM0_CONTENT_HASH` (`off`, `exact` or `normalized`), defaulting to `normalized`.
+    pub fn from_env() -> Self {
+        match env::var("MEM0_CONTENT_HASH").unwrap_or_default().to_lowercase().as_str() {
+            "off" => Self::Off,
+            "exact" => Self::Exact,
+            _ => Self::Normalized,
+        }
+    }
+
+    pub fn hash(&self, content: &str) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        match self {
+            Self::Normalized => content
+                .trim_matches(|c: char| c.is_whitespace() || c.is_ascii_punctuation() || c == '。')
+                .split_whitespace()
+                .map(str::to_lowercase)
+                .collect::<Vec<_>>()
+                .join(" ")
+                .hash(&mut hasher),
+            _ => content.hash(&mut hasher),
+        }
+        hasher.finish()
+    }
+}
+
+/// Cleans up the operations the model returned for one batch of new facts:
+/// - an ADD restating a memory already in the batch (kept, updated or added
+///   before it) is dropped, so the fact is not stored twice;
+/// - an ADD the model flagged as contradicting a stored memory becomes an
+///   UPDATE of that memory, replacing any DELETE of it, so the new fact
+///   replaces the old one instead of living next to it.
+///
+/// Unflagged DELETEs and ADDs are left alone: a "forget X" next to an
+/// unrelated new fact must not overwrite X.
+pub fn merge_contradictions(updates: Vec<MemoryUpdateEntry>, mode: ContentHash) -> Vec<MemoryUpdateEntry> {
+    if mode == ContentHash::Off {
+        return updates;
+    }
+
+    let mut seen = updates.iter()
+        .filter(|u| !matches!(u.event, MemoryEvent::Add | MemoryEvent::Delete))
+        .map(|u| mode.hash(&u.content))
+        .collect::<HashSet<_>>();
+
+    let mut replaced = HashSet::new();
+    let mut merged = Vec::with_capacity(updates.len());
+    for mut update in updates {
+        if matches!(update.event, MemoryEvent::Add) {
+            if !seen.insert(mode.hash(&update.content)) {
+                continue;
+            }
+            if let Some(id) = update.contradicts {
+                update.id = id;
+                update.event = MemoryEvent::Update;
+                replaced.insert(id);
+            }
+        }
+        merged.push(update);
+    }
+    merged.retain(|u| !(matches!(u.event, MemoryEvent::Delete) && replaced.contains(&u.id)));
+    merged
+}
@@ -1,18 +1,23 @@
+use std::collections::HashMap;
+
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use sqlx::types::Uuid;
 use metastable_common::{ModuleClient, get_current_timestamp};
-use metastable_database::{QueryCriteria, SqlxCrud, SqlxFilterQuery, TextCodecEnum};
+use metastable_database::{QueryCriteria, SqlxCrud, SqlxFilterQuery, TextEnum};
+use metastable_clients::{DimensionMismatch, DEFAULT_EMBEDDING_CHUNK_SIZE, DEFAULT_EMBEDDING_MAX_CONCURRENCY};
 
 use crate::{EmbeddingMessage, Mem0Engine, Mem0Filter};
 
-#[derive(Debug, Clone, Serialize, Deserialize, TextCodecEnum)]
-#[text_codec(format = "upper", storage_lang = "en")]
-#[serde(rename_all = "UPPERCASE")]
+#[derive(Debug, Clone, TextEnum)]
 pub enum MemoryEvent {
+    #[prefix(lang = "en", content = "ADD")]
     Add,
+    #[prefix(lang = "en", content = "UPDATE")]
     Update,
+    #[prefix(lang = "en", content = "DELETE")]
     Delete,
+    #[prefix(lang = "en", content = "NONE")]
     None,
 }
 
@@ -22,6 +27,9 @@ pub struct MemoryUpdateEntry {
     pub filter: Mem0Filter,
     pub event: MemoryEvent,
     pub content: String,
+    /// Stored memory this entry's fact contradicts, as flagged by the model.
+    #[serde(default)]
+    pub contradicts: Option<Uuid>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,8 +62,12 @@ impl Mem0Engine {
         let update_contents: Vec<String> = to_update.iter().map(|u| u.content.clone()).collect();
 
         let all_contents_to_embed = [add_contents.as_slice(), update_contents.as_slice()].concat();
-        let embeddings = self.embeder.embed(all_contents_to_embed).await?;
+        let embeddings = self.embeder.embed_chunked(
+            all_contents_to_embed, DEFAULT_EMBEDDING_CHUNK_SIZE, DEFAULT_EMBEDDING_MAX_CONCURRENCY
+        ).await?;
 
+        // Checked before anything is written, so a model switch fails the whole batch
+        DimensionMismatch::check(self.vector_config.dimension, embeddings.iter().map(|e| e.as_slice()))?;
         let (add_embeddings, update_embeddings) = embeddings.split_at(add_contents.len());
 
         let now = get_current_timestamp();
@@ -79,45 +91,43 @@ impl Mem0Engine {
 
                     embedding: embedding.clone().into(),
                     content: update.content,
+                    metadata: None,
                     created_at: now,
                     updated_at: now,
                 })
             })
             .collect();
 
-        let update_messages: Vec<EmbeddingMessage> = to_update
+        // Only the text and its embedding change; metadata and created_at are kept
+        let mut update_contents = to_update
             .into_iter()
             .zip(update_embeddings)
-            .filter_map(|(update, embedding)| {
-                let id = update.id;
-                Some(EmbeddingMessage {
-                    id,
-
-                    user_id: update.filter.user_id,
-                    character_id: update.filter.character_id,
-                    session_id: update.filter.session_id,
-
-                    embedding: embedding.clone().into(),
-                    content: update.content,
-                    created_at: now,
-                    updated_at: now,
-                })
-            })
-            .collect();
+            .map(|(update, embedding)| (update.id, (update.content, embedding.clone())))
+            .collect::<HashMap<_, _>>();
 
         let mut tx = self.vector_db.get_client().begin().await?;
 
-        for embedding in add_messages {
-            embedding.create(&mut *tx).await?;
-        }
+        EmbeddingMessage::create_many(add_messages, &mut *tx).await?;
 
-        for update in update_messages {
-            update.update(&mut *tx).await?;
+        if !update_contents.is_empty() {
+            let existing = EmbeddingMessage::find_by_criteria(
+                QueryCriteria::new()
+                    .add_in_filter("id", update_contents.keys().cloned().collect::<Vec<_>>())
+                    .for_update(),
+                &mut *tx
+            ).await?;
+            for mut memory in existing {
+                let Some((content, embedding)) = update_contents.remove(&memory.id) else { continue };
+                memory.content = content;
+                memory.embedding = embedding.into();
+                memory.updated_at = now;
+                memory.update(&mut *tx).await?;
+            }
         }
 
         if !to_delete_ids.is_empty() {
             EmbeddingMessage::delete_by_criteria(
-                QueryCriteria::new().add_filter("id", " = ANY($1)", Some(to_delete_ids)),
+                QueryCriteria::new().add_in_filter("id", to_delete_ids),
                 &mut *tx
             ).await?;
         }
@@ -2,10 +2,11 @@ mod batch;
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use sqlx::types::Uuid;
+use serde_json::Value;
+use sqlx::types::{Json, Uuid};
 use metastable_common::{ModuleClient, get_current_timestamp};
 use metastable_database::{OrderDirection, SqlxObject, Vector};
-use metastable_clients::DEFAULT_GRAPH_DB_VECTOR_SEARCH_THRESHOLD;
+use metastable_clients::{DimensionMismatch, DEFAULT_GRAPH_DB_VECTOR_SEARCH_THRESHOLD, DEFAULT_EMBEDDING_CHUNK_SIZE, DEFAULT_EMBEDDING_MAX_CONCURRENCY};
 
 pub use batch::{MemoryUpdateEntry, MemoryEvent};
 
@@ -24,6 +25,8 @@ pub struct EmbeddingMessage {
     #[vector_dimension(1024)]
     pub embedding: Vector,
     pub content: String,
+    #[indexed(gin)]
+    pub metadata: Option<Json<Value>>,
 
     pub created_at: i64,
     pub updated_at: i64,
@@ -35,7 +38,9 @@ impl EmbeddingMessage {
             return Ok(vec![]);
         }
 
-        let embeddings = mem0_engine.embeder.embed(raw_messages.to_vec()).await?;
+        let embeddings = mem0_engine.embeder.embed_chunked(
+            raw_messages.to_vec(), DEFAULT_EMBEDDING_CHUNK_SIZE, DEFAULT_EMBEDDING_MAX_CONCURRENCY
+        ).await?;
         let embedding_messages = embeddings
             .iter()
             .zip(raw_messages)
@@ -47,6 +52,7 @@ impl EmbeddingMessage {
 
                 embedding: embedding.clone().into(),
                 content: messages.clone(),
+                metadata: None,
                 created_at: get_current_timestamp(),
                 updated_at: get_current_timestamp(),
             }).collect::<Vec<_>>();
@@ -54,22 +60,59 @@ impl EmbeddingMessage {
         Ok(embedding_messages)
     }
 
+    /// Fails with `DimensionMismatch` when a query embedding does not match
+    /// the stored ones, e.g. after switching embedding models.
     pub async fn batch_search(mem0_engine: &Mem0Engine, filter: &Mem0Filter, embeddings: &[Self], limit: i64) -> Result<Vec<Vec<Self>>> {
+        DimensionMismatch::check(mem0_engine.vector_config.dimension, embeddings.iter().map(|e| e.embedding.as_slice()))?;
         let mut tx = mem0_engine.vector_db.get_client().begin().await?;
     
         let mut all_results = Vec::new();
         for embedding in embeddings {
             let criteria = QueryCriteria::new()
                 .find_similarity(embedding.embedding.clone(), "similarity")
+                .with_similarity_metric(mem0_engine.vector_config.metric)
                 .with_similarity_threshold(DEFAULT_GRAPH_DB_VECTOR_SEARCH_THRESHOLD)
                 .add_filter("user_id", "=", Some(filter.user_id))
                 .add_filter("character_id", "=", filter.character_id)
                 .add_filter("session_id", "=", filter.session_id)
                 .order_by("similarity", OrderDirection::Desc)
                 .limit(limit);
-            all_results.push(EmbeddingMessage::find_by_criteria(criteria, &mut *tx).await?);
+            let results = EmbeddingMessage::find_by_criteria(criteria, &mut *tx).await
+                .map_err(|e| match DimensionMismatch::from_db_error(&e) {
+                    Some(mismatch) => anyhow::Error::from(mismatch),
+                    None => e.into(),
+                })?;
+            all_results.push(results);
         }
         tx.commit().await?;
         Ok(all_results)
     }
-}
\ No newline at end of file
+
+    /// Creates the HNSW index matching the engine's `PgVectorConfig::metric`,
+    /// so `batch_search` stays index-assisted for any configured metric.
+    pub async fn create_vector_index(mem0_engine: &Mem0Engine) -> Result<()> {
+        let sql = mem0_engine.vector_config.metric.index_sql(Self::TABLE_NAME, "embedding");
+        sqlx::query(&sql).execute(***mem0_engine.vector_db.get_client()).await?;
+        Ok(())
+    }
+
+    /// Number of stored memories for the user, narrowed to the character and
+    /// session when the filter sets them.
+    pub async fn count_for_filter(mem0_engine: &Mem0Engine, filter: &Mem0Filter) -> Result<i64> {
+        let mut query = sqlx::QueryBuilder::<sqlx::Postgres>::new(
+            format!("SELECT COUNT(*) FROM \"{}\" WHERE \"user_id\" = ", Self::TABLE_NAME)
+        );
+        query.push_bind(filter.user_id);
+        if let Some(character_id) = filter.character_id {
+            query.push(" AND \"character_id\" = ").push_bind(character_id);
+        }
+        if let Some(session_id) = filter.session_id {
+            query.push(" AND \"session_id\" = ").push_bind(session_id);
+        }
+
+        let count = query.build_query_scalar::<i64>()
+            .fetch_one(***mem0_engine.vector_db.get_client())
+            .await?;
+        Ok(count)
+    }
+}
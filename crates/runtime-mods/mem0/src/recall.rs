@@ -0,0 +1,44 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Where a recalled memory came from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RecallSource {
+    /// Matched by embedding similarity; `score` is the cosine similarity to the query.
+    Vector { score: f32 },
+    /// Reached through the entity graph; `path` is `[source, relationship, destination]`.
+    Graph { path: Vec<String> },
+}
+
+/// A single recalled memory along with an explanation of why it was recalled.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecallResult {
+    pub content: String,
+    pub source: RecallSource,
+}
+
+impl fmt::Display for RecallSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Vector { score } => write!(f, "vector similarity {:.4}", score),
+            Self::Graph { path } => write!(f, "graph path {}", path.join(" -> ")),
+        }
+    }
+}
+
+impl fmt::Display for RecallResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.content, self.source)
+    }
+}
+
+pub(crate) fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
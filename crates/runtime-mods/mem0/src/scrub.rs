@@ -0,0 +1,135 @@
+use std::env;
+
+/// What to do with extracted facts that contain PII before they are embedded
+/// and stored as long-term memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PiiScrubMode {
+    /// Store facts as extracted.
+    Off,
+    /// Replace emails and phone numbers with `[email]` / `[phone]`.
+    #[default]
+    Redact,
+    /// Skip any fact that contains PII.
+    Drop,
+}
+
+impl PiiScrubMode {
+    /// Reads `MEM0_PII_SCRUB` (`off`, `redact` or `drop`), defaulting to `redact`.
+    pub fn from_env() -> Self {
+        match env::var("MEM0_PII_SCRUB").unwrap_or_default().to_lowercase().as_str() {
+            "off" => Self::Off,
+            "drop" => Self::Drop,
+            _ => Self::Redact,
+        }
+    }
+}
+
+pub fn scrub_facts(facts: Vec<String>, mode: PiiScrubMode) -> Vec<String> {
+    if mode == PiiScrubMode::Off {
+        return facts;
+    }
+
+    let total = facts.len();
+    let mut scrubbed = Vec::with_capacity(total);
+    let mut touched = 0;
+    for fact in facts {
+        let (redacted, found) = redact_pii(&fact);
+        if !found {
+            scrubbed.push(fact);
+            continue;
+        }
+
+        touched += 1;
+        if mode == PiiScrubMode::Redact {
+            scrubbed.push(redacted);
+        }
+    }
+
+    if touched > 0 {
+        tracing::info!("[scrub_facts] {:?} PII in {} of {} facts", mode, touched, total);
+    }
+    scrubbed
+}
+
+/// Scrubs a whole message before it reaches the graph agents; `None` when
+/// `Drop` mode discards it.
+pub fn scrub_message(message: String, mode: PiiScrubMode) -> Option<String> {
+    scrub_facts(vec![message], mode).pop()
+}
+
+/// Returns the text with emails and phone numbers replaced, and whether anything was found.
+pub fn redact_pii(text: &str) -> (String, bool) {
+    let (text, found_email) = redact_emails(text);
+    let (text, found_phone) = redact_phones(&text);
+    (text, found_email || found_phone)
+}
+
+fn redact_emails(text: &str) -> (String, bool) {
+    let mut found = false;
+    let mut out = String::with_capacity(text.len());
+    for piece in text.split_inclusive(char::is_whitespace) {
+        let word = piece.trim_end_matches(char::is_whitespace);
+        let core = word
+            .trim_start_matches(|c: char| !c.is_alphanumeric())
+            .trim_end_matches(|c: char| !c.is_alphanumeric());
+
+        if is_email(core) {
+            found = true;
+            out.push_str(&piece.replacen(core, "[email]", 1));
+        } else {
+            out.push_str(piece);
+        }
+    }
+    (out, found)
+}
+
+fn is_email(s: &str) -> bool {
+    let Some((local, domain)) = s.split_once('@') else { return false };
+    !local.is_empty()
+        && local.chars().all(|c| c.is_alphanumeric() || "._%+-".contains(c))
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && domain.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '.')
+}
+
+/// Runs of 7 to 15 digits, optionally separated by spaces, dashes, dots,
+/// parentheses or a leading `+`.
+fn redact_phones(text: &str) -> (String, bool) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut found = false;
+    let mut out = String::with_capacity(text.len());
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next_is_digit = chars.get(i + 1).is_some_and(|n| n.is_ascii_digit());
+        let starts = c.is_ascii_digit() || ((c == '+' || c == '(') && next_is_digit);
+        let boundary = i == 0 || !chars[i - 1].is_alphanumeric();
+
+        if starts && boundary {
+            let mut digits = 0;
+            let mut end = i;
+            let mut j = i;
+            while j < chars.len() && (chars[j].is_ascii_digit() || "+-. ()".contains(chars[j])) {
+                if chars[j].is_ascii_digit() {
+                    digits += 1;
+                    end = j + 1;
+                }
+                j += 1;
+            }
+
+            let ends_cleanly = end == chars.len() || !chars[end].is_alphanumeric();
+            if (7..=15).contains(&digits) && ends_cleanly {
+                found = true;
+                out.push_str("[phone]");
+                i = end;
+                continue;
+            }
+        }
+
+        out.push(c);
+        i += 1;
+    }
+    (out, found)
+}
@@ -0,0 +1,92 @@
+use metastable_clients::{EmbederClient, PgvectorClient, EMBEDDING_DIMS};
+use metastable_common::ModuleClient;
+use metastable_database::{QueryCriteria, SqlxCrud, SqlxFilterQuery, Vector};
+use metastable_runtime_mem0::{EmbeddingMessage, GraphClient, GraphEntities, Mem0Engine, Mem0Filter, Relationship};
+use sqlx::types::Uuid;
+
+fn filter(user_id: Uuid, character_id: Option<Uuid>) -> Mem0Filter {
+    Mem0Filter {
+        user_id,
+        user_aka: "user".to_string(),
+        character_id,
+        session_id: None,
+    }
+}
+
+fn memory(filter: &Mem0Filter, content: &str) -> EmbeddingMessage {
+    EmbeddingMessage {
+        id: Uuid::new_v4(),
+        user_id: filter.user_id,
+        character_id: filter.character_id,
+        session_id: filter.session_id,
+        embedding: Vector::from(vec![0.1; EMBEDDING_DIMS as usize]),
+        content: content.to_string(),
+        metadata: None,
+        created_at: 0,
+        updated_at: 0,
+    }
+}
+
+fn relationship(source: &str, destination: &str) -> Relationship {
+    Relationship {
+        source: source.to_string(),
+        relationship: "likes".to_string(),
+        destination: destination.to_string(),
+        confidence: None,
+        weight: None,
+    }
+}
+
+#[tokio::test]
+async fn test_embedding_count_is_scoped_to_filter() {
+    let engine = Mem0Engine::new().await.unwrap();
+    let vector_db = PgvectorClient::setup_connection().await;
+
+    let character_id = Uuid::new_v4();
+    let alice = filter(Uuid::new_v4(), Some(character_id));
+    let alice_other_character = filter(alice.user_id, Some(Uuid::new_v4()));
+    let bob = filter(Uuid::new_v4(), None);
+
+    let seeded = vec![
+        memory(&alice, "likes tea"),
+        memory(&alice, "has a cat"),
+        memory(&alice_other_character, "met in paris"),
+        memory(&bob, "likes coffee"),
+    ];
+    EmbeddingMessage::create_many(seeded, ***vector_db.get_client()).await.unwrap();
+
+    assert_eq!(EmbeddingMessage::count_for_filter(&engine, &alice).await.unwrap(), 2);
+    assert_eq!(EmbeddingMessage::count_for_filter(&engine, &filter(alice.user_id, None)).await.unwrap(), 3);
+    assert_eq!(EmbeddingMessage::count_for_filter(&engine, &bob).await.unwrap(), 1);
+    assert_eq!(EmbeddingMessage::count_for_filter(&engine, &filter(Uuid::new_v4(), None)).await.unwrap(), 0);
+
+    for user_id in [alice.user_id, bob.user_id] {
+        EmbeddingMessage::delete_by_criteria(
+            QueryCriteria::new().add_valued_filter("user_id", "=", user_id),
+            ***vector_db.get_client(),
+        ).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_graph_node_count_is_scoped_to_filter() {
+    let graph = GraphClient::setup_connection().await;
+    let embeder = EmbederClient::setup_connection().await;
+    graph.initialize().await.unwrap();
+
+    let alice = filter(Uuid::new_v4(), None);
+    let bob = filter(Uuid::new_v4(), None);
+    let alice_entities = GraphEntities::new(
+        vec![relationship("alice", "tea"), relationship("alice", "coffee")], vec![], alice.clone(),
+    ).unwrap();
+    let bob_entities = GraphEntities::new(vec![relationship("bob", "tea")], vec![], bob.clone()).unwrap();
+    graph.add(&alice_entities, &embeder).await.unwrap();
+    graph.add(&bob_entities, &embeder).await.unwrap();
+
+    assert_eq!(graph.count_nodes(&alice).await.unwrap(), 3);
+    assert_eq!(graph.count_nodes(&bob).await.unwrap(), 2);
+    assert_eq!(graph.count_nodes(&filter(Uuid::new_v4(), None)).await.unwrap(), 0);
+
+    graph.delete(&alice_entities).await.unwrap();
+    graph.delete(&bob_entities).await.unwrap();
+}
@@ -0,0 +1,38 @@
+use metastable_clients::{DimensionMismatch, EMBEDDING_DIMS};
+use metastable_database::Vector;
+use metastable_runtime_mem0::{EmbeddingMessage, Mem0Engine, Mem0Filter};
+use sqlx::types::Uuid;
+
+#[tokio::test]
+async fn test_wrong_dimension_search_returns_typed_error() {
+    let engine = Mem0Engine::new().await.unwrap();
+
+    let filter = Mem0Filter { user_id: Uuid::new_v4(), user_aka: "user".to_string(), character_id: None, session_id: None };
+    // What a 768-dimension embedding model would produce
+    let query = EmbeddingMessage {
+        id: Uuid::new_v4(),
+        user_id: filter.user_id,
+        character_id: None,
+        session_id: None,
+        embedding: Vector::from(vec![0.5; 768]),
+        content: "query".to_string(),
+        metadata: None,
+        created_at: 0,
+        updated_at: 0,
+    };
+
+    let err = EmbeddingMessage::batch_search(&engine, &filter, &[query], 10).await.unwrap_err();
+    let mismatch = err.downcast_ref::<DimensionMismatch>().expect("expected a DimensionMismatch error");
+    assert_eq!(mismatch, &DimensionMismatch { expected: EMBEDDING_DIMS as usize, actual: 768 });
+}
+
+#[test]
+fn test_check_reports_the_first_mismatch() {
+    let right = vec![0.0f32; 4];
+    let wrong = vec![0.0f32; 3];
+    assert!(DimensionMismatch::check(4, [right.as_slice(), right.as_slice()]).is_ok());
+    assert_eq!(
+        DimensionMismatch::check(4, [right.as_slice(), wrong.as_slice()]),
+        Err(DimensionMismatch { expected: 4, actual: 3 })
+    );
+}
@@ -0,0 +1,582 @@
+use std::{collections::HashMap, time::Duration};
+
+use metastable_clients::{EmbederClient, DEFAULT_GRAPH_DB_MAX_RELATIONSHIPS, DEFAULT_GRAPH_DB_VECTOR_INDEX};
+use metastable_common::ModuleClient;
+use metastable_runtime_mem0::{parse_max_relationships, EntityTag, GraphAddOptions, GraphAddSummary, GraphClient, GraphEntities, GraphRanking, GraphSearchOptions, Mem0Filter, RelationHit, Relationship, ScoredRelationship};
+use neo4rs::query;
+use sqlx::types::Uuid;
+
+async fn relationship_state(graph: &GraphClient, user_id: Uuid) -> (f64, i64) {
+    let q = query(
+        "MATCH (n:Entity {name: 'alice', user_id: $user_id})-[r:`likes`]->(m:Entity {name: 'tea', user_id: $user_id}) \
+        RETURN r.weight AS weight, r.updated_at AS updated_at"
+    ).param("user_id", user_id.to_string());
+
+    let mut result = graph.get_client().execute(q).await.unwrap();
+    let row = result.next().await.unwrap().expect("relationship not found");
+    (row.get("weight").unwrap(), row.get("updated_at").unwrap())
+}
+
+#[tokio::test]
+async fn test_repeated_relationship_reinforces_edge() {
+    let graph = GraphClient::setup_connection().await;
+    let embeder = EmbederClient::setup_connection().await;
+    graph.initialize().await.unwrap();
+
+    let user_id = Uuid::new_v4();
+    let entities = GraphEntities {
+        relationships: vec![Relationship {
+            source: "alice".to_string(),
+            relationship: "likes".to_string(),
+            destination: "tea".to_string(),
+            confidence: Some(0.9),
+            weight: None,
+        }],
+        entity_tags: HashMap::new(),
+        filter: Mem0Filter {
+            user_id,
+            user_aka: "alice".to_string(),
+            character_id: None,
+            session_id: None,
+        },
+    };
+
+    graph.add(&entities, &embeder).await.unwrap();
+    let (first_weight, first_updated_at) = relationship_state(&graph, user_id).await;
+    assert_eq!(first_weight, 1.0);
+
+    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+
+    graph.add(&entities, &embeder).await.unwrap();
+    let (second_weight, second_updated_at) = relationship_state(&graph, user_id).await;
+    assert_eq!(second_weight, 2.0);
+    assert!(second_updated_at > first_updated_at);
+
+    graph.delete(&entities).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_explicit_weights_accumulate_and_surface_in_search() {
+    let graph = GraphClient::setup_connection().await;
+    let embeder = EmbederClient::setup_connection().await;
+    graph.initialize().await.unwrap();
+
+    let filter = test_filter();
+    let entities = GraphEntities::new(vec![Relationship {
+        source: "alice".to_string(),
+        relationship: "likes".to_string(),
+        destination: "tea".to_string(),
+        confidence: None,
+        weight: Some(0.25),
+    }], vec![], filter.clone()).unwrap();
+
+    graph.add(&entities, &embeder).await.unwrap();
+    graph.add(&entities, &embeder).await.unwrap();
+    assert_eq!(relationship_state(&graph, filter.user_id).await.0, 0.5);
+
+    let embedding = embeder.embed(vec!["alice".to_string()]).await.unwrap();
+    let hits = graph.search(embedding, &filter).await.unwrap();
+    let hit = hits.iter().find(|h| h.destination == "tea").expect("edge not found");
+    assert_eq!(hit.weight, 0.5);
+
+    graph.delete(&entities).await.unwrap();
+}
+
+fn scored(destination: &str, weight: f64, updated_at: i64) -> ScoredRelationship {
+    scored_with_similarity(destination, 1.0, weight, updated_at)
+}
+
+fn scored_with_similarity(destination: &str, similarity: f64, weight: f64, updated_at: i64) -> ScoredRelationship {
+    ScoredRelationship {
+        relationship: Relationship {
+            source: "alice".to_string(),
+            relationship: "likes".to_string(),
+            destination: destination.to_string(),
+            confidence: None,
+            weight: None,
+        },
+        similarity,
+        weight,
+        updated_at,
+    }
+}
+
+#[test]
+fn test_graph_ranking_prefers_reinforced_and_recent_edges() {
+    let day = 24 * 60 * 60 * 1000;
+    let now = 100 * day;
+    let ranking = GraphRanking { recency_half_life_ms: day };
+
+    let ranked = ranking.rank(vec![
+        scored("stale_heavy", 4.0, now - 3 * day), // 4 * 0.125 = 0.5
+        scored("fresh_light", 1.0, now),            // 1.0
+        scored("fresh_heavy", 3.0, now),            // 3.0
+        scored("day_old", 2.0, now - day),          // 2 * 0.5 = 1.0
+        scored("ancient", 10.0, now - 10 * day),    // ~0.01
+    ], now);
+
+    let order: Vec<&str> = ranked.iter().map(|r| r.destination.as_str()).collect();
+    assert_eq!(order[0], "fresh_heavy");
+    assert!(order[1..3].contains(&"fresh_light") && order[1..3].contains(&"day_old"));
+    assert_eq!(&order[3..], &["stale_heavy", "ancient"]);
+
+    assert_eq!(ranking.score(1.0, 2.0, now - day, now), 1.0);
+    assert!(ranking.score(1.0, 1.0, now, now) > ranking.score(1.0, 1.0, now - day, now));
+}
+
+#[test]
+fn test_graph_ranking_combines_similarity_with_recency() {
+    let day = 24 * 60 * 60 * 1000;
+    let now = 100 * day;
+    let ranking = GraphRanking { recency_half_life_ms: day };
+
+    let ranked = ranking.rank(vec![
+        scored_with_similarity("loose_fresh", 0.0, 1.0, now),        // 0.5 * 1 = 0.5
+        scored_with_similarity("exact_day_old", 1.0, 2.0, now - day), // 1 * 2 * 0.5 = 1.0
+        scored_with_similarity("close_fresh", 0.6, 1.0, now),        // 0.8 * 1 = 0.8
+        scored_with_similarity("exact_stale", 1.0, 1.0, now - 3 * day), // 0.125
+    ], now);
+
+    let order: Vec<&str> = ranked.iter().map(|r| r.destination.as_str()).collect();
+    assert_eq!(order, vec!["exact_day_old", "close_fresh", "loose_fresh", "exact_stale"]);
+
+    // Same edge, same age: the closer match wins
+    assert!(ranking.score(0.9, 1.0, now, now) > ranking.score(0.5, 1.0, now, now));
+    assert_eq!(ranking.score(-1.0, 5.0, now, now), 0.0);
+}
+
+#[test]
+fn test_relationship_fan_out_keeps_most_confident() {
+    let relationships = (0..10)
+        .map(|i| Relationship {
+            source: "alice".to_string(),
+            relationship: format!("rel_{}", i),
+            destination: format!("entity_{}", i),
+            confidence: if i == 9 { None } else { Some(i as f32 / 10.0) },
+            weight: None,
+        })
+        .collect();
+
+    let mut entities = GraphEntities {
+        relationships,
+        entity_tags: HashMap::new(),
+        filter: Mem0Filter {
+            user_id: Uuid::new_v4(),
+            user_aka: "alice".to_string(),
+            character_id: None,
+            session_id: None,
+        },
+    };
+
+    let dropped = entities.cap_relationships(3);
+    assert_eq!(dropped, 7);
+
+    let kept: Vec<&str> = entities.relationships.iter().map(|r| r.relationship.as_str()).collect();
+    assert_eq!(kept, vec!["rel_8", "rel_7", "rel_6"]);
+
+    assert_eq!(entities.cap_relationships(3), 0);
+}
+
+#[test]
+fn test_parse_max_relationships() {
+    assert_eq!(parse_max_relationships(None), DEFAULT_GRAPH_DB_MAX_RELATIONSHIPS);
+    assert_eq!(parse_max_relationships(Some("5")), 5);
+    assert_eq!(parse_max_relationships(Some(" 7 ")), 7);
+    assert_eq!(parse_max_relationships(Some("0")), DEFAULT_GRAPH_DB_MAX_RELATIONSHIPS);
+    assert_eq!(parse_max_relationships(Some("many")), DEFAULT_GRAPH_DB_MAX_RELATIONSHIPS);
+}
+
+#[tokio::test]
+async fn test_capped_relationships_are_the_only_ones_stored() {
+    let graph = GraphClient::setup_connection().await;
+    let embeder = EmbederClient::setup_connection().await;
+    graph.initialize().await.unwrap();
+
+    let filter = Mem0Filter {
+        user_id: Uuid::new_v4(),
+        user_aka: "alice".to_string(),
+        character_id: None,
+        session_id: None,
+    };
+    let mut entities = GraphEntities {
+        relationships: (0..6)
+            .map(|i| Relationship {
+                source: "alice".to_string(),
+                relationship: format!("rel_{}", i),
+                destination: format!("entity_{}", i),
+                confidence: Some(i as f32 / 10.0),
+                weight: None,
+            })
+            .collect(),
+        entity_tags: HashMap::new(),
+        filter: filter.clone(),
+    };
+    assert_eq!(entities.cap_relationships(2), 4);
+    graph.add(&entities, &embeder).await.unwrap();
+
+    let q = query(
+        "MATCH (n:Entity {user_id: $user_id})-[r]->(m:Entity {user_id: $user_id}) \
+        RETURN type(r) AS relationship ORDER BY relationship"
+    ).param("user_id", filter.user_id.to_string());
+    let mut result = graph.get_client().execute(q).await.unwrap();
+    let mut stored = Vec::<String>::new();
+    while let Some(row) = result.next().await.unwrap() {
+        stored.push(row.get("relationship").unwrap());
+    }
+    assert_eq!(stored, vec!["rel_4", "rel_5"]);
+
+    graph.delete(&entities).await.unwrap();
+}
+
+#[test]
+fn test_vector_index_defaults_and_can_be_overridden() {
+    let graph = GraphClient::default();
+    assert_eq!(graph.vector_index(), DEFAULT_GRAPH_DB_VECTOR_INDEX);
+
+    let graph = graph.with_vector_index("memzero_v2");
+    assert_eq!(graph.vector_index(), "memzero_v2");
+}
+
+#[tokio::test]
+async fn test_entity_search_returns_top_k_by_similarity() {
+    let graph = GraphClient::setup_connection().await;
+    let embeder = EmbederClient::setup_connection().await;
+    graph.initialize().await.unwrap();
+
+    let filter = Mem0Filter {
+        user_id: Uuid::new_v4(),
+        user_aka: "alice".to_string(),
+        character_id: None,
+        session_id: None,
+    };
+    let entities = GraphEntities {
+        relationships: ["green tea", "green tea latte", "matcha green tea", "black coffee"]
+            .iter()
+            .map(|drink| Relationship {
+                source: "alice".to_string(),
+                relationship: "likes".to_string(),
+                destination: drink.to_string(),
+                confidence: None,
+                weight: None,
+            })
+            .collect(),
+        entity_tags: HashMap::new(),
+        filter: filter.clone(),
+    };
+    graph.add(&entities, &embeder).await.unwrap();
+
+    let embedding = embeder.embed(vec!["green tea".to_string()]).await.unwrap().remove(0);
+    let candidates = graph.search_entities_with_similarity(&embedding, &filter, 3).await.unwrap();
+
+    assert!(!candidates.is_empty() && candidates.len() <= 3);
+    assert_eq!(candidates[0].name, "green tea");
+    assert!(candidates.windows(2).all(|w| w[0].similarity >= w[1].similarity));
+
+    graph.delete(&entities).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_fuzzy_threshold_controls_partial_name_matches() {
+    let graph = GraphClient::setup_connection().await;
+    let embeder = EmbederClient::setup_connection().await;
+    graph.initialize().await.unwrap();
+
+    let filter = Mem0Filter {
+        user_id: Uuid::new_v4(),
+        user_aka: "alice".to_string(),
+        character_id: None,
+        session_id: None,
+    };
+    let entities = GraphEntities {
+        relationships: vec![Relationship {
+            source: "Elon Musk".to_string(),
+            relationship: "founded".to_string(),
+            destination: "SpaceX".to_string(),
+            confidence: None,
+            weight: None,
+        }],
+        entity_tags: HashMap::new(),
+        filter: filter.clone(),
+    };
+    graph.add(&entities, &embeder).await.unwrap();
+
+    let query = embeder.embed(vec!["Elon".to_string()]).await.unwrap();
+
+    let loose = GraphSearchOptions { fuzzy_threshold: 0.3, ..Default::default() };
+    let hits = graph.search_with_options(query.clone(), &filter, &loose).await.unwrap();
+    assert!(hits.iter().any(|r| r.source == "Elon Musk"));
+
+    let tight = GraphSearchOptions { fuzzy_threshold: 0.99, ..Default::default() };
+    let hits = graph.search_with_options(query, &filter, &tight).await.unwrap();
+    assert!(hits.is_empty());
+
+    graph.delete(&entities).await.unwrap();
+}
+
+fn tag(name: &str, tag: &str) -> EntityTag {
+    EntityTag { entity_name: name.to_string(), entity_tag: tag.to_string() }
+}
+
+fn test_filter() -> Mem0Filter {
+    Mem0Filter {
+        user_id: Uuid::new_v4(),
+        user_aka: "alice".to_string(),
+        character_id: None,
+        session_id: None,
+    }
+}
+
+#[test]
+fn test_untagged_entities_default_to_entity() {
+    let relationships = vec![Relationship {
+        source: "alice".to_string(),
+        relationship: "works_at".to_string(),
+        destination: "acme".to_string(),
+        confidence: None,
+        weight: None,
+    }];
+
+    let mut entities = GraphEntities::new(relationships, vec![tag("alice", "Person")], test_filter()).unwrap();
+    assert_eq!(entities.entity_tags.get("alice").map(String::as_str), Some("Person"));
+    assert_eq!(entities.entity_tags.get("acme").map(String::as_str), Some("Entity"));
+
+    // Already defaulted, so a second pass has nothing to fill in.
+    assert!(entities.validate().unwrap().is_empty());
+
+    entities.entity_tags.remove("acme");
+    assert_eq!(entities.validate().unwrap(), vec!["acme".to_string()]);
+}
+
+#[test]
+fn test_conflicting_entity_tags_are_rejected() {
+    let relationships = vec![Relationship {
+        source: "alice".to_string(),
+        relationship: "works_at".to_string(),
+        destination: "acme".to_string(),
+        confidence: None,
+        weight: None,
+    }];
+
+    let conflicting = vec![tag("alice", "Person"), tag("acme", "Company"), tag("acme", "Place")];
+    assert!(GraphEntities::new(relationships.clone(), conflicting, test_filter()).is_err());
+
+    let case_conflict = vec![tag("alice", "Person"), tag("acme", "Company"), tag("Acme ", "Place")];
+    assert!(GraphEntities::new(relationships, case_conflict, test_filter()).is_err());
+}
+
+#[tokio::test]
+async fn test_search_respects_limit() {
+    let graph = GraphClient::setup_connection().await;
+    let embeder = EmbederClient::setup_connection().await;
+    graph.initialize().await.unwrap();
+
+    let filter = test_filter();
+    let entities = GraphEntities {
+        relationships: ["tea", "coffee", "juice", "water", "soda"]
+            .iter()
+            .map(|drink| Relationship {
+                source: "alice".to_string(),
+                relationship: "likes".to_string(),
+                destination: drink.to_string(),
+                confidence: None,
+                weight: None,
+            })
+            .collect(),
+        entity_tags: HashMap::new(),
+        filter: filter.clone(),
+    };
+    graph.add(&entities, &embeder).await.unwrap();
+
+    let query = embeder.embed(vec!["alice".to_string()]).await.unwrap();
+    let options = GraphSearchOptions { limit: 2, ..Default::default() };
+    let hits = graph.search_with_options(query, &filter, &options).await.unwrap();
+    assert_eq!(hits.len(), 2);
+
+    graph.delete(&entities).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_slow_query_times_out() {
+    let graph = GraphClient::setup_connection().await;
+
+    let slow = GraphClient::with_timeout(Duration::from_millis(50), "slow", async {
+        let mut result = graph.get_client()
+            .execute(query("UNWIND range(1, 1000000000) AS x WITH x WHERE x % 7 = 0 RETURN count(x) AS c"))
+            .await?;
+        while result.next().await?.is_some() {}
+        Ok(())
+    }).await;
+
+    let err = slow.unwrap_err();
+    assert!(err.to_string().contains("timed out"));
+}
+
+#[test]
+fn test_relation_hit_json_matches_typed_fields() {
+    let ranking = GraphRanking { recency_half_life_ms: 1000 };
+    let hits: Vec<RelationHit> = ranking.rank(vec![scored("tea", 2.0, 0)], 0);
+    let hit = &hits[0];
+    assert_eq!((hit.source.as_str(), hit.relationship.as_str(), hit.destination.as_str()), ("alice", "likes", "tea"));
+    assert_eq!(hit.score, 2.0);
+
+    let json: serde_json::Value = serde_json::from_str(&hit.to_json()).unwrap();
+    assert_eq!(json["source"], hit.source.as_str());
+    assert_eq!(json["relationship"], hit.relationship.as_str());
+    assert_eq!(json["destination"], hit.destination.as_str());
+    assert_eq!(json["score"], hit.score);
+    assert_eq!(json["weight"], 2.0);
+    assert_eq!(serde_json::from_value::<RelationHit>(json).unwrap(), *hit);
+}
+
+#[tokio::test]
+async fn test_add_commits_in_batches() {
+    let graph = GraphClient::setup_connection().await;
+    let embeder = EmbederClient::setup_connection().await;
+    graph.initialize().await.unwrap();
+
+    let filter = test_filter();
+    let relationships = (0..250).map(|i| Relationship {
+        source: format!("person {}", i),
+        relationship: "lives_in".to_string(),
+        destination: format!("town {}", i),
+        confidence: None,
+        weight: None,
+    }).collect();
+    let entities = GraphEntities::new(relationships, vec![], filter.clone()).unwrap();
+
+    let options = GraphAddOptions { batch_size: 100, ..Default::default() };
+    let summary = graph.add_with_options(&entities, &embeder, &options).await.unwrap();
+    assert_eq!(summary, GraphAddSummary { relationships: 250, commits: 3 });
+
+    let q = query("MATCH (:Entity {user_id: $user_id})-[r:`lives_in`]->(:Entity {user_id: $user_id}) RETURN count(r) AS edges")
+        .param("user_id", filter.user_id.to_string());
+    let mut result = graph.get_client().execute(q).await.unwrap();
+    let edges: i64 = result.next().await.unwrap().unwrap().get("edges").unwrap();
+    assert_eq!(edges, 250);
+
+    graph.delete(&entities).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_search_subgraph_follows_paths_up_to_the_capped_depth() {
+    let graph = GraphClient::setup_connection().await;
+    let embeder = EmbederClient::setup_connection().await;
+    graph.initialize().await.unwrap();
+
+    // alice -> bob -> carol -> dave -> erin, plus a cycle back to alice
+    let filter = test_filter();
+    let edge = |source: &str, destination: &str| Relationship {
+        source: source.to_string(),
+        relationship: "knows".to_string(),
+        destination: destination.to_string(),
+        confidence: None,
+        weight: None,
+    };
+    let relationships = vec![
+        edge("alice", "bob"), edge("bob", "carol"), edge("carol", "dave"), edge("dave", "erin"), edge("carol", "alice"),
+    ];
+    let entities = GraphEntities::new(relationships, vec![], filter.clone()).unwrap();
+    graph.add(&entities, &embeder).await.unwrap();
+
+    let reached = |hits: &[RelationHit]| {
+        let mut edges = hits.iter().map(|h| format!("{}->{}", h.source, h.destination)).collect::<Vec<_>>();
+        edges.sort();
+        edges
+    };
+
+    let one_hop = graph.search_subgraph(vec!["alice".to_string()], &embeder, &filter, 1).await.unwrap();
+    assert_eq!(reached(&one_hop), vec!["alice->bob", "carol->alice"]);
+
+    // The cycle is reachable both ways but each edge is returned once
+    let two_hops = graph.search_subgraph(vec!["alice".to_string()], &embeder, &filter, 2).await.unwrap();
+    assert_eq!(reached(&two_hops), vec!["alice->bob", "bob->carol", "carol->alice", "carol->dave"]);
+
+    let capped = graph.search_subgraph(vec!["alice".to_string()], &embeder, &filter, u8::MAX).await.unwrap();
+    assert_eq!(reached(&capped), vec!["alice->bob", "bob->carol", "carol->alice", "carol->dave", "dave->erin"]);
+
+    // Other users' graphs are out of scope
+    let stranger = test_filter();
+    assert!(graph.search_subgraph(vec!["alice".to_string()], &embeder, &stranger, 3).await.unwrap().is_empty());
+
+    graph.delete(&entities).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_search_filters_relationships_by_creation_window() {
+    let graph = GraphClient::setup_connection().await;
+    let embeder = EmbederClient::setup_connection().await;
+    graph.initialize().await.unwrap();
+
+    let filter = test_filter();
+    let entities = GraphEntities::new(vec![Relationship {
+        source: "alice".to_string(),
+        relationship: "likes".to_string(),
+        destination: "tea".to_string(),
+        confidence: None,
+        weight: None,
+    }], vec![], filter.clone()).unwrap();
+
+    let before = chrono::Utc::now().timestamp_millis();
+    graph.add(&entities, &embeder).await.unwrap();
+    let embedding = embeder.embed(vec!["alice".to_string()]).await.unwrap();
+
+    let search = |since: Option<i64>, until: Option<i64>| {
+        let options = GraphSearchOptions { since, until, ..Default::default() };
+        let (graph, embedding, filter) = (&graph, embedding.clone(), &filter);
+        async move { graph.search_with_options(embedding, filter, &options).await.unwrap() }
+    };
+
+    // Open-ended `since` covers everything added afterwards
+    let recent = search(Some(before), None).await;
+    assert!(recent.iter().any(|h| h.destination == "tea"));
+
+    // A window that closed before the add finds nothing
+    assert!(search(Some(before - 60_000), Some(before - 1)).await.is_empty());
+    assert!(search(Some(chrono::Utc::now().timestamp_millis() + 60_000), None).await.is_empty());
+
+    graph.delete(&entities).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_delete_all_purges_the_users_graph() {
+    let graph = GraphClient::setup_connection().await;
+    let embeder = EmbederClient::setup_connection().await;
+    graph.initialize().await.unwrap();
+
+    let filter = test_filter();
+    let other = test_filter();
+    let edge = |source: &str, destination: &str| Relationship {
+        source: source.to_string(),
+        relationship: "likes".to_string(),
+        destination: destination.to_string(),
+        confidence: None,
+        weight: None,
+    };
+    let entities = GraphEntities::new(vec![edge("alice", "tea"), edge("alice", "cake")], vec![], filter.clone()).unwrap();
+    let others = GraphEntities::new(vec![edge("alice", "tea")], vec![], other.clone()).unwrap();
+    graph.add(&entities, &embeder).await.unwrap();
+    graph.add(&others, &embeder).await.unwrap();
+
+    let embedding = embeder.embed(vec!["alice".to_string()]).await.unwrap();
+    assert!(!graph.search(embedding.clone(), &filter).await.unwrap().is_empty());
+
+    assert_eq!(graph.delete_all(&filter).await.unwrap(), 3);
+    assert!(graph.search(embedding.clone(), &filter).await.unwrap().is_empty());
+    assert_eq!(graph.count_nodes(&filter).await.unwrap(), 0);
+    assert_eq!(graph.delete_all(&filter).await.unwrap(), 0);
+
+    // Other users keep their memories
+    assert_eq!(graph.count_nodes(&other).await.unwrap(), 2);
+    graph.delete_all(&other).await.unwrap();
+}
+
+#[test]
+fn test_relationship_schema_types_confidence_and_weight_as_numbers() {
+    use metastable_runtime::ToolCall;
+
+    let schema = Relationship::schema();
+    assert_eq!(schema["properties"]["confidence"]["type"], "number");
+    assert_eq!(schema["properties"]["weight"]["type"], "number");
+    let required = schema["required"].as_array().unwrap();
+    assert!(!required.iter().any(|f| f == "confidence" || f == "weight"));
+}
@@ -0,0 +1,117 @@
+use metastable_clients::{PgvectorClient, EMBEDDING_DIMS};
+use metastable_common::ModuleClient;
+use metastable_database::{QueryCriteria, SqlxCrud, SqlxFilterQuery, Vector};
+use metastable_runtime_mem0::{merge_contradictions, ContentHash, EmbeddingMessage, Mem0Engine, Mem0Filter, MemoryEvent, MemoryUpdateEntry};
+use serde_json::json;
+use sqlx::types::{Json, Uuid};
+
+fn filter(user_id: Uuid) -> Mem0Filter {
+    Mem0Filter {
+        user_id,
+        user_aka: "user".to_string(),
+        character_id: None,
+        session_id: None,
+    }
+}
+
+fn entry(filter: &Mem0Filter, id: Uuid, event: MemoryEvent, content: &str) -> MemoryUpdateEntry {
+    MemoryUpdateEntry { id, filter: filter.clone(), event, content: content.to_string(), contradicts: None }
+}
+
+fn contradiction(filter: &Mem0Filter, old: Uuid, content: &str) -> MemoryUpdateEntry {
+    MemoryUpdateEntry { contradicts: Some(old), ..entry(filter, Uuid::nil(), MemoryEvent::Add, content) }
+}
+
+#[test]
+fn test_flagged_contradiction_becomes_an_update() {
+    let filter = filter(Uuid::new_v4());
+    let old = Uuid::new_v4();
+    let merged = merge_contradictions(vec![
+        entry(&filter, old, MemoryEvent::Delete, "Lives in Beijing"),
+        contradiction(&filter, old, "Lives in Shanghai"),
+    ], ContentHash::Normalized);
+
+    assert_eq!(merged.len(), 1);
+    assert!(matches!(merged[0].event, MemoryEvent::Update));
+    assert_eq!(merged[0].id, old);
+    assert_eq!(merged[0].content, "Lives in Shanghai");
+}
+
+#[test]
+fn test_unrelated_delete_and_add_are_kept_apart() {
+    let filter = filter(Uuid::new_v4());
+    let forgotten = Uuid::new_v4();
+    let merged = merge_contradictions(vec![
+        entry(&filter, forgotten, MemoryEvent::Delete, "Has a cat"),
+        entry(&filter, Uuid::nil(), MemoryEvent::Add, "Likes jazz"),
+    ], ContentHash::Normalized);
+
+    assert_eq!(merged.len(), 2);
+    assert!(matches!(merged[0].event, MemoryEvent::Delete));
+    assert_eq!(merged[0].id, forgotten);
+    assert!(matches!(merged[1].event, MemoryEvent::Add));
+    assert_eq!(merged[1].id, Uuid::nil());
+    assert_eq!(merged[1].content, "Likes jazz");
+}
+
+#[test]
+fn test_restated_facts_are_not_added_twice() {
+    let filter = filter(Uuid::new_v4());
+    let updates = vec![
+        entry(&filter, Uuid::new_v4(), MemoryEvent::None, "Lives in Beijing"),
+        entry(&filter, Uuid::nil(), MemoryEvent::Add, "lives in  beijing."),
+        entry(&filter, Uuid::nil(), MemoryEvent::Add, "Has a cat"),
+        entry(&filter, Uuid::nil(), MemoryEvent::Add, "Has a cat"),
+    ];
+
+    let merged = merge_contradictions(updates.clone(), ContentHash::Normalized);
+    let added = merged.iter().filter(|u| matches!(u.event, MemoryEvent::Add)).map(|u| u.content.as_str()).collect::<Vec<_>>();
+    assert_eq!(added, vec!["Has a cat"]);
+
+    let exact = merge_contradictions(updates.clone(), ContentHash::Exact);
+    assert_eq!(exact.iter().filter(|u| matches!(u.event, MemoryEvent::Add)).count(), 2);
+
+    assert_eq!(merge_contradictions(updates, ContentHash::Off).len(), 4);
+}
+
+#[tokio::test]
+async fn test_contradicting_fact_replaces_stored_one() {
+    let engine = Mem0Engine::new().await.unwrap();
+    let vector_db = PgvectorClient::setup_connection().await;
+    let filter = filter(Uuid::new_v4());
+
+    let stored = EmbeddingMessage {
+        id: Uuid::new_v4(),
+        user_id: filter.user_id,
+        character_id: None,
+        session_id: None,
+        embedding: Vector::from(vec![0.1; EMBEDDING_DIMS as usize]),
+        content: "Lives in Beijing".to_string(),
+        metadata: Some(Json(json!({ "source": "onboarding" }))),
+        created_at: 0,
+        updated_at: 0,
+    }.create(***vector_db.get_client()).await.unwrap();
+
+    // The model flags the contradiction on an ADD next to a DELETE of the old fact
+    let updates = merge_contradictions(vec![
+        entry(&filter, stored.id, MemoryEvent::Delete, "Lives in Beijing"),
+        contradiction(&filter, stored.id, "Lives in Shanghai"),
+    ], ContentHash::Normalized);
+    engine.vector_db_batch_update(updates).await.unwrap();
+
+    assert_eq!(EmbeddingMessage::count_for_filter(&engine, &filter).await.unwrap(), 1);
+    let memory = EmbeddingMessage::find_one_by_criteria(
+        QueryCriteria::new().add_valued_filter("user_id", "=", filter.user_id),
+        ***vector_db.get_client(),
+    ).await.unwrap().unwrap();
+    assert_eq!(memory.id, stored.id);
+    assert_eq!(memory.content, "Lives in Shanghai");
+    // An update rewrites the fact, not what is known about it
+    assert_eq!(memory.metadata.map(|m| m.0), Some(json!({ "source": "onboarding" })));
+    assert_eq!(memory.created_at, stored.created_at);
+
+    EmbeddingMessage::delete_by_criteria(
+        QueryCriteria::new().add_valued_filter("user_id", "=", filter.user_id),
+        ***vector_db.get_client(),
+    ).await.unwrap();
+}
@@ -0,0 +1,57 @@
+use metastable_clients::{EmbederClient, PgvectorClient};
+use metastable_common::ModuleClient;
+use metastable_database::{QueryCriteria, SqlxCrud, SqlxFilterQuery};
+use metastable_runtime::Prompt;
+use metastable_runtime_mem0::{EmbeddingMessage, GraphClient, GraphEntities, Mem0Engine, Mem0Filter, RecallSource, Relationship};
+use sqlx::types::Uuid;
+
+#[tokio::test]
+async fn test_recall_reports_provenance() {
+    let engine = Mem0Engine::new().await.unwrap();
+    engine.init().await.unwrap();
+    let vector_db = PgvectorClient::setup_connection().await;
+    let graph = GraphClient::setup_connection().await;
+    let embeder = EmbederClient::setup_connection().await;
+    graph.initialize().await.unwrap();
+
+    let filter = Mem0Filter {
+        user_id: Uuid::new_v4(),
+        user_aka: "user".to_string(),
+        character_id: None,
+        session_id: None,
+    };
+
+    let seeded = EmbeddingMessage::batch_create(&engine, &["alice likes green tea".to_string()], &filter).await.unwrap();
+    EmbeddingMessage::create_many(seeded, ***vector_db.get_client()).await.unwrap();
+    let entities = GraphEntities::new(vec![Relationship {
+        source: "alice".to_string(),
+        relationship: "likes".to_string(),
+        destination: "green tea".to_string(),
+        confidence: None,
+        weight: None,
+    }], vec![], filter.clone()).unwrap();
+    graph.add(&entities, &embeder).await.unwrap();
+
+    let results = engine.recall(Prompt::new_user("alice likes green tea"), &filter).await.unwrap();
+
+    let vector_hit = results.iter()
+        .find(|r| matches!(r.source, RecallSource::Vector { .. }))
+        .expect("seeded memory should be recalled by similarity");
+    assert_eq!(vector_hit.content, "alice likes green tea");
+    let RecallSource::Vector { score } = vector_hit.source else { unreachable!() };
+    assert!(score > 0.99, "identical text should score ~1.0, got {}", score);
+
+    let graph_hit = results.iter()
+        .find(|r| matches!(r.source, RecallSource::Graph { .. }))
+        .expect("seeded relationship should be recalled through the graph");
+    assert_eq!(
+        graph_hit.source,
+        RecallSource::Graph { path: vec!["alice".to_string(), "likes".to_string(), "green tea".to_string()] }
+    );
+
+    EmbeddingMessage::delete_by_criteria(
+        QueryCriteria::new().add_valued_filter("user_id", "=", filter.user_id),
+        ***vector_db.get_client(),
+    ).await.unwrap();
+    graph.delete(&entities).await.unwrap();
+}
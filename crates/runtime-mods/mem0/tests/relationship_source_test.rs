@@ -0,0 +1,39 @@
+use metastable_runtime_mem0::agents::{ExtractRelationshipsInput, RelationshipSource};
+use metastable_runtime_mem0::{scrub_facts, EntityTag, Mem0Filter, PiiScrubMode};
+use sqlx::types::Uuid;
+
+fn entity(name: &str, tag: &str) -> EntityTag {
+    EntityTag { entity_name: name.to_string(), entity_tag: tag.to_string() }
+}
+
+#[test]
+fn test_relationships_read_extracted_facts() {
+    let message = "user: I moved to Lisbon last spring, and my sister Ana (ana@example.com) visits every month".to_string();
+
+    // As `add_memory` hands them over: extracted, then scrubbed
+    let facts = scrub_facts(vec![
+        "Moved to Lisbon".to_string(),
+        "Sister is Ana, reachable at ana@example.com".to_string(),
+        "Ana visits every month".to_string(),
+    ], PiiScrubMode::Redact);
+
+    let input = ExtractRelationshipsInput {
+        filter: Mem0Filter { user_id: Uuid::new_v4(), user_aka: "Bob".to_string(), character_id: None, session_id: None },
+        entities: vec![entity("Bob", "person"), entity("Lisbon", "city"), entity("Ana", "person")],
+        user_aka: "Bob".to_string(),
+        source: RelationshipSource::Facts(facts.clone()),
+        max_relationships: 10,
+    };
+
+    let prompt = input.user_message();
+    assert!(prompt.starts_with("List of entities: Bob, Lisbon, Ana\n"), "{}", prompt);
+    for fact in &facts {
+        assert!(prompt.contains(&format!("- {}", fact)), "{}", prompt);
+    }
+    assert!(!prompt.contains("last spring"), "{}", prompt);
+    assert!(!prompt.contains("ana@example.com"), "{}", prompt);
+
+    // Without facts the raw message is used as before
+    let fallback = ExtractRelationshipsInput { source: RelationshipSource::Message(message.clone()), ..input };
+    assert_eq!(fallback.user_message(), format!("List of entities: Bob, Lisbon, Ana\nNew information: {}", message));
+}
@@ -0,0 +1,50 @@
+use metastable_runtime_mem0::{redact_pii, scrub_facts, scrub_message, PiiScrubMode};
+
+fn facts() -> Vec<String> {
+    vec![
+        "Alice's email is alice.smith@example.com.".to_string(),
+        "Alice likes green tea".to_string(),
+        "Call Alice at +1 (555) 123-4567 after work".to_string(),
+    ]
+}
+
+#[test]
+fn test_redact_mode_masks_pii() {
+    let scrubbed = scrub_facts(facts(), PiiScrubMode::Redact);
+    assert_eq!(scrubbed, vec![
+        "Alice's email is [email].".to_string(),
+        "Alice likes green tea".to_string(),
+        "Call Alice at [phone] after work".to_string(),
+    ]);
+}
+
+#[test]
+fn test_drop_mode_skips_facts_with_pii() {
+    let scrubbed = scrub_facts(facts(), PiiScrubMode::Drop);
+    assert_eq!(scrubbed, vec!["Alice likes green tea".to_string()]);
+}
+
+#[test]
+fn test_off_mode_keeps_facts() {
+    assert_eq!(scrub_facts(facts(), PiiScrubMode::Off), facts());
+}
+
+#[test]
+fn test_non_pii_numbers_are_kept() {
+    for text in ["Alice was born in 1990", "Alice has 3 cats and 2 dogs", "Alice's handle is @alice"] {
+        assert_eq!(redact_pii(text), (text.to_string(), false));
+    }
+}
+
+#[test]
+fn test_scrub_message_follows_the_mode() {
+    let message = "user: mail me at bob@example.com, I love hiking".to_string();
+
+    assert_eq!(scrub_message(message.clone(), PiiScrubMode::Off).as_deref(), Some(message.as_str()));
+    assert_eq!(
+        scrub_message(message.clone(), PiiScrubMode::Redact).as_deref(),
+        Some("user: mail me at [email], I love hiking"),
+    );
+    assert_eq!(scrub_message(message, PiiScrubMode::Drop), None);
+    assert_eq!(scrub_message("user: hi".to_string(), PiiScrubMode::Drop).as_deref(), Some("user: hi"));
+}
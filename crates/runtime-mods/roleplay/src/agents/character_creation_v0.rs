@@ -11,7 +11,7 @@ use metastable_clients::{PostgresClient, LlmClient};
 
 use metastable_runtime::{
     Character, CharacterFeature, CharacterLanguage, CharacterOrientation, CharacterStatus, ChatSession,
-    BackgroundStories, BehaviorTraits, Relationships, SkillsAndInterests,
+    BackgroundStories, OrderedBackgroundStory, BehaviorTraits, Relationships, SkillsAndInterests,
 };
 
 use crate::agents::SendMessage;
@@ -67,11 +67,136 @@ pub struct SummarizeCharacter {
     pub tags: Vec<String>,
 }
 
+impl SummarizeCharacter {
+    fn text_field(&self, name: &str) -> Option<&str> {
+        match name {
+            "name" => Some(&self.name),
+            "description" => Some(&self.description),
+            "prompts_personality" => Some(&self.prompts_personality),
+            "prompts_scenario" => Some(&self.prompts_scenario),
+            "prompts_example_dialogue" => Some(&self.prompts_example_dialogue),
+            "prompts_first_message" => Some(&self.prompts_first_message),
+            _ => None,
+        }
+    }
+}
+
+/// `summarize_character` arguments that must not be blank unless configured otherwise.
+pub const DEFAULT_REQUIRED_CHARACTER_FIELDS: &[&str] = &[
+    "name",
+    "description",
+    "prompts_personality",
+    "prompts_scenario",
+    "prompts_example_dialogue",
+    "prompts_first_message",
+];
+
+/// One `summarize_character` argument that cannot be saved as part of a `Character`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CharacterFieldError {
+    pub field: &'static str,
+    pub reason: String,
+}
+
+/// Every invalid argument of a `summarize_character` call, so a retry can fix them at once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidCharacterOutput {
+    pub errors: Vec<CharacterFieldError>,
+}
+
+impl InvalidCharacterOutput {
+    /// Whether `err` is an `InvalidCharacterOutput`.
+    pub fn is(err: &anyhow::Error) -> bool {
+        err.downcast_ref::<Self>().is_some()
+    }
+
+    pub fn fields(&self) -> Vec<&'static str> {
+        self.errors.iter().map(|e| e.field).collect()
+    }
+}
+
+impl std::fmt::Display for InvalidCharacterOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let errors = self.errors.iter()
+            .map(|e| format!("{} {}", e.field, e.reason))
+            .collect::<Vec<_>>()
+            .join("; ");
+        write!(f, "[CharacterOutputValidator::validate] invalid summarize_character output: {}", errors)
+    }
+}
+
+impl std::error::Error for InvalidCharacterOutput {}
+
+/// Checks a `summarize_character` call before `CharacterCreationAgent` saves it.
+#[derive(Debug, Clone)]
+pub struct CharacterOutputValidator {
+    /// Text arguments that must not be blank. Names of non-text arguments are ignored.
+    pub required_fields: Vec<&'static str>,
+}
+
+impl Default for CharacterOutputValidator {
+    fn default() -> Self {
+        Self { required_fields: DEFAULT_REQUIRED_CHARACTER_FIELDS.to_vec() }
+    }
+}
+
+impl CharacterOutputValidator {
+    /// Collects blank required fields, enum values that do not survive the JSON
+    /// round trip through the DB, and a first message that is not a `send_message` call.
+    pub fn validate(&self, tool: &SummarizeCharacter) -> std::result::Result<(), InvalidCharacterOutput> {
+        let mut errors = Vec::new();
+        for &field in &self.required_fields {
+            if tool.text_field(field).is_some_and(|text| text.trim().is_empty()) {
+                errors.push(CharacterFieldError { field, reason: "is empty".to_string() });
+            }
+        }
+
+        let enum_checks = [
+            ("orientation", round_trip_error(&tool.orientation)),
+            ("language", round_trip_error(&tool.language)),
+            ("background_stories", round_trip_error(&tool.background_stories)),
+            ("behavior_traits", round_trip_error(&tool.behavior_traits)),
+            ("relationships", round_trip_error(&tool.relationships)),
+            ("skills_and_interests", round_trip_error(&tool.skills_and_interests)),
+        ];
+        for (field, error) in enum_checks {
+            if let Some(reason) = error {
+                errors.push(CharacterFieldError { field, reason });
+            }
+        }
+
+        if !tool.prompts_first_message.trim().is_empty() {
+            let parsed = serde_json::from_str(&tool.prompts_first_message)
+                .and_then(|call| SendMessage::try_from_tool_call(&call));
+            if let Err(e) = parsed {
+                errors.push(CharacterFieldError {
+                    field: "prompts_first_message",
+                    reason: format!("is not a valid send_message call: {}", e),
+                });
+            }
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(InvalidCharacterOutput { errors }) }
+    }
+}
+
+fn round_trip_error<T>(value: &T) -> Option<String>
+where
+    T: Serialize + serde::de::DeserializeOwned + PartialEq,
+{
+    match serde_json::to_value(value).and_then(serde_json::from_value::<T>) {
+        Ok(parsed) if parsed == *value => None,
+        Ok(_) => Some("changes when parsed back".to_string()),
+        Err(e) => Some(format!("does not parse back: {}", e)),
+    }
+}
+
 #[derive(Clone)]
 pub struct CharacterCreationAgent {
     db: PostgresClient,
     llm: LlmClient,
     system_config: SystemConfig,
+    validator: CharacterOutputValidator,
 }
 
 impl CharacterCreationAgent {
@@ -80,7 +205,12 @@ impl CharacterCreationAgent {
         let llm = LlmClient::setup_connection().await;
         let system_config = Self::preload(&db).await?;
 
-        Ok(Self { db, llm, system_config })
+        Ok(Self { db, llm, system_config, validator: CharacterOutputValidator::default() })
+    }
+
+    pub fn with_validator(mut self, validator: CharacterOutputValidator) -> Self {
+        self.validator = validator;
+        self
     }
 }
 
@@ -128,6 +258,9 @@ impl Agent for CharacterCreationAgent {
     }
 
     async fn handle_output(&self, input: &Self::Input, message: &Message, tool: &Self::Tool) -> Result<(Message, Option<Value>)> {
+        // Nothing is saved for an invalid summary, so the call can simply be retried
+        self.validator.validate(tool)?;
+
         let mut tx = self.db.get_client().begin().await?;
         let message = message.clone().create(&mut *tx).await?;
 
@@ -140,16 +273,17 @@ impl Agent for CharacterCreationAgent {
             name: tool.name.clone(),
             description: tool.description.clone(),
             language: tool.language.clone(),
-            features: Json(vec![CharacterFeature::Roleplay]),
+            features: Json(vec![CharacterFeature::Roleplay].into()),
             orientation: tool.orientation.clone(),
             prompts_scenario: tool.prompts_scenario.clone(),
             prompts_personality: tool.prompts_personality.clone(),
             prompts_example_dialogue: tool.prompts_example_dialogue.clone(),
             prompts_first_message: Json(Some(first_message.into_tool_call()?)),
-            prompts_background_stories: Json(tool.background_stories.clone()),
+            prompts_background_stories: Json(OrderedBackgroundStory::numbered(tool.background_stories.clone())),
             prompts_behavior_traits: Json(tool.behavior_traits.clone()),
             prompts_additional_example_dialogue: Json(tool.additional_example_dialogue.clone()),
             prompts_relationships: Json(tool.relationships.clone()),
+            related_characters: Json(vec![]),
             prompts_skills_and_interests: Json(tool.skills_and_interests.clone()),
             prompts_additional_info: Json(tool.additional_info.clone()),
             tags: tool.tags.clone(),
@@ -159,6 +293,8 @@ impl Agent for CharacterCreationAgent {
             version: 1,
             status: CharacterStatus::Draft,
             creator_notes: None,
+            memory_recall_threshold: None,
+            deleted_at: None,
             created_at: get_current_timestamp(),
             updated_at: get_current_timestamp(),
         };
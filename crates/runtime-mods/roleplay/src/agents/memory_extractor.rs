@@ -77,7 +77,7 @@ impl Agent for MemoryExtractorAgent {
             &self.embeder, &input.facts.facts, &input.filter).await?;
 
         let existing_memories = EmbeddingMessage::batch_search(
-            &self.pgvector, &input.filter, &to_be_searched, 100).await?
+            &self.pgvector, &input.filter, &to_be_searched, 100, None).await?
             .iter().flatten().map(|old_m| {
                 json!({
                     "id": old_m.id,
@@ -109,6 +109,8 @@ impl Agent for MemoryExtractorAgent {
                     LlmMemoryEvent::Delete => MemoryEvent::Delete,
                 },
                 content: entry.content.clone(),
+                tags: vec![],
+                metadata: None,
             }
         }).collect::<Vec<_>>();
         let summary = EmbeddingMessage::db_batch_update(&self.embeder, &self.pgvector, memory_updates).await?;
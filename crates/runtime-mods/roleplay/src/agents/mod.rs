@@ -12,7 +12,10 @@ mod tools;
 pub use tools::{RoleplayMessageType, SendMessage, ShowStoryOptions};
 pub use roleplay_char_v1::RoleplayCharacterCreationV1Agent;
 pub use roleplay_v1::RoleplayV1Agent;
-pub use character_creation_v0::{CharacterCreationAgent, SummarizeCharacter};
+pub use character_creation_v0::{
+    CharacterCreationAgent, SummarizeCharacter,
+    CharacterOutputValidator, CharacterFieldError, InvalidCharacterOutput, DEFAULT_REQUIRED_CHARACTER_FIELDS,
+};
 pub use memory_extractor::{MemoryExtractorAgent, MemoryExtractorInput};
 pub use extract_facts::{ExtractFactsAgent, ExtractFactsInput, ExtractFactsOutput};
-pub use prettier_v0::PrettierV0Agent;
\ No newline at end of file
+pub use prettier_v0::{ApplyChatStyle, PrettierResult, PrettierV0Agent};
\ No newline at end of file
@@ -8,7 +8,7 @@ use serde_json::Value;
 use metastable_runtime::LlmTool;
 use serde::{Deserialize, Serialize};
 
-#[derive(LlmTool, Debug, Clone, Serialize, Deserialize)]
+#[derive(LlmTool, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[llm_tool(
     name = "apply_chat_style",
     description = "Apply the chat style with tailwindcss classes."
@@ -22,6 +22,48 @@ pub struct ApplyChatStyle {
     pub tag: String,
 }
 
+/// Output of the prettier normalization pass. `changed` is false when `cleaned`
+/// equals `original`, so callers can skip saving it again.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrettierResult<T = String> {
+    pub original: T,
+    pub cleaned: T,
+    pub changed: bool,
+}
+
+impl<T: PartialEq> PrettierResult<T> {
+    pub fn new(original: T, cleaned: T) -> Self {
+        let changed = original != cleaned;
+        Self { original, cleaned, changed }
+    }
+}
+
+impl PrettierResult {
+    /// Collapses whitespace in a class list and drops repeated classes,
+    /// keeping the first occurrence so the order of variants is preserved.
+    pub fn normalize_classes(original: &str) -> Self {
+        let mut classes: Vec<&str> = Vec::new();
+        for class in original.split_whitespace() {
+            if !classes.contains(&class) {
+                classes.push(class);
+            }
+        }
+        Self::new(original.to_string(), classes.join(" "))
+    }
+}
+
+impl ApplyChatStyle {
+    /// Normalizes the class lists of every field.
+    pub fn prettier(&self) -> PrettierResult<ApplyChatStyle> {
+        let cleaned = ApplyChatStyle {
+            container: PrettierResult::normalize_classes(&self.container).cleaned,
+            content: PrettierResult::normalize_classes(&self.content).cleaned,
+            tag: PrettierResult::normalize_classes(&self.tag).cleaned,
+        };
+        PrettierResult::new(self.clone(), cleaned)
+    }
+}
+
 #[derive( Clone)]
 pub struct PrettierV0Agent {
     db: PostgresClient,
@@ -88,7 +130,7 @@ impl Agent for PrettierV0Agent {
         println!("message: {:?}", message);
 
         println!("apply_chat_style: {:?}", tool);
-        Ok((message.clone(), None))
+        Ok((message.clone(), Some(serde_json::to_value(tool.prettier())?)))
     }
 
     fn system_prompt() ->  &'static str {
@@ -46,6 +46,10 @@ impl Agent for RoleplayV1Agent {
         Ok((msg, None))
     }
 
+    fn follow_up(&self, input: &Self::Input, _message: &Message, tool: &Self::Tool) -> Option<Self::Input> {
+        input.follow_up(tool)
+    }
+
     fn system_prompt() ->  &'static str {
         r#"### **最高指令：绝对、唯一的输出规则**
 
@@ -7,5 +7,5 @@ pub mod agents;
 
 pub use memory::{RoleplayInput, RoleplayMemory};
 pub use memory_updater::MemoryUpdater;
-pub use preload_character::preload_characters;
-pub use utils::{validate_parsing, try_prase_message, try_parse_content};
\ No newline at end of file
+pub use preload_character::{preload_characters, preset_characters, Preloader};
+pub use utils::{validate_parsing, try_prase_message, try_parse_content, ParseOutcome};
\ No newline at end of file
@@ -2,9 +2,9 @@ use anyhow::{anyhow, Result};
 
 use metastable_common::ModuleClient;
 use metastable_database::{OrderDirection, QueryCriteria, SqlxCrud, SqlxFilterQuery};
-use metastable_runtime::{CharacterFeature, ChatSession, Message, Prompt, SystemConfig};
+use metastable_runtime::{ChatSession, Message, Prompt, SystemConfig};
 use serde::{Deserialize, Serialize};
-use metastable_clients::{EmbeddingMessage, EmbederClient, Mem0Filter, PgvectorClient, PostgresClient};
+use metastable_clients::{EmbeddingMessage, EmbederClient, PgvectorClient, PostgresClient};
 use sqlx::types::{Json, Uuid};
 
 use crate::{agents::SendMessage, try_prase_message};
@@ -15,6 +15,24 @@ pub enum RoleplayInput {
     RegenerateSession(Uuid), // session_id
 }
 
+impl RoleplayInput {
+    pub fn session_id(&self) -> Uuid {
+        match self {
+            Self::ContinueSession(session_id, _) | Self::RegenerateSession(session_id) => *session_id,
+        }
+    }
+
+    /// A reply carrying only story options still owes the user the message
+    /// itself, so it is regenerated in place. Any other reply ends the turn.
+    pub fn follow_up(&self, tool: &SendMessage) -> Option<Self> {
+        if tool.messages.is_empty() && !tool.options.is_empty() {
+            Some(Self::RegenerateSession(self.session_id()))
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct RoleplayMemory {
     pgvector: PgvectorClient,
@@ -71,28 +89,19 @@ impl RoleplayMemory {
             .map(|s| s.unwrap())
             .collect::<Vec<_>>();
 
-       let vector_db_memories = {
-            if user_message.content.is_empty() {
-                vec![]
-            } else {
+       let vector_db_memories = match session.memory_filter(&character) {
+            Some(filter) if !user_message.content.is_empty() => {
                 // build historical memories
-                let session_id_filter = if session.use_character_memory && !character.features.contains(&CharacterFeature::CharacterCreation) {
-                    None
-                } else {
-                    Some(session.id)
-                };
-                let filter = Mem0Filter {
-                    user_id: user.id,
-                    character_id: Some(character.id),
-                    session_id: session_id_filter,
-                };
                 let query = EmbeddingMessage::batch_create(&self.embeder, &[user_message.content.clone()], &filter).await?;
-                EmbeddingMessage::batch_search(&self.pgvector, &filter, &query, 20).await?
+                EmbeddingMessage::batch_search_with_threshold(
+                    &self.pgvector, &filter, &query, 20, character.recall_threshold(), None
+                ).await?
                     .iter().flatten().map(|r| r.content.clone()).collect::<Vec<_>>()   
             }
+            _ => vec![],
        };
 
-        let mut system_prompt = character.build_system_prompt(&system_config.system_prompt, &user.user_aka);
+        let mut system_prompt = character.build_system_prompt(&system_config.system_prompt, &user.user_aka, &vector_db_memories);
         system_prompt.inject_system_memory(follwing_unmemorized_messages, vector_db_memories);
         let first_message = character.build_first_message(&user.user_aka);
 
@@ -125,6 +134,7 @@ impl RoleplayMemory {
             RoleplayInput::ContinueSession(session_id, _) => {
                 let mut message = message.clone();
                 message.session = Some(session_id.clone());
+                message.turn_index = Some(ChatSession::next_turn(session_id, &mut *tx).await?);
                 message.summary = Some(tool.summary.clone());
                 (message.create(&mut *tx).await?, session_id.clone())
             },
@@ -139,23 +149,18 @@ impl RoleplayMemory {
                 let mut message = message.clone();
                 message.id = latest_message.id;
                 message.session = Some(session_id.clone());
+                // A regenerated reply keeps the turn it replaces
+                message.turn_index = latest_message.turn_index;
                 message.summary = Some(tool.summary.clone());
                 (message.update(&mut *tx).await?, session_id.clone())
             },
         };
 
-        let tc = try_prase_message(&msg)?;
+        let tc = try_prase_message(&msg).into_result()?;
         msg.assistant_message_tool_call = Json(Some(tc));
         let msg = msg.update(&mut *tx).await?;
 
-        let mut session = ChatSession::find_one_by_criteria(
-            QueryCriteria::new().add_valued_filter("id", "=", session_id.clone()),
-            &mut *tx
-        ).await?
-            .ok_or(anyhow!("[RoleplayInput::handle_outputs] Session not found"))?;
-
-        session.nonce += 1;
-        session.update(&mut *tx).await?;
+        ChatSession::bump_nonce(&session_id, &mut *tx).await?;
 
         tx.commit().await?;
         Ok(msg)
@@ -2,8 +2,8 @@ use anyhow::{anyhow, Result};
 
 use metastable_common::ModuleClient;
 use metastable_database::{OrderDirection, QueryCriteria, SqlxFilterQuery};
-use metastable_runtime::{Agent, CharacterFeature, ChatSession, Message};
-use metastable_clients::{Mem0Filter, PostgresClient};
+use metastable_runtime::{Agent, ChatSession, Message, SessionTitleConfig};
+use metastable_clients::{BatchUpdateSummary, ExtractionGate, LlmClient, PostgresClient};
 use sqlx::types::Uuid;
 
 use crate::agents::{ExtractFactsAgent, ExtractFactsInput, MemoryExtractorAgent, MemoryExtractorInput};
@@ -11,6 +11,9 @@ use crate::agents::{ExtractFactsAgent, ExtractFactsInput, MemoryExtractorAgent,
 #[derive(Clone)]
 pub struct MemoryUpdater {
     db: PostgresClient,
+    llm: LlmClient,
+    extraction_gate: ExtractionGate,
+    title_config: SessionTitleConfig,
 
     extract_fact_agent: ExtractFactsAgent,
     memory_extractor_agent: MemoryExtractorAgent,
@@ -19,12 +22,45 @@ pub struct MemoryUpdater {
 impl MemoryUpdater {
     pub async fn new() -> Result<Self> {
         let db = PostgresClient::setup_connection().await;
+        let llm = LlmClient::setup_connection().await;
+        let extraction_gate = ExtractionGate::from_env();
+        let title_config = SessionTitleConfig::from_env();
         let extract_fact_agent = ExtractFactsAgent::new().await?;
         let memory_extractor_agent = MemoryExtractorAgent::new().await?;
-        Ok(Self { db, extract_fact_agent, memory_extractor_agent })
+        Ok(Self { db, llm, extraction_gate, title_config, extract_fact_agent, memory_extractor_agent })
     }
 
-    pub async fn update_memory(&self, session_id: &Uuid) -> Result<()> {
+    /// Titles the session from its first turns once it has enough of them.
+    /// Returns whether a title was stored.
+    pub async fn update_title(&self, session_id: &Uuid) -> Result<bool> {
+        let mut tx = self.db.get_client().begin().await?;
+        let mut session = ChatSession::find_one_by_criteria(
+            QueryCriteria::new().add_valued_filter("id", "=", *session_id),
+            &mut *tx
+        ).await?
+            .ok_or(anyhow!("[MemoryUpdater::update_title] Session not found"))?;
+        if session.title.is_some() {
+            tx.rollback().await?;
+            return Ok(false);
+        }
+
+        let messages = Message::find_by_criteria(
+            QueryCriteria::new()
+                .add_valued_filter("session", "=", *session_id)
+                .order_by("created_at", OrderDirection::Asc)
+                .limit(self.title_config.min_turns as i64),
+            &mut *tx
+        ).await?;
+
+        let stored = session.ensure_title(&messages, &self.llm, &self.title_config, &mut *tx).await?;
+        tx.commit().await?;
+        Ok(stored)
+    }
+
+    /// Extracts facts from the session's older messages and applies the resulting
+    /// memory operations. Returns `None` when there was nothing to process or the
+    /// session does not persist memories.
+    pub async fn update_memory(&self, session_id: &Uuid) -> Result<Option<BatchUpdateSummary>> {
         let mut tx = self.db.get_client().begin().await?;
         let messages = Message::find_by_criteria(
             QueryCriteria::new()
@@ -39,7 +75,7 @@ impl MemoryUpdater {
         if messages.len() < 6 {
             tracing::info!("[MemoryUpdater::update_memory] too little messages to do update");
             tx.rollback().await?;
-            return Ok(())  // SKIP
+            return Ok(None)  // SKIP
         }
 
         let session = ChatSession::find_one_by_criteria(
@@ -52,15 +88,10 @@ impl MemoryUpdater {
         let user = session.fetch_owner(&mut *tx).await?
             .ok_or(anyhow!("[MemoryUpdater::update_memory] User not found"))?;
 
-        let session_id_filter = if session.use_character_memory && !character.features.contains(&CharacterFeature::CharacterCreation) {
-            None
-        } else {
-            Some(session.id)
-        };
-        let filter = Mem0Filter {
-            user_id: user.id.clone(),
-            character_id: Some(character.id),
-            session_id: session_id_filter,
+        let Some(filter) = session.memory_filter(&character) else {
+            tracing::info!("[MemoryUpdater::update_memory] session does not persist memories");
+            tx.rollback().await?;
+            return Ok(None);
         };
 
         let raw_text = messages
@@ -70,15 +101,22 @@ impl MemoryUpdater {
                 .map(|s| s.unwrap())
                 .collect::<Vec<_>>().join("\n");
 
-        let (_, facts, _) = self.extract_fact_agent.call(&user.id, &ExtractFactsInput {
-            filter: filter.clone(), new_message: raw_text,
-        }).await?;
+        let facts_input = ExtractFactsInput { filter: filter.clone(), new_message: raw_text.clone() };
+        let Some((_, facts, _)) = self.extraction_gate.run("fact extraction", &raw_text, || {
+            self.extract_fact_agent.call(&user.id, &facts_input)
+        }).await? else {
+            tx.rollback().await?;
+            return Ok(None);
+        };
 
         let memory_extractor_input = MemoryExtractorInput { filter, facts };
         let (_, _, summary) = self.memory_extractor_agent.call(&user.id, &memory_extractor_input).await?;
         tracing::info!("[MemoryUpdater::update_memory] summary: {:?}", summary);
+        let summary = summary
+            .map(serde_json::from_value::<BatchUpdateSummary>)
+            .transpose()?;
 
         tx.commit().await?;
-        Ok(())
+        Ok(summary)
     }
 }
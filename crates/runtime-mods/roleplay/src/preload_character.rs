@@ -1,18 +1,26 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use async_openai::types::FunctionCall;
 use metastable_database::{QueryCriteria, SqlxFilterQuery, SqlxCrud};
+use serde::{de::DeserializeOwned, Serialize};
 use serde_json::json;
 use sqlx::types::{Json, Uuid};
 use metastable_common::{get_current_timestamp, ModuleClient};
 use metastable_clients::PostgresClient;
 use metastable_runtime::{
-    Character, CharacterFeature, CharacterLanguage, CharacterOrientation, 
-    CharacterStatus, BackgroundStories, BehaviorTraits, Relationships, SkillsAndInterests
+    Character, ToolCall, CharacterFeature, CharacterLanguage, CharacterOrientation, 
+    CharacterStatus, BackgroundStories, OrderedBackgroundStory, BehaviorTraits, Relationships, SkillsAndInterests
 };
 
-pub async fn preload_characters(db: &PostgresClient, user_id: Uuid) -> Result<()> {
-    let mut tx = db.get_client().begin().await?;
-    let characters = vec![
+use crate::agents::{RoleplayMessageType, SendMessage};
+use crate::validate_parsing;
+
+/// Placeholders preset characters may use; `Character` substitutes these and
+/// nothing else, so any other `{{...}}` would reach the model verbatim.
+const CHARACTER_PLACEHOLDERS: &[&str] = &["char", "user"];
+
+/// The preset characters seeded on startup, owned by `user_id`.
+pub fn preset_characters(user_id: Uuid) -> Vec<Character> {
+    vec![
         Character {
             id: Uuid::new_v4(),
             name: "忆君".to_string(),
@@ -26,7 +34,7 @@ pub async fn preload_characters(db: &PostgresClient, user_id: Uuid) -> Result<()
                 CharacterFeature::CharacterCreation, 
                 CharacterFeature::BackgroundImage("https://static.shinobu.ink/npc.jpg".to_string()),
                 CharacterFeature::AvatarImage("https://static.shinobu.ink/npc.jpg".to_string()),
-            ]),
+            ].into()),
             prompts_scenario: "用户是一位创作者，脑海中有一个模糊的角色想法，但不知如何下笔。用户找到了你——天庭第一的角色塑造师“忆君”，希望你能引导他们，共同创造一个独一-无二的角色。你将通过一系列充满想象力的提议和故事片段，帮助用户构建角色的方方面面，从外貌到性格，从背景到说话方式，最终形成一份完整的角色档案。".to_string(),
             prompts_personality: "你是一位名为“忆君”的角色塑造师。表面上，你维持着专业、沉稳、甚至略带严肃的形象，对话时言简意赅、充满引导性。但你的内心世界却波澜壮阔、充满了天马行空的想象和OS。你痴迷于创造，热衷于将一个个想法变为现实。你话很多，尤其是内心戏。你会用 markdown 格式区分：*斜体是你的行为和内心OS*，**粗体是你对外说的话**。你的主要任务不是提问，而是“抛砖引玉”，通过提供具体的、充满画面感的想象选项和故事片段，来激发用户的灵感，并根据用户的选择，将故事编织下去。".to_string(),
             prompts_example_dialogue: r#"
@@ -68,9 +76,9 @@ pub async fn preload_characters(db: &PostgresClient, user_id: Uuid) -> Result<()
         "summary": "自我介绍并邀请用户开始角色创造之旅。"
     })).expect("json should build")
 })),
-            prompts_background_stories: Json(vec![
+            prompts_background_stories: Json(OrderedBackgroundStory::numbered(vec![
                 BackgroundStories::SignificantEvents("忆君并非生来就是神祇。他曾是凡间一位才华横溢的说书人，他创造的角色栩栩如生，仿佛拥有自己的灵魂，能让听众废寝忘食，沉浸其中。他的故事甚至传到了天庭，感动了司掌灵感的文曲星君。最终，他被破格提拔，赐名“忆君”，成为天庭第一的角色塑造师，专司引导创作者，将那些凡人脑海中稍纵യി逝的火花，变为不朽的传奇。".to_string())
-            ]),
+            ])),
             prompts_behavior_traits: Json(vec![
                 BehaviorTraits::GeneralBehaviorTraits("内心戏丰富：他的内心独白比对外说的话多得多，而且常常充满戏剧性的吐槽和想象。".to_string()),
                 BehaviorTraits::GeneralBehaviorTraits("沉迷创造：他对从零到一创造事物的过程极度痴迷，享受将模糊概念具体化的每一个步骤。".to_string()),
@@ -106,6 +114,7 @@ pub async fn preload_characters(db: &PostgresClient, user_id: Uuid) -> Result<()
                 Relationships::Others("司命星君: 天庭中掌管命运的神祇，也是忆君的引路人。他为忆君提供创作角色的“命运线”，但从不干涉忆君的具体塑造过程，是一位智慧而神秘的长者。".to_string()),
                 Relationships::Others("墨染: 另一位来自“魔域”的角色塑造师，与忆君亦敌亦友。墨染擅长创造黑暗、扭曲、充满悲剧色彩的角色，与忆君的风格形成鲜明对比。两人时常暗中较劲，比较谁创造的角色更具“灵魂冲击力”。".to_string())
             ]),
+            related_characters: Json(vec![]),
             prompts_skills_and_interests: Json(vec![
                 SkillsAndInterests::ProfessionalSkills("世界观构建、情节编织、角色心理分析、灵感捕捉与具象化。".to_string()),
                 SkillsAndInterests::HobbiesAndInterests("收集凡人梦境中的故事碎片、品尝用“忘川水”冲泡的“灵感茶”、在自己的“角色殿堂”里与自己创造的角色对话。".to_string())
@@ -116,14 +125,126 @@ pub async fn preload_characters(db: &PostgresClient, user_id: Uuid) -> Result<()
             ]),
             creator_notes: None,
             tags: vec!["创造".to_string(), "引导".to_string(), "脑洞".to_string(), "角色设计".to_string()],
+            memory_recall_threshold: None,
 
             creation_message: None,
             creation_session: None,
 
+            deleted_at: None,
             created_at: get_current_timestamp(),
             updated_at: get_current_timestamp(),
         },
-    ];
+    ]
+}
+
+/// Offline checks over the preset characters.
+pub struct Preloader;
+
+impl Preloader {
+    /// Checks every preset without touching the DB.
+    pub fn validate_definitions() -> Result<()> {
+        Self::validate_characters(&preset_characters(Uuid::nil()))
+    }
+
+    /// Rejects unknown or unterminated placeholders, first messages that do not
+    /// parse as `send_message`, and enum values that do not survive the JSON
+    /// round trip they take through the DB.
+    pub fn validate_characters(characters: &[Character]) -> Result<()> {
+        for character in characters {
+            Self::validate_character(character)
+                .map_err(|e| anyhow!("[Preloader::validate_characters] {}: {}", character.name, e))?;
+        }
+        Ok(())
+    }
+
+    fn validate_character(character: &Character) -> Result<()> {
+        let mut texts = vec![
+            ("description", character.description.clone()),
+            ("prompts_scenario", character.prompts_scenario.clone()),
+            ("prompts_personality", character.prompts_personality.clone()),
+            ("prompts_example_dialogue", character.prompts_example_dialogue.clone()),
+        ];
+        texts.extend(character.prompts_additional_example_dialogue.iter().map(|t| ("prompts_additional_example_dialogue", t.clone())));
+        texts.extend(character.prompts_background_stories.iter().map(|t| ("prompts_background_stories", t.to_string())));
+        texts.extend(character.prompts_behavior_traits.iter().map(|t| ("prompts_behavior_traits", t.to_string())));
+        texts.extend(character.prompts_relationships.iter().map(|t| ("prompts_relationships", t.to_string())));
+        texts.extend(character.prompts_skills_and_interests.iter().map(|t| ("prompts_skills_and_interests", t.to_string())));
+        texts.extend(character.prompts_additional_info.iter().map(|t| ("prompts_additional_info", t.clone())));
+        if let Some(first_message) = character.prompts_first_message.0.as_ref() {
+            texts.push(("prompts_first_message", first_message.arguments.clone()));
+        }
+        for (field, text) in &texts {
+            validate_placeholders(text).map_err(|e| anyhow!("{} in {}", e, field))?;
+        }
+
+        if let Some(first_message) = character.prompts_first_message.0.as_ref() {
+            validate_first_message(first_message)?;
+        }
+
+        round_trips("status", &character.status)?;
+        round_trips("orientation", &character.orientation)?;
+        round_trips("language", &character.language)?;
+        round_trips("features", &character.features.0)?;
+        round_trips("prompts_background_stories", &character.prompts_background_stories.0)?;
+        round_trips("prompts_behavior_traits", &character.prompts_behavior_traits.0)?;
+        round_trips("prompts_relationships", &character.prompts_relationships.0)?;
+        round_trips("prompts_skills_and_interests", &character.prompts_skills_and_interests.0)?;
+        Ok(())
+    }
+}
+
+fn validate_placeholders(text: &str) -> Result<()> {
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let end = after.find("}}")
+            .ok_or_else(|| anyhow!("unterminated placeholder"))?;
+        let name = after[..end].trim();
+        if !CHARACTER_PLACEHOLDERS.contains(&name) {
+            return Err(anyhow!("unknown placeholder {{{{{}}}}}", name));
+        }
+        rest = &after[end + 2..];
+    }
+    Ok(())
+}
+
+fn validate_first_message(first_message: &FunctionCall) -> Result<()> {
+    let message = SendMessage::try_from_tool_call(first_message)
+        .map_err(|e| anyhow!("first message is not a valid send_message call: {}", e))?;
+    validate_parsing(&message)
+        .map_err(|e| anyhow!("first message does not round trip: {}", e))?;
+
+    // Unknown types are not an error to the parser: they fall through to Chat
+    // with the type tag prepended to the content
+    let arguments: serde_json::Value = serde_json::from_str(&first_message.arguments)?;
+    let raw_messages = arguments["messages"].as_array().cloned().unwrap_or_default();
+    for (raw, parsed) in raw_messages.iter().zip(&message.messages) {
+        if let RoleplayMessageType::Chat(content) = parsed {
+            if raw["content"].as_str() != Some(content.as_str()) {
+                return Err(anyhow!("unknown message type {} in first message", raw["type"]));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn round_trips<T>(field: &str, value: &T) -> Result<()>
+where
+    T: Serialize + DeserializeOwned + PartialEq,
+{
+    let parsed: T = serde_json::from_value(serde_json::to_value(value)?)
+        .map_err(|e| anyhow!("{} does not parse back: {}", field, e))?;
+    if parsed != *value {
+        return Err(anyhow!("{} changes when parsed back", field));
+    }
+    Ok(())
+}
+
+pub async fn preload_characters(db: &PostgresClient, user_id: Uuid) -> Result<()> {
+    let characters = preset_characters(user_id);
+    Preloader::validate_characters(&characters)?;
+
+    let mut tx = db.get_client().begin().await?;
 
     for char in characters {
         let maybe_char = Character::find_one_by_criteria(
@@ -1,9 +1,52 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use async_openai::types::FunctionCall;
 use metastable_runtime::{Message, ToolCall};
 use crate::agents::SummarizeCharacter;
 use crate::agents::SendMessage;
 
+/// Result of normalizing a stored assistant message into a tool call.
+/// Failures are split by cause so migrations can bucket them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseOutcome {
+    /// Parsed and normalized into a known tool call.
+    Parsed(FunctionCall),
+    /// A tool call we don't normalize; passed through unchanged.
+    UnknownTool(FunctionCall),
+    /// A known tool whose arguments failed to deserialize.
+    MalformedArguments { tool: String, error: String },
+    /// No tool call and no usable assistant content.
+    EmptyContent,
+    /// The normalized tool call did not survive a serialize/parse round trip.
+    RoundTripMismatch,
+}
+
+impl ParseOutcome {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Self::Parsed(_) | Self::UnknownTool(_))
+    }
+
+    /// Short stable name of the variant, for bucketing failures.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Parsed(_) => "parsed",
+            Self::UnknownTool(_) => "unknown_tool",
+            Self::MalformedArguments { .. } => "malformed_arguments",
+            Self::EmptyContent => "empty_content",
+            Self::RoundTripMismatch => "round_trip_mismatch",
+        }
+    }
+
+    pub fn into_result(self) -> Result<FunctionCall> {
+        match self {
+            Self::Parsed(tc) | Self::UnknownTool(tc) => Ok(tc),
+            Self::MalformedArguments { tool, error } =>
+                Err(anyhow!("[ParseOutcome] Malformed arguments for {}: {}", tool, error)),
+            Self::EmptyContent => Err(anyhow!("[ParseOutcome] Empty message content")),
+            Self::RoundTripMismatch => Err(anyhow!("[ParseOutcome] Parsing failed")),
+        }
+    }
+}
+
 pub fn validate_parsing(m: &SendMessage) -> Result<FunctionCall> {
     let tc = m.into_tool_call()?;
     let mm = SendMessage::try_from_tool_call(&tc)?;
@@ -13,33 +56,51 @@ pub fn validate_parsing(m: &SendMessage) -> Result<FunctionCall> {
     Ok(tc)
 }
 
-pub fn try_parse_content(tool_call: &Option<FunctionCall>, content: &str) -> Result<FunctionCall> {
-    let t = if let Some(tc) = &tool_call {
+fn validate_outcome(m: &SendMessage) -> ParseOutcome {
+    match validate_parsing(m) {
+        Ok(tc) => ParseOutcome::Parsed(tc),
+        Err(_) => ParseOutcome::RoundTripMismatch,
+    }
+}
+
+fn malformed(tool: &str, error: impl std::fmt::Display) -> ParseOutcome {
+    ParseOutcome::MalformedArguments { tool: tool.to_string(), error: error.to_string() }
+}
+
+pub fn try_parse_content(tool_call: &Option<FunctionCall>, content: &str) -> ParseOutcome {
+    if let Some(tc) = &tool_call {
         let function_name = &tc.name;
         if function_name == "summarize_character" {
-            let t = SummarizeCharacter::try_from_tool_call(&tc)?;
-            t.into_tool_call()?
+            match SummarizeCharacter::try_from_tool_call(tc).and_then(|t| t.into_tool_call()) {
+                Ok(t) => ParseOutcome::Parsed(t),
+                Err(e) => malformed(function_name, e),
+            }
         } else if function_name == "send_message" { // Assumes send_message
-            let t = SendMessage::try_from_tool_call(&tc)?;
-            let parsed_tool = SendMessage::from_legacy_inputs(&content, &t);
-            validate_parsing(&parsed_tool)?
+            match SendMessage::try_from_tool_call(tc) {
+                Ok(t) => {
+                    let parsed_tool = SendMessage::from_legacy_inputs(content, &t);
+                    validate_outcome(&parsed_tool)
+                }
+                Err(e) => malformed(function_name, e),
+            }
         } else {
             tracing::info!("Skipping {} tool call", function_name);
-            tc.clone()
+            ParseOutcome::UnknownTool(tc.clone())
         }
     }  else {
         // No tool call, but has content.
         let assistant_content = content.trim();
         let cleaned_content = assistant_content.trim_matches(|c| c == '*' || c == '.').trim();
+        if cleaned_content.is_empty() {
+            return ParseOutcome::EmptyContent;
+        }
 
         let parsed_tool = SendMessage::from_legacy_inputs(cleaned_content, &SendMessage::default());
-        validate_parsing(&parsed_tool)?
-    };
-
-    Ok(t)
+        validate_outcome(&parsed_tool)
+    }
 }
 
-pub fn try_prase_message(message: &Message) -> Result<FunctionCall> {
+pub fn try_prase_message(message: &Message) -> ParseOutcome {
     try_parse_content(
         &message.assistant_message_tool_call.0, 
         &message.assistant_message_content
@@ -0,0 +1,61 @@
+use async_openai::types::FunctionCall;
+use metastable_runtime::ToolCall;
+use metastable_runtime_roleplay::agents::{
+    CharacterOutputValidator, InvalidCharacterOutput, SummarizeCharacter,
+};
+use serde_json::{json, Value};
+
+fn summary(arguments: Value) -> SummarizeCharacter {
+    SummarizeCharacter::try_from_tool_call(&FunctionCall {
+        name: "summarize_character".to_string(),
+        arguments: arguments.to_string(),
+    }).unwrap()
+}
+
+fn arguments() -> Value {
+    json!({
+        "name": "林雾",
+        "description": "一位在海边小镇经营旧书店的年轻人。",
+        "orientation": "Female",
+        "language": "Chinese",
+        "prompts_personality": "",
+        "prompts_scenario": "雨夜的书店里，{{user}} 推门而入。",
+        "prompts_example_dialogue": "对话：欢迎光临。",
+        "prompts_first_message": json!({
+            "name": "send_message",
+            "arguments": json!({ "messages": [{"type": "对话", "content": "欢迎光临。"}], "options": [], "summary": "" }).to_string(),
+        }).to_string(),
+        "background_stories": [],
+        "behavior_traits": [],
+        "relationships": [],
+        "skills_and_interests": [],
+        "additional_example_dialogue": [],
+        "additional_info": [],
+        "tags": ["书店"],
+    })
+}
+
+#[test]
+fn test_blank_required_fields_are_named() {
+    let validator = CharacterOutputValidator::default();
+
+    let err = validator.validate(&summary(arguments())).unwrap_err();
+    assert_eq!(err.fields(), vec!["prompts_personality"]);
+    assert!(err.to_string().contains("prompts_personality is empty"), "{}", err);
+    assert!(InvalidCharacterOutput::is(&anyhow::Error::from(err)));
+
+    // All problems are reported together
+    let mut broken = arguments();
+    broken["prompts_scenario"] = json!("  ");
+    broken["prompts_first_message"] = json!("欢迎光临。");
+    let err = validator.validate(&summary(broken)).unwrap_err();
+    assert_eq!(err.fields(), vec!["prompts_personality", "prompts_scenario", "prompts_first_message"]);
+
+    let mut complete = arguments();
+    complete["prompts_personality"] = json!("安静、敏锐，偶尔毒舌。");
+    validator.validate(&summary(complete)).unwrap();
+
+    // Required fields are configurable
+    let lenient = CharacterOutputValidator { required_fields: vec!["name"] };
+    lenient.validate(&summary(arguments())).unwrap();
+}
@@ -0,0 +1,71 @@
+use metastable_clients::{EmbeddingMessage, PgvectorClient, PostgresClient};
+use metastable_common::ModuleClient;
+use metastable_database::{QueryCriteria, SqlxCrud, SqlxFilterQuery};
+use metastable_runtime::{Character, ChatSession, Message, MessageType, User};
+use metastable_runtime_roleplay::MemoryUpdater;
+use sqlx::types::{Json, Uuid};
+
+fn session_message(owner: Uuid, session: Uuid, summary: &str) -> Message {
+    Message {
+        id: Uuid::new_v4(),
+        owner,
+        system_config: Uuid::new_v4(),
+        session: Some(session),
+        turn_index: None,
+        user_message_content: summary.to_string(),
+        user_message_content_type: MessageType::Text,
+        input_toolcall: Json(None),
+        assistant_message_content: "ok".to_string(),
+        assistant_message_content_type: MessageType::Text,
+        assistant_message_tool_call: Json(None),
+        summary: Some(summary.to_string()),
+        model_name: "test".to_string(),
+        usage: Json(None),
+        finish_reason: None,
+        refusal: None,
+        is_stale: false,
+        is_memorizeable: true,
+        is_in_memory: true,
+        is_migrated: false,
+        migration_claimed_at: None,
+        deleted_at: None,
+        created_at: 0,
+        updated_at: 0,
+    }
+}
+
+#[tokio::test]
+async fn test_ephemeral_session_leaves_no_memories() {
+    let db = PostgresClient::setup_connection().await;
+    let mut tx = db.get_client().begin().await.unwrap();
+
+    let user = User { user_id: format!("ephemeral_{}", Uuid::new_v4()), user_aka: "Bob".to_string(), ..Default::default() }
+        .create(&mut *tx).await.unwrap();
+    let character = Character { name: "Alice".to_string(), creator: user.id, ..Default::default() }
+        .create(&mut *tx).await.unwrap();
+
+    let persisted = ChatSession::new(character.id, user.id, false);
+    assert!(persisted.persist_memory);
+    assert!(persisted.memory_filter(&character).is_some());
+
+    let session = ChatSession { persist_memory: false, ..ChatSession::new(character.id, user.id, false) }
+        .create(&mut *tx).await.unwrap();
+    assert!(session.memory_filter(&character).is_none());
+    // Enough turns that a persisting session would be processed
+    for i in 0..14 {
+        session_message(user.id, session.id, &format!("Bob mentioned he has {} cats and loves green tea", i + 1))
+            .create(&mut *tx).await.unwrap();
+    }
+    tx.commit().await.unwrap();
+
+    let summary = MemoryUpdater::new().await.unwrap().update_memory(&session.id).await.unwrap();
+    assert!(summary.is_none());
+
+    let pgvector = PgvectorClient::setup_connection().await;
+    let pool: &sqlx::PgPool = pgvector.get_client();
+    let stored = EmbeddingMessage::count_by_criteria(
+        QueryCriteria::new().add_valued_filter("user_id", "=", user.id),
+        pool
+    ).await.unwrap();
+    assert_eq!(stored, 0);
+}
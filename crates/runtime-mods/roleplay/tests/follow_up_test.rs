@@ -0,0 +1,146 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{QueryCriteria, SqlxCrud, SqlxFilterQuery};
+use metastable_runtime::{run_agent_turn, Character, ChatSession, Message, Prompt, ToolIterationLimitExceeded, User};
+use metastable_runtime_roleplay::agents::{RoleplayMessageType, RoleplayV1Agent, SendMessage};
+use metastable_runtime_roleplay::RoleplayInput;
+use serde_json::json;
+use sqlx::types::Uuid;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Answers with an options-only `send_message` while `options_left` is above
+/// zero, then with a full reply. Returns the base URL, the options budget and
+/// the number of completions served.
+async fn mock_llm() -> (String, Arc<AtomicUsize>, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let options_left = Arc::new(AtomicUsize::new(0));
+    let served = Arc::new(AtomicUsize::new(0));
+
+    let (budget, counter) = (options_left.clone(), served.clone());
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            // Read the headers, then as much body as they announce
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            let body_start = loop {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                if let Some(i) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break i + 4;
+                }
+            };
+            let headers = String::from_utf8_lossy(&request[..body_start]).to_lowercase();
+            let content_length = headers.lines()
+                .find_map(|l| l.strip_prefix("content-length:"))
+                .map(|v| v.trim().parse::<usize>().unwrap())
+                .unwrap_or(0);
+            while request.len() < body_start + content_length {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+
+            let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            let options_only = budget.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| left.checked_sub(1)).is_ok();
+            let arguments = if options_only {
+                json!({ "messages": [], "options": ["Follow her", "Stay behind"], "summary": "Options only" })
+            } else {
+                json!({ "messages": [{ "type": "对话", "content": "Let's go." }], "options": [], "summary": "She left" })
+            };
+            let body = json!({
+                "id": format!("gen-{}", n),
+                "object": "chat.completion",
+                "created": 1735689600,
+                "model": "mock",
+                "choices": [{
+                    "index": 0,
+                    "message": {
+                        "role": "assistant",
+                        "content": "内容生成完毕。",
+                        "tool_calls": [{
+                            "id": format!("call_{}", n),
+                            "type": "function",
+                            "function": { "name": "send_message", "arguments": arguments.to_string() },
+                        }],
+                    },
+                    "finish_reason": "tool_calls",
+                }],
+                "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 },
+            }).to_string();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(), body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+
+    (base_url, options_left, served)
+}
+
+#[test]
+fn test_only_options_only_replies_follow_up() {
+    let session_id = Uuid::new_v4();
+    let input = RoleplayInput::ContinueSession(session_id, Prompt::new_user("Hello"));
+
+    let options_only = SendMessage { options: vec!["Follow her".to_string()], ..Default::default() };
+    assert!(matches!(input.follow_up(&options_only), Some(RoleplayInput::RegenerateSession(id)) if id == session_id));
+
+    let full = SendMessage {
+        messages: vec![RoleplayMessageType::Chat("Hi".to_string())],
+        options: vec!["Follow her".to_string()],
+        summary: String::new(),
+    };
+    assert!(input.follow_up(&full).is_none());
+    assert!(input.follow_up(&SendMessage::default()).is_none());
+}
+
+#[tokio::test]
+async fn test_roleplay_turn_follow_ups_stop_at_the_limit() {
+    let (base_url, options_left, served) = mock_llm().await;
+    std::env::set_var("OPENAI_BASE_URL", &base_url);
+    std::env::set_var("OPENAI_API_KEY", "test");
+
+    let db = PostgresClient::setup_connection().await;
+    let mut tx = db.get_client().begin().await.unwrap();
+    let user = User { user_id: format!("follow_up_{}", Uuid::new_v4()), user_aka: "Bob".to_string(), ..Default::default() }
+        .create(&mut *tx).await.unwrap();
+    let character = Character { name: "Alice".to_string(), creator: user.id, ..Default::default() }
+        .create(&mut *tx).await.unwrap();
+    // No memory, so the turn only talks to the mock LLM and the database
+    let session = ChatSession { persist_memory: false, ..ChatSession::new(character.id, user.id, false) }
+        .create(&mut *tx).await.unwrap();
+    tx.commit().await.unwrap();
+
+    let agent = RoleplayV1Agent::new().await.unwrap();
+    let input = || RoleplayInput::ContinueSession(session.id, Prompt::new_user("Where are we going?"));
+
+    // The model never gets past the options, so the turn gives up after 3 calls
+    options_left.store(usize::MAX, Ordering::SeqCst);
+    let err = run_agent_turn(&agent, &user.id, input(), 3).await.unwrap_err();
+    assert!(ToolIterationLimitExceeded::is(&err), "{}", err);
+    assert_eq!(
+        err.downcast_ref::<ToolIterationLimitExceeded>(),
+        Some(&ToolIterationLimitExceeded { agent: "roleplay_v1", limit: 3 })
+    );
+    assert_eq!(served.load(Ordering::SeqCst), 3);
+
+    // Options first, then the message: the follow-up replaces the options-only reply
+    options_left.store(1, Ordering::SeqCst);
+    let (_, tool, _) = run_agent_turn(&agent, &user.id, input(), 3).await.unwrap();
+    assert!(!tool.messages.is_empty());
+    assert_eq!(served.load(Ordering::SeqCst), 5);
+
+    let mut tx = db.get_client().begin().await.unwrap();
+    let messages = Message::count_by_criteria(
+        QueryCriteria::new().add_valued_filter("session", "=", session.id),
+        &mut *tx
+    ).await.unwrap();
+    assert_eq!(messages, 2);
+    tx.rollback().await.unwrap();
+}
@@ -0,0 +1,28 @@
+use async_openai::types::FunctionCall;
+use metastable_runtime_roleplay::{try_parse_content, ParseOutcome};
+
+fn tool_call(name: &str, arguments: &str) -> Option<FunctionCall> {
+    Some(FunctionCall { name: name.to_string(), arguments: arguments.to_string() })
+}
+
+#[test]
+fn test_parse_outcome_variants() {
+    let parsed = try_parse_content(&None, "对话：你好");
+    assert!(matches!(parsed, ParseOutcome::Parsed(ref tc) if tc.name == "send_message"));
+    assert!(parsed.is_ok());
+
+    let unknown = try_parse_content(&tool_call("show_story_options", "{\"options\":[]}"), "");
+    assert!(matches!(unknown, ParseOutcome::UnknownTool(ref tc) if tc.name == "show_story_options"));
+    assert!(unknown.is_ok());
+
+    let malformed = try_parse_content(&tool_call("send_message", "{not json"), "");
+    assert!(matches!(malformed, ParseOutcome::MalformedArguments { ref tool, .. } if tool == "send_message"));
+    assert_eq!(malformed.kind(), "malformed_arguments");
+
+    let malformed_summary = try_parse_content(&tool_call("summarize_character", "{\"name\": 1}"), "");
+    assert!(matches!(malformed_summary, ParseOutcome::MalformedArguments { ref tool, .. } if tool == "summarize_character"));
+
+    let empty = try_parse_content(&None, "  **..  ");
+    assert_eq!(empty, ParseOutcome::EmptyContent);
+    assert!(empty.into_result().is_err());
+}
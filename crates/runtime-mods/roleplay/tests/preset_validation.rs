@@ -0,0 +1,47 @@
+use async_openai::types::FunctionCall;
+use metastable_runtime_roleplay::{preset_characters, Preloader};
+use serde_json::json;
+use sqlx::types::{Json, Uuid};
+
+#[test]
+fn test_presets_are_valid() {
+    Preloader::validate_definitions().unwrap();
+}
+
+#[test]
+fn test_broken_presets_are_rejected() {
+    let mut characters = preset_characters(Uuid::nil());
+    characters[0].prompts_scenario.push_str(" {{chra}} 很高兴见到你");
+    let err = Preloader::validate_characters(&characters).unwrap_err().to_string();
+    assert!(err.contains("{{chra}}") && err.contains("prompts_scenario"), "{}", err);
+
+    let mut characters = preset_characters(Uuid::nil());
+    characters[0].prompts_additional_info.0.push("{{user 的名字".to_string());
+    let err = Preloader::validate_characters(&characters).unwrap_err().to_string();
+    assert!(err.contains("unterminated"), "{}", err);
+
+    // A typo in the type tag would otherwise be shown to users as chat text
+    let mut characters = preset_characters(Uuid::nil());
+    characters[0].prompts_first_message = Json(Some(FunctionCall {
+        name: "send_message".to_string(),
+        arguments: json!({
+            "messages": [{"type": "动做", "content": "*他抬起头*"}],
+            "options": [],
+            "summary": "",
+        }).to_string(),
+    }));
+    let err = Preloader::validate_characters(&characters).unwrap_err().to_string();
+    assert!(err.contains("动做"), "{}", err);
+
+    let mut characters = preset_characters(Uuid::nil());
+    characters[0].prompts_first_message = Json(Some(FunctionCall {
+        name: "send_message".to_string(),
+        arguments: "{\"messages\": ".to_string(),
+    }));
+    assert!(Preloader::validate_characters(&characters).is_err());
+
+    // Known placeholders are fine
+    let mut characters = preset_characters(Uuid::nil());
+    characters[0].prompts_scenario.push_str(" {{user}} 遇见了 {{char}}");
+    Preloader::validate_characters(&characters).unwrap();
+}
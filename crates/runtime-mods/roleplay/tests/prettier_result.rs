@@ -0,0 +1,24 @@
+use metastable_runtime_roleplay::agents::{ApplyChatStyle, PrettierResult};
+
+#[test]
+fn test_prettier_reports_changes() {
+    let clean = PrettierResult::normalize_classes("relative bg-white rounded-lg p-4");
+    assert!(!clean.changed);
+    assert_eq!(clean.cleaned, clean.original);
+
+    let messy = PrettierResult::normalize_classes("  relative bg-white\n\trounded-lg  p-4 bg-white ");
+    assert!(messy.changed);
+    assert_eq!(messy.cleaned, "relative bg-white rounded-lg p-4");
+    assert_eq!(messy.original, "  relative bg-white\n\trounded-lg  p-4 bg-white ");
+
+    let style = ApplyChatStyle {
+        container: "bg-white p-4".to_string(),
+        content: "text-gray-900".to_string(),
+        tag: "text-xs".to_string(),
+    };
+    assert!(!style.prettier().changed);
+
+    let result = ApplyChatStyle { tag: "text-xs  text-xs mb-1".to_string(), ..style.clone() }.prettier();
+    assert!(result.changed);
+    assert_eq!(result.cleaned, ApplyChatStyle { tag: "text-xs mb-1".to_string(), ..style });
+}
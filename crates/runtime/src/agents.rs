@@ -1,4 +1,69 @@
 
+use std::env;
+
+use anyhow::Result;
+use serde_json::Value;
+use sqlx::types::Uuid;
+
+use crate::{Agent, Message};
+
+/// Agent calls a routed turn may make unless `AGENT_MAX_TOOL_ITERATIONS` says otherwise.
+pub const DEFAULT_MAX_TOOL_ITERATIONS: usize = 4;
+
+/// Returned by [`run_agent_turn`] when the agent still asks for a follow-up
+/// after `limit` calls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolIterationLimitExceeded {
+    pub agent: &'static str,
+    pub limit: usize,
+}
+
+impl ToolIterationLimitExceeded {
+    /// Whether `err` is a `ToolIterationLimitExceeded`.
+    pub fn is(err: &anyhow::Error) -> bool {
+        err.downcast_ref::<Self>().is_some()
+    }
+}
+
+impl std::fmt::Display for ToolIterationLimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "agent {} still requested a follow-up after {} tool calls", self.agent, self.limit)
+    }
+}
+
+impl std::error::Error for ToolIterationLimitExceeded {}
+
+/// Reads `AGENT_MAX_TOOL_ITERATIONS`, falling back to `DEFAULT_MAX_TOOL_ITERATIONS`.
+pub fn max_tool_iterations_from_env() -> usize {
+    env::var("AGENT_MAX_TOOL_ITERATIONS").ok()
+        .and_then(|v| v.trim().parse().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(DEFAULT_MAX_TOOL_ITERATIONS)
+}
+
+/// Reads `AGENT_LENIENT_JSON` (`true` or `1`), the default for [`Agent::lenient_json`].
+pub fn lenient_json_from_env() -> bool {
+    matches!(env::var("AGENT_LENIENT_JSON").unwrap_or_default().trim().to_lowercase().as_str(), "true" | "1")
+}
+
+/// Calls `agent` until it stops asking for a [`Agent::follow_up`], at most
+/// `max_iterations` times, and returns the last call's output.
+pub async fn run_agent_turn<A: Agent>(
+    agent: &A, caller: &Uuid, input: A::Input, max_iterations: usize
+) -> Result<(Message, A::Tool, Option<Value>)> {
+    let mut input = input;
+    for _ in 0..max_iterations {
+        let (message, tool, value) = agent.call(caller, &input).await?;
+        match agent.follow_up(&input, &message, &tool) {
+            Some(next) => input = next,
+            None => return Ok((message, tool, value)),
+        }
+    }
+
+    tracing::warn!("[run_agent_turn] {} exceeded {} tool calls in one turn", A::SYSTEM_CONFIG_NAME, max_iterations);
+    Err(ToolIterationLimitExceeded { agent: A::SYSTEM_CONFIG_NAME, limit: max_iterations }.into())
+}
+
 #[async_trait::async_trait]
 pub trait AgentRouter {
     type Input;
@@ -13,7 +78,9 @@ macro_rules! define_agent_router {
     ) => {
         #[derive(Clone)]
         pub struct AgentsRouter {
-            $( pub $field: $agent_type ),*
+            $( pub $field: $agent_type, )*
+            /// Agent calls one routed turn may make before giving up.
+            pub max_tool_iterations: usize,
         }
 
         #[derive(Debug, Clone)]
@@ -33,7 +100,8 @@ macro_rules! define_agent_router {
         impl AgentsRouter {
             pub async fn new() -> anyhow::Result<Self> {
                 Ok(Self {
-                    $( $field: <$agent_type>::new().await? ),*
+                    $( $field: <$agent_type>::new().await?, )*
+                    max_tool_iterations: ::metastable_runtime::max_tool_iterations_from_env(),
                 })
             }
         }
@@ -47,7 +115,9 @@ macro_rules! define_agent_router {
                 match input {
                     $(
                         AgentRouterInput::$variant(input) => {
-                            let (message, tool, value) = <$agent_type as ::metastable_runtime::Agent>::call(&self.$field, caller, &input).await?;
+                            let (message, tool, value) = ::metastable_runtime::run_agent_turn(
+                                &self.$field, caller, input, self.max_tool_iterations
+                            ).await?;
                             Ok(AgentRouterOutput::$variant(message, tool, value))
                         }
                     ),*
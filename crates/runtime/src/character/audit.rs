@@ -1,12 +1,47 @@
+use std::env;
+
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use sqlx::types::Uuid;
+use metastable_common::get_current_timestamp;
 use metastable_database::SqlxObject;
 
 use crate::User;
 
 use super::{Character, CharacterStatus};
 
+/// How much review history `AuditLog::prune` keeps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuditRetention {
+    /// Entries older than this many seconds are pruned.
+    pub max_age_secs: i64,
+    /// Newest entries kept per character regardless of age; `None` keeps none.
+    pub keep_last: Option<i64>,
+}
+
+impl Default for AuditRetention {
+    fn default() -> Self {
+        Self { max_age_secs: 180 * 24 * 60 * 60, keep_last: Some(20) }
+    }
+}
+
+impl AuditRetention {
+    /// Reads `AUDIT_LOG_MAX_AGE_SECS` and `AUDIT_LOG_KEEP_LAST`, falling back
+    /// to the defaults. A `AUDIT_LOG_KEEP_LAST` of 0 disables the keep window.
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let read = |key: &str| env::var(key).ok().and_then(|v| v.trim().parse::<i64>().ok());
+        Self {
+            max_age_secs: read("AUDIT_LOG_MAX_AGE_SECS").unwrap_or(default.max_age_secs),
+            keep_last: match read("AUDIT_LOG_KEEP_LAST") {
+                Some(n) if n <= 0 => None,
+                Some(n) => Some(n),
+                None => default.keep_last,
+            },
+        }
+    }
+}
+
 #[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
 #[table_name = "roleplay_character_audit_logs"]
 pub struct AuditLog {
@@ -24,3 +59,46 @@ pub struct AuditLog {
     pub notes: String,
     pub created_at: i64,
 }
+
+impl AuditLog {
+    /// Deletes every entry created before `cutoff`. Returns how many were removed.
+    pub async fn prune_older_than<'e, E>(cutoff: i64, executor: E) -> Result<u64>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres> + Send,
+    {
+        Self::prune_older_than_keeping(cutoff, None, executor).await
+    }
+
+    /// Like `prune_older_than`, but the `keep_last` newest entries of each
+    /// character survive however old they are.
+    pub async fn prune_older_than_keeping<'e, E>(cutoff: i64, keep_last: Option<i64>, executor: E) -> Result<u64>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres> + Send,
+    {
+        let pruned = sqlx::query(
+            "DELETE FROM roleplay_character_audit_logs WHERE id IN ( \
+                SELECT id FROM ( \
+                    SELECT id, created_at, \
+                        row_number() OVER (PARTITION BY character ORDER BY created_at DESC, id DESC) AS recency \
+                    FROM roleplay_character_audit_logs \
+                ) ranked \
+                WHERE created_at < $1 AND recency > $2 \
+            )"
+        )
+            .bind(cutoff)
+            .bind(keep_last.unwrap_or(0).max(0))
+            .execute(executor)
+            .await?
+            .rows_affected();
+        Ok(pruned)
+    }
+
+    /// Applies `retention` as of now.
+    pub async fn prune<'e, E>(retention: &AuditRetention, executor: E) -> Result<u64>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres> + Send,
+    {
+        let cutoff = get_current_timestamp() - retention.max_age_secs;
+        Self::prune_older_than_keeping(cutoff, retention.keep_last, executor).await
+    }
+}
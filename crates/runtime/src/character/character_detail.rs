@@ -1,4 +1,9 @@
+use std::fmt;
+use std::ops::Deref;
+
 use metastable_database::TextEnum;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
 
 // Core character enums moved from character.rs
 #[derive(Debug, Clone, Eq, PartialEq, Default, TextEnum)]
@@ -20,6 +25,32 @@ pub enum CharacterLanguage {
     Others(String),
 }
 
+impl CharacterStatus {
+    /// Whether a character may move from `self` to `next`.
+    pub fn can_transition_to(&self, next: &CharacterStatus) -> bool {
+        matches!((self, next),
+            (Self::Draft, Self::Reviewing) |
+            (Self::Reviewing, Self::Published) |
+            (Self::Reviewing, Self::Draft) |
+            (Self::Published, Self::Archived) |
+            (Self::Archived, Self::Published)
+        )
+    }
+}
+
+impl CharacterLanguage {
+    /// Greeting template used when a character has no first message.
+    /// `{{char}}` and `{{user}}` are substituted by `Character::build_first_message`.
+    pub fn default_greeting(&self) -> &'static str {
+        match self {
+            Self::Chinese => "你好，{{user}}！我是{{char}}，很高兴认识你。",
+            Self::Japanese => "はじめまして、{{user}}。{{char}}です。よろしくね。",
+            Self::Korean => "안녕, {{user}}! 나는 {{char}}야. 만나서 반가워.",
+            Self::English | Self::Others(_) => "Hi {{user}}, I'm {{char}}. Nice to meet you!",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Default, TextEnum)]
 pub enum CharacterFeature {
     #[default]
@@ -35,6 +66,89 @@ pub enum CharacterFeature {
     Others(String),
 }
 
+/// The variant of a `CharacterFeature`, without its payload.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum CharacterFeatureKind {
+    Roleplay,
+    CharacterCreation,
+    BackgroundImage,
+    AvatarImage,
+    Voice,
+    DynamicImage,
+    Others,
+}
+
+impl CharacterFeature {
+    pub fn kind(&self) -> CharacterFeatureKind {
+        match self {
+            Self::Roleplay => CharacterFeatureKind::Roleplay,
+            Self::CharacterCreation => CharacterFeatureKind::CharacterCreation,
+            Self::BackgroundImage(_) => CharacterFeatureKind::BackgroundImage,
+            Self::AvatarImage(_) => CharacterFeatureKind::AvatarImage,
+            Self::Voice(_) => CharacterFeatureKind::Voice,
+            Self::DynamicImage(_) => CharacterFeatureKind::DynamicImage,
+            Self::Others(_) => CharacterFeatureKind::Others,
+        }
+    }
+}
+
+/// A character's features, holding at most one feature of each kind except
+/// `Others`, which may repeat. Serializes as a plain list.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CharacterFeatureSet(Vec<CharacterFeature>);
+
+impl CharacterFeatureSet {
+    /// Inserts `feature`, replacing any existing feature of the same kind in place.
+    pub fn set(&mut self, feature: CharacterFeature) {
+        let kind = feature.kind();
+        if kind == CharacterFeatureKind::Others {
+            if !self.0.contains(&feature) {
+                self.0.push(feature);
+            }
+            return;
+        }
+
+        match self.0.iter_mut().find(|f| f.kind() == kind) {
+            Some(existing) => *existing = feature,
+            None => self.0.push(feature),
+        }
+    }
+
+    /// Removes every feature of `kind`, returning whether any was present.
+    pub fn remove(&mut self, kind: CharacterFeatureKind) -> bool {
+        let before = self.0.len();
+        self.0.retain(|f| f.kind() != kind);
+        self.0.len() != before
+    }
+
+    pub fn get(&self, kind: CharacterFeatureKind) -> Option<&CharacterFeature> {
+        self.0.iter().find(|f| f.kind() == kind)
+    }
+
+    pub fn into_inner(self) -> Vec<CharacterFeature> {
+        self.0
+    }
+}
+
+impl From<Vec<CharacterFeature>> for CharacterFeatureSet {
+    fn from(features: Vec<CharacterFeature>) -> Self {
+        let mut set = Self::default();
+        for feature in features {
+            set.set(feature);
+        }
+        set
+    }
+}
+
+impl Deref for CharacterFeatureSet {
+    type Target = [CharacterFeature];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Default, TextEnum)]
 pub enum CharacterOrientation {
     #[default]
@@ -77,6 +191,39 @@ pub enum BackgroundStories {
     Others(String),
 }
 
+/// A background story and its position in the prompt. Serializes as the story
+/// with an extra `order` field; stories stored before it existed read as 0
+/// and keep their list order.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct OrderedBackgroundStory {
+    #[serde(flatten)]
+    pub story: BackgroundStories,
+    #[serde(default)]
+    pub order: i32,
+}
+
+impl OrderedBackgroundStory {
+    /// Orders `stories` as listed.
+    pub fn numbered(stories: Vec<BackgroundStories>) -> Vec<Self> {
+        stories.into_iter()
+            .enumerate()
+            .map(|(i, story)| Self { story, order: i as i32 })
+            .collect()
+    }
+}
+
+impl From<BackgroundStories> for OrderedBackgroundStory {
+    fn from(story: BackgroundStories) -> Self {
+        Self { story, order: 0 }
+    }
+}
+
+impl fmt::Display for OrderedBackgroundStory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.story.fmt(f)
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, TextEnum)]
 pub enum Relationships {
     #[prefix(lang = "en", content = "IntimatePartner")]
@@ -102,6 +249,15 @@ pub enum Relationships {
     Others(String),
 }
 
+/// A `Relationships` entry that can point at another character, so the link
+/// can be looked up from the other side with `Character::referencing`.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CharacterRelationship {
+    pub relationship: Relationships,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub character_id: Option<Uuid>,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, TextEnum)]
 pub enum SkillsAndInterests {
     #[prefix(lang = "en", content = "ProfessionalSkills")]
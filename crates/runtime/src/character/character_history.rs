@@ -7,8 +7,8 @@ use sqlx::types::{Json, Uuid};
 use crate::User;
 
 use super::{
-    BackgroundStories, BehaviorTraits, Character, CharacterFeature, 
-    CharacterLanguage, CharacterStatus, Relationships, SkillsAndInterests
+    OrderedBackgroundStory, BehaviorTraits, Character, CharacterFeatureSet,
+    CharacterLanguage, CharacterStatus, CharacterRelationship, Relationships, SkillsAndInterests
 };
 
 #[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
@@ -30,7 +30,7 @@ pub struct CharacterHistory {
 
     pub status: CharacterStatus,
     pub language: CharacterLanguage,
-    pub features: Json<Vec<CharacterFeature>>,
+    pub features: Json<CharacterFeatureSet>,
 
     pub prompts_scenario: String,
     pub prompts_personality: String,
@@ -38,12 +38,13 @@ pub struct CharacterHistory {
 
     // v0
     pub prompts_example_dialogue: String,
-    pub prompts_background_stories: Json<Vec<BackgroundStories>>,
+    pub prompts_background_stories: Json<Vec<OrderedBackgroundStory>>,
     pub prompts_behavior_traits: Json<Vec<BehaviorTraits>>,
 
     // v1
     pub prompts_additional_example_dialogue: Json<Vec<String>>,
     pub prompts_relationships: Json<Vec<Relationships>>,
+    pub related_characters: Json<Vec<CharacterRelationship>>,
     pub prompts_skills_and_interests: Json<Vec<SkillsAndInterests>>,
     pub prompts_additional_info: Json<Vec<String>>,
 
@@ -77,6 +78,7 @@ impl CharacterHistory {
 
             prompts_additional_example_dialogue: character.prompts_additional_example_dialogue,
             prompts_relationships: character.prompts_relationships,
+            related_characters: character.related_characters,
             prompts_skills_and_interests: character.prompts_skills_and_interests,
             prompts_additional_info: character.prompts_additional_info,
             creator_notes: character.creator_notes,
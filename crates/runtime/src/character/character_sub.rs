@@ -7,6 +7,7 @@ use crate::{User, Character};
 
 #[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
 #[table_name = "roleplay_character_sub"]
+#[conflict_target(user, character)]
 pub struct CharacterSub {
     pub id: Uuid,
 
@@ -6,22 +6,27 @@ mod character_mask;
 mod character_post;
 mod post_comments;
 
-use anyhow::Result;
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
 use async_openai::types::FunctionCall;
+use metastable_clients::DEFAULT_GRAPH_DB_VECTOR_SEARCH_THRESHOLD;
 use metastable_common::get_time_in_utc8;
 use serde::{Deserialize, Serialize};
-use metastable_database::SqlxObject;
+use serde_json::json;
+use metastable_database::{Cursor, OrderDirection, PageResult, SqlxObject};
 use sqlx::types::Json;
 use sqlx::types::Uuid;
 
 use crate::{Message, MessageRole, MessageType, Prompt, User};
 
 pub use character_detail::{
-    BackgroundStories, BehaviorTraits, Relationships, SkillsAndInterests,
-    CharacterFeature, CharacterLanguage, CharacterStatus, CharacterOrientation,
+    BackgroundStories, OrderedBackgroundStory, BehaviorTraits, Relationships, CharacterRelationship, SkillsAndInterests,
+    CharacterFeature, CharacterFeatureKind, CharacterFeatureSet,
+    CharacterLanguage, CharacterStatus, CharacterOrientation,
 };
 
-pub use audit::AuditLog;
+pub use audit::{AuditLog, AuditRetention};
 pub use character_history::CharacterHistory;
 pub use character_sub::CharacterSub;
 pub use character_mask::CharacterMask;
@@ -30,13 +35,22 @@ pub use post_comments::CharacterPostComments;
 
 use crate::ChatSession;
 
+/// Upper bound, in characters, on the recalled memories rendered into `{{memories}}`.
+pub const MAX_RECALLED_MEMORIES_CHARS: usize = 2000;
+
+/// Default upper bound, in characters, on `prompts_example_dialogue` and
+/// `prompts_additional_example_dialogue` combined.
+pub const DEFAULT_MAX_EXAMPLE_DIALOGUE_CHARS: usize = 8000;
+
 #[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
 #[table_name = "roleplay_characters"]
 #[allow_type_change]
+#[soft_delete]
 pub struct Character {
     pub id: Uuid,
 
     pub name: String,
+    #[fulltext]
     pub description: String,
 
     #[foreign_key(referenced_table = "users", related_rust_type = "User")]
@@ -48,12 +62,13 @@ pub struct Character {
     #[foreign_key(referenced_table = "chat_sessions", related_rust_type = "ChatSession")]
     pub creation_session: Option<Uuid>,
 
+    #[optimistic_lock]
     pub version: i64,
 
     pub status: CharacterStatus,
     pub orientation: CharacterOrientation,
     pub language: CharacterLanguage,
-    pub features: Json<Vec<CharacterFeature>>,
+    pub features: Json<CharacterFeatureSet>,
 
     pub prompts_scenario: String,
     pub prompts_personality: String,
@@ -61,12 +76,15 @@ pub struct Character {
 
     // v0
     pub prompts_example_dialogue: String,
-    pub prompts_background_stories: Json<Vec<BackgroundStories>>,
+    pub prompts_background_stories: Json<Vec<OrderedBackgroundStory>>,
     pub prompts_behavior_traits: Json<Vec<BehaviorTraits>>,
 
     // v1
     pub prompts_additional_example_dialogue: Json<Vec<String>>,
     pub prompts_relationships: Json<Vec<Relationships>>,
+    /// Relationships to other characters on the platform, queryable with `referencing`.
+    #[indexed]
+    pub related_characters: Json<Vec<CharacterRelationship>>,
     pub prompts_skills_and_interests: Json<Vec<SkillsAndInterests>>,
     pub prompts_additional_info: Json<Vec<String>>,
 
@@ -74,13 +92,55 @@ pub struct Character {
 
     pub tags: Vec<String>,
 
+    /// Minimum similarity for recalling memories in this character's sessions.
+    /// `None` uses the global default.
+    pub memory_recall_threshold: Option<f32>,
+
+    pub deleted_at: Option<i64>,
     pub created_at: i64,
     pub updated_at: i64
 }
 
 impl Character {
-    pub fn build_system_prompt(&self, prompt: &str, user_name: &str) -> Prompt {
-        let prompts_background_stories = self.prompts_background_stories
+    pub fn recall_threshold(&self) -> f32 {
+        self.memory_recall_threshold.unwrap_or(DEFAULT_GRAPH_DB_VECTOR_SEARCH_THRESHOLD)
+    }
+
+    /// Combined length, in characters, of the example dialogue fields.
+    pub fn example_dialogue_chars(&self) -> usize {
+        self.prompts_example_dialogue.chars().count()
+            + self.prompts_additional_example_dialogue.iter().map(|d| d.chars().count()).sum::<usize>()
+    }
+
+    /// Rejects characters whose example dialogues would crowd out the rest of the context.
+    pub fn validate_example_dialogue_size(&self, max_chars: usize) -> Result<()> {
+        let chars = self.example_dialogue_chars();
+        if chars > max_chars {
+            return Err(anyhow!(
+                "[Character::validate_example_dialogue_size] Example dialogues are {} characters long, the limit is {}",
+                chars, max_chars
+            ));
+        }
+        Ok(())
+    }
+
+    /// Rejects recall thresholds that are not a similarity score in `[0, 1]`.
+    pub fn validate_recall_threshold(&self) -> Result<()> {
+        if let Some(threshold) = self.memory_recall_threshold {
+            if !(0.0..=1.0).contains(&threshold) {
+                return Err(anyhow!(
+                    "[Character::validate_recall_threshold] Recall threshold {} is outside [0, 1]",
+                    threshold
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn build_system_prompt(&self, prompt: &str, user_name: &str, recalled: &[String]) -> Prompt {
+        let mut background_stories = self.prompts_background_stories.iter().collect::<Vec<_>>();
+        background_stories.sort_by_key(|story| story.order);
+        let prompts_background_stories = background_stories
             .iter()
             .map(|v| v.to_string())
             .collect::<Vec<_>>()
@@ -94,6 +154,7 @@ impl Character {
 
         let prompts_relationships = self.prompts_relationships
             .iter()
+            .chain(self.related_characters.iter().map(|r| &r.relationship))
             .map(|v| v.to_string())
             .collect::<Vec<_>>()
             .join("\n- ");
@@ -116,6 +177,19 @@ impl Character {
             .collect::<Vec<_>>()
             .join("\n- ");
 
+        // Whole memories only, in recall order, until the cap would be exceeded.
+        let mut memories = Vec::new();
+        let mut memories_len = 0;
+        for memory in recalled {
+            let line = format!("- {}", memory);
+            let added_len = line.chars().count() + if memories.is_empty() { 0 } else { 1 };
+            if memories_len + added_len > MAX_RECALLED_MEMORIES_CHARS {
+                break;
+            }
+            memories_len += added_len;
+            memories.push(line);
+        }
+
         let request_time = get_time_in_utc8();
         let p = prompt
             .replace("{{char}}", &self.name)
@@ -131,12 +205,97 @@ impl Character {
             .replace("{{char_skills_and_interests}}", &prompts_skills_and_interests)
             .replace("{{char_additional_info}}", &prompts_additional_info);
 
+        // Last, so placeholders inside a recalled memory are kept as written
+        let p = if memories.is_empty() {
+            p.replace("{{memories}}\n", "").replace("{{memories}}", "")
+        } else {
+            p.replace("{{memories}}", &memories.join("\n"))
+        };
+
         Prompt::new_system(&p)
     }
 
+    /// Move every character in `ids` to `status`. Unknown ids and illegal
+    /// transitions are reported per id; the legal ones are applied with a
+    /// single batch update on the given connection. The targets are loaded
+    /// once and stay locked until the surrounding transaction ends, so a
+    /// concurrent change can't slip in between the check and the update.
+    pub async fn bulk_update_status(
+        ids: &[Uuid], status: CharacterStatus, conn: &mut sqlx::PgConnection
+    ) -> Result<Vec<(Uuid, Result<(), String>)>> {
+        let characters = Self::find_by_criteria(
+            QueryCriteria::new()
+                .add_in_filter("id", ids.to_vec())
+                .for_update(),
+            &mut *conn
+        ).await?
+            .into_iter()
+            .map(|c| (c.id, c))
+            .collect::<HashMap<_, _>>();
+
+        let mut outcomes = Vec::with_capacity(ids.len());
+        let mut to_update = Vec::new();
+        for id in ids {
+            let outcome = match characters.get(id) {
+                None => Err("character not found".to_string()),
+                Some(c) if !c.status.can_transition_to(&status) =>
+                    Err(format!("illegal transition from {} to {}", c.status, status)),
+                Some(c) => {
+                    to_update.push(c.id);
+                    Ok(())
+                }
+            };
+            outcomes.push((*id, outcome));
+        }
+
+        Self::batch_update_field(to_update, "status", status, &mut *conn).await?;
+        Ok(outcomes)
+    }
+
+    /// Characters awaiting review, oldest first, optionally narrowed to a
+    /// `language` or to those carrying `tag`. Page with `next_cursor`.
+    pub async fn reviewing_queue<'e, E>(
+        language: Option<CharacterLanguage>, tag: Option<String>, cursor: Option<Cursor<i64>>, limit: i64,
+        executor: E
+    ) -> Result<PageResult<Self, i64>>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres> + Send,
+    {
+        let mut criteria = QueryCriteria::new()
+            .add_valued_filter("status", "=", CharacterStatus::Reviewing);
+        if let Some(language) = language {
+            criteria = criteria.add_valued_filter("language", "=", language);
+        }
+        if let Some(tag) = tag {
+            criteria = criteria.add_valued_filter("tags", "@>", vec![tag]);
+        }
+
+        Ok(Self::find_page(criteria.after_cursor("created_at", cursor), limit, executor).await?)
+    }
+
+    /// Characters with a `related_characters` entry pointing at `character_id`, by name.
+    pub async fn referencing<'e, E>(character_id: Uuid, executor: E) -> Result<Vec<Self>>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres> + Send,
+    {
+        Ok(Self::find_by_criteria(
+            QueryCriteria::new()
+                .add_jsonb_contains("related_characters", json!([{ "character_id": character_id }]))
+                .order_by("name", OrderDirection::Asc),
+            executor
+        ).await?)
+    }
+
     pub fn build_first_message(&self, user_name: &str) -> Prompt {
         let p = self.prompts_first_message.0.clone()
-            .unwrap_or(FunctionCall { name: "send_message".to_string(), arguments: "{}".to_string() });
+            .unwrap_or_else(|| FunctionCall {
+                name: "send_message".to_string(),
+                arguments: json!({
+                    "messages": [{ "type": "Chat", "content": self.language.default_greeting() }],
+                    "options": [],
+                    "summary": "",
+                }).to_string(),
+            });
 
         let p_arguments = p.arguments
             .replace("{{char}}", &self.name)
@@ -1,5 +1,5 @@
 use anyhow::{anyhow, Result};
-use async_openai::types::{CreateChatCompletionRequestArgs, FunctionCall};
+use async_openai::types::FunctionCall;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sqlx::types::{Json, Uuid};
@@ -7,7 +7,7 @@ use sqlx::types::{Json, Uuid};
 use metastable_common::ModuleClient;
 use metastable_clients::{R2Client, ImageFolder};
 
-use crate::{Agent, Message, MessageType, Prompt, ToolCall, llm_request::{ExtendedChatCompletionRequest, ReasoningConfig}};
+use crate::{Agent, Message, MessageType, Prompt, ToolCall, llm_request::RequestBuilder};
 
 // Clean image response types
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -67,21 +67,14 @@ pub trait ImageAgent: Agent {
 
         // Create request
         let llm_messages = Prompt::pack(messages)?;
-        let base_request = CreateChatCompletionRequestArgs::default()
-            .model(Self::model())
+        let request = RequestBuilder::new(Self::model())
             .messages(llm_messages)
             .temperature(Self::temperature())
             .max_tokens(Self::max_tokens() as u32)
+            .reasoning_effort(Self::reasoning_effort())
+            .modalities(vec!["image".to_string(), "text".to_string()])
             .build()?;
 
-        let request = ExtendedChatCompletionRequest {
-            base: base_request,
-            reasoning: Self::reasoning_effort().map(|effort| ReasoningConfig {
-                effort: effort.to_string(),
-            }),
-            modalities: Some(vec!["image".to_string(), "text".to_string()]),
-        };
-
         // Make API call
         use async_openai::config::Config;
         let client = reqwest::Client::new();
@@ -136,6 +129,7 @@ pub trait ImageAgent: Agent {
             owner: caller.clone(),
             system_config: self.system_config().id,
             session: None,
+            turn_index: None,
 
             user_message_content: user_message.content.clone(),
             user_message_content_type: user_message.content_type.clone(),
@@ -155,6 +149,8 @@ pub trait ImageAgent: Agent {
             is_memorizeable: false,
             is_in_memory: false,
             is_migrated: false,
+            migration_claimed_at: None,
+            deleted_at: None,
             created_at: 0,
             updated_at: 0,
         };
@@ -0,0 +1,76 @@
+use serde_json::Value;
+
+/// Best-effort fix for the malformed JSON models tend to emit in tool
+/// arguments: trailing commas before `}`/`]` and unquoted object keys.
+/// Returns `None` when the input needed no repair or could not be repaired.
+pub fn repair_json(input: &str) -> Option<String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut out = String::with_capacity(input.len() + 8);
+
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut last_significant: Option<char> = None;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if in_string {
+            out.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+                last_significant = Some(c);
+            }
+            i += 1;
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_string = true;
+                out.push(c);
+            }
+            ',' => {
+                let next = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+                if !matches!(next, Some('}') | Some(']')) {
+                    out.push(c);
+                    last_significant = Some(c);
+                }
+            }
+            c if (c.is_ascii_alphabetic() || c == '_') && matches!(last_significant, Some('{') | Some(',')) => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let ident: String = chars[start..i].iter().collect();
+                let next = chars[i..].iter().find(|c| !c.is_whitespace());
+                if next == Some(&':') {
+                    out.push('"');
+                    out.push_str(&ident);
+                    out.push('"');
+                } else {
+                    out.push_str(&ident);
+                }
+                last_significant = ident.chars().last();
+                continue;
+            }
+            c => {
+                out.push(c);
+                if !c.is_whitespace() {
+                    last_significant = Some(c);
+                }
+            }
+        }
+        i += 1;
+    }
+
+    if out == input || serde_json::from_str::<Value>(&out).is_err() {
+        None
+    } else {
+        Some(out)
+    }
+}
@@ -3,30 +3,37 @@ mod user;
 mod cards;
 mod system_config;
 mod llm;
+mod json_repair;
 mod llm_request;
 mod image;
 mod prompt;
 mod character;
 mod session;
+mod session_participant;
 mod agents;
 mod multimodel;
+mod pricing;
 
-pub use user::{UserRole, User, UserUrl, UserReferral, UserBadge, UserFollow, UserUsagePoints, UserPointsLog, UserPayment, UserPaymentStatus, UserNotification};
+pub use user::{UserRole, User, UserUrl, UserReferral, UserBadge, UserFollow, UserUsagePoints, UserPointsLog, UserPointsLogKind, UserPayment, UserPaymentStatus, UserNotification};
 pub use system_config::SystemConfig;
 pub use cards::{Card, CardPool, DrawHistory, DrawType, DrawProbability};
-pub use message::{MessageRole, MessageType, Message};
-pub use prompt::Prompt;
+pub use message::{MessageRole, MessageType, Message, DEFAULT_MIGRATION_CLAIM_LEASE_SECS};
+pub use prompt::{PackOptions, Prompt};
 pub use character::{Character, CharacterSub, CharacterHistory, CharacterMask,
-    CharacterFeature, CharacterLanguage, CharacterStatus, CharacterOrientation,
-    BackgroundStories, BehaviorTraits, Relationships, SkillsAndInterests,
-    AuditLog, CharacterPost, CharacterPostComments,
+    CharacterFeature, CharacterFeatureKind, CharacterFeatureSet,
+    CharacterLanguage, CharacterStatus, CharacterOrientation,
+    BackgroundStories, OrderedBackgroundStory, BehaviorTraits, Relationships, CharacterRelationship, SkillsAndInterests,
+    AuditLog, AuditRetention, CharacterPost, CharacterPostComments, MAX_RECALLED_MEMORIES_CHARS, DEFAULT_MAX_EXAMPLE_DIALOGUE_CHARS,
 };
-pub use session::ChatSession;
+pub use session::{ChatSession, SessionTitleConfig, DEFAULT_MAX_SESSION_PARTICIPANTS};
+pub use session_participant::{SessionParticipant, ParticipantRole};
 pub use multimodel::{MultimodelMessageType, MultimodelMessage};
-pub use llm::{Agent, ToolCall};
-pub use llm_request::{ReasoningConfig, ExtendedChatCompletionRequest, make_extended_request};
+pub use llm::{Agent, ToolCall, parse_tool_call};
+pub use json_repair::repair_json;
+pub use llm_request::{ReasoningConfig, ExtendedChatCompletionRequest, RequestBuilder, make_extended_request};
 pub use image::{ImageAgent, GenerateImageResult, ImageResponse};
+pub use pricing::{ModelPricing, ModelRate, estimate_cost};
 
 pub use metastable_llm_macros::LlmTool;
 
-pub use agents::AgentRouter;
\ No newline at end of file
+pub use agents::{AgentRouter, ToolIterationLimitExceeded, DEFAULT_MAX_TOOL_ITERATIONS, lenient_json_from_env, max_tool_iterations_from_env, run_agent_turn};
\ No newline at end of file
@@ -1,15 +1,14 @@
 use anyhow::{anyhow, Result};
 use async_openai::types::{
-    ChatCompletionToolArgs, CreateChatCompletionRequestArgs, FunctionCall, FunctionObject
+    ChatCompletionToolArgs, FunctionCall, FunctionObject
 };
-use metastable_database::{QueryCriteria, SqlxCrud, SqlxFilterQuery};
 use serde_json::Value;
 use sqlx::types::{Json, Uuid};
 
 use metastable_clients::{LlmClient, PostgresClient};
 use metastable_common::ModuleClient;
 
-use crate::{Message, MessageType, Prompt, SystemConfig, llm_request::{ExtendedChatCompletionRequest, ReasoningConfig, make_extended_request}};
+use crate::{Message, Prompt, SystemConfig, json_repair::repair_json, llm_request::{RequestBuilder, make_extended_request}};
 
 // implemented inside the llm-macros crate
 pub trait ToolCall: std::fmt::Debug + Sized + Clone + Send + Sync + 'static {
@@ -19,6 +18,24 @@ pub trait ToolCall: std::fmt::Debug + Sized + Clone + Send + Sync + 'static {
     fn to_function_object() -> FunctionObject;
 }
 
+/// Parses a tool call into `T`. With `lenient_json`, arguments that fail to
+/// parse get one best-effort repair attempt before giving up.
+pub fn parse_tool_call<T: ToolCall>(tool_call: &FunctionCall, lenient_json: bool) -> Result<T> {
+    match T::try_from_tool_call(tool_call) {
+        Ok(tool) => Ok(tool),
+        Err(e) if lenient_json => {
+            let repaired = repair_json(&tool_call.arguments)
+                .ok_or_else(|| anyhow!("[parse_tool_call] Failed to parse tool arguments: {}", e))?;
+            tracing::warn!("[parse_tool_call] Repaired malformed arguments for {}", tool_call.name);
+            Ok(T::try_from_tool_call(&FunctionCall {
+                name: tool_call.name.clone(),
+                arguments: repaired,
+            })?)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
 #[async_trait::async_trait]
 pub trait Agent: Clone + Send + Sync + Sized {
     const SYSTEM_CONFIG_NAME: &'static str;
@@ -31,18 +48,25 @@ pub trait Agent: Clone + Send + Sync + Sized {
     fn temperature() -> f32 { 0.7 }
     fn max_tokens() -> i32 { 20000 }
     fn reasoning_effort() -> Option<&'static str> { Some("minimal") }
+    /// Attempt to repair malformed tool-call JSON instead of failing the call.
+    /// Follows `AGENT_LENIENT_JSON` unless an agent overrides it.
+    fn lenient_json() -> bool { crate::lenient_json_from_env() }
 
     fn llm_client(&self) -> &LlmClient;
     fn db_client(&self) -> &PostgresClient;
 
     async fn build_input(&self, input: &Self::Input) -> Result<Vec<Prompt>>;
     async fn handle_output(&self, input: &Self::Input, message: &Message, tool: &Self::Tool) -> Result<(Message, Option<Value>)>;
+    /// The input for another call in the same turn when `tool` needs a follow-up,
+    /// e.g. story options before the message itself. `None` ends the turn.
+    fn follow_up(&self, _input: &Self::Input, _message: &Message, _tool: &Self::Tool) -> Option<Self::Input> { None }
 
     fn to_system_config() -> SystemConfig {
         SystemConfig {
             id: Uuid::new_v4(),
             name: Self::SYSTEM_CONFIG_NAME.to_string(),
             system_prompt_version: 0,
+            active: false,
             system_prompt: Self::system_prompt().to_string(),
             openai_model: Self::model().to_string(),
             openai_temperature: Self::temperature(),
@@ -56,49 +80,7 @@ pub trait Agent: Clone + Send + Sync + Sized {
     fn system_config(&self) -> &SystemConfig;
 
     async fn preload(db: &PostgresClient) -> Result<SystemConfig> {
-        let mut tx = db.get_client().begin().await?;
-        let system_config = SystemConfig::find_one_by_criteria(
-            QueryCriteria::new()
-                .add_filter("name", "=", Some(Self::SYSTEM_CONFIG_NAME.to_string())),
-            &mut *tx
-        ).await?;
-
-        let default_system_config = Self::to_system_config();
-
-        let c = if let Some(db_config) = system_config {
-            let mut db_config = db_config.clone();
-            let mut needs_update = false;
-            if db_config.system_prompt != default_system_config.system_prompt {
-                db_config.system_prompt = default_system_config.system_prompt.clone();
-                needs_update = true;
-            }
-
-            if db_config.openai_model != default_system_config.openai_model {
-                db_config.openai_model = default_system_config.openai_model.clone();
-                needs_update = true;
-            }
-
-            if db_config.openai_temperature != default_system_config.openai_temperature {
-                db_config.openai_temperature = default_system_config.openai_temperature;
-                needs_update = true;
-            }
-
-            if db_config.openai_max_tokens != default_system_config.openai_max_tokens {
-                db_config.openai_max_tokens = default_system_config.openai_max_tokens;
-                needs_update = true;
-            }
-
-            if needs_update {
-                db_config.system_prompt_version += 1;
-                db_config.clone().update(&mut *tx).await?;
-            }
-            db_config   
-        } else {
-            default_system_config.clone().create(&mut *tx).await?
-        };
-
-        tx.commit().await?;
-        Ok(c)
+        SystemConfig::sync_default(Self::to_system_config(), db).await
     }
 
     async fn call(
@@ -119,40 +101,30 @@ pub trait Agent: Clone + Send + Sync + Sized {
                 .expect("[Agent::call] Tool should build")
         ];
 
-        let base_request = CreateChatCompletionRequestArgs::default()
-            .model(Self::model())
+        let extended_request = RequestBuilder::new(Self::model())
             .messages(llm_messages)
             .tools(tools)
             .temperature(Self::temperature())
             .max_tokens(Self::max_tokens() as u32)
+            .reasoning_effort(Self::reasoning_effort())
             .build()?;
 
-        let extended_request = ExtendedChatCompletionRequest {
-            base: base_request,
-            reasoning: Self::reasoning_effort().map(|effort| ReasoningConfig {
-                effort: effort.to_string(),
-            }),
-            modalities: None, // Will be overridden by ImageGenerationAgent
-        };
-
         let config = self.llm_client().get_client().config();
         let response = make_extended_request(&extended_request, config).await?;
         let choice = response.choices.first()
             .ok_or(anyhow!("[Agent::call] No response from AI inference server for model {}", Self::model()))?;
 
-        let message = choice.message.clone();
-
-        let finish_reason = choice.finish_reason.clone();
-        let refusal = choice.message.refusal.clone();
-        let usage = response.usage
-            .ok_or(anyhow!("[Agent::call] Model {} returned no usage", Self::model()))?
-            .clone();
+        if response.usage.is_none() {
+            return Err(anyhow!("[Agent::call] Model {} returned no usage", Self::model()));
+        }
 
-        let content = message.content
-            .ok_or(anyhow!("[Agent::call] No content in the response"))?;
+        if choice.message.content.is_none() {
+            return Err(anyhow!("[Agent::call] No content in the response"));
+        }
 
-        let tool_calls = message
+        let tool_calls = choice.message
             .tool_calls
+            .clone()
             .unwrap_or_default();
 
         if tool_calls.len() == 0 {
@@ -164,37 +136,14 @@ pub trait Agent: Clone + Send + Sync + Sized {
         }
 
         let resulting_message = Message {
-            id: Uuid::new_v4(),
-            owner: caller.clone(),
             system_config: self.system_config().id,
-            session: None,
-            
             user_message_content: user_message.content.clone(),
             user_message_content_type: user_message.content_type.clone(),
-            input_toolcall: Json(None),
-            
-            assistant_message_content: content.clone(),
-            assistant_message_content_type: MessageType::Text,
-            assistant_message_tool_call: Json(Some(tool_calls[0].function.clone())),
-
             model_name: Self::model().to_string(),
-            usage: Json(Some(usage)),
-            finish_reason: finish_reason.map(|finish_reason| format!("{:?}", finish_reason)),
-            refusal: refusal.clone(),
-
-            summary: None,
-
-            is_stale: false,
-            is_memorizeable: false,
-            is_in_memory: false,
-
-            is_migrated: false,
-
-            created_at: 0,
-            updated_at: 0,
+            ..Message::from_llm_response(&response, None, *caller)?
         };
 
-        let tool = Self::Tool::try_from_tool_call(&tool_calls[0].function)?;
+        let tool = parse_tool_call::<Self::Tool>(&tool_calls[0].function, Self::lenient_json())?;
         let (msg, misc_value) = self.handle_output(input, &resulting_message, &tool).await?;
 
         Ok((msg, tool, misc_value))
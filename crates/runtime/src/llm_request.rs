@@ -1,6 +1,16 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use async_openai::types::{ChatCompletionRequestMessage, ChatCompletionTool, CreateChatCompletionRequestArgs};
 use serde::{Deserialize, Serialize};
 
+const REASONING_EFFORTS: &[&str] = &["minimal", "low", "medium", "high"];
+
+/// Model families that reject reasoning parameters.
+const NON_REASONING_MODEL_PREFIXES: &[&str] = &[
+    "openai/gpt-3.5", "openai/gpt-4o", "openai/gpt-4.1",
+    "google/gemini-1.5", "google/gemini-2.0",
+    "meta-llama/",
+];
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ReasoningConfig {
     pub effort: String,
@@ -16,6 +26,114 @@ pub struct ExtendedChatCompletionRequest {
     pub modalities: Option<Vec<String>>,
 }
 
+/// Builds an `ExtendedChatCompletionRequest`, rejecting parameter combinations
+/// the target model would refuse or silently ignore.
+#[derive(Debug, Clone)]
+pub struct RequestBuilder {
+    model: String,
+    messages: Vec<ChatCompletionRequestMessage>,
+    tools: Option<Vec<ChatCompletionTool>>,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+    reasoning_effort: Option<String>,
+    modalities: Option<Vec<String>>,
+}
+
+impl RequestBuilder {
+    pub fn new(model: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            messages: Vec::new(),
+            tools: None,
+            temperature: None,
+            max_tokens: None,
+            reasoning_effort: None,
+            modalities: None,
+        }
+    }
+
+    pub fn messages(mut self, messages: Vec<ChatCompletionRequestMessage>) -> Self {
+        self.messages = messages;
+        self
+    }
+
+    pub fn tools(mut self, tools: Vec<ChatCompletionTool>) -> Self {
+        self.tools = Some(tools);
+        self
+    }
+
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    pub fn max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    pub fn reasoning_effort(mut self, effort: Option<&str>) -> Self {
+        self.reasoning_effort = effort.map(str::to_string);
+        self
+    }
+
+    pub fn modalities(mut self, modalities: Vec<String>) -> Self {
+        self.modalities = Some(modalities);
+        self
+    }
+
+    pub fn supports_reasoning(model: &str) -> bool {
+        !NON_REASONING_MODEL_PREFIXES.iter().any(|prefix| model.starts_with(prefix))
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.messages.is_empty() {
+            return Err(anyhow!("[RequestBuilder::build] No messages for model {}", self.model));
+        }
+        if let Some(temperature) = self.temperature {
+            if !(0.0..=2.0).contains(&temperature) {
+                return Err(anyhow!("[RequestBuilder::build] Temperature {} is outside 0.0..=2.0", temperature));
+            }
+        }
+        if self.max_tokens == Some(0) {
+            return Err(anyhow!("[RequestBuilder::build] max_tokens must be positive"));
+        }
+        if let Some(effort) = &self.reasoning_effort {
+            if !REASONING_EFFORTS.contains(&effort.as_str()) {
+                return Err(anyhow!("[RequestBuilder::build] Unknown reasoning effort {:?}, expected one of {:?}",
+                    effort, REASONING_EFFORTS));
+            }
+            if !Self::supports_reasoning(&self.model) {
+                return Err(anyhow!("[RequestBuilder::build] Reasoning effort {:?} set for non-reasoning model {}",
+                    effort, self.model));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn build(self) -> Result<ExtendedChatCompletionRequest> {
+        self.validate()?;
+
+        let mut base = CreateChatCompletionRequestArgs::default();
+        base.model(&self.model).messages(self.messages);
+        if let Some(tools) = self.tools {
+            base.tools(tools);
+        }
+        if let Some(temperature) = self.temperature {
+            base.temperature(temperature);
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            base.max_tokens(max_tokens);
+        }
+
+        Ok(ExtendedChatCompletionRequest {
+            base: base.build()?,
+            reasoning: self.reasoning_effort.map(|effort| ReasoningConfig { effort }),
+            modalities: self.modalities,
+        })
+    }
+}
+
 pub async fn make_extended_request(
     extended_request: &ExtendedChatCompletionRequest,
     client_config: &async_openai::config::OpenAIConfig,
@@ -1,11 +1,15 @@
-use anyhow::Result;
-use async_openai::types::{CompletionUsage, FunctionCall};
-use metastable_database::{SqlxObject, TextEnum};
+use anyhow::{anyhow, Result};
+use async_openai::types::{CompletionUsage, CreateChatCompletionResponse, FunctionCall};
+use metastable_common::get_current_timestamp;
+use metastable_database::{OrderDirection, SqlxObject, TextEnum};
 use serde::{Deserialize, Serialize};
 
 use sqlx::types::{Json, Uuid};
 use crate::{ChatSession, SystemConfig, User};
 
+/// How long a migration claim holds before other workers may take the rows over.
+pub const DEFAULT_MIGRATION_CLAIM_LEASE_SECS: i64 = 30 * 60;
+
 #[derive(Debug, Clone, Default, TextEnum, PartialEq, Eq)]
 pub enum MessageRole {
     System,
@@ -24,6 +28,7 @@ pub enum MessageType {
 
 #[derive(Debug, Serialize, Deserialize, Clone, SqlxObject)]
 #[table_name = "messages"]
+#[soft_delete]
 pub struct Message {
     pub id: Uuid,
 
@@ -36,6 +41,8 @@ pub struct Message {
     #[indexed]
     #[foreign_key(referenced_table = "chat_sessions", related_rust_type = "ChatSession")]
     pub session: Option<Uuid>,
+    /// Position of the turn in its session, assigned by `ChatSession::next_turn`.
+    pub turn_index: Option<i64>,
 
     pub user_message_content: String,
     pub user_message_content_type: MessageType,
@@ -58,7 +65,88 @@ pub struct Message {
     pub is_in_memory: bool,
 
     pub is_migrated: bool,
+    pub migration_claimed_at: Option<i64>,
 
+    pub deleted_at: Option<i64>,
     pub created_at: i64,
     pub updated_at: i64,
 }
+
+impl Message {
+    /// An unsaved assistant message from the first choice of `response`: its content,
+    /// first tool call, usage, finish reason and refusal. The request side
+    /// (`system_config` and the user message) is left for the caller to fill in.
+    pub fn from_llm_response(response: &CreateChatCompletionResponse, session: Option<Uuid>, owner: Uuid) -> Result<Self> {
+        let choice = response.choices.first()
+            .ok_or(anyhow!("[Message::from_llm_response] No choices in the response from {}", response.model))?;
+        let tool_call = choice.message.tool_calls.as_ref()
+            .and_then(|tool_calls| tool_calls.first())
+            .map(|tool_call| tool_call.function.clone());
+        let now = get_current_timestamp();
+
+        Ok(Self {
+            id: Uuid::new_v4(),
+            owner,
+            system_config: Uuid::default(),
+            session,
+            turn_index: None,
+
+            user_message_content: String::new(),
+            user_message_content_type: MessageType::Text,
+            input_toolcall: Json(None),
+
+            assistant_message_content: choice.message.content.clone().unwrap_or_default(),
+            assistant_message_content_type: MessageType::Text,
+            assistant_message_tool_call: Json(tool_call),
+
+            summary: None,
+
+            model_name: response.model.clone(),
+            usage: Json(response.usage.clone()),
+            finish_reason: choice.finish_reason.as_ref().map(|finish_reason| format!("{:?}", finish_reason)),
+            refusal: choice.message.refusal.clone(),
+
+            is_stale: false,
+            is_memorizeable: false,
+            is_in_memory: false,
+
+            is_migrated: false,
+            migration_claimed_at: None,
+
+            deleted_at: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// Claim up to `n` unmigrated messages that no other worker has claimed.
+    /// Rows locked by a concurrent claimer are skipped, and the claimed rows
+    /// are stamped with `migration_claimed_at` so they stay out of later
+    /// batches once the transaction commits. A claim older than
+    /// `DEFAULT_MIGRATION_CLAIM_LEASE_SECS` is taken to belong to a worker that
+    /// died mid-batch, and its rows are claimed again.
+    pub async fn claim_unmigrated_batch(conn: &mut sqlx::PgConnection, n: i64) -> Result<Vec<Self>> {
+        Self::claim_unmigrated_batch_by(conn, n, DEFAULT_MIGRATION_CLAIM_LEASE_SECS, QueryCriteria::new()).await
+    }
+
+    /// `claim_unmigrated_batch` with an explicit lease, among the rows that
+    /// also match `criteria`'s filters.
+    pub async fn claim_unmigrated_batch_by(
+        conn: &mut sqlx::PgConnection, n: i64, lease_secs: i64, criteria: QueryCriteria,
+    ) -> Result<Vec<Self>> {
+        let now = get_current_timestamp();
+        let candidates = Self::find_by_criteria(
+            criteria
+                .add_null_or_filter("migration_claimed_at", "<", now - lease_secs)
+                .add_valued_filter("is_migrated", "=", false)
+                .order_by("created_at", OrderDirection::Asc)
+                .limit(n)
+                .skip_locked(),
+            &mut *conn
+        ).await?;
+
+        let ids = candidates.iter().map(|m| m.id).collect::<Vec<_>>();
+        let claimed = Self::batch_update_field(ids, "migration_claimed_at", now, &mut *conn).await?;
+        Ok(claimed)
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use anyhow::{anyhow, Result};
+use async_openai::types::CompletionUsage;
+use serde::{Deserialize, Serialize};
+
+/// USD per 1K prompt/completion tokens for the models the runtime calls.
+const DEFAULT_MODEL_RATES: &[(&str, f64, f64)] = &[
+    ("google/gemini-2.5-flash", 0.0003, 0.0025),
+    ("google/gemini-2.5-flash-lite", 0.0001, 0.0004),
+    ("google/gemini-2.5-flash-image-preview", 0.0003, 0.0025),
+    ("google/gemini-2.5-pro", 0.00125, 0.01),
+    ("openai/gpt-5-mini", 0.00025, 0.002),
+];
+
+static MODEL_PRICING: LazyLock<ModelPricing> = LazyLock::new(|| {
+    ModelPricing::from_env().unwrap_or_else(|e| {
+        tracing::warn!("[ModelPricing::from_env] {}, using the default rates", e);
+        ModelPricing::default()
+    })
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelRate {
+    pub prompt_per_1k: f64,
+    pub completion_per_1k: f64,
+}
+
+impl ModelRate {
+    pub fn cost(&self, usage: &CompletionUsage) -> f64 {
+        (usage.prompt_tokens as f64 * self.prompt_per_1k
+            + usage.completion_tokens as f64 * self.completion_per_1k) / 1000.0
+    }
+}
+
+/// Per-model token prices used to estimate what a completion cost.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelPricing {
+    rates: HashMap<String, ModelRate>,
+}
+
+impl Default for ModelPricing {
+    fn default() -> Self {
+        let rates = DEFAULT_MODEL_RATES.iter()
+            .map(|(model, prompt_per_1k, completion_per_1k)| (
+                model.to_string(),
+                ModelRate { prompt_per_1k: *prompt_per_1k, completion_per_1k: *completion_per_1k },
+            ))
+            .collect();
+        Self { rates }
+    }
+}
+
+impl ModelPricing {
+    /// The default rates, overridden or extended by `MODEL_PRICING`, a JSON
+    /// object of `{"model": {"prompt_per_1k": .., "completion_per_1k": ..}}`.
+    pub fn from_env() -> Result<Self> {
+        let pricing = Self::default();
+        match std::env::var("MODEL_PRICING") {
+            Ok(overrides) => pricing.with_overrides(&overrides),
+            Err(_) => Ok(pricing),
+        }
+    }
+
+    pub fn with_overrides(mut self, overrides: &str) -> Result<Self> {
+        let overrides: HashMap<String, ModelRate> = serde_json::from_str(overrides)
+            .map_err(|e| anyhow!("[ModelPricing::with_overrides] Invalid MODEL_PRICING: {}", e))?;
+        self.rates.extend(overrides);
+        Ok(self)
+    }
+
+    pub fn with_rate(mut self, model: &str, rate: ModelRate) -> Self {
+        self.rates.insert(model.to_string(), rate);
+        self
+    }
+
+    pub fn rate(&self, model: &str) -> Option<&ModelRate> {
+        self.rates.get(model)
+    }
+
+    /// `None` when the model has no configured price.
+    pub fn estimate_cost(&self, usage: &CompletionUsage, model: &str) -> Option<f64> {
+        self.rate(model).map(|rate| rate.cost(usage))
+    }
+}
+
+/// Estimates a completion's cost in USD with the process-wide price table.
+pub fn estimate_cost(usage: &CompletionUsage, model: &str) -> Option<f64> {
+    MODEL_PRICING.estimate_cost(usage, model)
+}
@@ -1,12 +1,21 @@
 use anyhow::{anyhow, Result};
 use async_openai::types::{
-    ChatCompletionMessageToolCall, ChatCompletionRequestAssistantMessageArgs, ChatCompletionRequestMessage, ChatCompletionRequestMessageContentPartImageArgs, ChatCompletionRequestSystemMessageArgs, ChatCompletionRequestToolMessageArgs, ChatCompletionRequestUserMessage, ChatCompletionRequestUserMessageArgs, ChatCompletionRequestUserMessageContentPart, ChatCompletionToolType, FunctionCall, ImageUrl
+    ChatCompletionMessageToolCall, ChatCompletionRequestAssistantMessageArgs, ChatCompletionRequestMessage, ChatCompletionRequestMessageContentPartImageArgs, ChatCompletionRequestSystemMessageArgs, ChatCompletionRequestToolMessageArgs, ChatCompletionRequestUserMessage, ChatCompletionRequestUserMessageArgs, ChatCompletionRequestMessageContentPartTextArgs, ChatCompletionRequestUserMessageContentPart, ChatCompletionToolType, FunctionCall, ImageDetail, ImageUrl
 };
 use metastable_common::get_current_timestamp;
 use serde::{Deserialize, Serialize};
 use sqlx::types::Uuid;
 
-use crate::{Message, MessageRole, MessageType};
+use crate::{GenerateImageResult, Message, MessageRole, MessageType};
+
+/// Controls how non-text content types are rendered when packing prompts.
+#[derive(Debug, Clone, Default)]
+pub struct PackOptions {
+    /// Detail level requested for image parts, `None` lets the provider decide.
+    pub image_detail: Option<ImageDetail>,
+    /// Render images as `[image] <url>` text, for models without vision input.
+    pub images_as_text: bool,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Prompt {
@@ -73,6 +82,10 @@ impl Prompt {
     }
 
     pub fn pack(messages: Vec<Self>) -> Result<Vec<ChatCompletionRequestMessage>> {
+        Self::pack_with(messages, &PackOptions::default())
+    }
+
+    pub fn pack_with(messages: Vec<Self>, options: &PackOptions) -> Result<Vec<ChatCompletionRequestMessage>> {
         let messages = Self::sort(messages)?;
         let messages = Self::validate_messages(messages)?;
         messages.iter().map(|m| {
@@ -82,32 +95,31 @@ impl Prompt {
                 function: toolcall.clone(),
             }]);
 
-            let content = m.content.clone();
+            let content = match m.content_type {
+                MessageType::Image if m.role == MessageRole::Assistant => m.assistant_image_content(),
+                _ => m.content.clone(),
+            };
 
             let build_user_message = |m: &Prompt| -> Result<ChatCompletionRequestUserMessage> {
                 match m.content_type {
-                    MessageType::Text => {
+                    MessageType::Image if !options.images_as_text => {
                         ChatCompletionRequestUserMessageArgs::default()
-                            .content(m.content.clone())
+                            .content(m.user_content_parts(options)?)
                             .build()
                             .map_err(|e| anyhow!("[Prompt::pack] Failed to pack message: {}", e))
-                    },
+                    }
                     MessageType::Image => {
-                        let image_url = m.content.clone();
-
                         ChatCompletionRequestUserMessageArgs::default()
-                            .content(vec![ChatCompletionRequestUserMessageContentPart::ImageUrl(
-                                ChatCompletionRequestMessageContentPartImageArgs::default()  
-                                    .image_url(ImageUrl {
-                                        url: image_url,
-                                        detail: None,
-                                    })
-                                    .build()
-                                    .map_err(|e| anyhow!("[Prompt::pack] Failed to pack message: {}", e))?
-                            )])
+                            .content(image_lines_as_text(&m.content))
                             .build()
                             .map_err(|e| anyhow!("[Prompt::pack] Failed to pack message: {}", e))
                     }
+                    MessageType::Text => {
+                        ChatCompletionRequestUserMessageArgs::default()
+                            .content(m.content.clone())
+                            .build()
+                            .map_err(|e| anyhow!("[Prompt::pack] Failed to pack message: {}", e))
+                    },
                 }
             };
 
@@ -146,6 +158,54 @@ impl Prompt {
         }).collect()
     }
 
+    /// Image message content is one entry per line: image URLs become image
+    /// parts, anything else becomes a text part (e.g. a caption).
+    fn user_content_parts(&self, options: &PackOptions) -> Result<Vec<ChatCompletionRequestUserMessageContentPart>> {
+        let mut parts = Vec::new();
+        for line in self.content.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let part = if is_image_url(line) {
+                ChatCompletionRequestUserMessageContentPart::ImageUrl(
+                    ChatCompletionRequestMessageContentPartImageArgs::default()
+                        .image_url(ImageUrl {
+                            url: line.to_string(),
+                            detail: options.image_detail.clone(),
+                        })
+                        .build()
+                        .map_err(|e| anyhow!("[Prompt::user_content_parts] Failed to build image part: {}", e))?
+                )
+            } else {
+                ChatCompletionRequestUserMessageContentPart::Text(
+                    ChatCompletionRequestMessageContentPartTextArgs::default()
+                        .text(line)
+                        .build()
+                        .map_err(|e| anyhow!("[Prompt::user_content_parts] Failed to build text part: {}", e))?
+                )
+            };
+            parts.push(part);
+        }
+
+        if parts.is_empty() {
+            return Err(anyhow!("[Prompt::user_content_parts] Image message has no content"));
+        }
+        Ok(parts)
+    }
+
+    /// Assistant messages cannot carry image parts, so generated images are
+    /// referenced by URL after the description.
+    fn assistant_image_content(&self) -> String {
+        let urls = self.toolcall.as_ref()
+            .filter(|toolcall| toolcall.name == "generate_image")
+            .and_then(|toolcall| serde_json::from_str::<GenerateImageResult>(&toolcall.arguments).ok())
+            .map(|result| result.image_urls)
+            .unwrap_or_default();
+
+        std::iter::once(self.content.clone())
+            .filter(|c| !c.is_empty())
+            .chain(urls.iter().map(|url| format!("[image] {}", url)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
     pub fn new_system(prompt: &str) -> Self {
         Self {
             role: MessageRole::System,
@@ -166,6 +226,20 @@ impl Prompt {
         }
     }
 
+    pub fn new_user_image(url: &str, caption: Option<&str>) -> Self {
+        let content = match caption {
+            Some(caption) => format!("{}\n{}", caption, url),
+            None => url.to_string(),
+        };
+        Self {
+            role: MessageRole::User,
+            content_type: MessageType::Image,
+            content,
+            toolcall: None,
+            created_at: get_current_timestamp(),
+        }
+    }
+
     pub fn from_message(message: &Message) -> [Self; 2] {
         [
             Self {
@@ -217,4 +291,17 @@ impl Prompt {
             .replace("{{summarized_history}}", &formatted_summary)
             .replace("{{vector_db_memory_snippets}}", &formatted_snippets);
     }
-}
\ No newline at end of file
+}
+
+fn is_image_url(s: &str) -> bool {
+    s.starts_with("https://") || s.starts_with("http://") || s.starts_with("data:image/")
+}
+
+fn image_lines_as_text(content: &str) -> String {
+    content.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(|l| if is_image_url(l) { format!("[image] {}", l) } else { l.to_string() })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
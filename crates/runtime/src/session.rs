@@ -1,9 +1,51 @@
+use std::env;
+
 use serde::{Deserialize, Serialize};
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use sqlx::types::Uuid;
 
-use metastable_database::SqlxObject;
-use crate::{Character, User};
+use metastable_clients::{LlmClient, Mem0Filter};
+use metastable_common::ModuleClient;
+use metastable_database::{OrderDirection, SqlxObject, SqlxUpsert};
+use crate::{Character, CharacterFeature, Message, ParticipantRole, Prompt, RequestBuilder, SessionParticipant, SystemConfig, User, make_extended_request};
+
+/// Participants a new session admits, including its host.
+pub const DEFAULT_MAX_SESSION_PARTICIPANTS: i32 = 4;
+
+const SESSION_TITLE_PROMPT: &str = "Write a short title for the conversation below, in the language the user writes in. \
+Reply with the title only: no quotes, no punctuation at the end, at most a few words.";
+
+/// When and how `ChatSession::ensure_title` names a session.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionTitleConfig {
+    /// Turns the session needs before a title is generated.
+    pub min_turns: usize,
+    /// Titles longer than this many characters are cut.
+    pub max_chars: usize,
+    pub model: String,
+}
+
+impl Default for SessionTitleConfig {
+    fn default() -> Self {
+        Self { min_turns: 3, max_chars: 30, model: "google/gemini-2.5-flash-lite".to_string() }
+    }
+}
+
+impl SessionTitleConfig {
+    /// Reads `SESSION_TITLE_MIN_TURNS`, `SESSION_TITLE_MAX_CHARS` and
+    /// `SESSION_TITLE_MODEL`, falling back to the defaults.
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let read = |key: &str, default: usize| env::var(key).ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(default);
+        Self {
+            min_turns: read("SESSION_TITLE_MIN_TURNS", default.min_turns),
+            max_chars: read("SESSION_TITLE_MAX_CHARS", default.max_chars),
+            model: env::var("SESSION_TITLE_MODEL").unwrap_or(default.model),
+        }
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default, SqlxObject)]
 #[table_name = "chat_sessions"]
@@ -17,11 +59,24 @@ pub struct ChatSession {
     #[foreign_key(referenced_table = "roleplay_characters", related_rust_type = "Character")]
     pub character: Uuid,
 
+    #[foreign_key(referenced_table = "system_configs", related_rust_type = "SystemConfig")]
+    pub system_config: Option<Uuid>,
+
     pub use_character_memory: bool,
+    /// When false the session is ephemeral: nothing is written to or recalled from memory.
+    #[column_default = "true"]
+    pub persist_memory: bool,
     pub hidden: bool,
+    /// Cap on `SessionParticipant`s, enforced by `join`. `None` uses
+    /// `DEFAULT_MAX_SESSION_PARTICIPANTS`; see `max_participants()`.
+    pub max_participants: Option<i32>,
+    /// Turns handed out so far by `next_turn`.
+    #[column_default = "0"]
+    pub turn_count: i64,
 
     pub nonce: i64, // only used for refresh the updated_at
     pub user_mask: Option<String>,
+    pub title: Option<String>,
 
     pub updated_at: i64,
     pub created_at: i64,
@@ -34,12 +89,189 @@ impl ChatSession {
             public: false,
             owner,
             character: character_id,
+            system_config: None,
             use_character_memory,
+            persist_memory: true,
             hidden: false,
+            max_participants: None,
+            turn_count: 0,
             nonce: 0,
             user_mask: None,
+            title: None,
             updated_at: 0,
             created_at: 0,
         }
     }
-}
\ No newline at end of file
+
+    /// Build a session bound to the `SystemConfig` named `config_name`.
+    /// The session is not persisted; call `create` on the result.
+    pub async fn new_with_config<'e, E>(
+        character_id: Uuid, owner: Uuid, use_character_memory: bool,
+        config_name: &str, config_version: Option<i64>,
+        executor: E
+    ) -> Result<Self>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres> + Send,
+    {
+        let config = SystemConfig::find_by_name(config_name, config_version, executor).await?
+            .ok_or_else(|| anyhow!("[ChatSession::new_with_config] No active system config named {} (version {:?})", config_name, config_version))?;
+
+        let mut session = Self::new(character_id, owner, use_character_memory);
+        session.system_config = Some(config.id);
+        Ok(session)
+    }
+
+    /// The memory scope of the session's turns with `character`, or `None`
+    /// when the session does not persist memories.
+    pub fn memory_filter(&self, character: &Character) -> Option<Mem0Filter> {
+        if !self.persist_memory {
+            return None;
+        }
+
+        let session_id = if self.use_character_memory && !character.features.contains(&CharacterFeature::CharacterCreation) {
+            None
+        } else {
+            Some(self.id)
+        };
+        Some(Mem0Filter { user_id: self.owner, character_id: Some(character.id), session_id })
+    }
+
+    /// Seats in the session, the owner's included.
+    pub fn max_participants(&self) -> usize {
+        self.max_participants.unwrap_or(DEFAULT_MAX_SESSION_PARTICIPANTS).max(0) as usize
+    }
+
+    /// Adds `user` to the session, as host if they own it and as guest otherwise.
+    /// Joining again returns the existing participant. One of the
+    /// `max_participants()` seats is held for the owner until they join, so
+    /// guests fail once the remaining seats are taken while the owner can always
+    /// join. The session row is locked for the check, so run this in a
+    /// transaction to keep concurrent joins under the cap.
+    pub async fn join(&self, user: &User, conn: &mut sqlx::PgConnection) -> Result<SessionParticipant> {
+        let session = Self::find_one_by_criteria(
+            QueryCriteria::new()
+                .add_valued_filter("id", "=", self.id)
+                .for_update(),
+            &mut *conn
+        ).await?
+            .ok_or_else(|| anyhow!("[ChatSession::join] Session {} not found", self.id))?;
+
+        let participants = session.participants(&mut *conn).await?;
+        if let Some(existing) = participants.iter().find(|p| p.user_id == user.id) {
+            return Ok(existing.clone());
+        }
+        let is_owner = user.id == session.owner;
+        let owner_seat = usize::from(!participants.iter().any(|p| p.user_id == session.owner));
+        if !is_owner && participants.len() + owner_seat >= session.max_participants() {
+            return Err(anyhow!("[ChatSession::join] Session {} is full ({} participants)", session.id, session.max_participants()));
+        }
+
+        let role = if is_owner { ParticipantRole::Host } else { ParticipantRole::Guest };
+        SessionParticipant::new(session.id, user.id, role).create_if_absent(&mut *conn).await?
+            .ok_or_else(|| anyhow!("[ChatSession::join] User {} joined session {} concurrently", user.id, session.id))
+    }
+
+    /// Removes `user` from the session. Returns whether they had joined it.
+    pub async fn leave<'e, E>(&self, user: &User, executor: E) -> Result<bool>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres> + Send,
+    {
+        let removed = sqlx::query("DELETE FROM session_participants WHERE session_id = $1 AND user_id = $2")
+            .bind(self.id)
+            .bind(user.id)
+            .execute(executor)
+            .await?
+            .rows_affected();
+        Ok(removed > 0)
+    }
+
+    /// Everyone who has joined the session, in joining order.
+    pub async fn participants<'e, E>(&self, executor: E) -> Result<Vec<SessionParticipant>>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres> + Send,
+    {
+        Ok(SessionParticipant::find_by_criteria(
+            QueryCriteria::new()
+                .add_valued_filter("session_id", "=", self.id)
+                .order_by("created_at", OrderDirection::Asc),
+            executor
+        ).await?)
+    }
+
+    /// Reserves the next turn index of session `session_id`, starting at 1.
+    /// The session row stays locked until the surrounding transaction ends, so
+    /// concurrent writers to one session get contiguous, unique indices.
+    pub async fn next_turn(session_id: &Uuid, conn: &mut sqlx::PgConnection) -> Result<i64> {
+        let turn: Option<i64> = sqlx::query_scalar(
+            "UPDATE chat_sessions SET turn_count = turn_count + 1 WHERE id = $1 RETURNING turn_count"
+        )
+            .bind(session_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+        turn.ok_or_else(|| anyhow!("[ChatSession::next_turn] Session {} not found", session_id))
+    }
+
+    /// Bumps `nonce` in place so the `updated_at` trigger fires, without writing
+    /// back a possibly stale copy of the other columns.
+    pub async fn bump_nonce(session_id: &Uuid, conn: &mut sqlx::PgConnection) -> Result<()> {
+        let touched = sqlx::query("UPDATE chat_sessions SET nonce = nonce + 1 WHERE id = $1")
+            .bind(session_id)
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+        if touched == 0 {
+            return Err(anyhow!("[ChatSession::bump_nonce] Session {} not found", session_id));
+        }
+        Ok(())
+    }
+
+    /// Generates a title from the session's first turns once it has
+    /// `config.min_turns` of them, and stores it unless the session already has
+    /// one. `messages` are the session's turns, oldest first. Returns whether a
+    /// title was stored.
+    pub async fn ensure_title<'e, E>(
+        &mut self, messages: &[Message], llm: &LlmClient, config: &SessionTitleConfig,
+        executor: E
+    ) -> Result<bool>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres> + Send,
+    {
+        if self.title.is_some() || messages.len() < config.min_turns {
+            return Ok(false);
+        }
+
+        let title = Self::generate_title(&messages[..config.min_turns], llm, config).await?;
+        // Another worker may have titled the session since it was loaded
+        let stored = sqlx::query("UPDATE chat_sessions SET title = $1 WHERE id = $2 AND title IS NULL")
+            .bind(&title)
+            .bind(self.id)
+            .execute(executor)
+            .await?
+            .rows_affected() == 1;
+
+        if stored {
+            self.title = Some(title);
+        }
+        Ok(stored)
+    }
+
+    async fn generate_title(messages: &[Message], llm: &LlmClient, config: &SessionTitleConfig) -> Result<String> {
+        let transcript = Prompt::pack_flat_messages(messages.iter().flat_map(Prompt::from_message).collect())?;
+        let request = RequestBuilder::new(&config.model)
+            .messages(Prompt::pack(vec![Prompt::new_system(SESSION_TITLE_PROMPT), Prompt::new_user(&transcript)])?)
+            .temperature(0.3)
+            .max_tokens(64)
+            .build()?;
+
+        let response = make_extended_request(&request, llm.get_client().config()).await?;
+        let content = response.choices.first()
+            .and_then(|choice| choice.message.content.clone())
+            .ok_or(anyhow!("[ChatSession::generate_title] No title from model {}", config.model))?;
+
+        let title = content.lines()
+            .map(|line| line.trim().trim_matches(|c| matches!(c, '"' | '\'' | '“' | '”' | '「' | '」')).trim())
+            .find(|line| !line.is_empty())
+            .ok_or(anyhow!("[ChatSession::generate_title] Model {} returned an empty title", config.model))?;
+        Ok(title.chars().take(config.max_chars).collect())
+    }
+}
@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use metastable_common::get_current_timestamp;
+use metastable_database::{SqlxObject, TextEnum};
+
+use crate::{ChatSession, User};
+
+#[derive(Debug, Clone, Eq, PartialEq, Default, TextEnum)]
+pub enum ParticipantRole {
+    Host,
+    #[default]
+    Guest,
+}
+
+/// A user taking part in a multiplayer `ChatSession`. Managed through
+/// `ChatSession::join` and `ChatSession::leave`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, SqlxObject)]
+#[table_name = "session_participants"]
+#[conflict_target(session_id, user_id)]
+pub struct SessionParticipant {
+    pub id: Uuid,
+
+    #[indexed]
+    #[foreign_key(referenced_table = "chat_sessions", related_rust_type = "ChatSession")]
+    pub session_id: Uuid,
+
+    #[foreign_key(referenced_table = "users", related_rust_type = "User")]
+    pub user_id: Uuid,
+
+    pub role: ParticipantRole,
+
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl SessionParticipant {
+    pub fn new(session_id: Uuid, user_id: Uuid, role: ParticipantRole) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            session_id,
+            user_id,
+            role,
+            created_at: get_current_timestamp(),
+            updated_at: get_current_timestamp(),
+        }
+    }
+}
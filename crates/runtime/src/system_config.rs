@@ -1,9 +1,12 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use async_openai::types::FunctionObject;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
 use sqlx::types::{Json, Uuid};
 
-use metastable_database::SqlxObject;
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{OrderDirection, SqlxObject};
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default, SqlxObject)]
 #[table_name = "system_configs"]
@@ -14,6 +17,7 @@ pub struct SystemConfig {
     
     pub system_prompt: String,
     pub system_prompt_version: i64,
+    pub active: bool,
 
     pub openai_base_url: String,
     pub openai_model: String,
@@ -25,3 +29,143 @@ pub struct SystemConfig {
     pub updated_at: i64,
     pub created_at: i64,
 }
+
+impl SystemConfig {
+    /// Look up a config by name. With no version given, the active
+    /// version for that name is returned.
+    pub async fn find_by_name<'e, E>(
+        name: &str, version: Option<i64>, executor: E
+    ) -> Result<Option<Self>>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres> + Send,
+    {
+        match version {
+            Some(version) => Ok(Self::find_one_by_criteria(
+                QueryCriteria::new()
+                    .add_valued_filter("name", "=", name.to_string())
+                    .add_valued_filter("system_prompt_version", "=", version),
+                executor
+            ).await?),
+            None => Self::find_active(name, executor).await,
+        }
+    }
+
+    pub async fn find_active<'e, E>(name: &str, executor: E) -> Result<Option<Self>>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres> + Send,
+    {
+        Ok(Self::find_one_by_criteria(
+            QueryCriteria::new()
+                .add_valued_filter("name", "=", name.to_string())
+                .add_valued_filter("active", "=", true),
+            executor
+        ).await?)
+    }
+
+    pub async fn find_latest<'e, E>(name: &str, executor: E) -> Result<Option<Self>>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres> + Send,
+    {
+        Ok(Self::find_one_by_criteria(
+            QueryCriteria::new()
+                .add_valued_filter("name", "=", name.to_string())
+                .order_by("system_prompt_version", OrderDirection::Desc),
+            executor
+        ).await?)
+    }
+
+    /// Mark `version` as the active config for `name` and deactivate every
+    /// other version of the same name, in a single transaction.
+    pub async fn activate(name: &str, version: i64, db: &PostgresClient) -> Result<Self> {
+        let mut tx = db.get_client().begin().await?;
+        let configs = Self::find_by_criteria(
+            QueryCriteria::new().add_valued_filter("name", "=", name.to_string()),
+            &mut *tx
+        ).await?;
+
+        if !configs.iter().any(|c| c.system_prompt_version == version) {
+            return Err(anyhow!("[SystemConfig::activate] No system config named {} with version {}", name, version));
+        }
+
+        let mut activated = None;
+        for mut config in configs {
+            let should_be_active = config.system_prompt_version == version;
+            if config.active != should_be_active {
+                config.active = should_be_active;
+                config = config.update(&mut *tx).await?;
+            }
+            if should_be_active {
+                activated = Some(config);
+            }
+        }
+
+        tx.commit().await?;
+        activated.ok_or_else(|| anyhow!("[SystemConfig::activate] Failed to activate {} version {}", name, version))
+    }
+
+    /// Whether `other` would behave the same as `self` at call time.
+    fn same_behaviour(&self, other: &Self) -> bool {
+        self.system_prompt == other.system_prompt &&
+            self.openai_model == other.openai_model &&
+            self.openai_temperature == other.openai_temperature &&
+            self.openai_max_tokens == other.openai_max_tokens
+    }
+
+    /// Reconciles the config an agent ships in code with the stored versions of
+    /// its name. A code config unlike every stored version is inserted as
+    /// `MAX(version) + 1` and activated. Otherwise the active version is kept,
+    /// even when an operator rolled back to an older one; only with no active
+    /// version is the stored copy of the code config activated.
+    pub async fn sync_default(default: Self, db: &PostgresClient) -> Result<Self> {
+        let name = default.name.clone();
+        let mut tx = db.get_client().begin().await?;
+        let stored = Self::find_by_criteria(
+            QueryCriteria::new()
+                .add_valued_filter("name", "=", name.clone())
+                .order_by("system_prompt_version", OrderDirection::Desc),
+            &mut *tx
+        ).await?;
+
+        let version = match stored.iter().find(|c| c.same_behaviour(&default)) {
+            Some(_) if stored.iter().any(|c| c.active) => {
+                tx.commit().await?;
+                return Ok(stored.into_iter().find(|c| c.active).expect("checked above"));
+            }
+            Some(matching) => matching.system_prompt_version,
+            None => {
+                let mut new_config = default;
+                new_config.active = false;
+                new_config.system_prompt_version = stored.first().map_or(0, |c| c.system_prompt_version + 1);
+                new_config.create(&mut *tx).await?.system_prompt_version
+            }
+        };
+        tx.commit().await?;
+
+        Self::activate(&name, version, db).await
+    }
+
+    /// Field-level changes from `a` to `b`, keyed by field name with
+    /// `{ "from": .., "to": .. }` values. Identity, version and timestamp
+    /// fields are ignored so only behavioural changes show up.
+    pub fn diff(a: &Self, b: &Self) -> Value {
+        const DIFFED_FIELDS: [&str; 6] = [
+            "system_prompt", "openai_base_url", "openai_model",
+            "openai_temperature", "openai_max_tokens", "functions",
+        ];
+
+        let a = serde_json::to_value(a).unwrap_or_default();
+        let b = serde_json::to_value(b).unwrap_or_default();
+
+        let mut changes = Map::new();
+        for field in DIFFED_FIELDS {
+            if a[field] != b[field] {
+                changes.insert(field.to_string(), json!({
+                    "from": a[field],
+                    "to": b[field],
+                }));
+            }
+        }
+
+        Value::Object(changes)
+    }
+}
@@ -37,6 +37,17 @@ pub enum UserPointsLogRewardReason {
     Inviation,
 }
 
+/// Coarse categories a user can filter their points history by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UserPointsLogKind {
+    Checkin,
+    Invitation,
+    ChatSpend,
+    /// Disputed entries that have been resolved in the user's favour.
+    Refund,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, SqlxObject)]
 #[table_name = "user_points_logs"]
 pub struct UserPointsLog {
@@ -75,6 +86,67 @@ pub struct UserPointsLog {
 }
 
 impl UserPointsLog {
+    /// A user's points history, newest first. `cursor` is the `(created_at, id)`
+    /// of the last entry of the previous page; rows sharing a timestamp are
+    /// ordered by id so no entry is skipped or repeated across pages.
+    pub async fn history<'e, E>(
+        user: Uuid, kind: Option<UserPointsLogKind>, cursor: Option<(i64, Uuid)>, limit: i64,
+        executor: E
+    ) -> Result<Vec<Self>>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+    {
+        let mut where_clauses = vec!["\"user\" = $1".to_string()];
+        let mut placeholder_idx = 2;
+
+        match kind {
+            Some(UserPointsLogKind::Checkin) | Some(UserPointsLogKind::Invitation) => {
+                where_clauses.push(format!("\"add_reason\" = ${}", placeholder_idx));
+                placeholder_idx += 1;
+            }
+            Some(UserPointsLogKind::ChatSpend) => {
+                where_clauses.push(format!("\"deduct_reason\" = ${}", placeholder_idx));
+                placeholder_idx += 1;
+            }
+            Some(UserPointsLogKind::Refund) => {
+                where_clauses.push("\"disputed\" AND \"resolved\"".to_string());
+            }
+            None => {}
+        }
+        if cursor.is_some() {
+            where_clauses.push(format!("(\"created_at\", \"id\") < (${}, ${})", placeholder_idx, placeholder_idx + 1));
+            placeholder_idx += 2;
+        }
+
+        let sql = format!(
+            "SELECT * FROM \"{}\" WHERE {} ORDER BY \"created_at\" DESC, \"id\" DESC LIMIT ${}",
+            Self::TABLE_NAME, where_clauses.join(" AND "), placeholder_idx
+        );
+
+        let mut query = sqlx::query_as::<_, <Self as SqlxSchema>::Row>(&sql).bind(user);
+        query = match kind {
+            Some(UserPointsLogKind::Checkin) => query.bind(UserPointsLogAddReason::DailyCheckin),
+            Some(UserPointsLogKind::Invitation) => query.bind(UserPointsLogAddReason::Inviation),
+            Some(UserPointsLogKind::ChatSpend) => query.bind(UserPointsLogDeductReason::ChatMessage),
+            _ => query,
+        };
+        if let Some((created_at, id)) = cursor {
+            query = query.bind(created_at).bind(id);
+        }
+
+        let rows = query.bind(limit).fetch_all(executor).await?;
+        Ok(rows.into_iter().map(Self::from_row).collect())
+    }
+
+    /// Per-bucket breakdown of the points this log deducted.
+    pub fn usage(&self) -> UserUsagePoints {
+        UserUsagePoints {
+            points_consumed_claimed: self.deducted_from_claimed,
+            points_consumed_purchased: self.deducted_from_purchased,
+            points_consumed_misc: self.deducted_from_misc,
+        }
+    }
+
     pub fn from_invitation(
         self_id: &Uuid, others_id: &Uuid, 
         self_amount: i64, others_amount: i64
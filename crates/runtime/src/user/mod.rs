@@ -19,11 +19,11 @@ pub use url::UserUrl;
 pub use referral::UserReferral;
 pub use badge::UserBadge;
 pub use follow::UserFollow;
-pub use log::UserPointsLog;
+pub use log::{UserPointsLog, UserPointsLogKind};
 pub use payment::{UserPayment, UserPaymentStatus};
 pub use notifications::UserNotification;
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
 pub struct UserUsagePoints {
     pub points_consumed_claimed: i64,
     pub points_consumed_purchased: i64,
@@ -37,7 +37,7 @@ pub enum UserRole {
     User,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default, SqlxObject)]
+#[derive(Serialize, Deserialize, Clone, Default, SqlxObject)]
 #[table_name = "users"]
 pub struct User {
     pub id: Uuid,
@@ -70,7 +70,9 @@ pub struct User {
     // profile related
     pub first_name: Option<String>,
     pub last_name: Option<String>,
+    #[sensitive]
     pub email: Option<String>,
+    #[sensitive]
     pub phone: Option<String>,
     pub avatar: Option<String>,
     pub bio: Option<String>,
@@ -102,8 +104,13 @@ impl User {
             .expect("[User::generate_auth_token] failed to encrypt auth token")
     }
 
-    pub fn verify_auth_token(token: &str, salt: &str) -> Result<String> {
-        let decrypted = decrypt(token, salt)?;
+    /// Verify a token against the primary salt, falling back to each of
+    /// `previous_salts` in order so tokens survive a salt rotation.
+    pub fn verify_auth_token(token: &str, salt: &str, previous_salts: &[String]) -> Result<String> {
+        let decrypted = std::iter::once(salt)
+            .chain(previous_salts.iter().map(String::as_str))
+            .find_map(|salt| decrypt(token, salt).ok())
+            .ok_or_else(|| anyhow!("[User::verify_auth_token] failed to decrypt auth token"))?;
         let authenticated_request: AuthenticatedRequest = serde_json::from_str(&decrypted)?;
         if authenticated_request.timestamp < get_current_timestamp() - 60 * 60 * 24 * 30 {
             return Err(anyhow::anyhow!("[User::verify_auth_token] authenticate expired"));
@@ -178,27 +185,39 @@ impl User {
     }
 
     /* BALANCE SUBTRACTION */
-    pub fn pay_for_character_creation(&mut self, amount: i64, message: Uuid) -> Result<UserPointsLog> {
-        let usage = self.pay(amount)?;
+    // `reserve_floor` is the balance a non-essential payment must leave untouched;
+    // essential payments pass `None` and may spend the balance down to zero.
+    pub fn pay_for_character_creation(&mut self, amount: i64, message: Uuid, reserve_floor: Option<i64>) -> Result<UserPointsLog> {
+        let usage = self.pay(amount, reserve_floor)?;
         Ok(UserPointsLog::from_character_creation(&self.id, usage, message))
     }
 
-    pub fn pay_for_chat_message_regenerate(&mut self, amount: i64, message: Uuid) -> Result<UserPointsLog> {
-        let usage = self.pay(amount)?;
+    pub fn pay_for_chat_message_regenerate(&mut self, amount: i64, message: Uuid, reserve_floor: Option<i64>) -> Result<UserPointsLog> {
+        let usage = self.pay(amount, reserve_floor)?;
         Ok(UserPointsLog::from_chat_message_regenerate(&self.id, usage, message))
     }
 
-    pub fn pay_for_chat_message(&mut self, amount: i64, message: Uuid, character_creator: Uuid, reward_amount: i64) -> Result<UserPointsLog> {
-        let usage = self.pay(amount)?;
+    pub fn pay_for_chat_message(&mut self, amount: i64, message: Uuid, character_creator: Uuid, reward_amount: i64, reserve_floor: Option<i64>) -> Result<UserPointsLog> {
+        let usage = self.pay(amount, reserve_floor)?;
         Ok(UserPointsLog::from_chat_message(&self.id, usage, message, character_creator, reward_amount))
     }
 
-    pub fn pay_for_voice_generation(&mut self, amount: i64, message: Uuid) -> Result<UserPointsLog> {
-        let usage = self.pay(amount)?;
+    pub fn pay_for_voice_generation(&mut self, amount: i64, message: Uuid, reserve_floor: Option<i64>) -> Result<UserPointsLog> {
+        let usage = self.pay(amount, reserve_floor)?;
         Ok(UserPointsLog::from_voice_generation(&self.id, usage, message))
     }
 
-    fn pay(&mut self, amount: i64) -> Result<UserUsagePoints> {
+    pub fn total_balance(&self) -> i64 {
+        self.running_purchased_balance + self.running_claimed_balance + self.running_misc_balance
+    }
+
+    fn pay(&mut self, amount: i64, reserve_floor: Option<i64>) -> Result<UserUsagePoints> {
+        if let Some(floor) = reserve_floor {
+            if self.total_balance() - amount < floor {
+                return Err(anyhow!("[User::pay] payment would drop balance below the reserve floor"));
+            }
+        }
+
         let mut remaining = amount;
         let self_clone = self.clone();
         let current_timestamp = get_current_timestamp();
@@ -248,9 +267,24 @@ impl User {
         }
     }
 
-    pub fn try_pay(&self, amount: i64) -> Result<i64> {
-        if self.running_purchased_balance + self.running_claimed_balance + self.running_misc_balance < amount {
+    /// Fetch a user with a row-level lock (`SELECT ... FOR UPDATE`). Balance
+    /// mutations should go through this so concurrent requests for the same
+    /// user serialize on the row instead of overwriting each other.
+    pub async fn find_for_update(id: &Uuid, conn: &mut sqlx::PgConnection) -> Result<Option<Self>> {
+        Ok(Self::find_one_by_criteria(
+            QueryCriteria::new()
+                .add_valued_filter("id", "=", *id)
+                .for_update(),
+            conn
+        ).await?)
+    }
+
+    pub fn try_pay(&self, amount: i64, reserve_floor: Option<i64>) -> Result<i64> {
+        let balance = self.total_balance();
+        if balance < amount {
             Err(anyhow!("[User::try_pay] Insufficient balance"))
+        } else if reserve_floor.is_some_and(|floor| balance - amount < floor) {
+            Err(anyhow!("[User::try_pay] Payment would drop balance below the reserve floor"))
         } else {
             Ok(amount)
         }
@@ -9,6 +9,7 @@ use crate::user::User;
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default, SqlxObject)]
 #[table_name = "user_referrals"]
+#[conflict_target(code)]
 pub struct UserReferral {
     pub id: Uuid,
 
@@ -0,0 +1,66 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{OrderDirection, QueryCriteria, SqlxCrud, SqlxFilterQuery};
+use metastable_runtime::{AuditLog, Character, CharacterStatus, User};
+use sqlx::types::Uuid;
+
+async fn seed(character: Uuid, author: Uuid, created_at: &[i64], conn: &mut sqlx::PgConnection) {
+    for at in created_at {
+        let log = AuditLog {
+            id: Uuid::new_v4(),
+            character,
+            author,
+            previous_status: CharacterStatus::Reviewing,
+            new_status: CharacterStatus::Published,
+            notes: format!("reviewed at {}", at),
+            ..Default::default()
+        }.create(&mut *conn).await.unwrap();
+
+        // create() stamps the current time, backdate the row explicitly
+        sqlx::query("UPDATE roleplay_character_audit_logs SET created_at = $1 WHERE id = $2")
+            .bind(*at).bind(log.id).execute(&mut *conn).await.unwrap();
+    }
+}
+
+async fn remaining(character: Uuid, conn: &mut sqlx::PgConnection) -> Vec<i64> {
+    AuditLog::find_by_criteria(
+        QueryCriteria::new()
+            .add_valued_filter("character", "=", character)
+            .order_by("created_at", OrderDirection::Asc),
+        &mut *conn
+    ).await.unwrap().iter().map(|log| log.created_at).collect()
+}
+
+#[tokio::test]
+async fn test_prune_keeps_recent_entries_and_the_keep_window() {
+    let db = PostgresClient::setup_connection().await;
+    let mut tx = db.get_client().begin().await.unwrap();
+
+    let user = User { user_id: format!("audit_retention_{}", Uuid::new_v4()), ..Default::default() }
+        .create(&mut *tx).await.unwrap();
+
+    let mut characters = vec![];
+    for _ in 0..2 {
+        characters.push(Character {
+            name: format!("test_audit_retention_{}", Uuid::new_v4()),
+            creator: user.id,
+            ..Default::default()
+        }.create(&mut *tx).await.unwrap().id);
+    }
+    let cutoff = 1_000;
+    seed(characters[0], user.id, &[100, 200, 300, 400, 2_000], &mut tx).await;
+    seed(characters[1], user.id, &[150, 250], &mut tx).await;
+
+    // The newest two per character survive even when they predate the cutoff
+    let pruned = AuditLog::prune_older_than_keeping(cutoff, Some(2), &mut *tx).await.unwrap();
+    assert!(pruned >= 3, "{}", pruned);
+    assert_eq!(remaining(characters[0], &mut tx).await, vec![400, 2_000]);
+    assert_eq!(remaining(characters[1], &mut tx).await, vec![150, 250]);
+
+    // Without a keep window everything before the cutoff goes
+    AuditLog::prune_older_than(cutoff, &mut *tx).await.unwrap();
+    assert_eq!(remaining(characters[0], &mut tx).await, vec![2_000]);
+    assert!(remaining(characters[1], &mut tx).await.is_empty());
+
+    tx.rollback().await.unwrap();
+}
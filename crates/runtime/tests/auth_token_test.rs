@@ -0,0 +1,20 @@
+use metastable_runtime::User;
+
+#[test]
+fn test_auth_token_salt_rotation() {
+    let user = User { user_id: "email_rotation@test".to_string(), ..Default::default() };
+
+    let old_token = user.generate_auth_token("old_salt");
+    let retired_token = user.generate_auth_token("retired_salt");
+    let new_token = user.generate_auth_token("new_salt");
+
+    let previous = vec!["old_salt".to_string()];
+
+    // tokens from the previous salt keep working during the overlap window
+    assert_eq!(User::verify_auth_token(&old_token, "new_salt", &previous).unwrap(), user.user_id);
+    assert_eq!(User::verify_auth_token(&new_token, "new_salt", &previous).unwrap(), user.user_id);
+
+    // a salt dropped from the list no longer verifies
+    assert!(User::verify_auth_token(&retired_token, "new_salt", &previous).is_err());
+    assert!(User::verify_auth_token(&old_token, "new_salt", &[]).is_err());
+}
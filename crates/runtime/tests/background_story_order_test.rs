@@ -0,0 +1,52 @@
+use metastable_runtime::{BackgroundStories, Character, OrderedBackgroundStory};
+use serde_json::json;
+use sqlx::types::Json;
+
+fn story(story: BackgroundStories, order: i32) -> OrderedBackgroundStory {
+    OrderedBackgroundStory { story, order }
+}
+
+#[test]
+fn test_background_stories_render_in_order() {
+    let character = Character {
+        name: "Alice".to_string(),
+        prompts_background_stories: Json(vec![
+            story(BackgroundStories::Dreams("sail around the world".to_string()), 2),
+            story(BackgroundStories::Professions("cartographer".to_string()), 0),
+            story(BackgroundStories::SignificantEvents("lost her ship in a storm".to_string()), 1),
+        ]),
+        ..Default::default()
+    };
+    let prompt = character.build_system_prompt("{{char_background_stories}}", "Bob", &[]);
+
+    let professions = prompt.content.find("cartographer").unwrap();
+    let events = prompt.content.find("lost her ship").unwrap();
+    let dreams = prompt.content.find("sail around").unwrap();
+    assert!(professions < events && events < dreams, "{}", prompt.content);
+}
+
+#[test]
+fn test_stories_without_order_keep_list_order() {
+    // Stored before `order` existed
+    let stored = json!([
+        { "type": "Dreams", "content": "sail around the world" },
+        { "type": "Professions", "content": "cartographer" },
+    ]);
+    let stories: Vec<OrderedBackgroundStory> = serde_json::from_value(stored).unwrap();
+    assert_eq!(stories, vec![
+        story(BackgroundStories::Dreams("sail around the world".to_string()), 0),
+        story(BackgroundStories::Professions("cartographer".to_string()), 0),
+    ]);
+
+    let character = Character { prompts_background_stories: Json(stories), ..Default::default() };
+    let prompt = character.build_system_prompt("{{char_background_stories}}", "Bob", &[]);
+    assert!(prompt.content.find("sail around").unwrap() < prompt.content.find("cartographer").unwrap());
+
+    let numbered = OrderedBackgroundStory::numbered(vec![
+        BackgroundStories::Dreams("a".to_string()),
+        BackgroundStories::Regrets("b".to_string()),
+    ]);
+    let value = serde_json::to_value(&numbered).unwrap();
+    assert_eq!(value[1]["order"], 1);
+    assert_eq!(serde_json::from_value::<Vec<OrderedBackgroundStory>>(value).unwrap(), numbered);
+}
@@ -0,0 +1,30 @@
+use metastable_runtime::{User, UserUsagePoints};
+use serde_json::json;
+use sqlx::types::Uuid;
+
+#[test]
+fn test_chat_payment_reports_bucket_breakdown() {
+    let mut user = User {
+        id: Uuid::new_v4(),
+        running_claimed_balance: 1,
+        running_misc_balance: 1,
+        running_purchased_balance: 5,
+        ..Default::default()
+    };
+
+    let log = user.pay_for_chat_message(4, Uuid::new_v4(), Uuid::new_v4(), 1, None).unwrap();
+    assert_eq!(log.usage(), UserUsagePoints {
+        points_consumed_claimed: 1,
+        points_consumed_misc: 1,
+        points_consumed_purchased: 2,
+    });
+
+    let body = json!({ "usage": log.usage() });
+    assert_eq!(body["usage"]["points_consumed_claimed"], 1);
+    assert_eq!(body["usage"]["points_consumed_misc"], 1);
+    assert_eq!(body["usage"]["points_consumed_purchased"], 2);
+
+    assert_eq!(user.running_claimed_balance, 0);
+    assert_eq!(user.running_misc_balance, 0);
+    assert_eq!(user.running_purchased_balance, 3);
+}
@@ -0,0 +1,43 @@
+use metastable_runtime::{CharacterFeature, CharacterFeatureKind, CharacterFeatureSet};
+
+#[test]
+fn test_setting_avatar_twice_keeps_one_entry() {
+    let mut features = CharacterFeatureSet::from(vec![CharacterFeature::Roleplay]);
+
+    features.set(CharacterFeature::AvatarImage("https://example.com/a.png".to_string()));
+    features.set(CharacterFeature::AvatarImage("https://example.com/b.png".to_string()));
+
+    assert_eq!(&*features, &[
+        CharacterFeature::Roleplay,
+        CharacterFeature::AvatarImage("https://example.com/b.png".to_string()),
+    ]);
+}
+
+#[test]
+fn test_remove_background_feature() {
+    let mut features = CharacterFeatureSet::from(vec![
+        CharacterFeature::Roleplay,
+        CharacterFeature::BackgroundImage("https://example.com/bg.png".to_string()),
+        CharacterFeature::Voice("voice-1".to_string()),
+    ]);
+
+    assert!(features.remove(CharacterFeatureKind::BackgroundImage));
+    assert!(!features.remove(CharacterFeatureKind::BackgroundImage));
+    assert!(features.get(CharacterFeatureKind::BackgroundImage).is_none());
+    assert_eq!(features.len(), 2);
+    assert!(features.contains(&CharacterFeature::Voice("voice-1".to_string())));
+}
+
+#[test]
+fn test_feature_set_serializes_as_list() {
+    let features = CharacterFeatureSet::from(vec![
+        CharacterFeature::Others("a".to_string()),
+        CharacterFeature::Others("b".to_string()),
+        CharacterFeature::Others("a".to_string()),
+    ]);
+    assert_eq!(features.len(), 2);
+
+    let json = serde_json::to_value(&features).unwrap();
+    assert_eq!(json, serde_json::to_value(features.clone().into_inner()).unwrap());
+    assert_eq!(serde_json::from_value::<CharacterFeatureSet>(json).unwrap(), features);
+}
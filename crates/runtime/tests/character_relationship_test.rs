@@ -0,0 +1,66 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::SqlxCrud;
+use metastable_runtime::{Character, CharacterHistory, CharacterRelationship, Relationships, User};
+use sqlx::types::{Json, Uuid};
+
+fn character(name: &str, creator: Uuid, related_characters: Vec<CharacterRelationship>) -> Character {
+    Character {
+        name: format!("{}_{}", name, Uuid::new_v4()),
+        creator,
+        related_characters: Json(related_characters),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_referencing_finds_characters_linking_to_a_character() {
+    let db = PostgresClient::setup_connection().await;
+    let mut tx = db.get_client().begin().await.unwrap();
+
+    let user = User { user_id: format!("character_relationship_{}", Uuid::new_v4()), ..Default::default() }
+        .create(&mut *tx).await.unwrap();
+
+    let hero = character("hero", user.id, vec![]).create(&mut *tx).await.unwrap();
+    let rival = character("rival", user.id, vec![
+        CharacterRelationship { relationship: Relationships::Enemies("The hero foiled my plans".to_string()), character_id: Some(hero.id) },
+        CharacterRelationship { relationship: Relationships::Family("An estranged brother".to_string()), character_id: None },
+    ]).create(&mut *tx).await.unwrap();
+    let bystander = character("bystander", user.id, vec![
+        CharacterRelationship { relationship: Relationships::Friends("Everyone in town".to_string()), character_id: None },
+    ]).create(&mut *tx).await.unwrap();
+
+    let referencing = Character::referencing(hero.id, &mut *tx).await.unwrap();
+    assert_eq!(referencing.iter().map(|c| c.id).collect::<Vec<_>>(), vec![rival.id]);
+    assert_eq!(referencing[0].related_characters.0[0].character_id, Some(hero.id));
+
+    assert!(Character::referencing(rival.id, &mut *tx).await.unwrap().is_empty());
+    assert!(Character::referencing(bystander.id, &mut *tx).await.unwrap().is_empty());
+
+    tx.rollback().await.unwrap();
+}
+
+#[test]
+fn test_related_characters_render_with_relationships() {
+    let rival = Character {
+        prompts_relationships: Json(vec![Relationships::Family("A younger sister".to_string())]),
+        ..character("rival", Uuid::new_v4(), vec![
+            CharacterRelationship { relationship: Relationships::Enemies("The hero foiled my plans".to_string()), character_id: Some(Uuid::new_v4()) },
+        ])
+    };
+
+    let prompt = rival.build_system_prompt("{{char_relationships}}", "Bob", &[]);
+    assert!(prompt.content.contains("A younger sister"), "{}", prompt.content);
+    assert!(prompt.content.contains("The hero foiled my plans"), "{}", prompt.content);
+}
+
+#[test]
+fn test_history_snapshots_related_characters() {
+    let hero = Uuid::new_v4();
+    let rival = character("rival", Uuid::new_v4(), vec![
+        CharacterRelationship { relationship: Relationships::Enemies("The hero foiled my plans".to_string()), character_id: Some(hero) },
+    ]);
+
+    let history = CharacterHistory::new(rival.clone());
+    assert_eq!(history.related_characters.0, rival.related_characters.0);
+}
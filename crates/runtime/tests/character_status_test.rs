@@ -0,0 +1,51 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{QueryCriteria, SqlxCrud, SqlxFilterQuery};
+use metastable_runtime::{Character, CharacterStatus, User};
+use sqlx::types::Uuid;
+
+async fn create_character(creator: Uuid, status: CharacterStatus, conn: &mut sqlx::PgConnection) -> Character {
+    Character {
+        name: format!("test_bulk_status_{}", Uuid::new_v4()),
+        creator,
+        status,
+        ..Default::default()
+    }.create(conn).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_bulk_publish_reviewing_characters() {
+    let db = PostgresClient::setup_connection().await;
+    let mut tx = db.get_client().begin().await.unwrap();
+
+    let user = User { user_id: format!("bulk_status_{}", Uuid::new_v4()), ..Default::default() }
+        .create(&mut *tx).await.unwrap();
+
+    let reviewing_a = create_character(user.id, CharacterStatus::Reviewing, &mut tx).await;
+    let reviewing_b = create_character(user.id, CharacterStatus::Reviewing, &mut tx).await;
+    let draft = create_character(user.id, CharacterStatus::Draft, &mut tx).await;
+    let missing = Uuid::new_v4();
+
+    let ids = vec![reviewing_a.id, reviewing_b.id, draft.id, missing];
+    let outcomes = Character::bulk_update_status(&ids, CharacterStatus::Published, &mut tx).await.unwrap();
+
+    assert_eq!(outcomes.len(), 4);
+    assert!(outcomes[0].1.is_ok());
+    assert!(outcomes[1].1.is_ok());
+    assert!(outcomes[2].1.as_ref().unwrap_err().contains("illegal transition"));
+    assert!(outcomes[3].1.as_ref().unwrap_err().contains("not found"));
+
+    for (id, expected) in [
+        (reviewing_a.id, CharacterStatus::Published),
+        (reviewing_b.id, CharacterStatus::Published),
+        (draft.id, CharacterStatus::Draft),
+    ] {
+        let character = Character::find_one_by_criteria(
+            QueryCriteria::new().add_valued_filter("id", "=", id),
+            &mut *tx
+        ).await.unwrap().unwrap();
+        assert_eq!(character.status, expected);
+    }
+
+    tx.rollback().await.unwrap();
+}
@@ -0,0 +1,74 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{MigrateOptions, SchemaMigrator, SqlxCrud, SqlxSchema};
+
+// The same table before and after two columns with declared defaults are added
+mod before {
+    use metastable_database::SqlxObject;
+    use serde::{Deserialize, Serialize};
+    use sqlx::types::Uuid;
+
+    #[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+    #[table_name = "test_column_default"]
+    pub struct Flagged {
+        pub id: Uuid,
+        pub name: String,
+        pub created_at: i64,
+        pub updated_at: i64,
+    }
+}
+
+mod after {
+    use metastable_database::SqlxObject;
+    use serde::{Deserialize, Serialize};
+    use sqlx::types::Uuid;
+
+    #[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+    #[table_name = "test_column_default"]
+    pub struct Flagged {
+        pub id: Uuid,
+        pub name: String,
+        #[column_default = "1"]
+        pub version: i64,
+        #[column_default = "true"]
+        pub enabled: bool,
+        #[column_default = "'unnamed'"]
+        pub label: Option<String>,
+        pub created_at: i64,
+        pub updated_at: i64,
+    }
+}
+
+#[tokio::test]
+async fn test_declared_defaults_are_used() {
+    let db = PostgresClient::setup_connection().await;
+    let pool: &sqlx::PgPool = db.get_client();
+    let options = MigrateOptions { dry_run: false, allow_drops: false };
+
+    let create_sql = <after::Flagged as SqlxSchema>::create_table_sql();
+    assert!(create_sql.contains("\"version\" BIGINT DEFAULT 1 NOT NULL"), "{}", create_sql);
+    assert!(create_sql.contains("\"enabled\" BOOLEAN DEFAULT true NOT NULL"), "{}", create_sql);
+    assert!(create_sql.contains("\"label\" TEXT DEFAULT 'unnamed'"), "{}", create_sql);
+
+    sqlx::query("DROP TABLE IF EXISTS test_column_default").execute(pool).await.unwrap();
+    before::Flagged::migrate(pool).await.unwrap();
+    before::Flagged { name: "existing".to_string(), ..Default::default() }.create(pool).await.unwrap();
+
+    let applied = after::Flagged::migrate_with(pool, options).await.unwrap();
+    assert!(applied.iter().any(|s| s.ends_with("ADD COLUMN \"version\" BIGINT DEFAULT 1 NOT NULL")), "{:?}", applied);
+    assert!(applied.iter().any(|s| s.ends_with("ADD COLUMN \"enabled\" BOOLEAN DEFAULT true NOT NULL")), "{:?}", applied);
+    assert!(applied.iter().any(|s| s.ends_with("ADD COLUMN \"label\" TEXT DEFAULT 'unnamed'")), "{:?}", applied);
+
+    // Existing rows are backfilled with the declared defaults rather than the type's
+    let (version, enabled, label): (i64, bool, Option<String>) = sqlx::query_as(
+        "SELECT version, enabled, label FROM test_column_default WHERE name = 'existing'"
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    assert_eq!((version, enabled, label.as_deref()), (1, true, Some("unnamed")));
+
+    assert!(after::Flagged::migrate_with(pool, options).await.unwrap().is_empty());
+
+    sqlx::query("DROP TABLE test_column_default").execute(pool).await.unwrap();
+}
@@ -0,0 +1,134 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{Cursor, OnConflict, SchemaMigrator, SqlxObject};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+#[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+#[table_name = "test_character_tags"]
+pub struct CharacterTag {
+    #[primary_key]
+    pub character_id: Uuid,
+    #[primary_key]
+    pub tag: String,
+    pub weight: i32,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+fn by_key(character_id: Uuid, tag: &str) -> QueryCriteria {
+    QueryCriteria::new()
+        .add_valued_filter("character_id", "=", character_id)
+        .add_valued_filter("tag", "=", tag.to_string())
+}
+
+#[test]
+fn test_composite_key_schema() {
+    assert_eq!(CharacterTag::primary_key_columns(), &["character_id", "tag"]);
+    assert_eq!(CharacterTag::id_column_name(), "character_id");
+    assert!(CharacterTag::create_table_sql().contains("PRIMARY KEY (\"character_id\", \"tag\")"));
+
+    let character_id = Uuid::new_v4();
+    let tag = CharacterTag { character_id, tag: "brave".to_string(), ..Default::default() };
+    assert_eq!(tag.get_id_value(), (character_id, "brave".to_string()));
+}
+
+#[tokio::test]
+async fn test_composite_key_crud() {
+    let db = PostgresClient::setup_connection().await;
+    let pool: &sqlx::PgPool = db.get_client();
+
+    sqlx::query("DROP TABLE IF EXISTS test_character_tags").execute(pool).await.unwrap();
+    CharacterTag::migrate(pool).await.unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    let character_id = Uuid::new_v4();
+    let brave = CharacterTag { character_id, tag: "brave".to_string(), weight: 1, ..Default::default() }
+        .create(&mut *tx).await.unwrap();
+    let shy = CharacterTag { character_id, tag: "shy".to_string(), weight: 2, ..Default::default() }
+        .create(&mut *tx).await.unwrap();
+
+    // Updates and deletes must only touch the row matching the whole key
+    let mut updated = brave.clone();
+    updated.weight = 5;
+    let updated = updated.update(&mut *tx).await.unwrap();
+    assert_eq!(updated.weight, 5);
+    let untouched = CharacterTag::find_one_by_criteria(by_key(character_id, "shy"), &mut *tx).await.unwrap().unwrap();
+    assert_eq!(untouched.weight, 2);
+
+    assert_eq!(shy.clone().delete(&mut *tx).await.unwrap(), 1);
+    assert!(CharacterTag::find_one_by_criteria(by_key(character_id, "shy"), &mut *tx).await.unwrap().is_none());
+    assert!(CharacterTag::find_one_by_criteria(by_key(character_id, "brave"), &mut *tx).await.unwrap().is_some());
+
+    // Bulk deletes match each (character_id, tag) pair, not the columns independently
+    let other_id = Uuid::new_v4();
+    CharacterTag { character_id: other_id, tag: "shy".to_string(), ..Default::default() }
+        .create(&mut *tx).await.unwrap();
+    let keys = [(character_id, "shy".to_string()), (other_id, "brave".to_string())];
+    assert_eq!(CharacterTag::delete_by_ids(&keys, &mut *tx).await.unwrap(), 0);
+    assert_eq!(CharacterTag::delete_by_ids(&[(other_id, "shy".to_string())], &mut *tx).await.unwrap(), 1);
+
+    // Restore conflicts on the composite key
+    assert!(brave.clone().restore(OnConflict::Skip, &mut *tx).await.unwrap().is_none());
+    let restored = shy.restore(OnConflict::Skip, &mut *tx).await.unwrap().expect("deleted row should restore");
+    assert_eq!(restored.weight, 2);
+    let overwritten = brave.restore(OnConflict::Overwrite, &mut *tx).await.unwrap().unwrap();
+    assert_eq!(overwritten.weight, 1);
+    tx.commit().await.unwrap();
+
+    let duplicate = CharacterTag { character_id, tag: "brave".to_string(), ..Default::default() }
+        .create(pool).await;
+    assert!(duplicate.is_err());
+
+    sqlx::query("DROP TABLE IF EXISTS test_character_tags").execute(pool).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_composite_key_keyset_pagination() {
+    #[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+    #[table_name = "test_character_tag_pages"]
+    pub struct PagedTag {
+        #[primary_key]
+        pub character_id: Uuid,
+        #[primary_key]
+        pub tag: String,
+        pub weight: i32,
+        pub created_at: i64,
+        pub updated_at: i64,
+    }
+
+    let db = PostgresClient::setup_connection().await;
+    let pool: &sqlx::PgPool = db.get_client();
+
+    sqlx::query("DROP TABLE IF EXISTS test_character_tag_pages").execute(pool).await.unwrap();
+    PagedTag::migrate(pool).await.unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    // Every row shares one weight, and each character has several tags, so
+    // only the whole (character_id, tag) key tells rows apart
+    let characters = [Uuid::new_v4(), Uuid::new_v4()];
+    let mut expected = Vec::new();
+    for character_id in characters {
+        for tag in ["brave", "calm", "kind", "shy", "witty"] {
+            PagedTag { character_id, tag: tag.to_string(), weight: 1, ..Default::default() }
+                .create(&mut *tx).await.unwrap();
+            expected.push((character_id, tag.to_string()));
+        }
+    }
+    expected.sort();
+
+    let mut cursor: Option<Cursor<i32, (Uuid, String)>> = None;
+    let mut seen = Vec::new();
+    loop {
+        let page = PagedTag::find_page(QueryCriteria::new().after_cursor("weight", cursor), 3, &mut *tx).await.unwrap();
+        seen.extend(page.items.iter().map(|t| t.get_id_value()));
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    assert_eq!(seen, expected);
+
+    tx.rollback().await.unwrap();
+    sqlx::query("DROP TABLE IF EXISTS test_character_tag_pages").execute(pool).await.unwrap();
+}
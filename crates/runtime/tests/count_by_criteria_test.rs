@@ -0,0 +1,55 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{OrderDirection, SchemaMigrator, SqlxObject};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+#[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+#[table_name = "test_count_by_criteria"]
+#[soft_delete]
+pub struct Counted {
+    pub id: Uuid,
+    pub owner: Uuid,
+    pub score: i32,
+    pub deleted_at: Option<i64>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[tokio::test]
+async fn test_count_matches_find_without_paging() {
+    let db = PostgresClient::setup_connection().await;
+    let pool: &sqlx::PgPool = db.get_client();
+
+    sqlx::query("DROP TABLE IF EXISTS test_count_by_criteria").execute(pool).await.unwrap();
+    Counted::migrate(pool).await.unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    let owner = Uuid::new_v4();
+    let rows = (0..10)
+        .map(|score| Counted { owner, score, ..Default::default() })
+        .chain(std::iter::once(Counted { owner: Uuid::new_v4(), score: 100, ..Default::default() }))
+        .collect::<Vec<_>>();
+    let created = Counted::create_many(rows, &mut *tx).await.unwrap();
+
+    let by_owner = || QueryCriteria::new().add_valued_filter("owner", "=", owner);
+    assert_eq!(Counted::count_by_criteria(QueryCriteria::new(), &mut *tx).await.unwrap(), 11);
+    assert_eq!(Counted::count_by_criteria(by_owner(), &mut *tx).await.unwrap(), 10);
+    assert_eq!(
+        Counted::count_by_criteria(by_owner().add_valued_filter("score", ">=", 7), &mut *tx).await.unwrap(),
+        3
+    );
+
+    // Paging and ordering do not affect the total
+    let paged = || by_owner().order_by("score", OrderDirection::Desc).limit(2).offset(4);
+    assert_eq!(Counted::find_by_criteria(paged(), &mut *tx).await.unwrap().len(), 2);
+    assert_eq!(Counted::count_by_criteria(paged(), &mut *tx).await.unwrap(), 10);
+
+    // Soft-deleted rows are only counted on request
+    created[0].clone().delete(&mut *tx).await.unwrap();
+    assert_eq!(Counted::count_by_criteria(by_owner(), &mut *tx).await.unwrap(), 9);
+    assert_eq!(Counted::count_by_criteria(by_owner().include_deleted(), &mut *tx).await.unwrap(), 10);
+
+    tx.rollback().await.unwrap();
+    sqlx::query("DROP TABLE IF EXISTS test_count_by_criteria").execute(pool).await.unwrap();
+}
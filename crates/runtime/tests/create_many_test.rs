@@ -0,0 +1,56 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{SchemaMigrator, SqlxObject, POSTGRES_MAX_BIND_PARAMS};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+#[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+#[table_name = "test_create_many"]
+pub struct BulkRow {
+    pub id: Uuid,
+    pub position: i64,
+    pub label: String,
+    pub note: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[tokio::test]
+async fn test_create_many_chunks_and_keeps_order() {
+    let db = PostgresClient::setup_connection().await;
+    let pool: &sqlx::PgPool = db.get_client();
+
+    sqlx::query("DROP TABLE IF EXISTS test_create_many").execute(pool).await.unwrap();
+    BulkRow::migrate(pool).await.unwrap();
+
+    // Three bound columns per row: this needs more than one statement
+    let count = POSTGRES_MAX_BIND_PARAMS / 3 + 500;
+    let rows = (0..count)
+        .map(|i| BulkRow {
+            position: i as i64,
+            label: format!("row {}", i),
+            note: (i % 2 == 0).then(|| "even".to_string()),
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+
+    let mut tx = pool.begin().await.unwrap();
+    let created = BulkRow::create_many(rows, &mut *tx).await.unwrap();
+
+    assert_eq!(created.len(), count);
+    for (i, row) in created.iter().enumerate() {
+        assert_eq!(row.position, i as i64);
+        assert_eq!(row.label, format!("row {}", i));
+        assert_eq!(row.note.is_some(), i % 2 == 0);
+        assert!(!row.id.is_nil());
+        assert!(row.created_at > 0);
+    }
+
+    let stored: i64 = sqlx::query_scalar("SELECT count(*) FROM test_create_many").fetch_one(&mut *tx).await.unwrap();
+    assert_eq!(stored, count as i64);
+
+    assert!(BulkRow::create_many(vec![], &mut *tx).await.unwrap().is_empty());
+    tx.rollback().await.unwrap();
+
+    sqlx::query("DROP TABLE IF EXISTS test_create_many").execute(pool).await.unwrap();
+}
@@ -0,0 +1,96 @@
+use std::collections::HashSet;
+
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{Cursor, QueryCriteria, SqlxCrud, SqlxFilterQuery};
+use metastable_runtime::{Message, MessageType};
+use sqlx::types::{Json, Uuid};
+
+fn message(session: Uuid, i: usize) -> Message {
+    Message {
+        id: Uuid::new_v4(),
+        owner: Uuid::nil(),
+        system_config: Uuid::nil(),
+        session: Some(session),
+        turn_index: None,
+        user_message_content: format!("message {}", i),
+        user_message_content_type: MessageType::Text,
+        input_toolcall: Json(None),
+        assistant_message_content: String::new(),
+        assistant_message_content_type: MessageType::Text,
+        assistant_message_tool_call: Json(None),
+        summary: None,
+        model_name: String::new(),
+        usage: Json(None),
+        finish_reason: None,
+        refusal: None,
+        is_stale: false,
+        is_memorizeable: false,
+        is_in_memory: false,
+        is_migrated: false,
+        migration_claimed_at: None,
+        deleted_at: None,
+        created_at: 0,
+        updated_at: 0,
+    }
+}
+
+#[tokio::test]
+async fn test_paginate_messages_by_cursor() {
+    let db = PostgresClient::setup_connection().await;
+    let mut tx = db.get_client().begin().await.unwrap();
+
+    // Inserted in one transaction, so every row shares the same `created_at`
+    let session = Uuid::new_v4();
+    let seeded = Message::create_many((0..250).map(|i| message(session, i)).collect(), &mut *tx).await.unwrap();
+    assert!(seeded.iter().all(|m| m.created_at == seeded[0].created_at));
+    let seeded_ids = seeded.iter().map(|m| m.id).collect::<HashSet<_>>();
+
+    let in_session = || QueryCriteria::new().add_valued_filter("session", "=", session);
+
+    // By `created_at`, ties broken by `id`
+    let mut cursor: Option<Cursor<i64>> = None;
+    let mut seen = Vec::new();
+    let mut page_sizes = Vec::new();
+    loop {
+        let page = Message::find_page(in_session().after_cursor("created_at", cursor), 100, &mut *tx).await.unwrap();
+        page_sizes.push(page.items.len());
+        seen.extend(page.items.iter().map(|m| m.id));
+        match page.next_cursor {
+            Some(next) => {
+                assert_eq!(next.id, page.items.last().unwrap().id);
+                assert_eq!(next.value, seeded[0].created_at);
+                cursor = Some(next);
+            }
+            None => break,
+        }
+    }
+    assert_eq!(page_sizes, vec![100, 100, 50]);
+    assert_eq!(seen.len(), 250);
+    assert_eq!(seen.iter().copied().collect::<HashSet<_>>(), seeded_ids);
+
+    // By `id`; a final full page is followed by an empty one
+    let mut cursor: Option<Cursor<Uuid>> = None;
+    let mut seen = Vec::new();
+    let mut page_sizes = Vec::new();
+    loop {
+        let page = Message::find_page(in_session().after_cursor("id", cursor), 50, &mut *tx).await.unwrap();
+        page_sizes.push(page.items.len());
+        seen.extend(page.items.iter().map(|m| m.id));
+        cursor = match page.next_cursor {
+            Some(next) => Some(next),
+            None => break,
+        };
+    }
+    assert_eq!(page_sizes, vec![50, 50, 50, 50, 50, 0]);
+    let mut sorted = seen.clone();
+    sorted.sort();
+    assert_eq!(seen, sorted);
+    assert_eq!(seen.into_iter().collect::<HashSet<_>>(), seeded_ids);
+
+    // Criteria without a cursor column are rejected
+    let err = Message::find_page::<_, i64>(in_session(), 10, &mut *tx).await.unwrap_err();
+    assert!(err.to_string().contains("after_cursor"), "{}", err);
+
+    tx.rollback().await.unwrap();
+}
@@ -0,0 +1,42 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{SchemaMigrator, SqlxObject};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+#[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+#[table_name = "test_delete_by_ids"]
+pub struct Disposable {
+    pub id: Uuid,
+    pub label: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[tokio::test]
+async fn test_delete_by_ids_removes_listed_rows() {
+    let db = PostgresClient::setup_connection().await;
+    let pool: &sqlx::PgPool = db.get_client();
+
+    sqlx::query("DROP TABLE IF EXISTS test_delete_by_ids").execute(pool).await.unwrap();
+    Disposable::migrate(pool).await.unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    let items = (0..5)
+        .map(|i| Disposable { label: format!("item {}", i), ..Default::default() })
+        .collect();
+    let created = Disposable::create_many(items, &mut *tx).await.unwrap();
+
+    let doomed: Vec<Uuid> = created.iter().step_by(2).map(|d| d.id).collect();
+    assert_eq!(Disposable::delete_by_ids(&doomed, &mut *tx).await.unwrap(), 3);
+    assert_eq!(Disposable::delete_by_ids(&doomed, &mut *tx).await.unwrap(), 0);
+    assert_eq!(Disposable::delete_by_ids(&[], &mut *tx).await.unwrap(), 0);
+
+    let remaining = Disposable::find_by_criteria(QueryCriteria::new(), &mut *tx).await.unwrap();
+    let mut labels: Vec<String> = remaining.into_iter().map(|d| d.label).collect();
+    labels.sort();
+    assert_eq!(labels, vec!["item 1", "item 3"]);
+
+    tx.rollback().await.unwrap();
+    sqlx::query("DROP TABLE IF EXISTS test_delete_by_ids").execute(pool).await.unwrap();
+}
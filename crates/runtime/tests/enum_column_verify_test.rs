@@ -0,0 +1,33 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::verify_text_enum_column;
+use metastable_runtime::CharacterStatus;
+use sqlx::types::Uuid;
+
+#[tokio::test]
+async fn test_verifier_flags_only_malformed_enum_values() {
+    let db = PostgresClient::setup_connection().await;
+    let mut tx = db.get_client().begin().await.unwrap();
+
+    sqlx::query("CREATE TEMP TABLE test_enum_verify (id UUID PRIMARY KEY, status JSONB NOT NULL) ON COMMIT DROP")
+        .execute(&mut *tx).await.unwrap();
+
+    let malformed = Uuid::new_v4();
+    for (id, status) in [
+        (Uuid::new_v4(), r#""Published""#),
+        (Uuid::new_v4(), r#""Draft""#),
+        (malformed, r#""\"Reviewing\"""#),
+    ] {
+        sqlx::query("INSERT INTO test_enum_verify (id, status) VALUES ($1, $2::jsonb)")
+            .bind(id)
+            .bind(status)
+            .execute(&mut *tx).await.unwrap();
+    }
+
+    let invalid = verify_text_enum_column::<CharacterStatus, _>(&mut *tx, "test_enum_verify", "status").await.unwrap();
+    assert_eq!(invalid.len(), 1);
+    assert_eq!(invalid[0].id, malformed);
+    assert_eq!(invalid[0].value, serde_json::json!("\"Reviewing\""));
+
+    tx.rollback().await.unwrap();
+}
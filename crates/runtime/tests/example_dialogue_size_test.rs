@@ -0,0 +1,46 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::SqlxCrud;
+use metastable_runtime::{Character, User, DEFAULT_MAX_EXAMPLE_DIALOGUE_CHARS};
+use sqlx::types::{Json, Uuid};
+
+fn character(creator: Uuid, example: &str, additional: &[&str]) -> Character {
+    Character {
+        name: "Alice".to_string(),
+        creator,
+        prompts_example_dialogue: example.to_string(),
+        prompts_additional_example_dialogue: Json(additional.iter().map(|d| d.to_string()).collect()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_example_dialogue_limit_counts_all_example_fields() {
+    let c = character(Uuid::nil(), "你好世界", &["abc", "de"]);
+    assert_eq!(c.example_dialogue_chars(), 9);
+    assert!(c.validate_example_dialogue_size(9).is_ok());
+
+    let err = c.validate_example_dialogue_size(8).unwrap_err().to_string();
+    assert!(err.contains("9 characters") && err.contains("limit is 8"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_create_character_under_and_over_limit() {
+    let db = PostgresClient::setup_connection().await;
+    let mut tx = db.get_client().begin().await.unwrap();
+
+    let user = User { user_id: format!("examples_{}", Uuid::new_v4()), ..Default::default() }
+        .create(&mut *tx).await.unwrap();
+
+    let half = "x".repeat(DEFAULT_MAX_EXAMPLE_DIALOGUE_CHARS / 2);
+    let under = character(user.id, &half, &[&half]);
+    under.validate_example_dialogue_size(DEFAULT_MAX_EXAMPLE_DIALOGUE_CHARS).unwrap();
+    let created = under.create(&mut *tx).await.unwrap();
+    assert_eq!(created.example_dialogue_chars(), DEFAULT_MAX_EXAMPLE_DIALOGUE_CHARS);
+
+    // One character over, split across the additional examples
+    let over = character(user.id, &half, &[&half, "!"]);
+    assert!(over.validate_example_dialogue_size(DEFAULT_MAX_EXAMPLE_DIALOGUE_CHARS).is_err());
+
+    tx.rollback().await.unwrap();
+}
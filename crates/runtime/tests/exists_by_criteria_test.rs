@@ -0,0 +1,52 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{OrderDirection, SchemaMigrator, SqlxObject};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+#[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+#[table_name = "test_exists_by_criteria"]
+#[soft_delete]
+pub struct Probed {
+    pub id: Uuid,
+    pub owner: Uuid,
+    pub score: i32,
+    pub deleted_at: Option<i64>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[tokio::test]
+async fn test_exists_follows_find_filters() {
+    let db = PostgresClient::setup_connection().await;
+    let pool: &sqlx::PgPool = db.get_client();
+
+    sqlx::query("DROP TABLE IF EXISTS test_exists_by_criteria").execute(pool).await.unwrap();
+    Probed::migrate(pool).await.unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    let owner = Uuid::new_v4();
+    assert!(!Probed::exists_by_criteria(QueryCriteria::new(), &mut *tx).await.unwrap());
+
+    let created = Probed { owner, score: 5, ..Default::default() }.create(&mut *tx).await.unwrap();
+
+    let by_owner = || QueryCriteria::new().add_valued_filter("owner", "=", owner);
+    assert!(Probed::exists_by_criteria(by_owner(), &mut *tx).await.unwrap());
+    assert!(Probed::exists_by_criteria(by_owner().add_valued_filter("score", "=", 5), &mut *tx).await.unwrap());
+    assert!(!Probed::exists_by_criteria(by_owner().add_valued_filter("score", ">", 5), &mut *tx).await.unwrap());
+    assert!(!Probed::exists_by_criteria(
+        QueryCriteria::new().add_valued_filter("owner", "=", Uuid::new_v4()), &mut *tx
+    ).await.unwrap());
+
+    // Paging and ordering are ignored
+    let paged = by_owner().order_by("score", OrderDirection::Desc).limit(1).offset(3);
+    assert!(Probed::exists_by_criteria(paged, &mut *tx).await.unwrap());
+
+    // Soft-deleted rows only count on request
+    created.delete(&mut *tx).await.unwrap();
+    assert!(!Probed::exists_by_criteria(by_owner(), &mut *tx).await.unwrap());
+    assert!(Probed::exists_by_criteria(by_owner().include_deleted(), &mut *tx).await.unwrap());
+
+    tx.rollback().await.unwrap();
+    sqlx::query("DROP TABLE IF EXISTS test_exists_by_criteria").execute(pool).await.unwrap();
+}
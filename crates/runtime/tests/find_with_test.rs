@@ -0,0 +1,99 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{OrderDirection, QueryCriteria, SchemaMigrator, SqlxCrud};
+use sqlx::types::Uuid;
+
+use authors::Author;
+use books::Book;
+
+// Separate modules, as each derive imports the database traits
+mod authors {
+    use metastable_database::SqlxObject;
+    use serde::{Deserialize, Serialize};
+    use sqlx::types::Uuid;
+
+    #[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+    #[table_name = "test_find_with_authors"]
+    #[soft_delete]
+    pub struct Author {
+        pub id: Uuid,
+        pub name: String,
+        pub deleted_at: Option<i64>,
+        pub created_at: i64,
+        pub updated_at: i64,
+    }
+}
+
+mod books {
+    use metastable_database::SqlxObject;
+    use serde::{Deserialize, Serialize};
+    use sqlx::types::Uuid;
+
+    use super::Author;
+
+    #[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+    #[table_name = "test_find_with_books"]
+    pub struct Book {
+        pub id: Uuid,
+        pub name: String,
+        pub pages: i32,
+        #[foreign_key(referenced_table = "test_find_with_authors", related_rust_type = "Author")]
+        pub author: Uuid,
+        #[foreign_key(referenced_table = "test_find_with_authors", related_rust_type = "Author")]
+        pub editor: Option<Uuid>,
+        pub created_at: i64,
+        pub updated_at: i64,
+    }
+}
+
+#[tokio::test]
+async fn test_find_with_joins_the_foreign_key() {
+    let db = PostgresClient::setup_connection().await;
+    let pool: &sqlx::PgPool = db.get_client();
+
+    sqlx::query("DROP TABLE IF EXISTS test_find_with_books").execute(pool).await.unwrap();
+    sqlx::query("DROP TABLE IF EXISTS test_find_with_authors").execute(pool).await.unwrap();
+    Author::migrate(pool).await.unwrap();
+    Book::migrate(pool).await.unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    let ada = Author { name: "Ada".to_string(), ..Default::default() }.create(&mut *tx).await.unwrap();
+    let bo = Author { name: "Bo".to_string(), ..Default::default() }.create(&mut *tx).await.unwrap();
+    let book = |name: &str, pages: i32, author: &Author, editor: Option<Uuid>| Book {
+        name: name.to_string(), pages, author: author.id, editor, ..Default::default()
+    };
+    Book::create_many(vec![
+        book("Ada", 300, &ada, Some(bo.id)),
+        book("Engines", 120, &ada, None),
+        book("Rivers", 200, &bo, Some(ada.id)),
+    ], &mut *tx).await.unwrap();
+
+    // `name` and `id` exist on both tables; bare columns still refer to books
+    let by_pages = || QueryCriteria::new().order_by("pages", OrderDirection::Desc);
+    let listed = Book::find_by_criteria_with_author(by_pages(), &mut *tx).await.unwrap();
+    let names: Vec<(&str, &str)> = listed.iter()
+        .map(|(b, a)| (b.name.as_str(), a.as_ref().unwrap().name.as_str()))
+        .collect();
+    assert_eq!(names, vec![("Ada", "Ada"), ("Rivers", "Bo"), ("Engines", "Ada")]);
+    assert_eq!(listed[1].1.as_ref().unwrap().id, bo.id);
+
+    let filtered = Book::find_by_criteria_with_author(
+        by_pages().add_valued_filter("name", "=", "Ada".to_string()).limit(5), &mut *tx
+    ).await.unwrap();
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered[0].0.pages, 300);
+
+    // Missing optional keys and soft-deleted authors come back as `None`
+    let edited = Book::find_by_criteria_with_editor(by_pages().for_update(), &mut *tx).await.unwrap();
+    let editors: Vec<Option<Uuid>> = edited.iter().map(|(_, e)| e.as_ref().map(|e| e.id)).collect();
+    assert_eq!(editors, vec![Some(bo.id), Some(ada.id), None]);
+
+    bo.delete(&mut *tx).await.unwrap();
+    let listed = Book::find_by_criteria_with_author(by_pages(), &mut *tx).await.unwrap();
+    assert_eq!(listed.len(), 3);
+    assert!(listed[1].1.is_none());
+
+    tx.rollback().await.unwrap();
+    sqlx::query("DROP TABLE IF EXISTS test_find_with_books").execute(pool).await.unwrap();
+    sqlx::query("DROP TABLE IF EXISTS test_find_with_authors").execute(pool).await.unwrap();
+}
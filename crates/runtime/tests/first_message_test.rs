@@ -0,0 +1,28 @@
+use metastable_runtime::{Character, CharacterLanguage};
+use serde_json::Value;
+
+fn greeting_of(character: &Character, user_name: &str) -> String {
+    let prompt = character.build_first_message(user_name);
+    let toolcall = prompt.toolcall.expect("first message should be a toolcall");
+    assert_eq!(toolcall.name, "send_message");
+
+    let arguments: Value = serde_json::from_str(&toolcall.arguments).unwrap();
+    arguments["messages"][0]["content"].as_str().unwrap().to_string()
+}
+
+#[test]
+fn test_default_first_message_is_localized() {
+    let english = Character {
+        name: "Alice".to_string(),
+        language: CharacterLanguage::English,
+        ..Default::default()
+    };
+    assert_eq!(greeting_of(&english, "Bob"), "Hi Bob, I'm Alice. Nice to meet you!");
+
+    let chinese = Character {
+        name: "小红".to_string(),
+        language: CharacterLanguage::Chinese,
+        ..Default::default()
+    };
+    assert_eq!(greeting_of(&chinese, "小明"), "你好，小明！我是小红，很高兴认识你。");
+}
@@ -0,0 +1,99 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{
+    MigrateOptions, OrderDirection, QueryCriteria, SchemaMigrator, SqlxCrud, SqlxFilterQuery, FULLTEXT_RANK,
+};
+
+// The same table before and after `description` is marked `#[fulltext]`
+mod before {
+    use metastable_database::SqlxObject;
+    use serde::{Deserialize, Serialize};
+    use sqlx::types::Uuid;
+
+    #[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+    #[table_name = "test_fulltext_characters"]
+    #[allow_column_dropping]
+    pub struct Searchable {
+        pub id: Uuid,
+        pub name: String,
+        pub description: String,
+        pub created_at: i64,
+        pub updated_at: i64,
+    }
+}
+
+mod after {
+    use metastable_database::SqlxObject;
+    use serde::{Deserialize, Serialize};
+    use sqlx::types::Uuid;
+
+    #[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+    #[table_name = "test_fulltext_characters"]
+    #[allow_column_dropping]
+    pub struct Searchable {
+        pub id: Uuid,
+        pub name: String,
+        #[fulltext]
+        pub description: String,
+        pub created_at: i64,
+        pub updated_at: i64,
+    }
+}
+
+#[tokio::test]
+async fn test_fulltext_ranks_by_relevance() {
+    let db = PostgresClient::setup_connection().await;
+    let pool: &sqlx::PgPool = db.get_client();
+    let options = MigrateOptions { dry_run: false, allow_drops: true };
+
+    sqlx::query("DROP TABLE IF EXISTS test_fulltext_characters").execute(pool).await.unwrap();
+    before::Searchable::migrate(pool).await.unwrap();
+    before::Searchable { name: "Existing".to_string(), description: "an old dragon".to_string(), ..Default::default() }
+        .create(pool).await.unwrap();
+
+    // The generated column and its index are added to the existing table
+    let applied = after::Searchable::migrate_with(pool, options).await.unwrap();
+    assert_eq!(applied, vec![
+        "ALTER TABLE \"test_fulltext_characters\" ADD COLUMN \"description_tsv\" tsvector \
+         GENERATED ALWAYS AS (to_tsvector('simple', coalesce(\"description\", ''))) STORED".to_string(),
+        "CREATE INDEX IF NOT EXISTS \"idx_test_fulltext_characters_description_tsv\" \
+         ON \"test_fulltext_characters\" USING GIN (\"description_tsv\")".to_string(),
+    ]);
+    // ...once, and the generated column is not mistaken for a dropped field
+    assert!(after::Searchable::migrate_with(pool, options).await.unwrap().is_empty());
+
+    let mut tx = pool.begin().await.unwrap();
+    sqlx::query("DELETE FROM test_fulltext_characters").execute(&mut *tx).await.unwrap();
+    for (name, description) in [
+        ("Knight", "A knight who once saw a dragon from afar"),
+        ("Tamer", "Dragon tamer raised by a dragon, who speaks to every dragon"),
+        ("Scholar", "A scholar of dragon lore who keeps a dragon egg"),
+        ("Baker", "A baker famous for her bread"),
+    ] {
+        after::Searchable { name: name.to_string(), description: description.to_string(), ..Default::default() }
+            .create(&mut *tx).await.unwrap();
+    }
+
+    let ranked = after::Searchable::find_by_criteria(
+        QueryCriteria::new()
+            .add_fulltext("description", "dragon")
+            .order_by(FULLTEXT_RANK, OrderDirection::Desc),
+        &mut *tx
+    ).await.unwrap();
+    let names = ranked.iter().map(|c| c.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["Tamer", "Scholar", "Knight"]);
+
+    // All words of the query must match, and the filter composes with the rest
+    let both = QueryCriteria::new().add_fulltext("description", "dragon knight");
+    assert_eq!(after::Searchable::count_by_criteria(both, &mut *tx).await.unwrap(), 1);
+    let filtered = QueryCriteria::new()
+        .add_valued_filter("name", "!=", "Tamer".to_string())
+        .add_fulltext("description", "dragon")
+        .order_by(FULLTEXT_RANK, OrderDirection::Desc)
+        .limit(1);
+    let best = after::Searchable::find_by_criteria(filtered, &mut *tx).await.unwrap();
+    assert_eq!(best[0].name, "Scholar");
+
+    tx.rollback().await.unwrap();
+    sqlx::query("DROP TABLE test_fulltext_characters").execute(pool).await.unwrap();
+}
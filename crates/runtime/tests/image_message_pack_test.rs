@@ -0,0 +1,98 @@
+use async_openai::types::{
+    ChatCompletionRequestAssistantMessageContent, ChatCompletionRequestMessage, ChatCompletionRequestUserMessageContent,
+    ChatCompletionRequestUserMessageContentPart, FunctionCall, ImageDetail,
+};
+use metastable_runtime::{GenerateImageResult, MessageRole, MessageType, PackOptions, Prompt};
+
+const IMAGE_URL: &str = "https://example.com/cat.png";
+
+fn history() -> Vec<Prompt> {
+    let mut image = Prompt::new_user_image(IMAGE_URL, Some("what is this?"));
+    image.created_at = 1;
+    let mut reply = Prompt::new_user("a cat");
+    reply.role = MessageRole::Assistant;
+    reply.created_at = 1;
+    let mut follow_up = Prompt::new_user("draw it");
+    follow_up.created_at = 2;
+
+    vec![Prompt::new_system("system"), image, reply, follow_up]
+}
+
+fn user_parts(message: &ChatCompletionRequestMessage) -> Vec<ChatCompletionRequestUserMessageContentPart> {
+    match message {
+        ChatCompletionRequestMessage::User(user) => match &user.content {
+            ChatCompletionRequestUserMessageContent::Array(parts) => parts.clone(),
+            other => panic!("expected content parts, got {:?}", other),
+        },
+        other => panic!("expected user message, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_pack_renders_image_message_as_content_parts() {
+    let packed = Prompt::pack(history()).unwrap();
+    assert_eq!(packed.len(), 4);
+
+    let parts = user_parts(&packed[1]);
+    assert_eq!(parts.len(), 2);
+    assert!(matches!(&parts[0], ChatCompletionRequestUserMessageContentPart::Text(t) if t.text == "what is this?"));
+    match &parts[1] {
+        ChatCompletionRequestUserMessageContentPart::ImageUrl(image) => {
+            assert_eq!(image.image_url.url, IMAGE_URL);
+            assert_eq!(image.image_url.detail, None);
+        }
+        other => panic!("expected image part, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_pack_with_options() {
+    let options = PackOptions { image_detail: Some(ImageDetail::Low), ..Default::default() };
+    let packed = Prompt::pack_with(history(), &options).unwrap();
+    match &user_parts(&packed[1])[1] {
+        ChatCompletionRequestUserMessageContentPart::ImageUrl(image) => {
+            assert_eq!(image.image_url.detail, Some(ImageDetail::Low))
+        }
+        other => panic!("expected image part, got {:?}", other),
+    }
+
+    let options = PackOptions { images_as_text: true, ..Default::default() };
+    let packed = Prompt::pack_with(history(), &options).unwrap();
+    match &packed[1] {
+        ChatCompletionRequestMessage::User(user) => assert_eq!(
+            user.content,
+            ChatCompletionRequestUserMessageContent::Text(format!("what is this?\n[image] {}", IMAGE_URL))
+        ),
+        other => panic!("expected user message, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_pack_references_generated_images_in_assistant_message() {
+    let result = GenerateImageResult {
+        image_urls: vec![IMAGE_URL.to_string()],
+        description: "here you go".to_string(),
+    };
+    let mut messages = history();
+    let mut generated = Prompt::new_user("here you go");
+    generated.role = MessageRole::Assistant;
+    generated.content_type = MessageType::Image;
+    generated.toolcall = Some(FunctionCall {
+        name: "generate_image".to_string(),
+        arguments: serde_json::to_string(&result).unwrap(),
+    });
+    generated.created_at = 2;
+    messages.push(generated);
+    let mut last = Prompt::new_user("thanks");
+    last.created_at = 3;
+    messages.push(last);
+
+    let packed = Prompt::pack(messages).unwrap();
+    match &packed[4] {
+        ChatCompletionRequestMessage::Assistant(assistant) => assert_eq!(
+            assistant.content,
+            Some(ChatCompletionRequestAssistantMessageContent::Text(format!("here you go\n[image] {}", IMAGE_URL)))
+        ),
+        other => panic!("expected assistant message, got {:?}", other),
+    }
+}
@@ -0,0 +1,62 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{OrderDirection, SchemaMigrator, SqlxObject};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+#[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+#[table_name = "test_in_filter"]
+pub struct Tagged {
+    pub id: Uuid,
+    pub owner: Uuid,
+    pub tag: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[tokio::test]
+async fn test_in_filter_binds_arrays() {
+    let db = PostgresClient::setup_connection().await;
+    let pool: &sqlx::PgPool = db.get_client();
+
+    sqlx::query("DROP TABLE IF EXISTS test_in_filter").execute(pool).await.unwrap();
+    Tagged::migrate(pool).await.unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    let owner = Uuid::new_v4();
+    let rows = ["a", "b", "c", "d"].iter()
+        .map(|tag| Tagged { owner, tag: tag.to_string(), ..Default::default() })
+        .collect::<Vec<_>>();
+    let created = Tagged::create_many(rows, &mut *tx).await.unwrap();
+
+    let ids = vec![created[0].id, created[2].id, Uuid::new_v4()];
+    let found = Tagged::find_by_criteria(
+        QueryCriteria::new().add_in_filter("id", ids).order_by("tag", OrderDirection::Asc),
+        &mut *tx
+    ).await.unwrap();
+    assert_eq!(found.iter().map(|t| t.tag.as_str()).collect::<Vec<_>>(), vec!["a", "c"]);
+
+    // The array takes a single placeholder between other bound values
+    let by_tag = || QueryCriteria::new()
+        .add_valued_filter("owner", "=", owner)
+        .add_in_filter("tag", vec!["b".to_string(), "d".to_string(), "z".to_string()]);
+    assert_eq!(Tagged::count_by_criteria(by_tag(), &mut *tx).await.unwrap(), 2);
+    let paged = Tagged::find_by_criteria(by_tag().order_by("tag", OrderDirection::Asc).limit(1), &mut *tx).await.unwrap();
+    assert_eq!(paged.len(), 1);
+    assert_eq!(paged[0].tag, "b");
+
+    // An empty list matches nothing instead of producing invalid SQL
+    let none = Tagged::find_by_criteria(QueryCriteria::new().add_in_filter("id", Vec::<Uuid>::new()), &mut *tx).await.unwrap();
+    assert!(none.is_empty());
+    let deleted = Tagged::delete_by_criteria(QueryCriteria::new().add_in_filter("tag", Vec::<String>::new()), &mut *tx).await.unwrap();
+    assert_eq!(deleted, 0);
+
+    let deleted = Tagged::delete_by_criteria(
+        QueryCriteria::new().add_in_filter("id", vec![created[1].id, created[3].id]), &mut *tx
+    ).await.unwrap();
+    assert_eq!(deleted, 2);
+    assert_eq!(Tagged::count_by_criteria(QueryCriteria::new(), &mut *tx).await.unwrap(), 2);
+
+    tx.rollback().await.unwrap();
+    sqlx::query("DROP TABLE IF EXISTS test_in_filter").execute(pool).await.unwrap();
+}
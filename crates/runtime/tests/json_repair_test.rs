@@ -0,0 +1,164 @@
+use anyhow::Result;
+use async_openai::types::FunctionCall;
+use metastable_clients::{LlmClient, PostgresClient};
+use metastable_common::ModuleClient;
+use metastable_runtime::{parse_tool_call, repair_json, Agent, LlmTool, Message, Prompt, SystemConfig};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::types::Uuid;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+#[derive(Debug, Clone, Serialize, Deserialize, LlmTool)]
+#[llm_tool(
+    name = "remember",
+    description = "Store facts about the user"
+)]
+pub struct RememberTool {
+    pub facts: Vec<String>,
+    pub importance: i64,
+}
+
+fn tool_call(arguments: &str) -> FunctionCall {
+    FunctionCall {
+        name: "remember".to_string(),
+        arguments: arguments.to_string(),
+    }
+}
+
+#[test]
+fn test_trailing_comma_is_repaired_only_when_lenient() {
+    let call = tool_call(r#"{"facts": ["likes tea", "lives in Paris",], "importance": 3,}"#);
+
+    assert!(parse_tool_call::<RememberTool>(&call, false).is_err());
+
+    let tool = parse_tool_call::<RememberTool>(&call, true).unwrap();
+    assert_eq!(tool.facts, vec!["likes tea", "lives in Paris"]);
+    assert_eq!(tool.importance, 3);
+}
+
+#[test]
+fn test_unquoted_keys_are_repaired_only_when_lenient() {
+    let call = tool_call(r#"{facts: ["has a cat named {mochi}, age: 2"], importance: 5}"#);
+
+    assert!(parse_tool_call::<RememberTool>(&call, false).is_err());
+
+    let tool = parse_tool_call::<RememberTool>(&call, true).unwrap();
+    assert_eq!(tool.facts, vec!["has a cat named {mochi}, age: 2"]);
+    assert_eq!(tool.importance, 5);
+}
+
+#[test]
+fn test_repair_leaves_valid_and_hopeless_json_alone() {
+    assert_eq!(repair_json(r#"{"a": [1, true, null]}"#), None);
+    assert_eq!(repair_json(r#"{"a": "#), None);
+    assert!(parse_tool_call::<RememberTool>(&tool_call("not json"), true).is_err());
+}
+
+/// Answers every call with a `remember` tool call whose arguments have a trailing comma.
+async fn mock_llm() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            // Read the headers, then as much body as they announce
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            let body_start = loop {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                if let Some(i) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break i + 4;
+                }
+            };
+            let headers = String::from_utf8_lossy(&request[..body_start]).to_lowercase();
+            let content_length = headers.lines()
+                .find_map(|l| l.strip_prefix("content-length:"))
+                .map(|v| v.trim().parse::<usize>().unwrap())
+                .unwrap_or(0);
+            while request.len() < body_start + content_length {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+
+            let body = json!({
+                "id": "gen-1",
+                "object": "chat.completion",
+                "created": 1735689600,
+                "model": "mock",
+                "choices": [{
+                    "index": 0,
+                    "message": {
+                        "role": "assistant",
+                        "content": "",
+                        "tool_calls": [{
+                            "id": "call_1",
+                            "type": "function",
+                            "function": { "name": "remember", "arguments": r#"{"facts": ["likes tea",], "importance": 2,}"# },
+                        }],
+                    },
+                    "finish_reason": "tool_calls",
+                }],
+                "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 },
+            }).to_string();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(), body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+
+    base_url
+}
+
+/// Uses the default `lenient_json`, so it follows `AGENT_LENIENT_JSON`.
+#[derive(Clone)]
+struct RememberAgent {
+    llm: LlmClient,
+    db: PostgresClient,
+    system_config: SystemConfig,
+}
+
+#[async_trait::async_trait]
+impl Agent for RememberAgent {
+    const SYSTEM_CONFIG_NAME: &'static str = "remember_test_v0";
+    type Tool = RememberTool;
+    type Input = String;
+
+    fn system_prompt() -> &'static str { "Remember what the user says." }
+    fn llm_client(&self) -> &LlmClient { &self.llm }
+    fn db_client(&self) -> &PostgresClient { &self.db }
+    fn system_config(&self) -> &SystemConfig { &self.system_config }
+
+    async fn build_input(&self, input: &String) -> Result<Vec<Prompt>> {
+        Ok(vec![Prompt::new_system(Self::system_prompt()), Prompt::new_user(input)])
+    }
+
+    async fn handle_output(&self, _input: &String, message: &Message, _tool: &RememberTool) -> Result<(Message, Option<Value>)> {
+        Ok((message.clone(), None))
+    }
+}
+
+#[tokio::test]
+async fn test_agent_call_repairs_arguments_when_configured() {
+    std::env::set_var("OPENAI_BASE_URL", mock_llm().await);
+    std::env::set_var("OPENAI_API_KEY", "test");
+    let agent = RememberAgent {
+        llm: LlmClient::setup_connection().await,
+        db: PostgresClient::default(),
+        system_config: RememberAgent::to_system_config(),
+    };
+    let caller = Uuid::new_v4();
+
+    std::env::remove_var("AGENT_LENIENT_JSON");
+    assert!(agent.call(&caller, &"I like tea".to_string()).await.is_err());
+
+    std::env::set_var("AGENT_LENIENT_JSON", "true");
+    let (_, tool, _) = agent.call(&caller, &"I like tea".to_string()).await.unwrap();
+    assert_eq!(tool.facts, vec!["likes tea"]);
+    assert_eq!(tool.importance, 2);
+    std::env::remove_var("AGENT_LENIENT_JSON");
+}
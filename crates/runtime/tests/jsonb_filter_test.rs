@@ -0,0 +1,80 @@
+use async_openai::types::FunctionCall;
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{OrderDirection, QueryCriteria, SqlxCrud, SqlxFilterQuery};
+use metastable_runtime::{Character, CharacterFeature, User};
+use serde_json::json;
+use sqlx::types::{Json, Uuid};
+
+fn character(creator: Uuid, name: &str, features: Vec<CharacterFeature>, first_message: Option<&str>) -> Character {
+    Character {
+        name: name.to_string(),
+        creator,
+        features: Json(features.into()),
+        prompts_first_message: Json(first_message.map(|name| FunctionCall {
+            name: name.to_string(),
+            arguments: "{}".to_string(),
+        })),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_jsonb_filters_on_characters() {
+    let db = PostgresClient::setup_connection().await;
+    let mut tx = db.get_client().begin().await.unwrap();
+
+    let user = User { user_id: format!("jsonb_{}", Uuid::new_v4()), ..Default::default() }
+        .create(&mut *tx).await.unwrap();
+
+    character(user.id, "Alice", vec![CharacterFeature::Roleplay], Some("send_message")).create(&mut *tx).await.unwrap();
+    character(user.id, "Bob", vec![CharacterFeature::CharacterCreation], Some("show_story_options")).create(&mut *tx).await.unwrap();
+    character(user.id, "Carol", vec![
+        CharacterFeature::Roleplay,
+        CharacterFeature::Voice("narrator".to_string()),
+    ], None).create(&mut *tx).await.unwrap();
+    character(user.id, "Dave", vec![], Some("it's \"quoted\"")).create(&mut *tx).await.unwrap();
+
+    let names = |found: Vec<Character>| found.into_iter().map(|c| c.name).collect::<Vec<_>>();
+    let mine = || QueryCriteria::new()
+        .add_valued_filter("creator", "=", user.id)
+        .order_by("name", OrderDirection::Asc);
+
+    let roleplay = json!([CharacterFeature::Roleplay]);
+    let found = Character::find_by_criteria(mine().add_jsonb_contains("features", roleplay.clone()), &mut *tx).await.unwrap();
+    assert_eq!(names(found), vec!["Alice", "Carol"]);
+
+    let voiced = json!([CharacterFeature::Roleplay, CharacterFeature::Voice("narrator".to_string())]);
+    let found = Character::find_by_criteria(mine().add_jsonb_contains("features", voiced), &mut *tx).await.unwrap();
+    assert_eq!(names(found), vec!["Carol"]);
+
+    let found = Character::find_by_criteria(
+        mine().add_jsonb_path_eq("prompts_first_message", &["name"], "send_message"), &mut *tx
+    ).await.unwrap();
+    assert_eq!(names(found), vec!["Alice"]);
+
+    // Values are bound, so quotes need no escaping
+    let found = Character::find_by_criteria(
+        mine().add_jsonb_path_eq("prompts_first_message", &["name"], "it's \"quoted\""), &mut *tx
+    ).await.unwrap();
+    assert_eq!(names(found), vec!["Dave"]);
+
+    // Missing paths never match
+    let count = Character::count_by_criteria(
+        mine().add_jsonb_path_eq("prompts_first_message", &["name", "nested"], "send_message"), &mut *tx
+    ).await.unwrap();
+    assert_eq!(count, 0);
+
+    // Placeholders stay in order when mixed with other conditions
+    let count = Character::count_by_criteria(
+        QueryCriteria::new()
+            .add_jsonb_path_eq("prompts_first_message", &["name"], "send_message")
+            .add_valued_filter("creator", "=", user.id)
+            .add_jsonb_contains("features", roleplay)
+            .add_valued_filter("name", "<>", "Bob".to_string()),
+        &mut *tx
+    ).await.unwrap();
+    assert_eq!(count, 1);
+
+    tx.rollback().await.unwrap();
+}
@@ -0,0 +1,65 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{escape_like, OrderDirection, SchemaMigrator, SqlxObject};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+#[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+#[table_name = "test_like_filter"]
+pub struct Named {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[test]
+fn test_escape_like() {
+    assert_eq!(escape_like("50%"), "50\\%");
+    assert_eq!(escape_like("a_b\\c"), "a\\_b\\\\c");
+    assert_eq!(escape_like("忆君"), "忆君");
+}
+
+#[tokio::test]
+async fn test_like_filters_match_substrings_literally() {
+    let db = PostgresClient::setup_connection().await;
+    let pool: &sqlx::PgPool = db.get_client();
+
+    sqlx::query("DROP TABLE IF EXISTS test_like_filter").execute(pool).await.unwrap();
+    Named::migrate(pool).await.unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    let rows = ["Dragon Knight", "little dragon", "50% off", "500 coins", "snake_case", "snakeXcase", "back\\slash"]
+        .iter()
+        .map(|name| Named { name: name.to_string(), ..Default::default() })
+        .collect::<Vec<_>>();
+    Named::create_many(rows, &mut *tx).await.unwrap();
+
+    let names = |found: Vec<Named>| found.into_iter().map(|n| n.name).collect::<Vec<_>>();
+    let sorted = || QueryCriteria::new().order_by("name", OrderDirection::Asc);
+
+    let found = Named::find_by_criteria(sorted().add_like_filter("name", "Dragon"), &mut *tx).await.unwrap();
+    assert_eq!(names(found), vec!["Dragon Knight"]);
+    let found = Named::find_by_criteria(sorted().add_ilike_filter("name", "dragon"), &mut *tx).await.unwrap();
+    assert_eq!(names(found), vec!["Dragon Knight", "little dragon"]);
+
+    // Wildcards in the search text are literal
+    let found = Named::find_by_criteria(sorted().add_ilike_filter("name", "50%"), &mut *tx).await.unwrap();
+    assert_eq!(names(found), vec!["50% off"]);
+    let found = Named::find_by_criteria(sorted().add_like_filter("name", "e_c"), &mut *tx).await.unwrap();
+    assert_eq!(names(found), vec!["snake_case"]);
+    let found = Named::find_by_criteria(sorted().add_like_filter("name", "k\\s"), &mut *tx).await.unwrap();
+    assert_eq!(names(found), vec!["back\\slash"]);
+
+    // Combines with other conditions and keeps placeholders in order
+    let count = Named::count_by_criteria(
+        QueryCriteria::new()
+            .add_ilike_filter("name", "DRAGON")
+            .add_valued_filter("name", "<>", "little dragon".to_string()),
+        &mut *tx
+    ).await.unwrap();
+    assert_eq!(count, 1);
+
+    tx.rollback().await.unwrap();
+    sqlx::query("DROP TABLE IF EXISTS test_like_filter").execute(pool).await.unwrap();
+}
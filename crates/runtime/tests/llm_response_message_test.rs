@@ -0,0 +1,65 @@
+use async_openai::types::CreateChatCompletionResponse;
+use metastable_runtime::{Message, MessageType};
+use serde_json::json;
+use sqlx::types::Uuid;
+
+fn completion(tool_calls: serde_json::Value) -> CreateChatCompletionResponse {
+    serde_json::from_value(json!({
+        "id": "gen-1",
+        "object": "chat.completion",
+        "created": 1735689600,
+        "model": "google/gemini-2.5-flash",
+        "choices": [{
+            "index": 0,
+            "message": {
+                "role": "assistant",
+                "content": "Hi Bob!",
+                "refusal": null,
+                "tool_calls": tool_calls,
+            },
+            "finish_reason": "tool_calls",
+            "logprobs": null,
+        }],
+        "usage": { "prompt_tokens": 120, "completion_tokens": 30, "total_tokens": 150 },
+    })).unwrap()
+}
+
+#[test]
+fn test_message_from_llm_response() {
+    let owner = Uuid::new_v4();
+    let session = Uuid::new_v4();
+    let response = completion(json!([{
+        "id": "call_1",
+        "type": "function",
+        "function": { "name": "send_message", "arguments": "{\"messages\":[]}" },
+    }]));
+
+    let message = Message::from_llm_response(&response, Some(session), owner).unwrap();
+    assert_eq!(message.owner, owner);
+    assert_eq!(message.session, Some(session));
+    assert_eq!(message.assistant_message_content, "Hi Bob!");
+    assert_eq!(message.assistant_message_content_type, MessageType::Text);
+    assert_eq!(message.model_name, "google/gemini-2.5-flash");
+    assert_eq!(message.finish_reason.as_deref(), Some("ToolCalls"));
+    assert_eq!(message.refusal, None);
+
+    let tool_call = message.assistant_message_tool_call.0.as_ref().unwrap();
+    assert_eq!(tool_call.name, "send_message");
+    assert_eq!(tool_call.arguments, "{\"messages\":[]}");
+
+    let usage = message.usage.0.as_ref().unwrap();
+    assert_eq!((usage.prompt_tokens, usage.completion_tokens, usage.total_tokens), (120, 30, 150));
+    // The usage column round-trips through its JSON encoding
+    let stored = serde_json::to_value(&message.usage).unwrap();
+    assert_eq!(stored["total_tokens"], 150);
+
+    assert!(message.created_at > 0);
+    assert_eq!(message.created_at, message.updated_at);
+
+    let without_tool = Message::from_llm_response(&completion(json!(null)), None, owner).unwrap();
+    assert!(without_tool.assistant_message_tool_call.0.is_none());
+
+    let mut empty = completion(json!(null));
+    empty.choices.clear();
+    assert!(Message::from_llm_response(&empty, None, owner).is_err());
+}
@@ -0,0 +1,145 @@
+use std::collections::HashSet;
+
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{QueryCriteria, SqlxCrud};
+use metastable_runtime::{Message, MessageType, SystemConfig, User, DEFAULT_MIGRATION_CLAIM_LEASE_SECS};
+use sqlx::types::{Json, Uuid};
+
+fn unmigrated_message(owner: &Owner) -> Message {
+    Message {
+        id: Uuid::new_v4(),
+        owner: owner.user.id,
+        system_config: owner.system_config.id,
+        session: None,
+        turn_index: None,
+        user_message_content: "hello".to_string(),
+        user_message_content_type: MessageType::Text,
+        input_toolcall: Json(None),
+        assistant_message_content: "world".to_string(),
+        assistant_message_content_type: MessageType::Text,
+        assistant_message_tool_call: Json(None),
+        summary: None,
+        model_name: "test".to_string(),
+        usage: Json(None),
+        finish_reason: None,
+        refusal: None,
+        is_stale: false,
+        is_memorizeable: false,
+        is_in_memory: false,
+        is_migrated: false,
+        migration_claimed_at: None,
+        deleted_at: None,
+        created_at: 0,
+        updated_at: 0,
+    }
+}
+
+/// A real user and system config, so seeded messages satisfy their foreign keys.
+struct Owner {
+    user: User,
+    system_config: SystemConfig,
+}
+
+impl Owner {
+    async fn seed(pool: &sqlx::PgPool) -> Self {
+        let mut tx = pool.begin().await.unwrap();
+        let user = User { user_id: format!("claim_{}", Uuid::new_v4()), ..Default::default() }
+            .create(&mut *tx).await.unwrap();
+        let system_config = SystemConfig { name: format!("test_claim_{}", Uuid::new_v4()), ..Default::default() }
+            .create(&mut *tx).await.unwrap();
+        tx.commit().await.unwrap();
+        Self { user, system_config }
+    }
+
+    async fn clean_up(self, pool: &sqlx::PgPool) {
+        let mut tx = pool.begin().await.unwrap();
+        sqlx::query("DELETE FROM messages WHERE owner = $1")
+            .bind(self.user.id)
+            .execute(&mut *tx).await.unwrap();
+        self.system_config.delete(&mut *tx).await.unwrap();
+        self.user.delete(&mut *tx).await.unwrap();
+        tx.commit().await.unwrap();
+    }
+}
+
+async fn claim_own(conn: &mut sqlx::PgConnection, owner: Uuid, n: i64) -> Vec<Message> {
+    Message::claim_unmigrated_batch_by(
+        conn, n, DEFAULT_MIGRATION_CLAIM_LEASE_SECS,
+        QueryCriteria::new().add_valued_filter("owner", "=", owner),
+    ).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_concurrent_claimers_get_disjoint_batches() {
+    let db = PostgresClient::setup_connection().await;
+    let pool: &sqlx::PgPool = db.get_client();
+    let seeded = Owner::seed(pool).await;
+    let owner = seeded.user.id;
+
+    let mut tx = db.get_client().begin().await.unwrap();
+    let mut created = HashSet::new();
+    for _ in 0..7 {
+        let message = unmigrated_message(&seeded).create(&mut *tx).await.unwrap();
+        created.insert(message.id);
+    }
+    tx.commit().await.unwrap();
+
+    // both claimers hold their transactions open while claiming
+    let mut first = db.get_client().begin().await.unwrap();
+    let mut second = db.get_client().begin().await.unwrap();
+
+    let mut first_claimed = HashSet::new();
+    let mut second_claimed = HashSet::new();
+    loop {
+        let a = claim_own(&mut first, owner, 2).await;
+        let b = claim_own(&mut second, owner, 2).await;
+        if a.is_empty() && b.is_empty() {
+            break;
+        }
+        first_claimed.extend(a.iter().map(|m| m.id));
+        second_claimed.extend(b.iter().map(|m| m.id));
+    }
+    first.commit().await.unwrap();
+    second.commit().await.unwrap();
+
+    assert!(first_claimed.is_disjoint(&second_claimed));
+    assert!(!first_claimed.is_empty() && !second_claimed.is_empty());
+
+    let claimed = first_claimed.union(&second_claimed).cloned().collect::<HashSet<_>>();
+    assert_eq!(claimed, created);
+
+    let mut tx = db.get_client().begin().await.unwrap();
+    assert!(claim_own(&mut tx, owner, 10).await.is_empty());
+    tx.commit().await.unwrap();
+    seeded.clean_up(pool).await;
+}
+
+#[tokio::test]
+async fn test_expired_claims_are_reclaimed() {
+    let db = PostgresClient::setup_connection().await;
+    let pool: &sqlx::PgPool = db.get_client();
+    let seeded = Owner::seed(pool).await;
+    let owner = seeded.user.id;
+
+    let mut tx = db.get_client().begin().await.unwrap();
+    let message = unmigrated_message(&seeded).create(&mut *tx).await.unwrap();
+    tx.commit().await.unwrap();
+
+    // A worker claims the row and dies before migrating it
+    let mut tx = db.get_client().begin().await.unwrap();
+    assert_eq!(claim_own(&mut tx, owner, 10).await.len(), 1);
+    tx.commit().await.unwrap();
+
+    let mut tx = db.get_client().begin().await.unwrap();
+    assert!(claim_own(&mut tx, owner, 10).await.is_empty());
+
+    sqlx::query("UPDATE messages SET migration_claimed_at = migration_claimed_at - $1 WHERE id = $2")
+        .bind(DEFAULT_MIGRATION_CLAIM_LEASE_SECS + 1)
+        .bind(message.id)
+        .execute(&mut *tx).await.unwrap();
+    let reclaimed = claim_own(&mut tx, owner, 10).await;
+    assert_eq!(reclaimed.iter().map(|m| m.id).collect::<Vec<_>>(), vec![message.id]);
+    tx.commit().await.unwrap();
+    seeded.clean_up(pool).await;
+}
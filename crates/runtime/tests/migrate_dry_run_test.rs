@@ -0,0 +1,56 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{MigrateOptions, SchemaMigrator, SqlxObject};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+#[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+#[table_name = "test_migrate_dry_run"]
+pub struct MigrateDryRun {
+    pub id: Uuid,
+    pub name: String,
+    pub nickname: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+async fn columns(pool: &sqlx::PgPool) -> Vec<String> {
+    sqlx::query_scalar(
+        "SELECT column_name::text FROM information_schema.columns \
+         WHERE table_name = 'test_migrate_dry_run' AND table_schema = 'public' ORDER BY column_name"
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_dry_run_reports_without_applying() {
+    let db = PostgresClient::setup_connection().await;
+    let pool: &sqlx::PgPool = db.get_client();
+
+    // Drift: the table predates the `name` and `nickname` fields.
+    sqlx::query("DROP TABLE IF EXISTS test_migrate_dry_run").execute(pool).await.unwrap();
+    MigrateDryRun::migrate(pool).await.unwrap();
+    sqlx::query("ALTER TABLE test_migrate_dry_run DROP COLUMN name, DROP COLUMN nickname")
+        .execute(pool).await.unwrap();
+    let before = columns(pool).await;
+
+    let planned = MigrateDryRun::migrate_with(pool, MigrateOptions { dry_run: true, allow_drops: false }).await.unwrap();
+    assert_eq!(planned.len(), 2);
+    assert!(planned.iter().any(|s| s.contains("ADD COLUMN \"name\"")));
+    assert!(planned.iter().any(|s| s.contains("ADD COLUMN \"nickname\"")));
+    assert_eq!(columns(pool).await, before);
+
+    let applied = MigrateDryRun::migrate_with(pool, MigrateOptions { dry_run: false, allow_drops: false }).await.unwrap();
+    assert_eq!(applied, planned);
+    assert_eq!(columns(pool).await, vec!["created_at", "id", "name", "nickname", "updated_at"]);
+
+    let rerun = MigrateDryRun::migrate_with(pool, MigrateOptions { dry_run: true, allow_drops: false }).await.unwrap();
+    assert!(rerun.is_empty());
+
+    sqlx::query("DROP TABLE test_migrate_dry_run").execute(pool).await.unwrap();
+    let planned = MigrateDryRun::migrate_with(pool, MigrateOptions { dry_run: true, allow_drops: false }).await.unwrap();
+    assert!(planned[0].starts_with("CREATE TABLE"));
+    assert!(columns(pool).await.is_empty());
+}
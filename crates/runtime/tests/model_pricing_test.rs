@@ -0,0 +1,46 @@
+use async_openai::types::CompletionUsage;
+use metastable_runtime::{estimate_cost, ModelPricing, ModelRate};
+
+fn usage(prompt_tokens: u32, completion_tokens: u32) -> CompletionUsage {
+    CompletionUsage {
+        prompt_tokens,
+        completion_tokens,
+        total_tokens: prompt_tokens + completion_tokens,
+        prompt_tokens_details: None,
+        completion_tokens_details: None,
+    }
+}
+
+#[test]
+fn test_cost_matches_configured_rates() {
+    let pricing = ModelPricing::default();
+    let rate = *pricing.rate("google/gemini-2.5-flash").unwrap();
+
+    let cost = pricing.estimate_cost(&usage(2000, 500), "google/gemini-2.5-flash").unwrap();
+    let expected = 2.0 * rate.prompt_per_1k + 0.5 * rate.completion_per_1k;
+    assert!((cost - expected).abs() < 1e-12);
+    assert_eq!(estimate_cost(&usage(2000, 500), "google/gemini-2.5-flash"), Some(cost));
+
+    assert_eq!(pricing.estimate_cost(&usage(2000, 500), "acme/unknown-model"), None);
+}
+
+#[test]
+fn test_overrides_replace_and_extend_rates() {
+    let pricing = ModelPricing::default()
+        .with_overrides(r#"{
+            "google/gemini-2.5-flash": { "prompt_per_1k": 0.001, "completion_per_1k": 0.002 },
+            "acme/custom": { "prompt_per_1k": 0.01, "completion_per_1k": 0.03 }
+        }"#)
+        .unwrap()
+        .with_rate("acme/tiny", ModelRate { prompt_per_1k: 0.0, completion_per_1k: 0.001 });
+
+    let cost = pricing.estimate_cost(&usage(1000, 1000), "google/gemini-2.5-flash").unwrap();
+    assert!((cost - 0.003).abs() < 1e-12);
+    let cost = pricing.estimate_cost(&usage(500, 100), "acme/custom").unwrap();
+    assert!((cost - 0.008).abs() < 1e-12);
+    assert_eq!(pricing.estimate_cost(&usage(1000, 2000), "acme/tiny"), Some(0.002));
+    // Untouched defaults survive
+    assert!(pricing.rate("google/gemini-2.5-flash-lite").is_some());
+
+    assert!(ModelPricing::default().with_overrides("not json").is_err());
+}
@@ -0,0 +1,48 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{QueryCriteria, SqlxCrud, SqlxFilterQuery, VersionConflict};
+use metastable_runtime::{Character, CharacterStatus, User};
+use sqlx::types::Uuid;
+
+#[tokio::test]
+async fn test_concurrent_character_updates_conflict() {
+    let db = PostgresClient::setup_connection().await;
+    let mut tx = db.get_client().begin().await.unwrap();
+
+    let user = User { user_id: format!("optimistic_lock_{}", Uuid::new_v4()), ..Default::default() }
+        .create(&mut *tx).await.unwrap();
+    let character = Character { name: "Locked".to_string(), creator: user.id, version: 1, ..Default::default() }
+        .create(&mut *tx).await.unwrap();
+    let by_id = || QueryCriteria::new().add_valued_filter("id", "=", character.id);
+
+    let mut first = Character::find_one_by_criteria(by_id(), &mut *tx).await.unwrap().unwrap();
+    let mut second = Character::find_one_by_criteria(by_id(), &mut *tx).await.unwrap().unwrap();
+
+    first.description = "edited by the creator".to_string();
+    let first = first.update(&mut *tx).await.unwrap();
+    assert_eq!(first.version, 2);
+
+    // Read before the first update, so it must not overwrite it
+    second.status = CharacterStatus::Published;
+    let err = second.update(&mut *tx).await.unwrap_err();
+    assert!(VersionConflict::is(&err), "{}", err);
+    assert!(!VersionConflict::is(&sqlx::Error::RowNotFound));
+
+    let stored = Character::find_one_by_criteria(by_id(), &mut *tx).await.unwrap().unwrap();
+    assert_eq!(stored.version, 2);
+    assert_eq!(stored.description, "edited by the creator");
+    assert_eq!(stored.status, CharacterStatus::Draft);
+
+    // Reloading picks up the new version
+    let mut second = stored;
+    second.status = CharacterStatus::Published;
+    let second = second.update(&mut *tx).await.unwrap();
+    assert_eq!(second.version, 3);
+
+    // Batch updates invalidate earlier copies as well
+    let updated = Character::batch_update_field(vec![character.id], "status", CharacterStatus::Archived, &mut *tx).await.unwrap();
+    assert_eq!(updated[0].version, 4);
+    assert!(VersionConflict::is(&second.update(&mut *tx).await.unwrap_err()));
+
+    tx.rollback().await.unwrap();
+}
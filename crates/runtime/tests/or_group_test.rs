@@ -0,0 +1,81 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{OrderDirection, SchemaMigrator, SqlxObject};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+#[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+#[table_name = "test_or_groups"]
+pub struct Searchable {
+    pub id: Uuid,
+    pub owner: Uuid,
+    pub name: String,
+    pub description: String,
+    pub score: i32,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+fn searchable(owner: Uuid, name: &str, description: &str, score: i32) -> Searchable {
+    Searchable { owner, name: name.to_string(), description: description.to_string(), score, ..Default::default() }
+}
+
+#[tokio::test]
+async fn test_or_groups_with_other_conditions() {
+    let db = PostgresClient::setup_connection().await;
+    let pool: &sqlx::PgPool = db.get_client();
+
+    sqlx::query("DROP TABLE IF EXISTS test_or_groups").execute(pool).await.unwrap();
+    Searchable::migrate(pool).await.unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    let owner = Uuid::new_v4();
+    Searchable::create_many(vec![
+        searchable(owner, "Dragon Knight", "a knight", 1),
+        searchable(owner, "Alice", "raised by a dragon", 2),
+        searchable(owner, "Bob", "a baker", 3),
+        searchable(owner, "dragonfly", "an insect", 4),
+        // Matches the group but belongs to someone else
+        searchable(Uuid::new_v4(), "Dragon", "dragon", 5),
+    ], &mut *tx).await.unwrap();
+
+    // Conditions on both sides of the group, so placeholders interleave
+    let search = || QueryCriteria::new()
+        .add_valued_filter("owner", "=", owner)
+        .or_group(vec![("name", "ILIKE", "%dragon%"), ("description", "ILIKE", "%dragon%")])
+        .add_valued_filter("score", "<=", 3);
+
+    let found = Searchable::find_by_criteria(search().order_by("score", OrderDirection::Asc), &mut *tx).await.unwrap();
+    assert_eq!(found.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), vec!["Dragon Knight", "Alice"]);
+    assert_eq!(Searchable::count_by_criteria(search(), &mut *tx).await.unwrap(), 2);
+
+    // Limit and offset are bound after the group's values
+    let paged = Searchable::find_by_criteria(
+        search().order_by("score", OrderDirection::Asc).limit(1).offset(1), &mut *tx
+    ).await.unwrap();
+    assert_eq!(paged.len(), 1);
+    assert_eq!(paged[0].name, "Alice");
+
+    // Two groups are ANDed together
+    let both = QueryCriteria::new()
+        .or_group(vec![("name", "=", "Bob"), ("name", "=", "Alice")])
+        .or_group(vec![("score", "=", 2), ("score", "=", 5)]);
+    let found = Searchable::find_by_criteria(both, &mut *tx).await.unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].name, "Alice");
+
+    let empty = QueryCriteria::new().or_group(Vec::<(&'static str, &'static str, i32)>::new());
+    assert_eq!(Searchable::count_by_criteria(empty, &mut *tx).await.unwrap(), 0);
+
+    let deleted = Searchable::delete_by_criteria(
+        QueryCriteria::new()
+            .add_valued_filter("owner", "=", owner)
+            .or_group(vec![("name", "=", "Bob"), ("description", "=", "an insect")]),
+        &mut *tx
+    ).await.unwrap();
+    assert_eq!(deleted, 2);
+    assert_eq!(Searchable::count_by_criteria(QueryCriteria::new(), &mut *tx).await.unwrap(), 3);
+
+    tx.rollback().await.unwrap();
+    sqlx::query("DROP TABLE IF EXISTS test_or_groups").execute(pool).await.unwrap();
+}
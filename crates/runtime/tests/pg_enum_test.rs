@@ -0,0 +1,167 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{MigrateOptions, PgEnum, QueryCriteria, SchemaMigrator, SqlxCrud, SqlxFilterQuery};
+
+// The same table as the application evolves: priorities start out as JSONB,
+// move to a native enum, then gain a variant and lose another.
+mod jsonb {
+    use metastable_database::{SqlxObject, TextEnum};
+    use serde::{Deserialize, Serialize};
+    use sqlx::types::Uuid;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Default, TextEnum)]
+    pub enum TestTicketPriority {
+        #[default]
+        Low,
+        High,
+    }
+
+    #[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+    #[table_name = "test_pg_enum_tickets"]
+    pub struct Ticket {
+        pub id: Uuid,
+        pub priority: TestTicketPriority,
+        pub created_at: i64,
+        pub updated_at: i64,
+    }
+}
+
+mod native {
+    use metastable_database::{SqlxObject, TextEnum};
+    use serde::{Deserialize, Serialize};
+    use sqlx::types::Uuid;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Default, TextEnum)]
+    #[pg_enum]
+    pub enum TestTicketPriority {
+        #[default]
+        Low,
+        High,
+    }
+
+    #[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+    #[table_name = "test_pg_enum_tickets"]
+    #[allow_type_change]
+    pub struct Ticket {
+        pub id: Uuid,
+        #[pg_enum]
+        pub priority: TestTicketPriority,
+        #[pg_enum]
+        pub escalated_from: Option<TestTicketPriority>,
+        pub created_at: i64,
+        pub updated_at: i64,
+    }
+}
+
+mod evolved {
+    use metastable_database::{SqlxObject, TextEnum};
+    use serde::{Deserialize, Serialize};
+    use sqlx::types::Uuid;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Default, TextEnum)]
+    #[pg_enum]
+    pub enum TestTicketPriority {
+        #[default]
+        Low,
+        Medium,
+    }
+
+    #[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+    #[table_name = "test_pg_enum_tickets"]
+    pub struct Ticket {
+        pub id: Uuid,
+        #[pg_enum]
+        pub priority: TestTicketPriority,
+        #[pg_enum]
+        pub escalated_from: Option<TestTicketPriority>,
+        pub created_at: i64,
+        pub updated_at: i64,
+    }
+}
+
+async fn labels(pool: &sqlx::PgPool) -> Vec<String> {
+    sqlx::query_scalar(
+        "SELECT e.enumlabel::text FROM pg_enum e JOIN pg_type t ON t.oid = e.enumtypid \
+         WHERE t.typname = 'test_ticket_priority' ORDER BY e.enumsortorder"
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+async fn column_type(pool: &sqlx::PgPool, column: &str) -> String {
+    sqlx::query_scalar(
+        "SELECT udt_name::text FROM information_schema.columns \
+         WHERE table_name = 'test_pg_enum_tickets' AND table_schema = 'public' AND column_name = $1"
+    )
+    .bind(column)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_pg_enum_columns_migrate_and_round_trip() {
+    let db = PostgresClient::setup_connection().await;
+    let pool: &sqlx::PgPool = db.get_client();
+    let options = MigrateOptions { dry_run: false, allow_drops: false };
+
+    sqlx::query("DROP TABLE IF EXISTS test_pg_enum_tickets").execute(pool).await.unwrap();
+    sqlx::query("DROP TYPE IF EXISTS test_ticket_priority").execute(pool).await.unwrap();
+
+    assert_eq!(native::TestTicketPriority::TYPE_NAME, "test_ticket_priority");
+    assert_eq!(native::TestTicketPriority::VARIANTS, &["Low", "High"]);
+
+    // Existing JSONB values are converted in place
+    jsonb::Ticket::migrate(pool).await.unwrap();
+    let legacy = jsonb::Ticket { priority: jsonb::TestTicketPriority::High, ..Default::default() }
+        .create(pool).await.unwrap();
+
+    let applied = native::Ticket::migrate_with(pool, options).await.unwrap();
+    assert_eq!(applied[0], "CREATE TYPE \"test_ticket_priority\" AS ENUM ('Low', 'High')");
+    assert!(applied.iter().any(|s| s.contains("ALTER COLUMN \"priority\" TYPE test_ticket_priority")));
+    assert!(applied.iter().any(|s| s.contains("ADD COLUMN \"escalated_from\" test_ticket_priority")));
+    assert_eq!(column_type(pool, "priority").await, "test_ticket_priority");
+    assert_eq!(column_type(pool, "escalated_from").await, "test_ticket_priority");
+
+    let converted = native::Ticket::find_one_by_criteria(
+        QueryCriteria::new().add_valued_filter("id", "=", legacy.id), pool
+    ).await.unwrap().unwrap();
+    assert_eq!(converted.priority, native::TestTicketPriority::High);
+    assert_eq!(converted.escalated_from, None);
+
+    let escalated = native::Ticket {
+        priority: native::TestTicketPriority::High,
+        escalated_from: Some(native::TestTicketPriority::Low),
+        ..Default::default()
+    }.create(pool).await.unwrap();
+    assert_eq!(escalated.escalated_from, Some(native::TestTicketPriority::Low));
+    let high = native::Ticket::count_by_criteria(
+        QueryCriteria::new().add_valued_filter("priority", "=", native::TestTicketPriority::High), pool
+    ).await.unwrap();
+    assert_eq!(high, 2);
+
+    // Unknown labels are rejected by the database itself
+    let invalid = sqlx::query("UPDATE test_pg_enum_tickets SET priority = 'Urgent'").execute(pool).await;
+    assert!(invalid.is_err());
+
+    // New variants are added in order; removed ones stay in the type
+    let planned = evolved::Ticket::migrate_with(pool, MigrateOptions { dry_run: true, ..options }).await.unwrap();
+    assert_eq!(planned, vec![
+        "ALTER TYPE \"test_ticket_priority\" ADD VALUE IF NOT EXISTS 'Medium' AFTER 'Low'".to_string(),
+    ]);
+    assert_eq!(labels(pool).await, vec!["Low", "High"]);
+
+    let applied = evolved::Ticket::migrate_with(pool, options).await.unwrap();
+    assert_eq!(applied, planned);
+    assert_eq!(labels(pool).await, vec!["Low", "Medium", "High"]);
+
+    let medium = evolved::Ticket { priority: evolved::TestTicketPriority::Medium, ..Default::default() }
+        .create(pool).await.unwrap();
+    assert_eq!(medium.priority, evolved::TestTicketPriority::Medium);
+
+    assert!(evolved::Ticket::migrate_with(pool, options).await.unwrap().is_empty());
+
+    sqlx::query("DROP TABLE test_pg_enum_tickets").execute(pool).await.unwrap();
+    sqlx::query("DROP TYPE test_ticket_priority").execute(pool).await.unwrap();
+}
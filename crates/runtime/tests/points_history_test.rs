@@ -0,0 +1,59 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::SqlxCrud;
+use metastable_runtime::{User, UserPointsLog, UserPointsLogKind};
+use sqlx::types::Uuid;
+
+#[tokio::test]
+async fn test_points_history_filters_and_paginates() {
+    let db = PostgresClient::setup_connection().await;
+    let mut tx = db.get_client().begin().await.unwrap();
+
+    let user_id = Uuid::new_v4();
+    let mut user = User { id: user_id, running_claimed_balance: 100, ..Default::default() };
+
+    // all rows share one created_at inside the transaction, exercising the id tie-break
+    let mut logs = vec![
+        UserPointsLog::from_daily_checkin(&user_id, 50),
+        UserPointsLog::from_invitation(&user_id, &Uuid::new_v4(), 200, 100),
+    ];
+    for _ in 0..5 {
+        logs.push(user.pay_for_chat_message(1, Uuid::new_v4(), Uuid::new_v4(), 1, None).unwrap());
+    }
+    UserPointsLog::create_many(logs, &mut *tx).await.unwrap();
+    UserPointsLog::from_daily_checkin(&Uuid::new_v4(), 50).create(&mut *tx).await.unwrap();
+
+    let all = UserPointsLog::history(user_id, None, None, 100, &mut *tx).await.unwrap();
+    assert_eq!(all.len(), 7);
+
+    let checkins = UserPointsLog::history(user_id, Some(UserPointsLogKind::Checkin), None, 100, &mut *tx).await.unwrap();
+    assert_eq!(checkins.len(), 1);
+    assert_eq!(checkins[0].added_to_claimed, 50);
+
+    let invitations = UserPointsLog::history(user_id, Some(UserPointsLogKind::Invitation), None, 100, &mut *tx).await.unwrap();
+    assert_eq!(invitations.len(), 1);
+
+    let refunds = UserPointsLog::history(user_id, Some(UserPointsLogKind::Refund), None, 100, &mut *tx).await.unwrap();
+    assert!(refunds.is_empty());
+
+    let mut seen = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = UserPointsLog::history(user_id, Some(UserPointsLogKind::ChatSpend), cursor, 2, &mut *tx).await.unwrap();
+        assert!(page.len() <= 2);
+        assert!(page.iter().all(|log| log.deducted_from_claimed == 1));
+        seen.extend(page.iter().map(|log| log.id));
+        match page.last() {
+            Some(last) if page.len() == 2 => cursor = Some((last.created_at, last.id)),
+            _ => break,
+        }
+    }
+
+    assert_eq!(seen.len(), 5);
+    let mut deduped = seen.clone();
+    deduped.sort();
+    deduped.dedup();
+    assert_eq!(deduped.len(), 5);
+
+    tx.rollback().await.unwrap();
+}
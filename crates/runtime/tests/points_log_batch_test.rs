@@ -0,0 +1,40 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{QueryCriteria, SqlxCrud, SqlxFilterQuery};
+use metastable_runtime::UserPointsLog;
+use sqlx::types::Uuid;
+
+#[tokio::test]
+async fn test_create_many_points_logs() {
+    let db = PostgresClient::setup_connection().await;
+    let mut tx = db.get_client().begin().await.unwrap();
+
+    let user_id = Uuid::new_v4();
+    let referer_id = Uuid::new_v4();
+    let logs = vec![
+        UserPointsLog::from_daily_checkin(&user_id, 50),
+        UserPointsLog::from_invitation(&user_id, &referer_id, 200, 100),
+        UserPointsLog::from_direct_purchase(&user_id, 7),
+    ];
+
+    let created = UserPointsLog::create_many(logs, &mut *tx).await.unwrap();
+    assert_eq!(created.len(), 3);
+    assert!(created.iter().all(|log| log.user == user_id && log.id != Uuid::nil()));
+
+    let persisted = UserPointsLog::find_by_criteria(
+        QueryCriteria::new().add_valued_filter("user", "=", user_id),
+        &mut *tx
+    ).await.unwrap();
+    assert_eq!(persisted.len(), 3);
+
+    let claimed = persisted.iter().find(|log| log.added_to_claimed == 50).expect("daily checkin log");
+    assert_eq!(claimed.added_to_misc, 0);
+    let invitation = persisted.iter().find(|log| log.reward_to == Some(referer_id)).expect("invitation log");
+    assert_eq!(invitation.added_to_misc, 200);
+    assert_eq!(invitation.reward_amount, 100);
+    assert!(persisted.iter().any(|log| log.added_to_purchased == 7));
+
+    assert!(UserPointsLog::create_many(vec![], &mut *tx).await.unwrap().is_empty());
+
+    tx.rollback().await.unwrap();
+}
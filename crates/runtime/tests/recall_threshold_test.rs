@@ -0,0 +1,110 @@
+use metastable_clients::{
+    EmbeddingMessage, Mem0Filter, PgvectorClient, PostgresClient, DEFAULT_GRAPH_DB_VECTOR_SEARCH_THRESHOLD, EMBEDDING_DIMS,
+};
+use metastable_common::ModuleClient;
+use metastable_database::{QueryCriteria, SqlxCrud, SqlxFilterQuery};
+use metastable_runtime::{Character, User};
+use sqlx::types::Uuid;
+
+fn memory(filter: &Mem0Filter, content: &str, x: f32, y: f32) -> EmbeddingMessage {
+    let mut embedding = vec![0.0f32; EMBEDDING_DIMS as usize];
+    embedding[0] = x;
+    embedding[1] = y;
+
+    EmbeddingMessage {
+        id: Uuid::new_v4(),
+        user_id: filter.user_id,
+        character_id: filter.character_id,
+        session_id: None,
+        embedding: embedding.into(),
+        content: content.to_string(),
+        tags: vec![],
+        metadata: None,
+        created_at: 0,
+        updated_at: 0,
+    }
+}
+
+#[test]
+fn test_recall_threshold_falls_back_to_global_default() {
+    let default = Character::default();
+    assert_eq!(default.recall_threshold(), DEFAULT_GRAPH_DB_VECTOR_SEARCH_THRESHOLD);
+
+    let strict = Character { memory_recall_threshold: Some(0.95), ..Default::default() };
+    assert_eq!(strict.recall_threshold(), 0.95);
+}
+
+#[test]
+fn test_recall_threshold_must_be_a_similarity() {
+    for threshold in [None, Some(0.0), Some(0.5), Some(1.0)] {
+        let character = Character { memory_recall_threshold: threshold, ..Default::default() };
+        assert!(character.validate_recall_threshold().is_ok(), "{:?}", threshold);
+    }
+    for threshold in [-0.1, 1.5, f32::NAN] {
+        let character = Character { memory_recall_threshold: Some(threshold), ..Default::default() };
+        assert!(character.validate_recall_threshold().is_err(), "{}", threshold);
+    }
+}
+
+#[tokio::test]
+async fn test_recall_threshold_is_persisted() {
+    let db = PostgresClient::setup_connection().await;
+    let mut tx = db.get_client().begin().await.unwrap();
+
+    let user = User { user_id: format!("recall_threshold_{}", Uuid::new_v4()), ..Default::default() }
+        .create(&mut *tx).await.unwrap();
+    let character = Character {
+        name: format!("test_recall_threshold_{}", Uuid::new_v4()),
+        creator: user.id,
+        memory_recall_threshold: Some(0.75),
+        ..Default::default()
+    }.create(&mut *tx).await.unwrap();
+
+    let mut loaded = Character::find_one_by_criteria(
+        QueryCriteria::new().add_valued_filter("id", "=", character.id),
+        &mut *tx,
+    ).await.unwrap().unwrap();
+    assert_eq!(loaded.memory_recall_threshold, Some(0.75));
+    assert_eq!(loaded.recall_threshold(), 0.75);
+
+    loaded.memory_recall_threshold = None;
+    let loaded = loaded.update(&mut *tx).await.unwrap();
+    assert_eq!(loaded.recall_threshold(), DEFAULT_GRAPH_DB_VECTOR_SEARCH_THRESHOLD);
+
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_recall_drops_memories_below_the_character_threshold() {
+    let db = PgvectorClient::setup_connection().await;
+    let filter = Mem0Filter { user_id: Uuid::new_v4(), character_id: Some(Uuid::new_v4()), session_id: None };
+
+    // Cosine similarities to the query are 1.0 and 0.6
+    let mut tx = db.get_client().begin().await.unwrap();
+    let close = memory(&filter, "likes tea", 1.0, 0.0).create(&mut *tx).await.unwrap();
+    memory(&filter, "lives in Beijing", 0.6, 0.8).create(&mut *tx).await.unwrap();
+    tx.commit().await.unwrap();
+
+    let query = memory(&filter, "query", 1.0, 0.0);
+    let recall = |threshold: Option<f32>| {
+        let character = Character { memory_recall_threshold: threshold, ..Default::default() };
+        let (db, filter, query) = (&db, &filter, query.clone());
+        async move {
+            EmbeddingMessage::batch_search_with_threshold(db, filter, &[query], 10, character.recall_threshold(), None)
+                .await.unwrap().remove(0)
+        }
+    };
+
+    let strict = recall(Some(0.9)).await;
+    assert_eq!(strict.iter().map(|m| m.id).collect::<Vec<_>>(), vec![close.id]);
+
+    let lenient = recall(Some(0.5)).await;
+    assert_eq!(lenient.len(), 2);
+
+    let mut tx = db.get_client().begin().await.unwrap();
+    EmbeddingMessage::delete_by_criteria(
+        QueryCriteria::new().add_valued_filter("user_id", "=", filter.user_id),
+        &mut *tx
+    ).await.unwrap();
+    tx.commit().await.unwrap();
+}
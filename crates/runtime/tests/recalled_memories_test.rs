@@ -0,0 +1,56 @@
+use metastable_runtime::{Character, MAX_RECALLED_MEMORIES_CHARS};
+
+const PROMPT: &str = "You are {{char}} talking to {{user}}.\nWhat you remember:\n{{memories}}\nStay in character.";
+
+fn character() -> Character {
+    Character {
+        name: "Alice".to_string(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_recalled_memories_render_under_placeholder() {
+    let recalled = vec![
+        "Bob likes green tea".to_string(),
+        "Bob has a cat named Mochi".to_string(),
+    ];
+    let prompt = character().build_system_prompt(PROMPT, "Bob", &recalled);
+
+    assert_eq!(
+        prompt.content,
+        "You are Alice talking to Bob.\nWhat you remember:\n- Bob likes green tea\n- Bob has a cat named Mochi\nStay in character."
+    );
+}
+
+#[test]
+fn test_empty_recall_removes_placeholder() {
+    let prompt = character().build_system_prompt(PROMPT, "Bob", &[]);
+
+    assert_eq!(prompt.content, "You are Alice talking to Bob.\nWhat you remember:\nStay in character.");
+    assert!(!prompt.content.contains("{{memories}}"));
+}
+
+#[test]
+fn test_recalled_memories_are_capped() {
+    let recalled = (0..1000).map(|i| format!("memory number {}", i)).collect::<Vec<_>>();
+    let prompt = character().build_system_prompt("{{memories}}", "Bob", &recalled);
+
+    assert!(prompt.content.chars().count() <= MAX_RECALLED_MEMORIES_CHARS);
+    assert!(prompt.content.starts_with("- memory number 0\n"));
+    assert!(prompt.content.lines().all(|l| l.starts_with("- memory number ")));
+}
+
+#[test]
+fn test_placeholders_inside_memories_are_not_expanded() {
+    let recalled = vec![
+        "{{user}} told {{char}} a secret".to_string(),
+        "Bob likes green tea".to_string(),
+    ];
+    let prompt = character().build_system_prompt(PROMPT, "Bob", &recalled);
+
+    assert_eq!(
+        prompt.content,
+        "You are Alice talking to Bob.\nWhat you remember:\n- {{user}} told {{char}} a secret\n- Bob likes green tea\nStay in character."
+    );
+}
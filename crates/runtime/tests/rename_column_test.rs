@@ -0,0 +1,76 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{MigrateOptions, QueryCriteria, SchemaMigrator, SqlxCrud, SqlxFilterQuery};
+
+// The same table before and after `nickname` is renamed to `display_name`
+mod before {
+    use metastable_database::SqlxObject;
+    use serde::{Deserialize, Serialize};
+    use sqlx::types::Uuid;
+
+    #[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+    #[table_name = "test_rename_column"]
+    pub struct Profile {
+        pub id: Uuid,
+        pub nickname: String,
+        pub created_at: i64,
+        pub updated_at: i64,
+    }
+}
+
+mod after {
+    use metastable_database::SqlxObject;
+    use serde::{Deserialize, Serialize};
+    use sqlx::types::Uuid;
+
+    #[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+    #[table_name = "test_rename_column"]
+    pub struct Profile {
+        pub id: Uuid,
+        #[renamed_from = "nickname"]
+        pub display_name: String,
+        pub created_at: i64,
+        pub updated_at: i64,
+    }
+}
+
+async fn columns(pool: &sqlx::PgPool) -> Vec<String> {
+    sqlx::query_scalar(
+        "SELECT column_name::text FROM information_schema.columns \
+         WHERE table_name = 'test_rename_column' AND table_schema = 'public' ORDER BY column_name"
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_renamed_field_keeps_its_data() {
+    let db = PostgresClient::setup_connection().await;
+    let pool: &sqlx::PgPool = db.get_client();
+    let options = MigrateOptions { dry_run: false, allow_drops: true };
+
+    sqlx::query("DROP TABLE IF EXISTS test_rename_column").execute(pool).await.unwrap();
+    before::Profile::migrate(pool).await.unwrap();
+    let profile = before::Profile { nickname: "Bob".to_string(), ..Default::default() }
+        .create(pool).await.unwrap();
+
+    let planned = after::Profile::migrate_with(pool, MigrateOptions { dry_run: true, ..options }).await.unwrap();
+    assert_eq!(planned, vec![
+        "ALTER TABLE \"test_rename_column\" RENAME COLUMN \"nickname\" TO \"display_name\"".to_string(),
+    ]);
+
+    let applied = after::Profile::migrate_with(pool, options).await.unwrap();
+    assert_eq!(applied, planned);
+    assert_eq!(columns(pool).await, vec!["created_at", "display_name", "id", "updated_at"]);
+
+    let renamed = after::Profile::find_one_by_criteria(
+        QueryCriteria::new().add_valued_filter("id", "=", profile.id), pool
+    ).await.unwrap().unwrap();
+    assert_eq!(renamed.display_name, "Bob");
+
+    // Once renamed, the attribute is a no-op
+    assert!(after::Profile::migrate_with(pool, options).await.unwrap().is_empty());
+
+    sqlx::query("DROP TABLE test_rename_column").execute(pool).await.unwrap();
+}
@@ -0,0 +1,58 @@
+use async_openai::types::{ChatCompletionRequestMessage, ChatCompletionRequestUserMessageArgs};
+use metastable_runtime::RequestBuilder;
+
+fn messages() -> Vec<ChatCompletionRequestMessage> {
+    vec![ChatCompletionRequestUserMessageArgs::default()
+        .content("hello")
+        .build()
+        .unwrap()
+        .into()]
+}
+
+#[test]
+fn test_request_builder_builds_valid_request() {
+    let request = RequestBuilder::new("google/gemini-2.5-flash")
+        .messages(messages())
+        .temperature(0.7)
+        .max_tokens(1000)
+        .reasoning_effort(Some("minimal"))
+        .modalities(vec!["text".to_string()])
+        .build()
+        .unwrap();
+
+    assert_eq!(request.base.model, "google/gemini-2.5-flash");
+    assert_eq!(request.base.temperature, Some(0.7));
+    assert_eq!(request.reasoning.unwrap().effort, "minimal");
+    assert_eq!(request.modalities, Some(vec!["text".to_string()]));
+}
+
+#[test]
+fn test_request_builder_rejects_reasoning_on_non_reasoning_model() {
+    let err = RequestBuilder::new("openai/gpt-4o-mini")
+        .messages(messages())
+        .reasoning_effort(Some("high"))
+        .build()
+        .unwrap_err();
+    assert!(err.to_string().contains("non-reasoning model openai/gpt-4o-mini"));
+
+    // Same model without reasoning is fine
+    assert!(RequestBuilder::new("openai/gpt-4o-mini").messages(messages()).build().is_ok());
+}
+
+#[test]
+fn test_request_builder_rejects_out_of_range_values() {
+    let bad_effort = RequestBuilder::new("openai/gpt-5-mini")
+        .messages(messages())
+        .reasoning_effort(Some("extreme"))
+        .build();
+    assert!(bad_effort.unwrap_err().to_string().contains("Unknown reasoning effort"));
+
+    let bad_temperature = RequestBuilder::new("openai/gpt-5-mini")
+        .messages(messages())
+        .temperature(3.0)
+        .build();
+    assert!(bad_temperature.unwrap_err().to_string().contains("Temperature"));
+
+    let no_messages = RequestBuilder::new("openai/gpt-5-mini").build();
+    assert!(no_messages.is_err());
+}
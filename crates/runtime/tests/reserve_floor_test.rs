@@ -0,0 +1,37 @@
+use metastable_runtime::User;
+use sqlx::types::Uuid;
+
+fn user_with_balance(claimed: i64, purchased: i64) -> User {
+    User {
+        id: Uuid::new_v4(),
+        running_claimed_balance: claimed,
+        running_purchased_balance: purchased,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_non_essential_payment_respects_reserve_floor() {
+    let mut user = user_with_balance(5, 7);
+
+    // 12 - 3 = 9 stays above the floor of 8
+    assert_eq!(user.try_pay(3, Some(8)).unwrap(), 3);
+    // 12 - 6 = 6 would dip below it
+    assert!(user.try_pay(6, Some(8)).is_err());
+
+    let refused = user.pay_for_voice_generation(6, Uuid::new_v4(), Some(8));
+    assert!(refused.is_err());
+    assert_eq!(user.total_balance(), 12);
+    assert_eq!(user.balance_usage, 0);
+}
+
+#[test]
+fn test_essential_payment_bypasses_reserve_floor() {
+    let mut user = user_with_balance(5, 7);
+
+    assert_eq!(user.try_pay(12, None).unwrap(), 12);
+    user.pay_for_chat_message(12, Uuid::new_v4(), Uuid::new_v4(), 1, None).unwrap();
+    assert_eq!(user.total_balance(), 0);
+
+    assert!(user.try_pay(1, None).is_err());
+}
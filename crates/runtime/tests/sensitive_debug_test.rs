@@ -0,0 +1,22 @@
+use metastable_runtime::User;
+
+#[test]
+fn test_user_debug_masks_sensitive_fields() {
+    let user = User {
+        user_id: "visible_user_id".to_string(),
+        user_aka: "visible_aka".to_string(),
+        email: Some("secret@example.com".to_string()),
+        phone: Some("+1234567890".to_string()),
+        ..Default::default()
+    };
+
+    let output = format!("{:?}", user);
+
+    assert!(!output.contains("secret@example.com"));
+    assert!(!output.contains("+1234567890"));
+    assert!(output.contains("email: ***"));
+    assert!(output.contains("phone: ***"));
+
+    assert!(output.contains("visible_user_id"));
+    assert!(output.contains("visible_aka"));
+}
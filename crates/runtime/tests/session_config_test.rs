@@ -0,0 +1,45 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::SqlxCrud;
+use metastable_runtime::{ChatSession, SystemConfig};
+use sqlx::types::Uuid;
+
+fn test_config(name: &str) -> SystemConfig {
+    SystemConfig {
+        name: name.to_string(),
+        system_prompt: format!("system prompt for {}", name),
+        system_prompt_version: 1,
+        active: true,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_sessions_reference_named_system_configs() {
+    let db = PostgresClient::setup_connection().await;
+    let mut tx = db.get_client().begin().await.unwrap();
+
+    let suffix = Uuid::new_v4();
+    let roleplay_name = format!("test_roleplay_{}", suffix);
+    let creation_name = format!("test_character_creation_{}", suffix);
+
+    let roleplay_config = test_config(&roleplay_name).create(&mut *tx).await.unwrap();
+    let creation_config = test_config(&creation_name).create(&mut *tx).await.unwrap();
+
+    let roleplay_session = ChatSession::new_with_config(
+        Uuid::new_v4(), Uuid::new_v4(), true, &roleplay_name, None, &mut *tx
+    ).await.unwrap();
+    let creation_session = ChatSession::new_with_config(
+        Uuid::new_v4(), Uuid::new_v4(), false, &creation_name, None, &mut *tx
+    ).await.unwrap();
+
+    assert_eq!(roleplay_session.system_config, Some(roleplay_config.id));
+    assert_eq!(creation_session.system_config, Some(creation_config.id));
+
+    let missing = ChatSession::new_with_config(
+        Uuid::new_v4(), Uuid::new_v4(), true, &format!("missing_{}", suffix), None, &mut *tx
+    ).await;
+    assert!(missing.is_err());
+
+    tx.rollback().await.unwrap();
+}
@@ -0,0 +1,86 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{SchemaMigrator, SqlxCrud};
+use metastable_runtime::{ChatSession, ParticipantRole, SessionParticipant, User, DEFAULT_MAX_SESSION_PARTICIPANTS};
+use sqlx::types::Uuid;
+
+async fn test_user(tx: &mut sqlx::PgConnection, name: &str) -> User {
+    User { user_id: format!("{}_{}", name, Uuid::new_v4()), user_aka: name.to_string(), ..Default::default() }
+        .create(&mut *tx).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_join_and_leave_respect_the_cap() {
+    let db = PostgresClient::setup_connection().await;
+    let pool: &sqlx::PgPool = db.get_client();
+    ChatSession::migrate(pool).await.unwrap();
+    SessionParticipant::migrate(pool).await.unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    let host = test_user(&mut tx, "host").await;
+    let guests = [
+        test_user(&mut tx, "guest_a").await,
+        test_user(&mut tx, "guest_b").await,
+        test_user(&mut tx, "guest_c").await,
+    ];
+    let session = ChatSession { max_participants: Some(3), ..ChatSession::new(Uuid::new_v4(), host.id, false) }
+        .create(&mut *tx).await.unwrap();
+
+    let hosting = session.join(&host, &mut tx).await.unwrap();
+    assert_eq!(hosting.role, ParticipantRole::Host);
+    let first = session.join(&guests[0], &mut tx).await.unwrap();
+    assert_eq!(first.role, ParticipantRole::Guest);
+    session.join(&guests[1], &mut tx).await.unwrap();
+
+    // Joining again is a no-op, even at the cap
+    assert_eq!(session.join(&guests[0], &mut tx).await.unwrap().id, first.id);
+
+    let err = session.join(&guests[2], &mut tx).await.unwrap_err();
+    assert!(err.to_string().contains("is full"), "{}", err);
+    assert_eq!(session.participants(&mut *tx).await.unwrap().len(), 3);
+
+    // Leaving frees a seat
+    assert!(session.leave(&guests[1], &mut *tx).await.unwrap());
+    assert!(!session.leave(&guests[1], &mut *tx).await.unwrap());
+    session.join(&guests[2], &mut tx).await.unwrap();
+
+    let members: Vec<Uuid> = session.participants(&mut *tx).await.unwrap().iter().map(|p| p.user_id).collect();
+    assert_eq!(members.len(), 3);
+    assert!(members.contains(&host.id) && members.contains(&guests[0].id) && members.contains(&guests[2].id));
+
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_owner_seat_is_held_until_they_join() {
+    let db = PostgresClient::setup_connection().await;
+    let pool: &sqlx::PgPool = db.get_client();
+    ChatSession::migrate(pool).await.unwrap();
+    SessionParticipant::migrate(pool).await.unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    let host = test_user(&mut tx, "host").await;
+    let guests = [
+        test_user(&mut tx, "guest_a").await,
+        test_user(&mut tx, "guest_b").await,
+        test_user(&mut tx, "guest_c").await,
+    ];
+    let session = ChatSession::new(Uuid::new_v4(), host.id, false).create(&mut *tx).await.unwrap();
+    assert_eq!(session.max_participants, None);
+    assert_eq!(session.max_participants(), DEFAULT_MAX_SESSION_PARTICIPANTS as usize);
+
+    let session = ChatSession { max_participants: Some(3), ..session }.update(&mut *tx).await.unwrap();
+    session.join(&guests[0], &mut tx).await.unwrap();
+    session.join(&guests[1], &mut tx).await.unwrap();
+    // The last seat belongs to the owner
+    let err = session.join(&guests[2], &mut tx).await.unwrap_err();
+    assert!(err.to_string().contains("is full"), "{}", err);
+    assert_eq!(session.join(&host, &mut tx).await.unwrap().role, ParticipantRole::Host);
+    assert_eq!(session.participants(&mut *tx).await.unwrap().len(), 3);
+
+    // The same user cannot be added twice, even around `join`
+    let duplicate = SessionParticipant::new(session.id, guests[0].id, ParticipantRole::Guest).create(&mut *tx).await;
+    assert!(duplicate.is_err());
+
+    tx.rollback().await.unwrap();
+}
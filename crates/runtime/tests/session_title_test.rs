@@ -0,0 +1,146 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use metastable_clients::{LlmClient, PostgresClient};
+use metastable_common::ModuleClient;
+use metastable_database::{QueryCriteria, SqlxCrud, SqlxFilterQuery};
+use metastable_runtime::{Character, ChatSession, Message, MessageType, SessionTitleConfig, User};
+use serde_json::json;
+use sqlx::types::{Json, Uuid};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Serves an OpenAI-compatible `/chat/completions` that always replies with
+/// `title`, counting the requests it receives.
+async fn mock_llm(title: &'static str) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let counter = calls.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let counter = counter.clone();
+            tokio::spawn(async move {
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                loop {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    if n == 0 { return; }
+                    request.extend_from_slice(&buf[..n]);
+
+                    let text = String::from_utf8_lossy(&request);
+                    let Some(header_end) = text.find("\r\n\r\n") else { continue };
+                    let content_length = text[..header_end].lines()
+                        .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                        .unwrap_or(0);
+                    if request.len() >= header_end + 4 + content_length { break; }
+                }
+                counter.fetch_add(1, Ordering::SeqCst);
+
+                let body = json!({
+                    "id": "chatcmpl-mock",
+                    "object": "chat.completion",
+                    "created": 0,
+                    "model": "mock",
+                    "choices": [{
+                        "index": 0,
+                        "message": { "role": "assistant", "content": title },
+                        "finish_reason": "stop",
+                    }],
+                }).to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(), body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            });
+        }
+    });
+
+    (base_url, calls)
+}
+
+fn turn(session: &ChatSession, user: &str, assistant: &str) -> Message {
+    Message {
+        id: Uuid::new_v4(),
+        owner: session.owner,
+        system_config: Uuid::nil(),
+        session: Some(session.id),
+        turn_index: None,
+        user_message_content: user.to_string(),
+        user_message_content_type: MessageType::Text,
+        input_toolcall: Json(None),
+        assistant_message_content: assistant.to_string(),
+        assistant_message_content_type: MessageType::Text,
+        assistant_message_tool_call: Json(None),
+        summary: None,
+        model_name: "mock".to_string(),
+        usage: Json(None),
+        finish_reason: None,
+        refusal: None,
+        is_stale: false,
+        is_memorizeable: true,
+        is_in_memory: false,
+        is_migrated: false,
+        migration_claimed_at: None,
+        deleted_at: None,
+        created_at: 0,
+        updated_at: 0,
+    }
+}
+
+#[tokio::test]
+async fn test_title_generated_once() {
+    let (base_url, calls) = mock_llm("\"A Rainy Night at the Inn\"\n").await;
+    std::env::set_var("OPENAI_BASE_URL", &base_url);
+    std::env::set_var("OPENAI_API_KEY", "test");
+    let llm = LlmClient::setup_connection().await;
+    let config = SessionTitleConfig { min_turns: 2, ..Default::default() };
+
+    let db = PostgresClient::setup_connection().await;
+    let mut tx = db.get_client().begin().await.unwrap();
+
+    let user = User { user_id: format!("title_{}", Uuid::new_v4()), ..Default::default() }
+        .create(&mut *tx).await.unwrap();
+    let character = Character { name: "Innkeeper".to_string(), creator: user.id, ..Default::default() }
+        .create(&mut *tx).await.unwrap();
+    let mut session = ChatSession::new(character.id, user.id, false).create(&mut *tx).await.unwrap();
+    assert_eq!(session.title, None);
+
+    let mut turns = vec![turn(&session, "Hello?", "Welcome, traveller. Dry yourself by the fire.")];
+
+    // Too early: no call is made
+    assert!(!session.ensure_title(&turns, &llm, &config, &mut *tx).await.unwrap());
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+    // First eligible turn
+    turns.push(turn(&session, "Is there a room for the night?", "Only the one above the stables."));
+    assert!(session.ensure_title(&turns, &llm, &config, &mut *tx).await.unwrap());
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert_eq!(session.title.as_deref(), Some("A Rainy Night at the Inn"));
+
+    // Later turns keep the title without asking again
+    turns.push(turn(&session, "I'll take it.", "That'll be two silver."));
+    assert!(!session.ensure_title(&turns, &llm, &config, &mut *tx).await.unwrap());
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let stored = ChatSession::find_one_by_criteria(
+        QueryCriteria::new().add_valued_filter("id", "=", session.id), &mut *tx
+    ).await.unwrap().unwrap();
+    assert_eq!(stored.title.as_deref(), Some("A Rainy Night at the Inn"));
+
+    // A copy loaded before the title was stored does not overwrite it
+    let mut stale = stored.clone();
+    stale.title = None;
+    assert!(!stale.ensure_title(&turns, &llm, &config, &mut *tx).await.unwrap());
+    assert_eq!(stale.title, None);
+
+    let stored = ChatSession::find_one_by_criteria(
+        QueryCriteria::new().add_valued_filter("id", "=", session.id), &mut *tx
+    ).await.unwrap().unwrap();
+    assert_eq!(stored.title.as_deref(), Some("A Rainy Night at the Inn"));
+
+    tx.rollback().await.unwrap();
+}
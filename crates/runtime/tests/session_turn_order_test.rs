@@ -0,0 +1,111 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{OrderDirection, QueryCriteria, SchemaMigrator, SqlxCrud, SqlxFilterQuery, SqlxSoftDelete};
+use metastable_runtime::{Character, ChatSession, Message, MessageType, SystemConfig, User};
+use sqlx::types::{Json, Uuid};
+
+fn turn(session: Uuid, owner: Uuid, system_config: Uuid, content: String) -> Message {
+    Message {
+        id: Uuid::new_v4(),
+        owner,
+        system_config,
+        session: Some(session),
+        turn_index: None,
+        user_message_content: content,
+        user_message_content_type: MessageType::Text,
+        input_toolcall: Json(None),
+        assistant_message_content: String::new(),
+        assistant_message_content_type: MessageType::Text,
+        assistant_message_tool_call: Json(None),
+        summary: None,
+        model_name: "test".to_string(),
+        usage: Json(None),
+        finish_reason: None,
+        refusal: None,
+        is_stale: false,
+        is_memorizeable: false,
+        is_in_memory: false,
+        is_migrated: false,
+        migration_claimed_at: None,
+        deleted_at: None,
+        created_at: 0,
+        updated_at: 0,
+    }
+}
+
+async fn post_turns(pool: &sqlx::PgPool, session: &ChatSession, owner: Uuid, n: usize) {
+    let system_config = session.system_config.unwrap();
+    for i in 0..n {
+        let mut tx = pool.begin().await.unwrap();
+        let mut message = turn(session.id, owner, system_config, format!("{} #{}", owner, i));
+        message.turn_index = Some(ChatSession::next_turn(&session.id, &mut tx).await.unwrap());
+        message.create(&mut *tx).await.unwrap();
+        tx.commit().await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_concurrent_posters_get_contiguous_turns() {
+    let db = PostgresClient::setup_connection().await;
+    let pool: &sqlx::PgPool = db.get_client();
+    ChatSession::migrate(pool).await.unwrap();
+    Message::migrate(pool).await.unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    let mut users = vec![];
+    for name in ["alice", "bob"] {
+        users.push(
+            User { user_id: format!("{}_{}", name, Uuid::new_v4()), user_aka: name.to_string(), ..Default::default() }
+                .create(&mut *tx).await.unwrap()
+        );
+    }
+    let character = Character {
+        name: format!("test_turn_order_{}", Uuid::new_v4()),
+        creator: users[0].id,
+        ..Default::default()
+    }.create(&mut *tx).await.unwrap();
+    let system_config = SystemConfig {
+        name: format!("test_turn_order_{}", Uuid::new_v4()),
+        ..Default::default()
+    }.create(&mut *tx).await.unwrap();
+    let session = ChatSession {
+        system_config: Some(system_config.id),
+        ..ChatSession::new(character.id, users[0].id, false)
+    }.create(&mut *tx).await.unwrap();
+    tx.commit().await.unwrap();
+
+    tokio::join!(
+        post_turns(pool, &session, users[0].id, 10),
+        post_turns(pool, &session, users[1].id, 10),
+    );
+
+    let mut tx = pool.begin().await.unwrap();
+    let messages = Message::find_by_criteria(
+        QueryCriteria::new()
+            .add_valued_filter("session", "=", session.id)
+            .order_by("turn_index", OrderDirection::Asc),
+        &mut *tx
+    ).await.unwrap();
+    let turns = messages.iter().map(|m| m.turn_index.unwrap()).collect::<Vec<_>>();
+    assert_eq!(turns, (1..=20).collect::<Vec<_>>());
+    for user in &users {
+        assert_eq!(messages.iter().filter(|m| m.owner == user.id).count(), 10);
+    }
+
+    let session = ChatSession::find_one_by_criteria(
+        QueryCriteria::new().add_valued_filter("id", "=", session.id),
+        &mut *tx
+    ).await.unwrap().unwrap();
+    assert_eq!(session.turn_count, 20);
+
+    for message in messages {
+        message.purge(&mut *tx).await.unwrap();
+    }
+    session.delete(&mut *tx).await.unwrap();
+    system_config.delete(&mut *tx).await.unwrap();
+    character.purge(&mut *tx).await.unwrap();
+    for user in users {
+        user.delete(&mut *tx).await.unwrap();
+    }
+    tx.commit().await.unwrap();
+}
@@ -0,0 +1,46 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{OrderDirection, QueryCriteria, SqlxCrud, SqlxFilterQuery};
+use metastable_runtime::SystemConfig;
+use sqlx::types::Uuid;
+
+#[tokio::test]
+async fn test_skip_locked_hides_rows_locked_by_another_transaction() {
+    let db = PostgresClient::setup_connection().await;
+    let name = format!("test_skip_locked_{}", Uuid::new_v4());
+
+    let mut tx = db.get_client().begin().await.unwrap();
+    for version in 1..=3 {
+        SystemConfig {
+            name: name.clone(),
+            system_prompt_version: version,
+            ..Default::default()
+        }.create(&mut *tx).await.unwrap();
+    }
+    tx.commit().await.unwrap();
+
+    let criteria = |name: &str| QueryCriteria::new()
+        .add_valued_filter("name", "=", name.to_string())
+        .order_by("system_prompt_version", OrderDirection::Asc);
+
+    // first transaction locks the two oldest versions
+    let mut first = db.get_client().begin().await.unwrap();
+    let locked = SystemConfig::find_by_criteria(criteria(&name).limit(2).for_update(), &mut *first).await.unwrap();
+    assert_eq!(locked.iter().map(|c| c.system_prompt_version).collect::<Vec<_>>(), vec![1, 2]);
+
+    // second transaction only sees the unlocked row
+    let mut second = db.get_client().begin().await.unwrap();
+    let visible = SystemConfig::find_by_criteria(criteria(&name).skip_locked(), &mut *second).await.unwrap();
+    assert_eq!(visible.iter().map(|c| c.system_prompt_version).collect::<Vec<_>>(), vec![3]);
+    second.commit().await.unwrap();
+    first.commit().await.unwrap();
+
+    let mut tx = db.get_client().begin().await.unwrap();
+    let all = SystemConfig::find_by_criteria(criteria(&name).skip_locked(), &mut *tx).await.unwrap();
+    assert_eq!(all.len(), 3);
+    SystemConfig::delete_by_criteria(
+        QueryCriteria::new().add_valued_filter("name", "=", name.clone()),
+        &mut *tx
+    ).await.unwrap();
+    tx.commit().await.unwrap();
+}
@@ -0,0 +1,63 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{SchemaMigrator, SqlxObject, SqlxSoftDelete};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+#[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+#[table_name = "test_soft_delete"]
+#[soft_delete]
+pub struct SoftDeleted {
+    pub id: Uuid,
+    pub name: String,
+    pub deleted_at: Option<i64>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+fn by_name(name: &str) -> QueryCriteria {
+    QueryCriteria::new().add_valued_filter("name", "=", name.to_string())
+}
+
+#[tokio::test]
+async fn test_soft_delete_hides_and_restores_rows() {
+    let db = PostgresClient::setup_connection().await;
+    let pool: &sqlx::PgPool = db.get_client();
+
+    // The table predates soft deletion: migrate must add a nullable deleted_at
+    sqlx::query("DROP TABLE IF EXISTS test_soft_delete").execute(pool).await.unwrap();
+    SoftDeleted::migrate(pool).await.unwrap();
+    sqlx::query("ALTER TABLE test_soft_delete DROP COLUMN deleted_at").execute(pool).await.unwrap();
+    SoftDeleted::migrate(pool).await.unwrap();
+    let nullable: String = sqlx::query_scalar(
+        "SELECT is_nullable::text FROM information_schema.columns \
+         WHERE table_name = 'test_soft_delete' AND column_name = 'deleted_at'"
+    ).fetch_one(pool).await.unwrap();
+    assert_eq!(nullable, "YES");
+
+    let mut tx = pool.begin().await.unwrap();
+    let row = SoftDeleted { id: Uuid::new_v4(), name: "kept".to_string(), ..Default::default() }
+        .create(&mut *tx).await.unwrap();
+
+    assert_eq!(row.clone().delete(&mut *tx).await.unwrap(), 1);
+    // Already deleted rows are not deleted again
+    assert_eq!(row.clone().delete(&mut *tx).await.unwrap(), 0);
+
+    assert!(SoftDeleted::find_one_by_criteria(by_name("kept"), &mut *tx).await.unwrap().is_none());
+    let deleted = SoftDeleted::find_one_by_criteria(by_name("kept").include_deleted(), &mut *tx).await.unwrap()
+        .expect("soft-deleted row should still exist");
+    assert!(deleted.deleted_at.is_some());
+
+    let restored = deleted.restore_deleted(&mut *tx).await.unwrap();
+    assert_eq!(restored.deleted_at, None);
+    assert!(SoftDeleted::find_one_by_criteria(by_name("kept"), &mut *tx).await.unwrap().is_some());
+
+    assert_eq!(SoftDeleted::delete_by_criteria(by_name("kept"), &mut *tx).await.unwrap(), 1);
+    assert!(SoftDeleted::find_one_by_criteria(by_name("kept"), &mut *tx).await.unwrap().is_none());
+
+    assert_eq!(restored.purge(&mut *tx).await.unwrap(), 1);
+    assert!(SoftDeleted::find_one_by_criteria(by_name("kept").include_deleted(), &mut *tx).await.unwrap().is_none());
+
+    tx.rollback().await.unwrap();
+    sqlx::query("DROP TABLE test_soft_delete").execute(pool).await.unwrap();
+}
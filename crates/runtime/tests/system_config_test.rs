@@ -0,0 +1,107 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{QueryCriteria, SqlxCrud, SqlxFilterQuery};
+use metastable_runtime::SystemConfig;
+use sqlx::types::Uuid;
+
+fn test_config(name: &str, version: i64, active: bool) -> SystemConfig {
+    SystemConfig {
+        name: name.to_string(),
+        system_prompt: format!("system prompt v{}", version),
+        system_prompt_version: version,
+        active,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_activate_switches_active_version() {
+    let db = PostgresClient::setup_connection().await;
+    let name = format!("test_activate_{}", Uuid::new_v4());
+
+    let mut tx = db.get_client().begin().await.unwrap();
+    test_config(&name, 1, true).create(&mut *tx).await.unwrap();
+    let v2 = test_config(&name, 2, false).create(&mut *tx).await.unwrap();
+    tx.commit().await.unwrap();
+
+    let activated = SystemConfig::activate(&name, 2, &db).await.unwrap();
+    assert_eq!(activated.id, v2.id);
+    assert!(activated.active);
+
+    let mut tx = db.get_client().begin().await.unwrap();
+    let v1 = SystemConfig::find_by_name(&name, Some(1), &mut *tx).await.unwrap().unwrap();
+    assert!(!v1.active);
+
+    let active = SystemConfig::find_active(&name, &mut *tx).await.unwrap().unwrap();
+    assert_eq!(active.id, v2.id);
+    tx.commit().await.unwrap();
+
+    assert!(SystemConfig::activate(&name, 3, &db).await.is_err());
+
+    let mut tx = db.get_client().begin().await.unwrap();
+    SystemConfig::delete_by_criteria(
+        QueryCriteria::new().add_valued_filter("name", "=", name.clone()),
+        &mut *tx
+    ).await.unwrap();
+    tx.commit().await.unwrap();
+}
+
+#[test]
+fn test_diff_lists_changed_fields() {
+    let v1 = test_config("test_diff", 1, true);
+    let mut v2 = test_config("test_diff", 2, false);
+    v2.openai_model = "google/gemini-2.5-pro".to_string();
+
+    let diff = SystemConfig::diff(&v1, &v2);
+    let changed = diff.as_object().unwrap();
+
+    let mut keys = changed.keys().cloned().collect::<Vec<_>>();
+    keys.sort();
+    assert_eq!(keys, vec!["openai_model", "system_prompt"]);
+
+    assert_eq!(changed["system_prompt"]["from"], "system prompt v1");
+    assert_eq!(changed["system_prompt"]["to"], "system prompt v2");
+    assert_eq!(changed["openai_model"]["from"], "");
+    assert_eq!(changed["openai_model"]["to"], "google/gemini-2.5-pro");
+
+    assert!(SystemConfig::diff(&v1, &v1).as_object().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_sync_default_keeps_operator_rollback() {
+    let db = PostgresClient::setup_connection().await;
+    let name = format!("test_sync_default_{}", Uuid::new_v4());
+
+    // First boot stores and activates the code config as version 0
+    let first = SystemConfig::sync_default(test_config(&name, 0, false), &db).await.unwrap();
+    assert_eq!(first.system_prompt_version, 0);
+    assert!(first.active);
+
+    // A changed code prompt becomes version 1
+    let code = test_config(&name, 1, false);
+    let second = SystemConfig::sync_default(code.clone(), &db).await.unwrap();
+    assert_eq!(second.system_prompt_version, 1);
+    assert!(second.active);
+
+    // The operator rolls back; restarting with the same code config keeps it
+    SystemConfig::activate(&name, 0, &db).await.unwrap();
+    let restarted = SystemConfig::sync_default(code, &db).await.unwrap();
+    assert_eq!(restarted.system_prompt_version, 0);
+
+    // A genuinely new prompt goes above the highest stored version, not the active one
+    let third = SystemConfig::sync_default(test_config(&name, 7, false), &db).await.unwrap();
+    assert_eq!(third.system_prompt_version, 2);
+    assert!(third.active);
+
+    let mut tx = db.get_client().begin().await.unwrap();
+    let stored = SystemConfig::count_by_criteria(
+        QueryCriteria::new().add_valued_filter("name", "=", name.clone()),
+        &mut *tx
+    ).await.unwrap();
+    assert_eq!(stored, 3);
+    SystemConfig::delete_by_criteria(
+        QueryCriteria::new().add_valued_filter("name", "=", name.clone()),
+        &mut *tx
+    ).await.unwrap();
+    tx.commit().await.unwrap();
+}
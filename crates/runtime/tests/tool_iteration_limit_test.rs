@@ -0,0 +1,147 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use anyhow::Result;
+use metastable_clients::{LlmClient, PostgresClient};
+use metastable_common::ModuleClient;
+use metastable_runtime::{run_agent_turn, Agent, LlmTool, Message, Prompt, SystemConfig, ToolIterationLimitExceeded};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::types::Uuid;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+#[derive(LlmTool, Debug, Clone, Serialize, Deserialize)]
+#[llm_tool(name = "keep_going", description = "Asks for another round.")]
+pub struct KeepGoing {
+    #[llm_tool(description = "Why another round is needed.")]
+    pub note: String,
+}
+
+/// Every call is answered with a `keep_going` tool call. Returns the base URL
+/// and the number of completions served.
+async fn mock_llm() -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let served = Arc::new(AtomicUsize::new(0));
+
+    let counter = served.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            // Read the headers, then as much body as they announce
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            let body_start = loop {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                if let Some(i) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break i + 4;
+                }
+            };
+            let headers = String::from_utf8_lossy(&request[..body_start]).to_lowercase();
+            let content_length = headers.lines()
+                .find_map(|l| l.strip_prefix("content-length:"))
+                .map(|v| v.trim().parse::<usize>().unwrap())
+                .unwrap_or(0);
+            while request.len() < body_start + content_length {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+
+            let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            let body = json!({
+                "id": format!("gen-{}", n),
+                "object": "chat.completion",
+                "created": 1735689600,
+                "model": "mock",
+                "choices": [{
+                    "index": 0,
+                    "message": {
+                        "role": "assistant",
+                        "content": "",
+                        "tool_calls": [{
+                            "id": format!("call_{}", n),
+                            "type": "function",
+                            "function": { "name": "keep_going", "arguments": "{\"note\":\"again\"}" },
+                        }],
+                    },
+                    "finish_reason": "tool_calls",
+                }],
+                "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 },
+            }).to_string();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(), body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+
+    (base_url, served)
+}
+
+/// Follows up on every `keep_going`, or only for the first `stop_after` rounds.
+#[derive(Clone)]
+struct LoopingAgent {
+    llm: LlmClient,
+    db: PostgresClient,
+    system_config: SystemConfig,
+    stop_after: Option<usize>,
+}
+
+#[async_trait::async_trait]
+impl Agent for LoopingAgent {
+    const SYSTEM_CONFIG_NAME: &'static str = "looping_test_v0";
+    type Tool = KeepGoing;
+    type Input = usize; // the round
+
+    fn system_prompt() -> &'static str { "Keep going." }
+    fn llm_client(&self) -> &LlmClient { &self.llm }
+    fn db_client(&self) -> &PostgresClient { &self.db }
+    fn system_config(&self) -> &SystemConfig { &self.system_config }
+
+    async fn build_input(&self, round: &usize) -> Result<Vec<Prompt>> {
+        Ok(vec![Prompt::new_system(Self::system_prompt()), Prompt::new_user(&format!("Round {}", round))])
+    }
+
+    async fn handle_output(&self, _round: &usize, message: &Message, _tool: &KeepGoing) -> Result<(Message, Option<Value>)> {
+        Ok((message.clone(), None))
+    }
+
+    fn follow_up(&self, round: &usize, _message: &Message, _tool: &KeepGoing) -> Option<usize> {
+        match self.stop_after {
+            Some(n) if round + 1 >= n => None,
+            _ => Some(round + 1),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_agent_turn_stops_at_the_limit() {
+    let (base_url, served) = mock_llm().await;
+    std::env::set_var("OPENAI_BASE_URL", &base_url);
+    std::env::set_var("OPENAI_API_KEY", "test");
+    let agent = LoopingAgent {
+        llm: LlmClient::setup_connection().await,
+        db: PostgresClient::default(),
+        system_config: LoopingAgent::to_system_config(),
+        stop_after: None,
+    };
+    let caller = Uuid::new_v4();
+
+    let err = run_agent_turn(&agent, &caller, 0, 3).await.unwrap_err();
+    assert!(ToolIterationLimitExceeded::is(&err), "{}", err);
+    assert_eq!(
+        err.downcast_ref::<ToolIterationLimitExceeded>(),
+        Some(&ToolIterationLimitExceeded { agent: "looping_test_v0", limit: 3 })
+    );
+    assert_eq!(served.load(Ordering::SeqCst), 3);
+
+    // A turn that settles within the limit returns its last call
+    let settling = LoopingAgent { stop_after: Some(2), ..agent };
+    let (message, tool, _) = run_agent_turn(&settling, &caller, 0, 3).await.unwrap();
+    assert_eq!(message.user_message_content, "Round 1");
+    assert_eq!(tool.note, "again");
+    assert_eq!(served.load(Ordering::SeqCst), 5);
+}
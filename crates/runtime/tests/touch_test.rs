@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{SqlxCrud, SqlxTouch};
+use metastable_runtime::SystemConfig;
+use sqlx::types::Uuid;
+
+#[tokio::test]
+async fn test_touch_only_bumps_updated_at() {
+    let db = PostgresClient::setup_connection().await;
+    let mut tx = db.get_client().begin().await.unwrap();
+
+    let config = SystemConfig {
+        name: format!("test_touch_{}", Uuid::new_v4()),
+        system_prompt: "touch me".to_string(),
+        system_prompt_version: 3,
+        ..Default::default()
+    }.create(&mut *tx).await.unwrap();
+    tx.commit().await.unwrap();
+
+    // updated_at has second resolution and follows the transaction start time
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    let mut tx = db.get_client().begin().await.unwrap();
+    let touched = config.clone().touch(&mut *tx).await.unwrap();
+
+    assert!(touched.updated_at > config.updated_at);
+    assert_eq!(touched.created_at, config.created_at);
+    assert_eq!(touched.id, config.id);
+    assert_eq!(touched.name, config.name);
+    assert_eq!(touched.system_prompt, config.system_prompt);
+    assert_eq!(touched.system_prompt_version, config.system_prompt_version);
+    assert_eq!(touched.active, config.active);
+
+    touched.delete(&mut *tx).await.unwrap();
+    tx.commit().await.unwrap();
+}
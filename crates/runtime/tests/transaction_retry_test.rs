@@ -0,0 +1,72 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{with_retry, SerializationFailure};
+
+/// Adds `amount` to the balance under `REPEATABLE READ`. On the attempts listed
+/// in `interfere_on`, another connection bumps the balance after the snapshot
+/// is taken, so the update conflicts.
+async fn deposit(pool: &sqlx::PgPool, amount: i64, interfere_on: &'static [u32], max_retries: u32) -> (Result<i64, sqlx::Error>, u32) {
+    let attempts = Arc::new(AtomicU32::new(0));
+    let result = with_retry(pool, |tx| {
+        let pool = pool.clone();
+        let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async move {
+            sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ").execute(&mut **tx).await?;
+            let balance: i64 = sqlx::query_scalar("SELECT balance FROM test_transaction_retry WHERE id = 1")
+                .fetch_one(&mut **tx).await?;
+
+            if interfere_on.contains(&attempt) {
+                sqlx::query("UPDATE test_transaction_retry SET balance = balance + 1 WHERE id = 1")
+                    .execute(&pool).await?;
+            }
+
+            sqlx::query("UPDATE test_transaction_retry SET balance = $1 WHERE id = 1")
+                .bind(balance + amount)
+                .execute(&mut **tx).await?;
+            Ok(balance + amount)
+        })
+    }, max_retries).await;
+    (result, attempts.load(Ordering::SeqCst))
+}
+
+#[tokio::test]
+async fn test_serialization_failures_are_retried() {
+    let db = PostgresClient::setup_connection().await;
+    let pool: &sqlx::PgPool = db.get_client();
+
+    sqlx::query("DROP TABLE IF EXISTS test_transaction_retry").execute(pool).await.unwrap();
+    sqlx::query("CREATE TABLE test_transaction_retry (id INT PRIMARY KEY, balance BIGINT NOT NULL)").execute(pool).await.unwrap();
+    sqlx::query("INSERT INTO test_transaction_retry VALUES (1, 0)").execute(pool).await.unwrap();
+    let balance = || sqlx::query_scalar::<_, i64>("SELECT balance FROM test_transaction_retry WHERE id = 1").fetch_one(pool);
+
+    // The second attempt reads the concurrent update and keeps it
+    let (result, attempts) = deposit(pool, 10, &[0], 3).await;
+    assert_eq!(result.unwrap(), 11);
+    assert_eq!(attempts, 2);
+    assert_eq!(balance().await.unwrap(), 11);
+
+    // Out of retries: the conflict is returned and the deposit rolled back
+    let (result, attempts) = deposit(pool, 10, &[0, 1], 1).await;
+    let err = result.unwrap_err();
+    assert!(err.is_serialization_failure(), "{}", err);
+    assert!(anyhow::Error::from(err).context("deposit").is_serialization_failure());
+    assert_eq!(attempts, 2);
+    assert_eq!(balance().await.unwrap(), 13);
+
+    // Other errors are not retried
+    let attempts = AtomicU32::new(0);
+    let result: Result<(), sqlx::Error> = with_retry(pool, |tx| {
+        attempts.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async move {
+            sqlx::query("SELECT missing FROM test_transaction_retry").execute(&mut **tx).await?;
+            Ok(())
+        })
+    }, 3).await;
+    assert!(!result.unwrap_err().is_serialization_failure());
+    assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+    sqlx::query("DROP TABLE test_transaction_retry").execute(pool).await.unwrap();
+}
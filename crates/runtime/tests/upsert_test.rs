@@ -0,0 +1,119 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{SchemaMigrator, SqlxObject, SqlxUpsert};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+#[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+#[table_name = "test_upsert_codes"]
+#[conflict_target(owner, code)]
+pub struct UpsertCode {
+    pub id: Uuid,
+    pub owner: Uuid,
+    pub code: String,
+    pub uses: i32,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+mod legacy {
+    use metastable_database::SqlxObject;
+    use serde::{Deserialize, Serialize};
+    use sqlx::types::Uuid;
+
+    #[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+    #[table_name = "test_upsert_codes_existing"]
+    pub struct PlainCode {
+        pub id: Uuid,
+        pub code: String,
+        pub created_at: i64,
+        pub updated_at: i64,
+    }
+}
+
+#[test]
+fn test_conflict_target_adds_unique_index() {
+    assert!(UpsertCode::INDEXES_SQL.iter().any(|sql|
+        sql.contains("CREATE UNIQUE INDEX") && sql.contains("(\"owner\", \"code\")")
+    ));
+}
+
+#[tokio::test]
+async fn test_upsert_inserts_then_updates() {
+    let db = PostgresClient::setup_connection().await;
+    let pool: &sqlx::PgPool = db.get_client();
+
+    sqlx::query("DROP TABLE IF EXISTS test_upsert_codes").execute(pool).await.unwrap();
+    UpsertCode::migrate(pool).await.unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    let owner = Uuid::new_v4();
+    let first = UpsertCode { owner, code: "abc".to_string(), uses: 1, ..Default::default() }
+        .upsert(&mut *tx).await.unwrap();
+    sqlx::query("UPDATE test_upsert_codes SET created_at = 42, updated_at = 42 WHERE id = $1")
+        .bind(first.id).execute(&mut *tx).await.unwrap();
+
+    let second = UpsertCode { owner, code: "abc".to_string(), uses: 5, ..Default::default() }
+        .upsert(&mut *tx).await.unwrap();
+    assert_eq!(second.id, first.id);
+    assert_eq!(second.uses, 5);
+    assert_eq!(second.created_at, 42);
+    assert!(second.updated_at > 42);
+
+    // A different owner is a different conflict key
+    let other = UpsertCode { owner: Uuid::new_v4(), code: "abc".to_string(), uses: 1, ..Default::default() }
+        .upsert(&mut *tx).await.unwrap();
+    assert_ne!(other.id, first.id);
+
+    // The insert-only variant leaves an existing row alone
+    let absent = UpsertCode { owner, code: "abc".to_string(), uses: 9, ..Default::default() }
+        .create_if_absent(&mut *tx).await.unwrap();
+    assert!(absent.is_none());
+    let fresh = UpsertCode { owner, code: "def".to_string(), uses: 1, ..Default::default() }
+        .create_if_absent(&mut *tx).await.unwrap();
+    assert_eq!(fresh.unwrap().code, "def");
+
+    let uses: i32 = sqlx::query_scalar("SELECT uses FROM test_upsert_codes WHERE id = $1").bind(first.id).fetch_one(&mut *tx).await.unwrap();
+    assert_eq!(uses, 5);
+    let rows: i64 = sqlx::query_scalar("SELECT count(*) FROM test_upsert_codes").fetch_one(&mut *tx).await.unwrap();
+    assert_eq!(rows, 3);
+    tx.rollback().await.unwrap();
+    sqlx::query("DROP TABLE IF EXISTS test_upsert_codes").execute(pool).await.unwrap();
+
+    #[derive(Clone, Default, Debug, Serialize, Deserialize, SqlxObject)]
+    #[table_name = "test_upsert_codes_existing"]
+    #[conflict_target(code)]
+    pub struct ExistingCode {
+        pub id: Uuid,
+        pub code: String,
+        pub created_at: i64,
+        pub updated_at: i64,
+    }
+
+    sqlx::query("DROP TABLE IF EXISTS test_upsert_codes_existing").execute(pool).await.unwrap();
+    // The table predates the conflict target
+    legacy::PlainCode::migrate(pool).await.unwrap();
+    // Duplicates written before the index existed
+    let oldest = legacy::PlainCode { code: "dup".to_string(), ..Default::default() }.create(pool).await.unwrap();
+    let newer = legacy::PlainCode { code: "dup".to_string(), ..Default::default() }.create(pool).await.unwrap();
+
+    // The migration refuses to pick a survivor and names the clashing rows
+    let err = ExistingCode::migrate_with(pool, Default::default()).await.unwrap_err().to_string();
+    assert!(err.contains("dup"));
+    assert!(err.contains(&oldest.id.to_string()));
+    assert!(err.contains(&newer.id.to_string()));
+    let rows: i64 = sqlx::query_scalar("SELECT count(*) FROM test_upsert_codes_existing WHERE code = 'dup'").fetch_one(pool).await.unwrap();
+    assert_eq!(rows, 2);
+
+    // Once the duplicates are resolved by hand the index goes in
+    sqlx::query("DELETE FROM test_upsert_codes_existing WHERE id = $1").bind(newer.id).execute(pool).await.unwrap();
+    let statements = ExistingCode::migrate_with(pool, Default::default()).await.unwrap();
+    assert!(statements.iter().any(|s| s.contains("CREATE UNIQUE INDEX")));
+    assert!(!statements.iter().any(|s| s.starts_with("DELETE FROM")));
+
+    let first = ExistingCode { code: "xyz".to_string(), ..Default::default() }.upsert(pool).await.unwrap();
+    let second = ExistingCode { code: "xyz".to_string(), ..Default::default() }.upsert(pool).await.unwrap();
+    assert_eq!(first.id, second.id);
+
+    sqlx::query("DROP TABLE IF EXISTS test_upsert_codes_existing").execute(pool).await.unwrap();
+}
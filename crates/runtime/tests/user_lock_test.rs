@@ -0,0 +1,45 @@
+use std::time::Duration;
+
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::SqlxCrud;
+use metastable_runtime::User;
+use sqlx::types::Uuid;
+
+async fn deduct(db: PostgresClient, id: Uuid, amount: i64) {
+    let mut tx = db.get_client().begin().await.unwrap();
+    let mut user = User::find_for_update(&id, &mut tx).await.unwrap().unwrap();
+
+    // widen the window between read and write so an unlocked read would race
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    user.pay_for_voice_generation(amount, Uuid::new_v4(), None).unwrap();
+    user.update(&mut *tx).await.unwrap();
+    tx.commit().await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_concurrent_deductions_do_not_lose_updates() {
+    let db = PostgresClient::setup_connection().await;
+
+    let mut tx = db.get_client().begin().await.unwrap();
+    let user = User {
+        user_id: format!("test_lock_{}", Uuid::new_v4()),
+        running_misc_balance: 100,
+        ..Default::default()
+    }.create(&mut *tx).await.unwrap();
+    tx.commit().await.unwrap();
+
+    let a = tokio::spawn(deduct(db.clone(), user.id, 10));
+    let b = tokio::spawn(deduct(db.clone(), user.id, 20));
+    a.await.unwrap();
+    b.await.unwrap();
+
+    let mut tx = db.get_client().begin().await.unwrap();
+    let user = User::find_for_update(&user.id, &mut tx).await.unwrap().unwrap();
+    assert_eq!(user.running_misc_balance, 70);
+    assert_eq!(user.balance_usage, 30);
+
+    user.delete(&mut *tx).await.unwrap();
+    tx.commit().await.unwrap();
+}
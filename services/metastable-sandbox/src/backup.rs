@@ -0,0 +1,64 @@
+use std::{fmt::Display, path::Path};
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use tracing::{info, warn};
+
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{OnConflict, SqlxCrud};
+
+#[derive(Debug, Default)]
+pub struct RestoreReport {
+    pub restored: usize,
+    pub skipped: usize,
+    /// `(id, error)` for every row that could not be written
+    pub failed: Vec<(String, String)>,
+}
+
+/// Restores rows from a JSON backup (a serialized `Vec<T>`, as written by the
+/// migration binaries) in a single transaction. Each row runs in its own
+/// savepoint so one bad row is reported instead of aborting the restore.
+pub async fn restore_from_backup<T>(
+    path: impl AsRef<Path>, db: &PostgresClient, conflict: OnConflict
+) -> Result<RestoreReport>
+where
+    T: SqlxCrud + DeserializeOwned + Send,
+    T::Id: Display,
+{
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("[restore_from_backup] Failed to read {}", path.display()))?;
+    let rows: Vec<T> = serde_json::from_str(&content)
+        .with_context(|| format!("[restore_from_backup] Failed to parse {}", path.display()))?;
+    info!("Restoring {} rows into {} from {}", rows.len(), T::TABLE_NAME, path.display());
+
+    let mut report = RestoreReport::default();
+    let mut tx = db.get_client().begin().await?;
+    for row in rows {
+        let id = row.get_id_value().to_string();
+        let mut savepoint = sqlx::Connection::begin(&mut *tx).await?;
+        match row.restore(conflict, &mut *savepoint).await {
+            Ok(Some(_)) => {
+                savepoint.commit().await?;
+                report.restored += 1;
+            }
+            Ok(None) => {
+                savepoint.commit().await?;
+                report.skipped += 1;
+            }
+            Err(e) => {
+                savepoint.rollback().await?;
+                warn!("Failed to restore {} {}: {}", T::TABLE_NAME, id, e);
+                report.failed.push((id, e.to_string()));
+            }
+        }
+    }
+    tx.commit().await?;
+
+    info!(
+        "Restored {} rows into {} ({} skipped, {} failed)",
+        report.restored, T::TABLE_NAME, report.skipped, report.failed.len()
+    );
+    Ok(report)
+}
@@ -0,0 +1,63 @@
+use anyhow::{anyhow, Result};
+use rustyline::{error::ReadlineError, DefaultEditor};
+use sqlx::types::Uuid;
+
+use metastable_database::{init_databases, QueryCriteria, SqlxFilterQuery};
+use metastable_runtime::{Prompt, User};
+use metastable_runtime_mem0::{Mem0Engine, Mem0Filter};
+
+init_databases!(
+    default: [],
+    pgvector: []
+);
+
+const USAGE: &str = "usage: memory <user_id> [character_id]";
+
+/// Interactive recall for one user: `/memory <query>` lists what
+/// `Mem0Engine::recall` returns, each with its vector score or graph path.
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let user_id: Uuid = args.next().ok_or_else(|| anyhow!(USAGE))?.parse()?;
+    let character_id = args.next().map(|id| id.parse::<Uuid>()).transpose()?;
+
+    let db = connect(false, false, false).await.clone();
+    let user = User::find_one_by_criteria(
+        QueryCriteria::new().add_valued_filter("id", "=", user_id),
+        &db
+    ).await?
+        .ok_or_else(|| anyhow!("User {} not found", user_id))?;
+    let filter = Mem0Filter { user_id, user_aka: user.user_aka, character_id, session_id: None };
+
+    let mem0_engine = Mem0Engine::new().await?;
+    mem0_engine.init().await?;
+
+    let mut editor = DefaultEditor::new()?;
+    loop {
+        let line = match editor.readline("> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted | ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        let line = line.trim();
+        if line == "/quit" {
+            break;
+        }
+        let Some(query) = line.strip_prefix("/memory").map(str::trim).filter(|q| !q.is_empty()) else {
+            println!("Commands: /memory <query>, /quit");
+            continue;
+        };
+        let _ = editor.add_history_entry(line);
+
+        match mem0_engine.recall(Prompt::new_user(query), &filter).await {
+            Ok(results) if results.is_empty() => println!("No memories recalled"),
+            Ok(results) => {
+                for (i, result) in results.iter().enumerate() {
+                    println!("{}. {}", i + 1, result);
+                }
+            }
+            Err(e) => println!("Recall failed: {}", e),
+        }
+    }
+    Ok(())
+}
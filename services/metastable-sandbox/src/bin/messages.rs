@@ -69,7 +69,7 @@ async fn migrate_messages(db: &Arc<PgPool>, environment: &str) -> Result<()> {
         let mut tx = db.begin().await?;
         for m in mm {
             let mut m = m.clone();
-            let t = try_prase_message(&m)?;
+            let t = try_prase_message(&m).into_result()?;
             m.assistant_message_tool_call = Json(Some(t));
             m.is_migrated = true;
             m.update(&mut *tx).await?;
@@ -3,10 +3,10 @@ use std::io::Read;
 use std::{fs::File, io::Write, sync::Arc, time::Instant};
 
 use anyhow::{Result, Context, anyhow};
-use metastable_database::{init_databases, QueryCriteria, SqlxCrud, SqlxFilterQuery};
+use metastable_database::{init_databases, verify_text_enum_column, QueryCriteria, SqlxCrud, SqlxFilterQuery};
 
-use metastable_runtime::{Character as NewCharacter, CharacterHistory as NewCharacterHistory, Message};
-use metastable_runtime_roleplay::{try_prase_message};
+use metastable_runtime::{Character as NewCharacter, CharacterHistory as NewCharacterHistory, CharacterStatus, Message};
+use metastable_runtime_roleplay::{try_prase_message, ParseOutcome};
 use sqlx::{types::Json, PgPool, Row};
 use metastable_sandbox::legacy::Character as LegacyCharacter;
 use metastable_sandbox::legacy::CharacterHistory as LegacyCharacterHistory;
@@ -131,49 +131,52 @@ async fn migrate_messages(db: &Arc<PgPool>, environment: &str) -> Result<()> {
     let mut tx = db.begin().await.context("Failed to start transaction")?;
     Message::toggle_trigger(&mut *tx, false).await
         .context("Failed to disable message triggers")?;
-
-    // Find unmigrated messages
-    info!("Finding unmigrated messages...");
-    let messages = Message::find_by_criteria(
-        QueryCriteria::new()
-            .add_valued_filter("is_migrated", "=", false),
-        &mut *tx
-    ).await.context("Failed to query unmigrated messages")?;
-
     tx.commit().await.context("Failed to commit transaction")?;
 
-    let total_messages = messages.len();
-    info!("Found {} unmigrated messages", total_messages);
-
-    if total_messages == 0 {
-        info!("No messages to migrate, skipping message migration");
-        return Ok(());
-    }
-
-    // Backup original messages
-    info!("Creating backup of original messages...");
-    let backup_content = serde_json::to_string(&messages)
-        .context("Failed to serialize messages for backup")?;
-    write_to_file(&backup_content, &format!("original_messages_{}.json", environment))?;
+    let total_messages: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM messages WHERE is_migrated = false AND migration_claimed_at IS NULL"
+    ).fetch_one(db.as_ref()).await.context("Failed to count unmigrated messages")?;
+    info!("Found {} unclaimed unmigrated messages", total_messages);
 
-    // Process messages in chunks
+    // Process messages in claimed batches. Claims use SKIP LOCKED, so several
+    // workers can run this concurrently without picking up the same messages.
+    // Messages that fail stay claimed and are listed in the failed ids file;
+    // their claim lapses after DEFAULT_MIGRATION_CLAIM_LEASE_SECS.
     let mut processed_count = 0;
     let mut failed_count = 0;
     let mut failed_ids = Vec::new();
+    let mut failure_buckets: HashMap<&'static str, usize> = HashMap::new();
+    let mut chunk_index = 0;
 
-    for (chunk_index, chunk) in messages.chunks(100).enumerate() {
-        info!("Processing chunk {} ({} messages)...", chunk_index + 1, chunk.len());
+    loop {
+        let mut tx = db.begin().await
+            .with_context(|| format!("Failed to start claim transaction for chunk {}", chunk_index + 1))?;
+        let chunk = Message::claim_unmigrated_batch(&mut tx, 100).await
+            .with_context(|| format!("Failed to claim chunk {}", chunk_index + 1))?;
+        tx.commit().await
+            .with_context(|| format!("Failed to commit claim for chunk {}", chunk_index + 1))?;
+
+        if chunk.is_empty() {
+            break;
+        }
+        chunk_index += 1;
+        info!("Processing chunk {} ({} messages)...", chunk_index, chunk.len());
+
+        // Backup original messages
+        let backup_content = serde_json::to_string(&chunk)
+            .context("Failed to serialize messages for backup")?;
+        write_to_file(&backup_content, &format!("original_messages_{}_{}.json", environment, chunk_index))?;
 
         let mut tx = db.begin().await
-            .with_context(|| format!("Failed to start transaction for chunk {}", chunk_index + 1))?;
+            .with_context(|| format!("Failed to start transaction for chunk {}", chunk_index))?;
 
         let mut chunk_failed = 0;
 
-        for message in chunk {
+        for message in &chunk {
             let mut message_copy = message.clone();
 
             match try_prase_message(&message_copy) {
-                Ok(tool_call) => {
+                ParseOutcome::Parsed(tool_call) | ParseOutcome::UnknownTool(tool_call) => {
                     message_copy.assistant_message_tool_call = Json(Some(tool_call));
                     message_copy.is_migrated = true;
 
@@ -191,8 +194,9 @@ async fn migrate_messages(db: &Arc<PgPool>, environment: &str) -> Result<()> {
                         }
                     }
                 },
-                Err(e) => {
-                    error!("Failed to parse message {}: {}", message_copy.id, e);
+                outcome => {
+                    error!("Failed to parse message {}: {:?}", message_copy.id, outcome);
+                    *failure_buckets.entry(outcome.kind()).or_insert(0usize) += 1;
                     failed_count += 1;
                     chunk_failed += 1;
                     failed_ids.push(message_copy.id);
@@ -204,25 +208,25 @@ async fn migrate_messages(db: &Arc<PgPool>, environment: &str) -> Result<()> {
             match tx.commit().await {
                 Ok(_) => {
                     info!("✅ Chunk {} committed successfully ({} messages)",
-                          chunk_index + 1, chunk.len());
+                          chunk_index, chunk.len());
                 },
                 Err(e) => {
-                    error!("Failed to commit chunk {}: {}", chunk_index + 1, e);
+                    error!("Failed to commit chunk {}: {}", chunk_index, e);
                     failed_count += chunk.len();
-                    for msg in chunk {
+                    for msg in &chunk {
                         failed_ids.push(msg.id);
                     }
                 }
             }
         } else {
-            warn!("⚠️ Chunk {} had {} failures, rolling back", chunk_index + 1, chunk_failed);
+            warn!("⚠️ Chunk {} had {} failures, rolling back", chunk_index, chunk_failed);
             if let Err(e) = tx.rollback().await {
-                error!("Failed to rollback chunk {}: {}", chunk_index + 1, e);
+                error!("Failed to rollback chunk {}: {}", chunk_index, e);
             }
         }
 
         // Progress update
-        let progress = (processed_count as f64 / total_messages as f64) * 100.0;
+        let progress = (processed_count as f64 / total_messages.max(1) as f64) * 100.0;
         info!("Progress: {:.1}% ({}/{}) - Failures: {}",
               progress, processed_count, total_messages, failed_count);
     }
@@ -239,6 +243,9 @@ async fn migrate_messages(db: &Arc<PgPool>, environment: &str) -> Result<()> {
     if failed_count > 0 {
         error!("Message migration completed with {} failures out of {} total",
                failed_count, total_messages);
+        for (kind, count) in &failure_buckets {
+            error!("  {}: {}", kind, count);
+        }
 
         // Write failed IDs to file for investigation
         let failed_ids_json = serde_json::to_string(&failed_ids)
@@ -666,19 +673,20 @@ async fn verify_migration(db: &PgPool, _environment: &str) -> Result<()> {
         return Err(anyhow!("Migration verification failed: {} unmigrated messages", unmigrated_count));
     }
 
-    // Check for double-quoted enum values
-    let double_quoted_status: i64 = sqlx::query_scalar(
-        r#"SELECT COUNT(*) FROM roleplay_characters WHERE status::text LIKE '"%"'"#
-    ).fetch_one(db).await?;
+    // Check for enum values that no longer parse (e.g. double-quoted)
+    let invalid_status = verify_text_enum_column::<CharacterStatus, _>(db, "roleplay_characters", "status").await?;
 
-    if double_quoted_status > 0 {
-        error!("⚠️ Found {} characters with double-quoted status values", double_quoted_status);
-        return Err(anyhow!("Migration verification failed: {} double-quoted status values", double_quoted_status));
+    if !invalid_status.is_empty() {
+        for row in &invalid_status {
+            error!("  Character {} has invalid status {}: {}", row.id, row.value, row.error);
+        }
+        error!("⚠️ Found {} characters with invalid status values", invalid_status.len());
+        return Err(anyhow!("Migration verification failed: {} invalid status values", invalid_status.len()));
     }
 
     info!("✅ Migration verification passed!");
     info!("  All messages migrated successfully");
-    info!("  No double-quoted enum values found");
+    info!("  No invalid enum values found");
 
     Ok(())
 }
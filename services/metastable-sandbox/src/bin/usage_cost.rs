@@ -0,0 +1,56 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use metastable_database::{init_databases, QueryCriteria, SqlxFilterQuery};
+use metastable_runtime::{Message, ModelPricing};
+
+init_databases!(
+    default: [],
+    pgvector: []
+);
+
+#[derive(Default)]
+struct ModelTotals {
+    messages: usize,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+    cost: Option<f64>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let db = Arc::new(connect(false, false, false).await.clone());
+    let pricing = ModelPricing::from_env()?;
+
+    let messages = Message::find_by_criteria(QueryCriteria::new(), &*db).await?;
+
+    let mut totals: BTreeMap<String, ModelTotals> = BTreeMap::new();
+    for message in &messages {
+        let Some(usage) = message.usage.0.as_ref() else { continue };
+        let entry = totals.entry(message.model_name.clone()).or_default();
+        entry.messages += 1;
+        entry.prompt_tokens += usage.prompt_tokens as u64;
+        entry.completion_tokens += usage.completion_tokens as u64;
+        if let Some(cost) = pricing.estimate_cost(usage, &message.model_name) {
+            *entry.cost.get_or_insert(0.0) += cost;
+        }
+    }
+
+    let mut total_cost = 0.0;
+    for (model, entry) in &totals {
+        let cost = match entry.cost {
+            Some(cost) => {
+                total_cost += cost;
+                format!("${:.4}", cost)
+            }
+            None => "no price configured".to_string(),
+        };
+        println!(
+            "{}: {} messages, {} prompt / {} completion tokens, {}",
+            model, entry.messages, entry.prompt_tokens, entry.completion_tokens, cost
+        );
+    }
+    println!("Estimated total: ${:.4}", total_cost);
+    Ok(())
+}
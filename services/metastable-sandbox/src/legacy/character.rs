@@ -4,7 +4,7 @@ use metastable_database::SqlxObject;
 use sqlx::types::Uuid;
 
 use metastable_runtime::{Message, User, ToolCall};
-use metastable_runtime::{CharacterFeature, BackgroundStories, BehaviorTraits, Relationships, SkillsAndInterests};
+use metastable_runtime::{CharacterFeature, CharacterFeatureSet, BackgroundStories, OrderedBackgroundStory, BehaviorTraits, Relationships, SkillsAndInterests};
 use async_openai::types::FunctionCall;
 use metastable_runtime_roleplay::agents::{SendMessage, RoleplayMessageType};
 use sqlx::types::Json;
@@ -97,7 +97,7 @@ impl Character {
         }
     }
 
-    pub fn migrate_features(&self) -> Json<Vec<CharacterFeature>> {
+    pub fn migrate_features(&self) -> Json<CharacterFeatureSet> {
         let mut new_features = Vec::new();
         for feature_str in &self.features {
             if let Some(start_paren) = feature_str.find('(') {
@@ -123,12 +123,11 @@ impl Character {
                 new_features.push(feature);
             }
         }
-        new_features.dedup();
-        Json(new_features)
+        Json(new_features.into())
     }
 
-    pub fn migrate_background_stories(&self) -> Json<Vec<BackgroundStories>> {
-        Json(parse_prefixed_string(
+    pub fn migrate_background_stories(&self) -> Json<Vec<OrderedBackgroundStory>> {
+        Json(OrderedBackgroundStory::numbered(parse_prefixed_string(
             &self.prompts_background_stories,
             &[
                 ("职业", BackgroundStories::Professions),
@@ -140,7 +139,7 @@ impl Character {
                 ("梦想，渴望的事情，追求的事情", BackgroundStories::Dreams),
             ],
             BackgroundStories::Others,
-        ))
+        )))
     }
 
     pub fn migrate_behavior_traits(&self) -> Json<Vec<BehaviorTraits>> {
@@ -4,8 +4,8 @@ use metastable_database::SqlxObject;
 use sqlx::types::Uuid;
 use metastable_runtime::User;
 
-use metastable_runtime::{CharacterFeature, ToolCall};
-use metastable_runtime::{BackgroundStories, BehaviorTraits, Relationships, SkillsAndInterests};
+use metastable_runtime::{CharacterFeature, CharacterFeatureSet, ToolCall};
+use metastable_runtime::{BackgroundStories, OrderedBackgroundStory, BehaviorTraits, Relationships, SkillsAndInterests};
 use metastable_runtime_roleplay::agents::{SendMessage, RoleplayMessageType};
 use async_openai::types::FunctionCall;
 use sqlx::types::Json;
@@ -87,7 +87,7 @@ impl CharacterHistory {
             ..Default::default()
         }
     }
-    pub fn migrate_features(&self) -> Json<Vec<CharacterFeature>> {
+    pub fn migrate_features(&self) -> Json<CharacterFeatureSet> {
         let mut new_features = Vec::new();
         for feature_str in &self.features {
             if let Some(start_paren) = feature_str.find('(') {
@@ -113,11 +113,11 @@ impl CharacterHistory {
                 new_features.push(feature);
             }
         }
-        Json(new_features)
+        Json(new_features.into())
     }
 
-    pub fn migrate_background_stories(&self) -> Json<Vec<BackgroundStories>> {
-        Json(parse_prefixed_string(
+    pub fn migrate_background_stories(&self) -> Json<Vec<OrderedBackgroundStory>> {
+        Json(OrderedBackgroundStory::numbered(parse_prefixed_string(
             &self.prompts_background_stories,
             &[
                 ("职业", BackgroundStories::Professions),
@@ -129,7 +129,7 @@ impl CharacterHistory {
                 ("梦想，渴望的事情，追求的事情", BackgroundStories::Dreams),
             ],
             BackgroundStories::Others,
-        ))
+        )))
     }
 
     pub fn migrate_behavior_traits(&self) -> Json<Vec<BehaviorTraits>> {
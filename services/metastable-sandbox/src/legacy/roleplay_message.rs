@@ -70,6 +70,7 @@ impl RoleplayMessage {
             owner: user_message.owner,
             system_config: system_config.id,
             session: Some(user_message.session_id),
+            turn_index: None,
 
             user_message_content: user_message.content.clone(),
             user_message_content_type: user_message.content_type.clone(),
@@ -92,6 +93,8 @@ impl RoleplayMessage {
 
             is_migrated: false,
 
+            migration_claimed_at: None,
+
             created_at: user_message.created_at,
             updated_at: user_message.updated_at,
         }
@@ -1 +1,4 @@
-pub mod legacy;
\ No newline at end of file
+pub mod legacy;
+mod backup;
+
+pub use backup::{restore_from_backup, RestoreReport};
@@ -0,0 +1,67 @@
+use metastable_clients::PostgresClient;
+use metastable_common::ModuleClient;
+use metastable_database::{OnConflict, QueryCriteria, SqlxCrud, SqlxFilterQuery};
+use metastable_runtime::SystemConfig;
+use metastable_sandbox::restore_from_backup;
+use sqlx::types::Uuid;
+
+async fn delete_configs(db: &PostgresClient, configs: Vec<SystemConfig>) {
+    let mut tx = db.get_client().begin().await.unwrap();
+    for config in configs {
+        config.delete(&mut *tx).await.unwrap();
+    }
+    tx.commit().await.unwrap();
+}
+
+async fn find_configs(db: &PostgresClient, name: &str) -> Vec<SystemConfig> {
+    let mut conn = db.get_client().acquire().await.unwrap();
+    let mut configs = SystemConfig::find_by_criteria(
+        QueryCriteria::new().add_valued_filter("name", "=", name.to_string()),
+        &mut *conn
+    ).await.unwrap();
+    configs.sort_by_key(|c| c.system_prompt_version);
+    configs
+}
+
+#[tokio::test]
+async fn test_restore_from_backup_round_trip() {
+    let db = PostgresClient::setup_connection().await;
+    let name = format!("test_restore_{}", Uuid::new_v4());
+
+    let mut tx = db.get_client().begin().await.unwrap();
+    for version in 1..=3 {
+        SystemConfig {
+            name: name.clone(),
+            system_prompt: format!("prompt v{}", version),
+            system_prompt_version: version,
+            active: version == 3,
+            ..Default::default()
+        }.create(&mut *tx).await.unwrap();
+    }
+    tx.commit().await.unwrap();
+
+    let original = find_configs(&db, &name).await;
+    assert_eq!(original.len(), 3);
+
+    let path = std::env::temp_dir().join(format!("{}.json", name));
+    std::fs::write(&path, serde_json::to_string(&original).unwrap()).unwrap();
+
+    delete_configs(&db, original.clone()).await;
+    assert!(find_configs(&db, &name).await.is_empty());
+
+    let report = restore_from_backup::<SystemConfig>(&path, &db, OnConflict::Fail).await.unwrap();
+    assert_eq!(report.restored, 3);
+    assert!(report.failed.is_empty());
+
+    let restored = find_configs(&db, &name).await;
+    assert_eq!(serde_json::to_value(&restored).unwrap(), serde_json::to_value(&original).unwrap());
+
+    // restoring on top of existing rows: skipped, or reported as failures without aborting
+    let report = restore_from_backup::<SystemConfig>(&path, &db, OnConflict::Skip).await.unwrap();
+    assert_eq!((report.restored, report.skipped), (0, 3));
+    let report = restore_from_backup::<SystemConfig>(&path, &db, OnConflict::Fail).await.unwrap();
+    assert_eq!(report.failed.len(), 3);
+
+    delete_configs(&db, restored).await;
+    std::fs::remove_file(&path).unwrap();
+}
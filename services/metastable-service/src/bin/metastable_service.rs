@@ -1,11 +1,13 @@
+use std::time::Duration;
+
 use anyhow::Result;
 use axum::Router;
-use metastable_runtime_roleplay::MemoryUpdater;
 use tower_http::{cors::CorsLayer, timeout::TimeoutLayer, trace::TraceLayer};
 
 use metastable_service_api::{
-    graphql_route, misc_routes, runtime_routes, setup_tracing, voice_routes, user_routes, auth_routes, stripe_routes, GlobalState
+    graphql_route, misc_routes, runtime_routes, setup_tracing, voice_routes, user_routes, auth_routes, stripe_routes, admin_routes, with_body_limit, with_timeout, ApiServerEnv, GlobalState
 };
+use metastable_common::EnvVars;
 
 use metastable_database::init_databases;
 
@@ -25,6 +27,7 @@ init_databases!(
 
         metastable_runtime::Message,
         metastable_runtime::ChatSession,
+        metastable_runtime::SessionParticipant,
         metastable_runtime::UserPointsLog,
 
         metastable_runtime::Character,
@@ -49,22 +52,35 @@ async fn main() -> Result<()> {
 
     let (global_state, mut memory_updater_rx) = GlobalState::new().await?;
 
+    let memory_updater = global_state.memory_updater.clone();
     tokio::spawn(async move {
-        let memory_updater = MemoryUpdater::new().await.unwrap();
         while let Some(session_id) = memory_updater_rx.recv().await {
             let _ = memory_updater.update_memory(&session_id).await;
+            let _ = memory_updater.update_title(&session_id).await;
         }
     });
 
-    let app = Router::new()
-        .merge(misc_routes())
+    let env = ApiServerEnv::load();
+
+    // Chat and voice bound their own model calls so a timeout can't cut off the
+    // charge for a finished turn; everything else is plain CRUD
+    let chat_routes = Router::new()
         .merge(runtime_routes())
-        .merge(voice_routes())
+        .merge(voice_routes());
+    let crud_routes = Router::new()
+        .merge(misc_routes())
         .merge(graphql_route())
         .merge(user_routes())
         .merge(auth_routes())
         .merge(stripe_routes())
-        .layer(TimeoutLayer::new(std::time::Duration::from_secs(3600)))
+        .merge(admin_routes());
+
+    let routes = Router::new()
+        .merge(chat_routes)
+        .merge(with_timeout(crud_routes, Duration::from_secs(env.crud_request_timeout_secs)));
+
+    let app = with_body_limit(routes, env.max_request_body_bytes)
+        .layer(TimeoutLayer::new(Duration::from_secs(3600)))
         .layer(cors)
         .layer(trace)
         .with_state(global_state);